@@ -0,0 +1,252 @@
+// src/backtest/download.rs
+use crate::connectors::binance::BinanceClient;
+use crate::types::{Kline, Ticker};
+use crate::utils::error::{BotError, Result};
+use crate::utils::rate_limit::TokenBucket;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+// Свечей на страницу: максимум /klines
+const PAGE_LIMIT: usize = 1000;
+// Вес запроса /fapi/v1/klines при limit 500..=1000
+const PAGE_WEIGHT: u32 = 5;
+// Половина минутного лимита веса фьючерсов (2400): бот на том же IP тоже ходит в REST
+const WEIGHT_PER_MINUTE: u32 = 1200;
+// 429/418 подряд — дальше не пытаемся
+const MAX_RATE_LIMIT_HITS: u32 = 5;
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(30);
+
+pub const CSV_HEADER: &str = "open_time,open,high,low,close,volume,close_time,gap";
+
+/// Итог загрузки для вывода в консоль
+#[derive(Debug, Default)]
+pub struct DownloadSummary {
+    // Свечей в файле, включая заполненные пропуски
+    pub candles: usize,
+    // Свечи, пришедшие повторно на стыке страниц
+    pub duplicates: usize,
+    // Дыры в данных биржи и сколько свечей ими заполнено
+    pub gaps: usize,
+    pub filled: usize,
+}
+
+/// Длительность интервала Binance ("1m", "4h", "1d"...). Месячный ("1M") не поддерживается:
+/// у него нет постоянной длины
+pub fn interval_ms(interval: &str) -> Option<u64> {
+    let unit = interval.chars().last()?;
+    let count: u64 = interval[..interval.len() - unit.len_utf8()].parse().ok()?;
+    let unit_ms = match unit {
+        'm' => 60_000,
+        'h' => 3_600_000,
+        'd' => 86_400_000,
+        'w' => 7 * 86_400_000,
+        _ => return None,
+    };
+    (count > 0).then_some(count * unit_ms)
+}
+
+/// Граница периода: мс с эпохи, RFC 3339, "YYYY-MM-DD HH:MM" или "YYYY-MM-DD" (полночь UTC)
+pub fn parse_time(value: &str) -> Option<u64> {
+    if let Ok(ms) = value.parse::<u64>() {
+        return Some(ms);
+    }
+    let datetime = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M")
+                .ok()
+                .map(|dt| dt.and_utc())
+        })
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })?;
+    u64::try_from(datetime.timestamp_millis()).ok()
+}
+
+/// Свечи symbol/interval с open_time в [start_ms, end_ms] постранично -> CSV для `--backtest`.
+/// Страницы склеиваются по open_time (повторы на стыке отбрасываются), незакрытая свеча
+/// не пишется, дыры заполняются плоскими свечами по прошлому закрытию с gap = 1
+pub async fn download(
+    client: &BinanceClient,
+    symbol: &str,
+    interval: &str,
+    start_ms: u64,
+    end_ms: u64,
+    path: &str,
+) -> Result<DownloadSummary> {
+    let step = interval_ms(interval)
+        .ok_or_else(|| BotError::Config(format!("Unsupported kline interval {:?}", interval)))?;
+    if start_ms > end_ms {
+        return Err(BotError::Config(format!(
+            "Download period is empty: start {} is after end {}",
+            start_ms, end_ms
+        )));
+    }
+
+    let mut bucket = TokenBucket::new(WEIGHT_PER_MINUTE, Duration::from_secs(60));
+    let mut candles: BTreeMap<u64, Kline> = BTreeMap::new();
+    let mut summary = DownloadSummary::default();
+    let mut rate_limit_hits = 0;
+    let mut cursor = start_ms;
+    while cursor <= end_ms {
+        bucket.acquire(PAGE_WEIGHT).await;
+        let page = match client
+            .fetch_klines_page(symbol, interval, cursor, end_ms, PAGE_LIMIT)
+            .await
+        {
+            Ok(page) => page,
+            Err(BotError::RateLimited { status, msg }) if rate_limit_hits < MAX_RATE_LIMIT_HITS => {
+                rate_limit_hits += 1;
+                warn!(
+                    "⏳ Rate limited by Binance ({} {}). Waiting {}s",
+                    status,
+                    msg,
+                    RATE_LIMIT_BACKOFF.as_secs()
+                );
+                bucket.drain();
+                sleep(RATE_LIMIT_BACKOFF).await;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        rate_limit_hits = 0;
+        let Some(next) = page.last().map(|k| k.open_time + step) else {
+            break;
+        };
+        for kline in page {
+            if candles.insert(kline.open_time, kline).is_some() {
+                summary.duplicates += 1;
+            }
+        }
+        info!("📥 {} {}: {} candles", symbol, interval, candles.len());
+        // Биржа вернула страницу не дальше курсора: дальше данных нет
+        if next <= cursor {
+            break;
+        }
+        cursor = next;
+    }
+
+    // Последняя свеча может ещё формироваться
+    let now_ms = Utc::now().timestamp_millis().max(0) as u64;
+    candles.retain(|_, k| k.close_time < now_ms);
+    if candles.is_empty() {
+        return Err(BotError::Config(format!(
+            "Binance returned no {} {} candles for the period",
+            symbol, interval
+        )));
+    }
+
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    let mut previous: Option<&Kline> = None;
+    for kline in candles.values() {
+        if let Some(prev) = previous {
+            let missing = (kline.open_time.saturating_sub(prev.open_time) / step).saturating_sub(1);
+            if missing > 0 {
+                warn!(
+                    "🕳️ Gap in {} {}: {} candle(s) missing after {}",
+                    symbol, interval, missing, prev.open_time
+                );
+                summary.gaps += 1;
+                for i in 1..=missing {
+                    let open_time = prev.open_time + i * step;
+                    let flat = Kline {
+                        open_time,
+                        open: prev.close,
+                        high: prev.close,
+                        low: prev.close,
+                        close: prev.close,
+                        volume: Decimal::ZERO,
+                        close_time: open_time + step - 1,
+                    };
+                    push_row(&mut csv, &flat, true);
+                    summary.filled += 1;
+                }
+            }
+        }
+        push_row(&mut csv, kline, false);
+        previous = Some(kline);
+    }
+    summary.candles = candles.len() + summary.filled;
+
+    tokio::fs::write(path, csv)
+        .await
+        .map_err(|e| BotError::io(format!("Failed to write {}", path), e))?;
+    Ok(summary)
+}
+
+fn push_row(csv: &mut String, k: &Kline, gap: bool) {
+    csv.push_str(&format!(
+        "{},{},{},{},{},{},{},{}\n",
+        k.open_time,
+        k.open,
+        k.high,
+        k.low,
+        k.close,
+        k.volume,
+        k.close_time,
+        u8::from(gap)
+    ));
+}
+
+/// Свечи из CSV загрузчика. Заголовок и битые строки пропускаются
+pub fn parse_csv(content: &str) -> Vec<Kline> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let decimal = |i: usize| fields.get(i).and_then(|v| Decimal::from_str(v).ok());
+            let time = |i: usize| fields.get(i).and_then(|v| v.parse::<u64>().ok());
+            Some(Kline {
+                open_time: time(0)?,
+                open: decimal(1)?,
+                high: decimal(2)?,
+                low: decimal(3)?,
+                close: decimal(4)?,
+                volume: decimal(5)?,
+                close_time: time(6)?,
+            })
+        })
+        .collect()
+}
+
+/// Тики из свечи для бумажного движка: open, затем ближний к нему экстремум, дальний, close.
+/// Спреда и стакана в свечах нет: bid = ask, объём на уровне — объём свечи
+pub fn candle_ticks(kline: &Kline, symbol: &str) -> Vec<Ticker> {
+    let (first, second) = if kline.close >= kline.open {
+        (kline.low, kline.high)
+    } else {
+        (kline.high, kline.low)
+    };
+    let span = kline.close_time.saturating_sub(kline.open_time);
+    let qty = if kline.volume.is_zero() {
+        Decimal::ONE
+    } else {
+        kline.volume
+    };
+    [
+        (kline.open_time, kline.open),
+        (kline.open_time + span / 3, first),
+        (kline.open_time + span * 2 / 3, second),
+        (kline.close_time, kline.close),
+    ]
+    .into_iter()
+    .map(|(timestamp, price)| Ticker {
+        symbol: symbol.to_string(),
+        price,
+        bid_price: price,
+        ask_price: price,
+        bid_qty: qty,
+        ask_qty: qty,
+        timestamp,
+    })
+    .collect()
+}
@@ -0,0 +1,164 @@
+// src/backtest/fill.rs
+use crate::config::{AppConfig, FillModelKind};
+use crate::core::paper::{FillModel, PaperFill};
+use crate::strategies::scalper::CANDLE_MS;
+use crate::types::{Side, Ticker};
+use crate::utils::math::BPS_PER_UNIT;
+use crate::utils::precision::normalize_price;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+/// Модель исполнения прогона по [backtest]. None — paper: движок исполняет по стакану сам
+pub fn model(config: &AppConfig, ticks: Arc<Vec<Ticker>>) -> Option<Box<dyn FillModel>> {
+    let backtest = &config.backtest;
+    match backtest.fill_model {
+        FillModelKind::Paper => None,
+        FillModelKind::Optimistic => Some(Box::new(OptimisticFill)),
+        FillModelKind::Pessimistic => Some(Box::new(PessimisticFill {
+            bars: Bars(ticks),
+            slippage_bps: backtest.slippage_bps,
+        })),
+        FillModelKind::Realistic => Some(Box::new(RealisticFill {
+            bars: Bars(ticks),
+            range_fraction: backtest.range_fraction,
+        })),
+    }
+}
+
+/// Модель с параметрами для отчёта: по этой строке прогон можно повторить
+pub fn describe(config: &AppConfig) -> String {
+    let backtest = &config.backtest;
+    match backtest.fill_model {
+        FillModelKind::Paper => format!(
+            "paper (slippage {} bps, depth impact {} bps{})",
+            config.paper.slippage_bps,
+            config.paper.depth_impact_bps,
+            if config.paper.partial_fills {
+                ", partial fills"
+            } else {
+                ""
+            }
+        ),
+        FillModelKind::Optimistic => "optimistic (signal price, no slippage)".to_string(),
+        FillModelKind::Pessimistic => format!(
+            "pessimistic (next candle open + {} bps)",
+            backtest.slippage_bps
+        ),
+        FillModelKind::Realistic => format!(
+            "realistic (bid/ask + {} of previous candle range)",
+            backtest.range_fraction
+        ),
+    }
+}
+
+fn paper_fill(price: Decimal, quantity: Decimal, ticker: &Ticker, tick_size: Decimal) -> PaperFill {
+    let price = normalize_price(price, tick_size);
+    PaperFill {
+        price,
+        quantity,
+        slippage_cost: (price - ticker.price).abs() * quantity,
+    }
+}
+
+/// Цена, по которой реально можно исполниться на тике сигнала
+fn top_of_book(side: Side, ticker: &Ticker) -> Decimal {
+    match side {
+        Side::Buy => ticker.ask_price,
+        Side::Sell => ticker.bid_price,
+    }
+}
+
+/// Минутные свечи (как у стратегии) поверх тиков прогона. Тики идут по времени
+struct Bars(Arc<Vec<Ticker>>);
+
+impl Bars {
+    /// Первая цена следующей свечи после тика сигнала. None — запись кончилась
+    fn next_open(&self, timestamp: u64) -> Option<Decimal> {
+        let next_start = (timestamp / CANDLE_MS + 1) * CANDLE_MS;
+        let index = self.0.partition_point(|t| t.timestamp < next_start);
+        self.0.get(index).map(|t| t.price)
+    }
+
+    /// high - low свечи перед свечой сигнала. Текущую не берём: её остаток ещё в будущем
+    fn previous_range(&self, timestamp: u64) -> Option<Decimal> {
+        let start = (timestamp / CANDLE_MS).checked_sub(1)? * CANDLE_MS;
+        let from = self.0.partition_point(|t| t.timestamp < start);
+        let to = self.0.partition_point(|t| t.timestamp < start + CANDLE_MS);
+        let prices = self.0[from..to].iter().map(|t| t.price);
+        let high = prices.clone().max()?;
+        let low = prices.min()?;
+        Some(high - low)
+    }
+}
+
+/// Верхняя граница: всё по цене сигнала (close последнего тика), без спреда и проскальзывания
+pub struct OptimisticFill;
+
+impl FillModel for OptimisticFill {
+    fn fill(
+        &self,
+        _side: Side,
+        quantity: Decimal,
+        ticker: &Ticker,
+        _step_size: Decimal,
+        tick_size: Decimal,
+    ) -> PaperFill {
+        paper_fill(ticker.price, quantity, ticker, tick_size)
+    }
+}
+
+/// Нижняя граница: ордер доходит до биржи к открытию следующей свечи и платит slippage_bps.
+/// Если следующая свеча открылась лучше bid/ask сигнала, берётся bid/ask: выигрыша от задержки нет
+pub struct PessimisticFill {
+    bars: Bars,
+    slippage_bps: Decimal,
+}
+
+impl FillModel for PessimisticFill {
+    fn fill(
+        &self,
+        side: Side,
+        quantity: Decimal,
+        ticker: &Ticker,
+        _step_size: Decimal,
+        tick_size: Decimal,
+    ) -> PaperFill {
+        let top = top_of_book(side, ticker);
+        let open = self.bars.next_open(ticker.timestamp).unwrap_or(top);
+        let price = match side {
+            Side::Buy => open.max(top) * (Decimal::ONE + self.slippage_bps / BPS_PER_UNIT),
+            Side::Sell => open.min(top) * (Decimal::ONE - self.slippage_bps / BPS_PER_UNIT),
+        };
+        paper_fill(price, quantity, ticker, tick_size)
+    }
+}
+
+/// Проскальзывание растёт с волатильностью: bid/ask сигнала плюс range_fraction размаха
+/// предыдущей свечи
+pub struct RealisticFill {
+    bars: Bars,
+    range_fraction: Decimal,
+}
+
+impl FillModel for RealisticFill {
+    fn fill(
+        &self,
+        side: Side,
+        quantity: Decimal,
+        ticker: &Ticker,
+        _step_size: Decimal,
+        tick_size: Decimal,
+    ) -> PaperFill {
+        let top = top_of_book(side, ticker);
+        let slippage = self
+            .bars
+            .previous_range(ticker.timestamp)
+            .unwrap_or_default()
+            * self.range_fraction;
+        let price = match side {
+            Side::Buy => top + slippage,
+            Side::Sell => (top - slippage).max(Decimal::ZERO),
+        };
+        paper_fill(price, quantity, ticker, tick_size)
+    }
+}
@@ -0,0 +1,196 @@
+// src/backtest/mod.rs
+pub mod download;
+pub mod fill;
+pub mod report;
+pub mod sweep;
+
+use crate::config::{AppConfig, StrategyConfig, StrategySource, TradingMode};
+use crate::connectors::binance::BinanceClient;
+use crate::connectors::messages::TickerMerger;
+use crate::connectors::recorder::parse_record_line;
+use crate::core::engine::TradingEngine;
+use crate::strategies::grid::GridStrategy;
+use crate::strategies::scalper::RsiBollingerStrategy;
+use crate::strategies::traits::Strategy;
+use crate::types::{EngineEvent, Ticker};
+use crate::utils::clock::{Clock, SimulatedClock};
+use crate::utils::error::{BotError, Result};
+use crate::utils::ring_channel::ring_channel;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{info, warn};
+
+// Номер прогона для уникального файла состояния: прогоны идут параллельно
+static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Тики записи (`record_file`) целиком в памяти: один раз читаем, много раз прогоняем.
+/// `.csv` — свечи из `--download`, по четыре тика на свечу
+pub async fn load_ticks(path: &str, symbol: &str) -> Result<Vec<Ticker>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| BotError::io(format!("Failed to read recording {}", path), e))?;
+    let ticks: Vec<Ticker> = if path.ends_with(".csv") {
+        download::parse_csv(&content)
+            .iter()
+            .flat_map(|kline| download::candle_ticks(kline, symbol))
+            .collect()
+    } else {
+        let mut merger = TickerMerger::default();
+        content
+            .lines()
+            .filter_map(parse_record_line)
+            .map(|(recv_ts, event)| merger.on_event(event, symbol, recv_ts))
+            .collect()
+    };
+    info!("📼 Loaded {} ticks from {}", ticks.len(), path);
+    Ok(ticks)
+}
+
+/// Параметры стратегии для офлайн-прогона: без истории с биржи, чтобы решения зависели
+/// только от записанных тиков, и без проверки возраста тика (записанные заведомо "старые")
+pub fn offline_strategy_config(config: &StrategyConfig) -> StrategyConfig {
+    let mut config = config.clone();
+    config.seed_from_history = false;
+    config.max_tick_age_ms = None;
+    config
+}
+
+/// Стратегия офлайн-прогона: сетка или скальпер. Внешние сигналы в записи не повторить,
+/// для них тоже скальпер
+pub fn offline_strategy(config: &AppConfig, clock: Arc<dyn Clock>) -> Box<dyn Strategy> {
+    match config.strategy_source {
+        StrategySource::Grid => Box::new(GridStrategy::new(config.symbol.clone(), &config.grid)),
+        StrategySource::Scalper | StrategySource::External => Box::new(
+            RsiBollingerStrategy::new(
+                config.symbol.clone(),
+                offline_strategy_config(&config.strategy),
+            )
+            .with_clock(clock),
+        ),
+    }
+}
+
+/// Закрытая сделка (или закрытая часть позиции) в прогоне
+#[derive(Debug, Clone)]
+pub struct ClosedTrade {
+    // Чистый PnL и нотионал входа закрытого объёма
+    pub pnl: Decimal,
+    pub notional: Decimal,
+    // мс по времени тиков
+    pub opened_at: u64,
+    pub closed_at: u64,
+}
+
+impl ClosedTrade {
+    /// Доходность сделки на вложенный нотионал
+    pub fn return_rate(&self) -> f64 {
+        if self.notional.is_zero() {
+            return 0.0;
+        }
+        (self.pnl / self.notional).to_f64().unwrap_or_default()
+    }
+}
+
+/// Итог прогона: сделки по порядку закрытия
+#[derive(Debug, Default, Clone)]
+pub struct BacktestResult {
+    pub trades: Vec<ClosedTrade>,
+    // Модель исполнения с параметрами (fill::describe)
+    pub fill_model: String,
+    // Актив, в котором посчитан PnL (config.quote_asset())
+    pub quote_asset: String,
+}
+
+/// Один прогон бумажного движка по тикам. Тики отдаются без потерь, как в `--replay`.
+/// Время движка и стратегии — время тиков, так что прогон детерминирован
+pub async fn run(config: AppConfig, ticks: Arc<Vec<Ticker>>) -> Result<BacktestResult> {
+    let clock: Arc<dyn Clock> = Arc::new(SimulatedClock::default());
+    let strategy = offline_strategy(&config, clock.clone());
+    let fill_model = fill::model(&config, ticks.clone());
+    let fill_label = fill::describe(&config);
+    let quote_asset = config.quote_asset();
+    // В бумажном режиме движок к бирже не обращается
+    let execution_handler = Box::new(BinanceClient::new(String::new(), String::new()));
+
+    let (ticker_tx, ticker_rx) = ring_channel(config.ticker_buffer);
+    // UI нет: канал никто не читает, переполнение движок молча пропускает
+    let (ui_tx, _ui_rx) = mpsc::channel(1);
+    let (_command_tx, command_rx) = mpsc::channel(1);
+    let (event_tx, mut event_rx) = broadcast::channel(1024);
+
+    let state_file = std::env::temp_dir().join(format!(
+        "sniper_backtest_{}_{}.json",
+        std::process::id(),
+        RUN_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    let state_file = state_file.to_string_lossy().into_owned();
+
+    // Сделки собираем параллельно с прогоном, чтобы broadcast не переполнился
+    let collector = tokio::spawn(async move {
+        let mut result = BacktestResult::default();
+        loop {
+            match event_rx.recv().await {
+                Ok(EngineEvent::Exit {
+                    entry_price,
+                    quantity,
+                    pnl,
+                    opened_at,
+                    closed_at,
+                    ..
+                }) => result.trades.push(ClosedTrade {
+                    pnl,
+                    notional: entry_price * quantity,
+                    opened_at,
+                    closed_at,
+                }),
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(
+                        "Backtest lost {} engine events, result is incomplete",
+                        skipped
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return result,
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        for ticker in ticks.iter() {
+            if ticker_tx.send_wait(ticker.clone()).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    let mut engine = TradingEngine::new(
+        config,
+        execution_handler,
+        strategy,
+        ticker_rx,
+        ui_tx,
+        command_rx,
+        TradingMode::Paper,
+    )
+    .with_clock(clock)
+    .with_state_file(state_file.clone())
+    .with_event_sender(event_tx);
+    if let Some(model) = fill_model {
+        engine = engine.with_fill_model(model);
+    }
+    let outcome = engine.run().await;
+    // Движок держит отправителя событий: без drop коллектор не дождётся Closed
+    drop(engine);
+    let _ = tokio::fs::remove_file(&state_file).await;
+    outcome?;
+
+    let mut result = collector
+        .await
+        .map_err(|e| BotError::Strategy(anyhow::anyhow!("Backtest collector failed: {}", e)))?;
+    result.fill_model = fill_label;
+    result.quote_asset = quote_asset;
+    Ok(result)
+}
@@ -0,0 +1,161 @@
+// src/backtest/report.rs
+use crate::backtest::{BacktestResult, ClosedTrade};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// Риск-метрики прогона по ряду доходностей сделок и кривой equity
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    // Модель исполнения: без неё цифры разных прогонов несравнимы
+    pub fill_model: String,
+    pub quote_asset: String,
+    pub trades: usize,
+    pub wins: usize,
+    pub net_pnl: Decimal,
+    // Прибыль / убыток. None — убыточных сделок не было
+    pub profit_factor: Option<Decimal>,
+    // Безрисковая доходность на сделку (доля), вычитается в Sharpe и Sortino
+    pub risk_free_rate: f64,
+    // По доходностям сделок, без годового пересчёта. None — мало сделок или нет разброса
+    pub sharpe: Option<f64>,
+    pub sortino: Option<f64>,
+    // Пик-впадина equity (стартовый баланс + накопленный PnL): в quote_asset и в доле от пика
+    pub max_drawdown: Decimal,
+    pub max_drawdown_pct: Decimal,
+    // Средняя длительность сделки. None — время входа неизвестно ни для одной
+    pub avg_trade_secs: Option<u64>,
+}
+
+impl BacktestReport {
+    pub fn new(result: &BacktestResult, starting_equity: Decimal, risk_free_rate: f64) -> Self {
+        let trades = &result.trades;
+        let net_pnl: Decimal = trades.iter().map(|t| t.pnl).sum();
+        let wins = trades.iter().filter(|t| t.pnl > Decimal::ZERO).count();
+        let gross_profit: Decimal = trades.iter().map(|t| t.pnl.max(Decimal::ZERO)).sum();
+        let gross_loss: Decimal = trades.iter().map(|t| (-t.pnl).max(Decimal::ZERO)).sum();
+
+        let excess: Vec<f64> = trades
+            .iter()
+            .map(|t| t.return_rate() - risk_free_rate)
+            .collect();
+
+        let (max_drawdown, max_drawdown_pct) = max_drawdown(trades, starting_equity);
+
+        let durations: Vec<u64> = trades
+            .iter()
+            .filter(|t| t.opened_at > 0)
+            .map(|t| t.closed_at.saturating_sub(t.opened_at) / 1000)
+            .collect();
+        let avg_trade_secs =
+            (!durations.is_empty()).then(|| durations.iter().sum::<u64>() / durations.len() as u64);
+
+        Self {
+            fill_model: result.fill_model.clone(),
+            quote_asset: result.quote_asset.clone(),
+            trades: trades.len(),
+            wins,
+            net_pnl,
+            profit_factor: (!gross_loss.is_zero()).then(|| gross_profit / gross_loss),
+            risk_free_rate,
+            sharpe: sharpe(&excess),
+            sortino: sortino(&excess),
+            max_drawdown,
+            max_drawdown_pct,
+            avg_trade_secs,
+        }
+    }
+
+    pub fn win_rate(&self) -> Decimal {
+        if self.trades == 0 {
+            return Decimal::ZERO;
+        }
+        Decimal::from(self.wins) / Decimal::from(self.trades)
+    }
+}
+
+/// Средняя избыточная доходность / стандартное отклонение (выборочное)
+fn sharpe(excess: &[f64]) -> Option<f64> {
+    if excess.len() < 2 {
+        return None;
+    }
+    let n = excess.len() as f64;
+    let mean = excess.iter().sum::<f64>() / n;
+    let variance = excess.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let std_dev = variance.sqrt();
+    (std_dev > 0.0).then(|| mean / std_dev)
+}
+
+/// Как Sharpe, но в знаменателе только убыточная часть разброса
+fn sortino(excess: &[f64]) -> Option<f64> {
+    if excess.len() < 2 {
+        return None;
+    }
+    let n = excess.len() as f64;
+    let mean = excess.iter().sum::<f64>() / n;
+    let downside = (excess.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / n).sqrt();
+    (downside > 0.0).then(|| mean / downside)
+}
+
+fn max_drawdown(trades: &[ClosedTrade], starting_equity: Decimal) -> (Decimal, Decimal) {
+    let mut equity = starting_equity;
+    let mut peak = starting_equity;
+    let (mut worst, mut worst_pct) = (Decimal::ZERO, Decimal::ZERO);
+    for trade in trades {
+        equity += trade.pnl;
+        peak = peak.max(equity);
+        let drawdown = peak - equity;
+        if drawdown > worst {
+            worst = drawdown;
+            if peak > Decimal::ZERO {
+                worst_pct = drawdown / peak;
+            }
+        }
+    }
+    (worst, worst_pct)
+}
+
+fn ratio(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{:.2}", v))
+        .unwrap_or_else(|| "n/a".to_string())
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Fill model:     {}", self.fill_model)?;
+        writeln!(
+            f,
+            "Trades:         {} (wins {}, {:.2}%)",
+            self.trades,
+            self.wins,
+            self.win_rate() * Decimal::from(100)
+        )?;
+        writeln!(
+            f,
+            "Net PnL:        {:.4} {}",
+            self.net_pnl, self.quote_asset
+        )?;
+        match self.profit_factor {
+            Some(pf) => writeln!(f, "Profit factor:  {:.2}", pf)?,
+            None => writeln!(f, "Profit factor:  n/a (no losing trades)")?,
+        }
+        writeln!(
+            f,
+            "Sharpe:         {} (risk-free {} per trade)",
+            ratio(self.sharpe),
+            self.risk_free_rate
+        )?;
+        writeln!(f, "Sortino:        {}", ratio(self.sortino))?;
+        writeln!(
+            f,
+            "Max drawdown:   {:.4} {} ({:.2}%)",
+            self.max_drawdown,
+            self.quote_asset,
+            self.max_drawdown_pct * Decimal::from(100)
+        )?;
+        match self.avg_trade_secs {
+            Some(secs) => write!(f, "Avg trade:      {}m {}s", secs / 60, secs % 60),
+            None => write!(f, "Avg trade:      n/a"),
+        }
+    }
+}
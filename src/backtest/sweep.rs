@@ -0,0 +1,559 @@
+// src/backtest/sweep.rs
+use crate::backtest;
+use crate::backtest::report::BacktestReport;
+use crate::config::{AppConfig, StrategyConfig, TakeProfitLevel};
+use crate::types::Ticker;
+use clap::ValueEnum;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tracing::{error, info, warn};
+
+/// Чем ранжировать конфигурации
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RankBy {
+    NetPnl,
+    Sharpe,
+    Sortino,
+    // Чистый PnL на единицу максимальной просадки
+    PnlToDrawdown,
+}
+
+/// Значения параметров для перебора. Пустой список — значение из Settings.toml
+#[derive(Debug, Default)]
+pub struct SweepGrid {
+    pub rsi_period: Vec<usize>,
+    pub obi_threshold: Vec<f64>,
+    pub min_volatility: Vec<Decimal>,
+    pub trailing_callback: Vec<Decimal>,
+    // pct первого уровня take_profit_levels; без уровней в конфиге — один уровень на всю позицию
+    pub take_profit_pct: Vec<Decimal>,
+}
+
+impl SweepGrid {
+    /// Декартово произведение всех значений поверх базового конфига
+    pub fn combinations(&self, base: &StrategyConfig) -> Vec<StrategyConfig> {
+        let mut combos = vec![base.clone()];
+        expand(&mut combos, &self.rsi_period, |c, v| c.rsi_period = v);
+        expand(&mut combos, &self.obi_threshold, |c, v| c.obi_threshold = v);
+        expand(&mut combos, &self.min_volatility, |c, v| {
+            c.min_volatility = v
+        });
+        expand(&mut combos, &self.trailing_callback, |c, v| {
+            c.trailing_callback = v
+        });
+        expand(&mut combos, &self.take_profit_pct, set_take_profit_pct);
+        combos
+    }
+}
+
+/// Каждую комбинацию размножает по значениям параметра. Пустой список — параметр не трогаем
+fn expand<T: Copy>(
+    combos: &mut Vec<StrategyConfig>,
+    values: &[T],
+    set: impl Fn(&mut StrategyConfig, T),
+) {
+    if values.is_empty() {
+        return;
+    }
+    *combos = combos
+        .iter()
+        .flat_map(|combo| {
+            values.iter().map(|&value| {
+                let mut config = combo.clone();
+                set(&mut config, value);
+                config
+            })
+        })
+        .collect();
+}
+
+fn set_take_profit_pct(config: &mut StrategyConfig, pct: Decimal) {
+    match config.take_profit_levels.first_mut() {
+        Some(level) => level.pct = pct,
+        None => config.take_profit_levels.push(TakeProfitLevel {
+            pct,
+            close_fraction: Decimal::ONE,
+        }),
+    }
+}
+
+/// Значения параметра: список "10,14,20" или диапазон "start:end:step" (end включительно)
+pub fn parse_values(spec: &str) -> Result<Vec<Decimal>, String> {
+    let parse = |s: &str| {
+        Decimal::from_str(s.trim()).map_err(|e| format!("bad number {:?}: {}", s.trim(), e))
+    };
+    let parts: Vec<&str> = spec.split(':').collect();
+    match parts.as_slice() {
+        [start, end, step] => {
+            let (start, end, step) = (parse(start)?, parse(end)?, parse(step)?);
+            if step <= Decimal::ZERO || end < start {
+                return Err(format!("range {:?} needs start <= end and step > 0", spec));
+            }
+            let mut values = Vec::new();
+            let mut value = start;
+            while value <= end {
+                values.push(value);
+                value += step;
+            }
+            Ok(values)
+        }
+        [_] => spec.split(',').map(parse).collect(),
+        _ => Err(format!(
+            "expected \"a,b,c\" or \"start:end:step\", got {:?}",
+            spec
+        )),
+    }
+}
+
+/// Результат одной конфигурации: на участке перебора и на отложенном участке
+pub struct SweepRow {
+    pub config: StrategyConfig,
+    pub report: BacktestReport,
+    // None — holdout_fraction = 0
+    pub out_of_sample: Option<BacktestReport>,
+}
+
+impl SweepRow {
+    fn score(&self, rank_by: RankBy) -> f64 {
+        let report = &self.report;
+        let net_pnl = report.net_pnl.to_f64().unwrap_or_default();
+        match rank_by {
+            RankBy::NetPnl => net_pnl,
+            RankBy::Sharpe => report.sharpe.unwrap_or(f64::NEG_INFINITY),
+            RankBy::Sortino => report.sortino.unwrap_or(f64::NEG_INFINITY),
+            // Прибыль без просадки — лучше любой с просадкой
+            RankBy::PnlToDrawdown if report.max_drawdown.is_zero() => {
+                if net_pnl > 0.0 {
+                    f64::INFINITY
+                } else {
+                    net_pnl
+                }
+            }
+            RankBy::PnlToDrawdown => (report.net_pnl / report.max_drawdown)
+                .to_f64()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Делит тики на участок перебора и отложенный хвост (`holdout_fraction` от конца)
+fn split_holdout(ticks: Vec<Ticker>, holdout_fraction: Decimal) -> (Vec<Ticker>, Vec<Ticker>) {
+    let holdout = (Decimal::from(ticks.len()) * holdout_fraction)
+        .floor()
+        .to_usize()
+        .unwrap_or_default();
+    let mut in_sample = ticks;
+    let out_of_sample = in_sample.split_off(in_sample.len() - holdout);
+    (in_sample, out_of_sample)
+}
+
+/// Прогоняет все комбинации параллельно (не больше числа ядер одновременно)
+/// и возвращает их, отсортированными от лучшей к худшей. Ранжирование — только по участку
+/// перебора; отложенный хвост записи каждая конфигурация проходит отдельно, с холодного старта
+pub async fn run(
+    config: &AppConfig,
+    ticks: Vec<Ticker>,
+    grid: &SweepGrid,
+    rank_by: RankBy,
+    risk_free_rate: f64,
+) -> Vec<SweepRow> {
+    let (in_sample, out_of_sample) = split_holdout(ticks, config.sweep.holdout_fraction);
+    let ticks = Arc::new(in_sample);
+    let holdout = (!out_of_sample.is_empty()).then(|| Arc::new(out_of_sample));
+    let combos = grid.combinations(&config.strategy);
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    info!(
+        "🔬 Sweep: {} configurations over {} ticks ({} held out), {} at a time",
+        combos.len(),
+        ticks.len(),
+        holdout.as_ref().map_or(0, |h| h.len()),
+        workers
+    );
+    let permits = Arc::new(Semaphore::new(workers));
+
+    let mut handles = Vec::with_capacity(combos.len());
+    for strategy in combos {
+        let mut run_config = config.clone();
+        run_config.strategy = strategy;
+        if let Err(e) = run_config.validate() {
+            warn!("Sweep: skipping invalid combination: {}", e);
+            continue;
+        }
+        let ticks = ticks.clone();
+        let holdout = holdout.clone();
+        let permits = permits.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            let strategy = run_config.strategy.clone();
+            let starting_equity = run_config.paper.starting_balance_usdt;
+            let report = |result| BacktestReport::new(&result, starting_equity, risk_free_rate);
+            let in_sample = match backtest::run(run_config.clone(), ticks).await {
+                Ok(result) => report(result),
+                Err(e) => {
+                    error!("Sweep run failed: {}", e);
+                    return None;
+                }
+            };
+            let out_of_sample = match holdout {
+                Some(holdout) => match backtest::run(run_config, holdout).await {
+                    Ok(result) => Some(report(result)),
+                    Err(e) => {
+                        error!("Sweep out-of-sample run failed: {}", e);
+                        return None;
+                    }
+                },
+                None => None,
+            };
+            Some(SweepRow {
+                config: strategy,
+                report: in_sample,
+                out_of_sample,
+            })
+        }));
+    }
+
+    let mut rows = Vec::with_capacity(handles.len());
+    for handle in handles {
+        if let Ok(Some(row)) = handle.await {
+            rows.push(row);
+        }
+    }
+    rank(&mut rows, rank_by);
+    rows
+}
+
+/// От лучшей конфигурации к худшей
+fn rank(rows: &mut [SweepRow], rank_by: RankBy) {
+    rows.sort_by(|a, b| {
+        b.score(rank_by)
+            .partial_cmp(&a.score(rank_by))
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// Таблица лучших `top` конфигураций в stdout
+pub fn print_table(rows: &[SweepRow], top: usize) {
+    let ratio = |value: Option<f64>| {
+        value
+            .map(|v| format!("{:.2}", v))
+            .unwrap_or_else(|| "n/a".to_string())
+    };
+    let out_of_sample = |row: &SweepRow| {
+        row.out_of_sample
+            .as_ref()
+            .map(|report| report.net_pnl.round_dp(4).to_string())
+            .unwrap_or_else(|| "n/a".to_string())
+    };
+    if let Some(row) = rows.first() {
+        println!("Fill model: {}", row.report.fill_model);
+    }
+    println!(
+        "{:>4}  {:>4}  {:>8}  {:>10}  {:>10}  {:>8}  {:>7}  {:>12}  {:>7}  {:>7}  {:>7}  {:>10}  {:>12}",
+        "#",
+        "RSI",
+        "OBI",
+        "MIN_VOL",
+        "TRAIL_CB",
+        "TP",
+        "TRADES",
+        "NET_PNL",
+        "PF",
+        "SHARPE",
+        "SORTINO",
+        "MAX_DD",
+        "OOS_PNL"
+    );
+    for (rank, row) in rows.iter().take(top).enumerate() {
+        let report = &row.report;
+        let take_profit = row
+            .config
+            .take_profit_levels
+            .first()
+            .map(|level| level.pct.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:>4}  {:>4}  {:>8}  {:>10}  {:>10}  {:>8}  {:>7}  {:>12}  {:>7}  {:>7}  {:>7}  {:>10}  {:>12}",
+            rank + 1,
+            row.config.rsi_period,
+            row.config.obi_threshold,
+            row.config.min_volatility,
+            row.config.trailing_callback,
+            take_profit,
+            report.trades,
+            report.net_pnl.round_dp(4),
+            ratio(report.profit_factor.and_then(|pf| pf.to_f64())),
+            ratio(report.sharpe),
+            ratio(report.sortino),
+            report.max_drawdown.round_dp(4),
+            out_of_sample(row)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn base_strategy() -> StrategyConfig {
+        serde_json::from_value(json!({
+            "rsi_period": 14,
+            "obi_threshold": 0.3,
+            "bb_period": 20,
+            "bb_std_dev": 2.0,
+            "min_volatility": "0.0005",
+            "atr_multiplier": 2.0,
+        }))
+        .unwrap()
+    }
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn row(net_pnl: &str, sharpe: Option<f64>) -> SweepRow {
+        SweepRow {
+            config: base_strategy(),
+            report: BacktestReport {
+                fill_model: "paper".to_string(),
+                quote_asset: "USDT".to_string(),
+                trades: 10,
+                wins: 5,
+                net_pnl: dec(net_pnl),
+                profit_factor: None,
+                risk_free_rate: 0.0,
+                sharpe,
+                sortino: None,
+                max_drawdown: Decimal::ONE,
+                max_drawdown_pct: dec("0.001"),
+                avg_trade_secs: None,
+            },
+            out_of_sample: None,
+        }
+    }
+
+    #[test]
+    fn combinations_are_the_cartesian_product_over_the_base() {
+        let grid = SweepGrid {
+            rsi_period: vec![10, 14, 20],
+            trailing_callback: vec![dec("0.001"), dec("0.002")],
+            ..SweepGrid::default()
+        };
+        let combos = grid.combinations(&base_strategy());
+        let pairs: Vec<_> = combos
+            .iter()
+            .map(|c| (c.rsi_period, c.trailing_callback))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (10, dec("0.001")),
+                (10, dec("0.002")),
+                (14, dec("0.001")),
+                (14, dec("0.002")),
+                (20, dec("0.001")),
+                (20, dec("0.002")),
+            ]
+        );
+        // Не перебираемые параметры остаются из конфига
+        assert!(combos
+            .iter()
+            .all(|c| c.obi_threshold == 0.3 && c.min_volatility == dec("0.0005")));
+
+        // Пустая сетка — один прогон с конфигом как есть
+        let single = SweepGrid::default().combinations(&base_strategy());
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].rsi_period, 14);
+    }
+
+    #[test]
+    fn take_profit_pct_sets_the_first_level() {
+        let grid = SweepGrid {
+            take_profit_pct: vec![dec("0.004"), dec("0.008")],
+            ..SweepGrid::default()
+        };
+
+        // Уровней нет — один уровень на всю позицию
+        let combos = grid.combinations(&base_strategy());
+        assert_eq!(combos.len(), 2);
+        for (combo, pct) in combos.iter().zip(["0.004", "0.008"]) {
+            assert_eq!(combo.take_profit_levels.len(), 1);
+            assert_eq!(combo.take_profit_levels[0].pct, dec(pct));
+            assert_eq!(combo.take_profit_levels[0].close_fraction, Decimal::ONE);
+        }
+
+        // Уровни есть — меняется только pct первого, остальные как в конфиге
+        let mut base = base_strategy();
+        base.take_profit_levels = vec![
+            TakeProfitLevel {
+                pct: dec("0.003"),
+                close_fraction: dec("0.5"),
+            },
+            TakeProfitLevel {
+                pct: dec("0.01"),
+                close_fraction: Decimal::ONE,
+            },
+        ];
+        let combos = grid.combinations(&base);
+        assert_eq!(combos[1].take_profit_levels[0].pct, dec("0.008"));
+        assert_eq!(combos[1].take_profit_levels[0].close_fraction, dec("0.5"));
+        assert_eq!(combos[1].take_profit_levels[1].pct, dec("0.01"));
+    }
+
+    #[test]
+    fn values_are_parsed_from_lists_and_inclusive_ranges() {
+        assert_eq!(
+            parse_values("10,14, 20").unwrap(),
+            vec![dec("10"), dec("14"), dec("20")]
+        );
+        assert_eq!(
+            parse_values("0.001:0.003:0.001").unwrap(),
+            vec![dec("0.001"), dec("0.002"), dec("0.003")]
+        );
+        // Конец не попадает на шаг — последнее значение до него
+        assert_eq!(
+            parse_values("10:15:2").unwrap(),
+            vec![dec("10"), dec("12"), dec("14")]
+        );
+        assert_eq!(parse_values("5:5:1").unwrap(), vec![dec("5")]);
+
+        assert!(parse_values("10:5:1").is_err());
+        assert!(parse_values("1:5:0").is_err());
+        assert!(parse_values("1:5").is_err());
+        assert!(parse_values("10,abc").is_err());
+    }
+
+    #[test]
+    fn rows_are_ranked_best_first_by_the_chosen_objective() {
+        let mut rows = vec![row("1.5", Some(0.2)), row("-2", None), row("3", Some(0.1))];
+
+        rank(&mut rows, RankBy::NetPnl);
+        let pnl: Vec<_> = rows.iter().map(|r| r.report.net_pnl).collect();
+        assert_eq!(pnl, vec![dec("3"), dec("1.5"), dec("-2")]);
+
+        // Без Sharpe (мало сделок) — в конец
+        rank(&mut rows, RankBy::Sharpe);
+        let pnl: Vec<_> = rows.iter().map(|r| r.report.net_pnl).collect();
+        assert_eq!(pnl, vec![dec("1.5"), dec("3"), dec("-2")]);
+    }
+
+    // Конфиг прогона с заданными шагами символа (без них офлайн-прогон не стартует)
+    fn sweep_config(holdout_fraction: &str) -> AppConfig {
+        let mut config: AppConfig = serde_json::from_value(json!({
+            "api_key": "",
+            "secret_key": "",
+            "symbol": "BTCUSDT",
+            "leverage": 5,
+            "order_size_usdt": 100.0,
+            "symbol_tick_size": "0.01",
+            "symbol_step_size": "0.001",
+            "strategy": {
+                "rsi_period": 14,
+                "obi_threshold": 0.3,
+                "bb_period": 20,
+                "bb_std_dev": 2.0,
+                "min_volatility": "0",
+                "atr_multiplier": 2.0,
+            },
+        }))
+        .unwrap();
+        config.sweep.holdout_fraction = dec(holdout_fraction);
+        config
+    }
+
+    // Боковик: цена ходит синусом около 100, тик в секунду, стакан с перекосом по фазе
+    fn sideways_ticks(count: u64) -> Vec<Ticker> {
+        (0..count)
+            .map(|i| {
+                let phase = i as f64 / 90.0;
+                let price = Decimal::try_from(100.0 + phase.sin()).unwrap().round_dp(2);
+                let spread = dec("0.01");
+                let (bid_qty, ask_qty) = if phase.cos() > 0.0 {
+                    (dec("3"), Decimal::ONE)
+                } else {
+                    (Decimal::ONE, dec("3"))
+                };
+                Ticker {
+                    symbol: "BTCUSDT".to_string(),
+                    price,
+                    bid_price: price - spread,
+                    ask_price: price + spread,
+                    bid_qty,
+                    ask_qty,
+                    timestamp: 1_700_000_000_000 + i * 1_000,
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn two_by_two_sweep_gives_four_ranked_rows_with_out_of_sample() {
+        let grid = SweepGrid {
+            rsi_period: vec![7, 14],
+            obi_threshold: vec![0.2, 0.4],
+            ..SweepGrid::default()
+        };
+        let rows = run(
+            &sweep_config("0.25"),
+            sideways_ticks(4_000),
+            &grid,
+            RankBy::NetPnl,
+            0.0,
+        )
+        .await;
+
+        assert_eq!(rows.len(), 4);
+        let mut tried: Vec<_> = rows
+            .iter()
+            .map(|r| (r.config.rsi_period, r.config.obi_threshold.to_string()))
+            .collect();
+        tried.sort();
+        assert_eq!(
+            tried,
+            vec![
+                (7, "0.2".to_string()),
+                (7, "0.4".to_string()),
+                (14, "0.2".to_string()),
+                (14, "0.4".to_string()),
+            ]
+        );
+        assert!(rows
+            .windows(2)
+            .all(|pair| pair[0].report.net_pnl >= pair[1].report.net_pnl));
+        assert!(rows.iter().all(|r| r.out_of_sample.is_some()));
+    }
+
+    #[test]
+    fn holdout_is_cut_from_the_end_of_the_recording() {
+        let (in_sample, out_of_sample) = split_holdout(sideways_ticks(10), dec("0.3"));
+        assert_eq!(in_sample.len(), 7);
+        assert_eq!(out_of_sample.len(), 3);
+        assert!(in_sample.last().unwrap().timestamp < out_of_sample[0].timestamp);
+
+        let (in_sample, out_of_sample) = split_holdout(sideways_ticks(10), Decimal::ZERO);
+        assert_eq!(in_sample.len(), 10);
+        assert!(out_of_sample.is_empty());
+    }
+
+    #[test]
+    fn pnl_to_drawdown_prefers_smooth_profit() {
+        // 3 при просадке 1 лучше 4 при просадке 2; прибыль без просадки — лучше всех
+        let mut steady = row("3", None);
+        steady.report.max_drawdown = Decimal::ONE;
+        let mut bumpy = row("4", None);
+        bumpy.report.max_drawdown = dec("2");
+        let mut flawless = row("0.5", None);
+        flawless.report.max_drawdown = Decimal::ZERO;
+        let mut rows = vec![bumpy, steady, flawless];
+
+        rank(&mut rows, RankBy::PnlToDrawdown);
+        let pnl: Vec<_> = rows.iter().map(|r| r.report.net_pnl).collect();
+        assert_eq!(pnl, vec![dec("0.5"), dec("3"), dec("4")]);
+    }
+}
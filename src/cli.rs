@@ -0,0 +1,101 @@
+// src/cli.rs
+use crate::backtest::sweep::RankBy;
+use crate::config::FillModelKind;
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(name = "the_sniper", version, about = "Binance Futures scalping bot")]
+pub struct Cli {
+    /// Config file name without extension: `--config eth_swing` reads eth_swing.toml
+    #[arg(long, value_name = "NAME", default_value = "Settings")]
+    pub config: String,
+
+    /// Profile layered over the config: `--profile prod` reads Settings.prod.toml on top of Settings.toml
+    #[arg(long, value_name = "ENV")]
+    pub profile: Option<String>,
+
+    /// Replay a session recorded via `record_file` instead of connecting to Binance (paper mode)
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<String>,
+
+    /// Replay speed multiplier: 1 = real time, 10 = ten times faster, 0 = as fast as possible
+    #[arg(long, value_name = "X", default_value_t = 0.0, requires = "replay")]
+    pub replay_speed: f64,
+
+    /// Confirm real-money trading when `mode = "live"` (same as I_UNDERSTAND_LIVE_TRADING=1).
+    /// Without it the bot falls back to paper mode
+    #[arg(long)]
+    pub live: bool,
+
+    /// Run the paper engine over a recording (or a `--download` CSV) once (offline),
+    /// print the risk report and exit
+    #[arg(long, value_name = "FILE", conflicts_with = "sweep")]
+    pub backtest: Option<String>,
+
+    /// Download Binance klines into a CSV for `--backtest` and exit.
+    /// START/END: 2024-01-31, "2024-01-31 12:00", RFC 3339 or ms since epoch
+    #[arg(
+        long,
+        num_args = 5,
+        value_names = ["SYMBOL", "INTERVAL", "START", "END", "OUTFILE"],
+        conflicts_with_all = ["backtest", "sweep", "replay"]
+    )]
+    pub download: Option<Vec<String>>,
+
+    /// Fill model of `--backtest`/`--sweep`, overrides `[backtest] fill_model`.
+    /// Run optimistic and pessimistic to bound the expected result
+    #[arg(long, value_enum, value_name = "MODEL")]
+    pub fill_model: Option<FillModelKind>,
+
+    /// Risk-free return per trade (fraction) subtracted in Sharpe/Sortino of `--backtest`/`--sweep`
+    #[arg(long, value_name = "RATE", default_value_t = 0.0)]
+    pub risk_free_rate: f64,
+
+    /// Grid-search strategy parameters on a recording (paper engine, offline), print the best and exit.
+    /// Parameters not given below or in [sweep] keep their [strategy] values. The last
+    /// sweep.holdout_fraction of the recording is left out of the search for an out-of-sample check
+    #[arg(long, value_name = "FILE")]
+    pub sweep: Option<String>,
+
+    /// Sweep values for strategy.rsi_period: "10,14,20" or "start:end:step"
+    #[arg(long, value_name = "VALUES", requires = "sweep")]
+    pub rsi_period: Option<String>,
+
+    /// Sweep values for strategy.obi_threshold
+    #[arg(long, value_name = "VALUES", requires = "sweep")]
+    pub obi_threshold: Option<String>,
+
+    /// Sweep values for strategy.min_volatility
+    #[arg(long, value_name = "VALUES", requires = "sweep")]
+    pub min_volatility: Option<String>,
+
+    /// Sweep values for strategy.trailing_callback
+    #[arg(long, value_name = "VALUES", requires = "sweep")]
+    pub trailing_callback: Option<String>,
+
+    /// Sweep values for the first strategy.take_profit_levels pct
+    /// (a single full-close level if none are configured)
+    #[arg(long, value_name = "VALUES", requires = "sweep")]
+    pub take_profit_pct: Option<String>,
+
+    /// How to rank sweep results
+    #[arg(long, value_enum, default_value_t = RankBy::NetPnl, requires = "sweep")]
+    pub rank_by: RankBy,
+
+    /// How many of the best configurations to print
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "sweep")]
+    pub top: usize,
+
+    /// Convert the trade ledger to CSV at FILE, print summary stats and exit (offline)
+    #[arg(long, value_name = "FILE")]
+    pub export_trades: Option<String>,
+
+    /// Trade ledger to read for `--export-trades`
+    #[arg(
+        long,
+        value_name = "FILE",
+        default_value = "trades.jsonl",
+        requires = "export_trades"
+    )]
+    pub ledger: String,
+}
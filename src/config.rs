@@ -1,8 +1,65 @@
 // src/config.rs
 
-use config::{Config, ConfigError, File};
+use crate::types::PositionSide;
+use crate::utils::error::{BotError, Result};
+use crate::utils::fees::FeeModel;
+use crate::utils::math::{ONE_PCT, ONE_TENTH_PCT};
+use crate::utils::precision::SymbolPrecision;
+use crate::utils::retry::RetryPolicy;
+use clap::ValueEnum;
+use config::{Config, ConfigError, File, FileFormat};
 use rust_decimal::Decimal;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+// Больше повторов только задерживает вход: цена к тому времени уйдёт
+const MAX_ORDER_RETRY_ATTEMPTS: u32 = 10;
+
+// Котируемые активы для разбора символа по суффиксу. Длинные раньше коротких: FDUSD до USD
+const QUOTE_ASSETS: [&str; 12] = [
+    "USDT", "USDC", "FDUSD", "BUSD", "TUSD", "USD", "EUR", "TRY", "BRL", "BTC", "ETH", "BNB",
+];
+
+/// В какую сторону стратегии разрешено открываться
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeDirection {
+    #[default]
+    LongOnly,
+    ShortOnly,
+    Both,
+}
+
+impl TradeDirection {
+    pub fn allows(self, side: PositionSide) -> bool {
+        matches!(
+            (self, side),
+            (Self::Both, _)
+                | (Self::LongOnly, PositionSide::Long)
+                | (Self::ShortOnly, PositionSide::Short)
+        )
+    }
+}
+
+/// Уровень частичной фиксации прибыли
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct TakeProfitLevel {
+    // Прибыль от цены входа (0.004 = +0.4%; для шорта — падение на столько же)
+    pub pct: Decimal,
+    // Доля текущей позиции к закрытию (0.5 = половина). 1 — закрыть остаток
+    pub close_fraction: Decimal,
+}
+
+/// Ступень profit-lock: с прибыли profit_pct трейлинг не дальше callback от цены
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct TrailTier {
+    // Прибыль от цены входа (0.005 = +0.5%; для шорта — падение на столько же)
+    pub profit_pct: Decimal,
+    // Дистанция трейлинга в доле цены (0.0015 = 0.15%)
+    pub callback: Decimal,
+}
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct StrategyConfig {
@@ -14,27 +71,1437 @@ pub struct StrategyConfig {
     pub min_volatility: Decimal,
     // Множитель ATR для динамического стопа
     pub atr_multiplier: f64,
+    // Жёсткий стоп от цены входа (0.01 = 1%): ниже для лонга, выше для шорта
+    #[serde(default = "default_hard_stop_pct")]
+    pub hard_stop_pct: Decimal,
+    // Минимальная дистанция трейлинга от экстремума (0.001 = 0.1%), когда ATR почти нулевой
+    #[serde(default = "default_trailing_callback")]
+    pub trailing_callback: Decimal,
+    // Трейлинг взводится, только когда цена ушла в плюс на столько от входа (0.005 = 0.5%).
+    // До этого работает только жёсткий стоп. 0 — трейлинг с момента входа
+    #[serde(default)]
+    pub trail_activation_pct: Decimal,
+    // Частичные выходы по уровням прибыли (по возрастанию pct); остаток ведёт трейлинг
+    #[serde(default)]
+    pub take_profit_levels: Vec<TakeProfitLevel>,
+    // Profit-lock: ступени по возрастанию profit_pct. Трейлинг берёт самый узкий callback из
+    // достигнутых ступеней и не расширяется, даже если прибыль откатилась ниже ступени
+    #[serde(default)]
+    pub trail_tiers: Vec<TrailTier>,
+    // Прогрев индикаторов историческими 1m свечами вместо ожидания живых
+    #[serde(default)]
+    pub seed_from_history: bool,
+    // Сколько исторических свечей запросить (по умолчанию — весь прогрев). Максимум 1500
+    pub seed_candles: Option<usize>,
+    // Трейлинг по закрытиям свечей вместо каждого тика (меньше шума от теней)
+    #[serde(default)]
+    pub trail_on_candle_close: bool,
+    // Тик старше N мс (движок отстаёт) — OBI устарел, вход по нему не делаем
+    pub max_tick_age_ms: Option<u64>,
+    // MACD как подтверждение входа: лонг при растущей гистограмме, шорт при падающей
+    #[serde(default)]
+    pub macd_filter: bool,
+    #[serde(default = "default_macd_fast")]
+    pub macd_fast: usize,
+    #[serde(default = "default_macd_slow")]
+    pub macd_slow: usize,
+    #[serde(default = "default_macd_signal")]
+    pub macd_signal: usize,
+    // VWAP-реверсия: вход, когда цена на vwap_entry_std_dev σ ниже VWAP (шорт — выше).
+    // Работает вместе с входами по Bollinger/RSI, а не вместо них
+    #[serde(default)]
+    pub vwap_entry: bool,
+    // Окно VWAP в свечах
+    #[serde(default = "default_vwap_period")]
+    pub vwap_period: usize,
+    #[serde(default = "default_vwap_entry_std_dev")]
+    pub vwap_entry_std_dev: f64,
+    // Подтверждение входа дивергенцией RSI: лонг при бычьей, шорт при медвежьей
+    #[serde(default)]
+    pub divergence_filter: bool,
+    // Вершина — свеча выше divergence_pivot соседних с каждой стороны (впадина — ниже)
+    #[serde(default = "default_divergence_pivot")]
+    pub divergence_pivot: usize,
+    // Максимум свечей между двумя сравниваемыми вершинами
+    #[serde(default = "default_divergence_lookback")]
+    pub divergence_lookback: usize,
+    // long_only | short_only | both. Движок тоже не откроет запрещённую сторону
+    #[serde(default)]
+    pub trade_direction: TradeDirection,
+    // Сколько держать позицию до выхода по трейлингу. Жёсткий стоп срабатывает всегда
+    #[serde(default)]
+    pub min_hold_seconds: u64,
+    // Пауза после закрытия, в течение которой новые входы игнорируются
+    #[serde(default)]
+    pub reentry_cooldown_seconds: u64,
+    // После выхода по стопу (жёсткому или трейлингу, не по тейку) столько секунд без входов:
+    // стоп — признак движения против нас, и оно часто продолжается
+    #[serde(default)]
+    pub post_stop_cooldown_seconds: u64,
+}
+
+fn default_hard_stop_pct() -> Decimal {
+    ONE_PCT
+}
+
+fn default_trailing_callback() -> Decimal {
+    ONE_TENTH_PCT
+}
+
+fn default_macd_fast() -> usize {
+    12
+}
+
+fn default_macd_slow() -> usize {
+    26
+}
+
+fn default_macd_signal() -> usize {
+    9
+}
+
+fn default_vwap_period() -> usize {
+    20
+}
+
+fn default_vwap_entry_std_dev() -> f64 {
+    2.0
+}
+
+fn default_divergence_pivot() -> usize {
+    3
+}
+
+fn default_divergence_lookback() -> usize {
+    30
+}
+
+fn default_max_notional_bump() -> Decimal {
+    Decimal::new(5, 1)
+}
+
+fn default_order_retry_attempts() -> u32 {
+    3
+}
+
+fn default_order_retry_backoff_ms() -> u64 {
+    200
+}
+
+fn default_log_dir() -> String {
+    "logs".to_string()
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_ticker_buffer() -> usize {
+    1
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RiskConfig {
+    // Дневной лимит убытка в USDT (реализованный PnL с полуночи UTC)
+    pub max_daily_loss_usdt: Option<Decimal>,
+    // Допуск проскальзывания на входе (0.001 = 0.1%). Дальше — не догоняем рынок
+    pub max_entry_slippage: Option<Decimal>,
+    // Live: вход исполнился хуже цены сигнала больше чем на N bps (по avgPrice) —
+    // исполненный объём сразу закрывается reduce-only
+    pub max_slippage_bps: Option<Decimal>,
+    // Не входить, если спред bid/ask шире N bps (тонкая ликвидность). Выходы не блокируются
+    pub max_spread_bps: Option<Decimal>,
+    // Не открываться за N минут до выплаты funding, если позиция будет платить
+    pub funding_avoid_window_minutes: Option<u64>,
+    // Порог "сильной" ставки funding (0.0005 = 0.05%)
+    #[serde(default = "default_funding_rate_threshold")]
+    pub funding_rate_threshold: Decimal,
+    // Не больше N новых входов за одну свечу
+    pub max_entries_per_candle: Option<u32>,
+    // Не больше X USDT нового нотионала за одну свечу (лишний размер урезается)
+    pub max_entry_notional_per_candle: Option<Decimal>,
+    // Эталон для контроля депега квотируемого стейблкоина, например "USDCUSDT". Пусто — выключено
+    pub depeg_symbol: Option<String>,
+    // Допустимое отклонение эталона от 1.0 (0.01 = 1%)
+    #[serde(default = "default_depeg_threshold")]
+    pub depeg_threshold: Decimal,
+    // При депеге не только алерт, но и остановка: закрыть позицию и не входить
+    #[serde(default)]
+    pub depeg_halt: bool,
+    // Аварийный стоп: просадка equity от пика сессии (0.05 = 5%) — закрыть всё и остановиться
+    pub max_session_drawdown: Option<Decimal>,
+    // Столько отказов биржи "Margin is insufficient" (-2019) подряд — прекратить входы
+    #[serde(default = "default_max_margin_rejections")]
+    pub max_margin_rejections: u32,
+    // Dead man's switch: нет тиков N секунд при открытой позиции — закрыть по рынку и не входить
+    pub dead_man_timeout_seconds: Option<u64>,
+    // Тик дальше X от последней валидной цены (0.02 = 2%) — выброс, если не держится несколько тиков
+    pub max_tick_move_pct: Option<Decimal>,
+    // Размах цены больше X за flash_window_seconds — входы на паузу flash_halt_seconds
+    pub flash_move_pct: Option<Decimal>,
+    #[serde(default = "default_flash_window_seconds")]
+    pub flash_window_seconds: u64,
+    #[serde(default = "default_flash_halt_seconds")]
+    pub flash_halt_seconds: u64,
+    // Не больше N открытых позиций одновременно по всем символам; выходы не ограничиваются
+    pub max_concurrent_positions: Option<usize>,
+    // Не отправлять ордера чаще раза в N мс, в любую сторону: защита от стратегии, которая
+    // сыплет сигналами. Выход по жёсткому стопу не ограничивается
+    pub min_order_interval_ms: Option<u64>,
+    // Группы коррелированных символов (BTC/ETH): лимит чистой направленной экспозиции на группу
+    #[serde(default)]
+    pub correlation_groups: Vec<CorrelationGroup>,
+    // Баланс счёта в USDT ниже порога — новые входы не открываются (выходы работают).
+    // Live/dry-run — баланс с биржи (кэш на короткое время), paper — бумажный кошелёк
+    pub min_account_balance_usdt: Option<Decimal>,
+}
+
+/// Коррелированные символы считаются одной ставкой: лонг BTC и лонг ETH складываются,
+/// шорт вычитается. Вход, после которого |сумма| превысит лимит, пропускается
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorrelationGroup {
+    pub symbols: Vec<String>,
+    // Лимит |лонги - шорты| группы по нотионалу входа, USDT
+    pub max_net_exposure_usdt: Decimal,
+}
+
+fn default_flash_window_seconds() -> u64 {
+    60
+}
+
+fn default_flash_halt_seconds() -> u64 {
+    300
+}
+
+fn default_funding_rate_threshold() -> Decimal {
+    Decimal::new(5, 4)
+}
+
+fn default_max_margin_rejections() -> u32 {
+    3
+}
+
+fn default_depeg_threshold() -> Decimal {
+    Decimal::new(1, 2)
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_daily_loss_usdt: None,
+            max_entry_slippage: None,
+            max_slippage_bps: None,
+            max_spread_bps: None,
+            funding_avoid_window_minutes: None,
+            funding_rate_threshold: default_funding_rate_threshold(),
+            max_entries_per_candle: None,
+            max_entry_notional_per_candle: None,
+            depeg_symbol: None,
+            depeg_threshold: default_depeg_threshold(),
+            depeg_halt: false,
+            max_session_drawdown: None,
+            max_margin_rejections: default_max_margin_rejections(),
+            dead_man_timeout_seconds: None,
+            max_tick_move_pct: None,
+            flash_move_pct: None,
+            flash_window_seconds: default_flash_window_seconds(),
+            flash_halt_seconds: default_flash_halt_seconds(),
+            max_concurrent_positions: None,
+            min_order_interval_ms: None,
+            correlation_groups: Vec::new(),
+            min_account_balance_usdt: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PaperConfig {
+    pub starting_balance_usdt: Decimal,
+    // Фиксированное проскальзывание поверх bid/ask, в bps
+    pub slippage_bps: Decimal,
+    // Доп. bps при объёме = всей глубине лучшего уровня (линейно от qty / top_qty)
+    pub depth_impact_bps: Decimal,
+    // Исполнять только объём, доступный на лучшем уровне
+    pub partial_fills: bool,
+    // Задержка ордера до биржи: исполнение по первому тику после неё, а не по тику сигнала.
+    // 0 — мгновенно. Только paper (и бэктест/replay), в live не действует
+    pub simulated_latency_ms: u64,
+    // Тиков после задержки нет столько мс — исполнение по последней известной цене
+    pub latency_timeout_ms: u64,
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self {
+            starting_balance_usdt: Decimal::from(1000),
+            slippage_bps: Decimal::ZERO,
+            depth_impact_bps: Decimal::ZERO,
+            partial_fills: false,
+            simulated_latency_ms: 0,
+            latency_timeout_ms: 2_000,
+        }
+    }
+}
+
+/// Сетка для strategy_source = "grid": уровни от lower_price до upper_price через spacing.
+/// Лот (order_size_usdt) покупается на уровне и продаётся уровнем выше. Сетка виртуальная:
+/// заявки не стоят в стакане, уровень исполняется по стакану на тике, который его коснулся
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GridConfig {
+    pub lower_price: Decimal,
+    pub upper_price: Decimal,
+    // Шаг между уровнями в единицах цены
+    pub spacing: Decimal,
+}
+
+/// Модель исполнения бэктеста (`--backtest`, `--sweep`). Не влияет на paper-торговлю
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum FillModelKind {
+    // Как бумажный режим: bid/ask + проскальзывание секции [paper]
+    #[default]
+    Paper,
+    // По цене сигнала (close), без проскальзывания — верхняя граница результата
+    Optimistic,
+    // По open следующей свечи (но не лучше bid/ask сигнала) + slippage_bps — нижняя граница
+    Pessimistic,
+    // bid/ask сигнала + range_fraction размаха предыдущей свечи
+    Realistic,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct BacktestConfig {
+    pub fill_model: FillModelKind,
+    // Проскальзывание pessimistic поверх open следующей свечи, в bps
+    pub slippage_bps: Decimal,
+    // Доля размаха (high - low) предыдущей свечи, которую теряет realistic-исполнение
+    pub range_fraction: Decimal,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            fill_model: FillModelKind::Paper,
+            slippage_bps: Decimal::from(5),
+            range_fraction: Decimal::new(1, 1),
+        }
+    }
+}
+
+/// Перебор параметров (`--sweep`). Значения — как у флагов: "10,14,20" или "start:end:step";
+/// флаг командной строки перекрывает значение отсюда. Пусто — параметр из [strategy]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SweepConfig {
+    pub rsi_period: Option<String>,
+    pub obi_threshold: Option<String>,
+    pub min_volatility: Option<String>,
+    pub trailing_callback: Option<String>,
+    pub take_profit_pct: Option<String>,
+    // Доля записи в конце, которая в перебор не идёт: на ней каждая конфигурация
+    // проверяется вне выборки (0.3 = последние 30% тиков). 0 — без проверки
+    pub holdout_fraction: Decimal,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            rsi_period: None,
+            obi_threshold: None,
+            min_volatility: None,
+            trailing_callback: None,
+            take_profit_pct: None,
+            holdout_fraction: Decimal::new(3, 1),
+        }
+    }
+}
+
+/// Режим торговли
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingMode {
+    // Симуляция исполнения и PnL на бумажном кошельке
+    #[default]
+    Paper,
+    // Реальные ордера на бирже
+    Live,
+    // Живой путь построения ордера, но вместо отправки — только лог запроса
+    DryRun,
+}
+
+/// Откуда берутся сигналы
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategySource {
+    // Встроенный RSI/Bollinger/OBI скальпер
+    #[default]
+    Scalper,
+    // Внешний генератор сигналов (JSONL-файл external_signal_file)
+    External,
+    // Сетка уровней для бокового рынка (секция [grid])
+    Grid,
+}
+
+/// Биржа, через которую идут данные и ордера
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Exchange {
+    // USDT-M futures
+    #[default]
+    Binance,
+    // Линейные SWAP к USDT и USDC: BTCUSDT -> BTC-USDT-SWAP, BTCUSDC -> BTC-USDC-SWAP
+    Okx,
+    // Спот Advanced Trade; symbol BTCUSD превращается в BTC-USD.
+    // api_key — имя CDP-ключа, secret_key — EC-ключ в PEM
+    Coinbase,
+}
+
+/// Рынок Binance
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Market {
+    // USDT-M perpetual (fapi/fstream)
+    #[default]
+    Futures,
+    // Спот (api/stream.binance.com): без плеча, шортов, funding и user-data
+    Spot,
+}
+
+/// Из каких потоков Binance собирается Ticker
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DataStream {
+    // @bookTicker: price = mid, bid/ask и их объёмы из стакана
+    #[default]
+    BookTicker,
+    // @trade: price = последняя сделка, bid = ask = price, объёмы стакана нулевые (OBI = 0).
+    // Объём сделок идёт в свечи
+    Trade,
+    // Оба: bid/ask и объёмы из bookTicker, price — последняя сделка, объём сделок — в свечи
+    Both,
+}
+
+impl DataStream {
+    pub fn has_trades(self) -> bool {
+        self != DataStream::BookTicker
+    }
+}
+
+/// Как исполнять живые ордера
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderExecution {
+    // IOC-лимит через спред, комиссия taker
+    #[default]
+    Taker,
+    // Входы post-only (GTX) на лучшем bid/ask, комиссия maker; ордер может висеть.
+    // Выходы всё равно IOC
+    Maker,
+}
+
+/// Что делать с входом, лимитный ордер которого не исполнился за order_timeout_seconds
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderTimeoutAction {
+    // Снять и забыть: следующий вход — по новому сигналу
+    #[default]
+    Abandon,
+    // Снять и выставить заново по текущему bid/ask
+    Reprice,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub api_key: String,
     pub secret_key: String,
+    #[serde(default)]
+    pub exchange: Exchange,
+    // Только для OKX: passphrase API-ключа (APP_OKX_PASSPHRASE)
+    #[serde(default)]
+    pub okx_passphrase: String,
+    // Только для Binance: futures или spot
+    #[serde(default)]
+    pub market: Market,
+    // Только для Binance: bookTicker | trade | both (см. DataStream)
+    #[serde(default)]
+    pub data_stream: DataStream,
     pub symbol: String,
+    // Актив баланса и PnL (USDC для BTCUSDC, ETH для coin-margined ETHUSD_PERP). Пусто — из символа
+    pub quote_asset: Option<String>,
+    // Базовый актив для подписей объёма. Пусто — из символа
+    pub base_asset: Option<String>,
+    #[serde(default)]
+    pub mode: TradingMode,
+    // Binance futures testnet (testnet.binancefuture.com) / OKX demo trading — нужны отдельные ключи
+    #[serde(default)]
+    pub testnet: bool,
+    #[serde(default)]
+    pub order_execution: OrderExecution,
+    // Лимитный ордер висит дольше N секунд — снять. Пусто — ждать исполнения сколько угодно
+    pub order_timeout_seconds: Option<u64>,
+    // Вход после снятия: abandon | reprice. Выход всегда перевыставляется
+    #[serde(default)]
+    pub order_timeout_action: OrderTimeoutAction,
     pub leverage: u8,
+    // Плечо по символам: [symbol_leverage] BTCUSDT = 10. Символа нет в таблице — берётся leverage
+    #[serde(default)]
+    pub symbol_leverage: HashMap<String, u8>,
+    // Биржа поставила плечо ниже запрошенного (лимит тира) — останавливаться, а не продолжать
+    #[serde(default)]
+    pub strict_leverage: bool,
     pub order_size_usdt: f64,
-    pub symbol_step_size: Decimal,
-    pub symbol_tick_size: Decimal,
+    // Если размер чуть меньше min notional — поднять до минимума вместо пропуска
+    #[serde(default)]
+    pub bump_to_min_notional: bool,
+    // Потолок такого подъёма: не больше order_size_usdt * (1 + X). 0.5 = +50%
+    #[serde(default = "default_max_notional_bump")]
+    pub max_notional_bump: Decimal,
+    // Попыток отправки ордера при таймауте/обрыве/5xx (включая первую). Отказы биржи не повторяются
+    #[serde(default = "default_order_retry_attempts")]
+    pub order_retry_attempts: u32,
+    // Пауза перед первым повтором, мс; дальше удваивается
+    #[serde(default = "default_order_retry_backoff_ms")]
+    pub order_retry_backoff_ms: u64,
+    // Неудачных переподключений WS подряд, после которых поток бросается и движок
+    // останавливается. Пусто — переподключаться бесконечно
+    pub max_reconnect_attempts: Option<u32>,
+    // Переопределения шага объёма и цены. Пусто — фильтры символа из exchangeInfo при старте
+    pub symbol_step_size: Option<Decimal>,
+    pub symbol_tick_size: Option<Decimal>,
+    // Минимальный нотионал ордера в котируемом активе. Пусто — фильтр символа с биржи
+    pub symbol_min_notional: Option<Decimal>,
+    // Фильтры по символам, загруженные с биржи. В Settings.toml не задаются
+    #[serde(skip)]
+    pub precision: HashMap<String, SymbolPrecision>,
     pub strategy: StrategyConfig,
+    #[serde(default)]
+    pub strategy_source: StrategySource,
+    // JSONL с сигналами для strategy_source = "external"
+    pub external_signal_file: Option<String>,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    #[serde(default)]
+    pub paper: PaperConfig,
+    #[serde(default)]
+    pub grid: GridConfig,
+    #[serde(default)]
+    pub backtest: BacktestConfig,
+    #[serde(default)]
+    pub sweep: SweepConfig,
+    #[serde(default)]
+    pub fees: FeeModel,
+    // Буфер тиков WS -> движок. При переполнении выбрасываются самые старые (latest-wins)
+    #[serde(default = "default_ticker_buffer")]
+    pub ticker_buffer: usize,
+    // Адрес для Prometheus (`GET /metrics`), например "127.0.0.1:9898". Пусто — выключено
+    pub metrics_addr: Option<String>,
+    // Файл для записи сырых bookTicker-сообщений (для `--replay`). Пусто — не пишем
+    pub record_file: Option<String>,
+    // Binance: объём живых свечей из потока @aggTrade. Выключено — у живых свечей объём нулевой
+    #[serde(default)]
+    pub agg_trade_volume: bool,
+    // Журнал закрытых сделок (JSONL) для `--export-trades`. Пусто — не пишем
+    pub trade_ledger_file: Option<String>,
+    // Каталог sled-базы: позиции и последние свечи по символам, прогрев после перезапуска
+    // без запроса истории. Пусто — только файл состояния JSON
+    pub store_path: Option<String>,
+    // Discord webhook для уведомлений о сделках и остановках (APP_DISCORD_WEBHOOK_URL)
+    pub discord_webhook_url: Option<String>,
+    // Каталог логов: bot.log.YYYY-MM-DD, новый файл каждые сутки (APP_LOG_DIR)
+    #[serde(default = "default_log_dir")]
+    pub log_dir: String,
+    // error | warn | info | debug | trace (APP_LOG_LEVEL)
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    // Объединять промежуточные записи состояния (трейлинг) в окне N мс. 0 — писать каждую
+    #[serde(default)]
+    pub state_save_debounce_ms: u64,
+}
+
+/// Файлы конфига: `<name>.toml` и поверх него `<name>.<profile>.toml`.
+/// Переменные APP_* перекрывают оба
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    pub name: String,
+    pub profile: Option<String>,
+}
+
+impl ConfigSource {
+    pub fn new(name: String, profile: Option<String>) -> Self {
+        Self { name, profile }
+    }
+
+    /// Пути файлов в порядке наложения (профиль — последним)
+    pub fn files(&self) -> Vec<String> {
+        let stem = self.name.strip_suffix(".toml").unwrap_or(&self.name);
+        let mut files = vec![format!("{}.toml", stem)];
+        if let Some(profile) = &self.profile {
+            files.push(format!("{}.{}.toml", stem, profile));
+        }
+        files
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.files().join(" + "))
+    }
 }
 
 impl AppConfig {
-    pub fn new() -> Result<Self, ConfigError> {
-        let builder = Config::builder()
-            .add_source(File::with_name("Settings"))
+    pub fn load(source: &ConfigSource) -> Result<Self, ConfigError> {
+        // Явные пути: File::with_name("Settings.prod") отрезал бы ".prod" как расширение
+        let builder = source
+            .files()
+            .into_iter()
+            .fold(Config::builder(), |builder, file| {
+                builder.add_source(File::new(&file, FileFormat::Toml))
+            })
             .add_source(config::Environment::with_prefix("APP"));
 
         let config = builder.build()?;
         config.try_deserialize()
     }
+
+    /// Повторы отправки ордера движком
+    pub fn order_retry(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.order_retry_attempts,
+            Duration::from_millis(self.order_retry_backoff_ms),
+        )
+    }
+
+    /// Символы, для которых нужны фильтры биржи: основной и все из [symbol_leverage]
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols = vec![self.symbol.clone()];
+        for symbol in self.symbol_leverage.keys() {
+            // Ключи таблиц config приводит к нижнему регистру
+            let symbol = symbol.to_uppercase();
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+        symbols
+    }
+
+    /// Шаг цены и объёма символа: переопределение из Settings.toml, иначе фильтры биржи.
+    /// None — ни того, ни другого: шаги не угадываем. Min notional без обоих — 0 (не проверяется)
+    pub fn precision_for(&self, symbol: &str) -> Option<SymbolPrecision> {
+        let fetched = self.precision.get(symbol);
+        Some(SymbolPrecision {
+            tick_size: self.symbol_tick_size.or(fetched.map(|p| p.tick_size))?,
+            step_size: self.symbol_step_size.or(fetched.map(|p| p.step_size))?,
+            min_notional: self
+                .symbol_min_notional
+                .or(fetched.map(|p| p.min_notional))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Запрошенное плечо символа: symbol_leverage, иначе общее leverage
+    pub fn leverage_for(&self, symbol: &str) -> u8 {
+        // config приводит ключи таблиц к нижнему регистру: BTCUSDT приходит как btcusdt
+        self.symbol_leverage
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(symbol))
+            .map(|(_, leverage)| *leverage)
+            .unwrap_or(self.leverage)
+    }
+
+    /// Актив, в котором считаются баланс и PnL: quote_asset, иначе из символа
+    pub fn quote_asset(&self) -> String {
+        self.quote_asset
+            .clone()
+            .unwrap_or_else(|| symbol_assets(&self.symbol).1)
+    }
+
+    /// Проверяет значения конфига до старта, чтобы ошибки не всплывали глубоко в движке
+    pub fn validate(&self) -> Result<()> {
+        if self.symbol.trim().is_empty() {
+            return Err(BotError::Config("symbol must not be empty".to_string()));
+        }
+        if !(1..=125).contains(&self.leverage) {
+            return Err(BotError::Config(format!(
+                "leverage must be in 1..=125, got {}",
+                self.leverage
+            )));
+        }
+        for (symbol, leverage) in &self.symbol_leverage {
+            if !(1..=125).contains(leverage) {
+                return Err(BotError::Config(format!(
+                    "symbol_leverage.{} must be in 1..=125, got {}",
+                    symbol, leverage
+                )));
+            }
+        }
+        if self.order_size_usdt.is_nan() || self.order_size_usdt <= 0.0 {
+            return Err(BotError::Config(format!(
+                "order_size_usdt must be > 0, got {}",
+                self.order_size_usdt
+            )));
+        }
+        if !(1..=MAX_ORDER_RETRY_ATTEMPTS).contains(&self.order_retry_attempts) {
+            return Err(BotError::Config(format!(
+                "order_retry_attempts must be in 1..={}, got {}",
+                MAX_ORDER_RETRY_ATTEMPTS, self.order_retry_attempts
+            )));
+        }
+        for (name, value) in [
+            ("symbol_step_size", self.symbol_step_size),
+            ("symbol_tick_size", self.symbol_tick_size),
+        ] {
+            if let Some(value) = value.filter(|v| *v <= Decimal::ZERO) {
+                return Err(BotError::Config(format!(
+                    "{} must be > 0, got {}",
+                    name, value
+                )));
+            }
+        }
+        if let Some(value) = self.symbol_min_notional.filter(|v| *v < Decimal::ZERO) {
+            return Err(BotError::Config(format!(
+                "symbol_min_notional must be >= 0, got {}",
+                value
+            )));
+        }
+
+        let strategy = &self.strategy;
+        if strategy.rsi_period <= 1 {
+            return Err(BotError::Config(format!(
+                "strategy.rsi_period must be > 1, got {}",
+                strategy.rsi_period
+            )));
+        }
+        if strategy.bb_period <= 1 {
+            return Err(BotError::Config(format!(
+                "strategy.bb_period must be > 1, got {}",
+                strategy.bb_period
+            )));
+        }
+        if strategy.min_volatility < Decimal::ZERO {
+            return Err(BotError::Config(format!(
+                "strategy.min_volatility must be >= 0, got {}",
+                strategy.min_volatility
+            )));
+        }
+        for (name, value) in [
+            ("hard_stop_pct", strategy.hard_stop_pct),
+            ("trailing_callback", strategy.trailing_callback),
+        ] {
+            if value <= Decimal::ZERO || value >= Decimal::ONE {
+                return Err(BotError::Config(format!(
+                    "strategy.{} must be a fraction in (0, 1), got {}",
+                    name, value
+                )));
+            }
+        }
+
+        if strategy.trail_activation_pct < Decimal::ZERO
+            || strategy.trail_activation_pct >= Decimal::ONE
+        {
+            return Err(BotError::Config(format!(
+                "strategy.trail_activation_pct must be a fraction in [0, 1), got {}",
+                strategy.trail_activation_pct
+            )));
+        }
+
+        let mut previous_pct = Decimal::ZERO;
+        for (i, level) in strategy.take_profit_levels.iter().enumerate() {
+            if level.pct <= previous_pct || level.pct >= Decimal::ONE {
+                return Err(BotError::Config(format!(
+                    "strategy.take_profit_levels[{}].pct must be in (0, 1) and above the previous level, got {}",
+                    i, level.pct
+                )));
+            }
+            if level.close_fraction <= Decimal::ZERO || level.close_fraction > Decimal::ONE {
+                return Err(BotError::Config(format!(
+                    "strategy.take_profit_levels[{}].close_fraction must be in (0, 1], got {}",
+                    i, level.close_fraction
+                )));
+            }
+            previous_pct = level.pct;
+        }
+
+        for (i, tier) in strategy.trail_tiers.iter().enumerate() {
+            let above_previous = i == 0 || tier.profit_pct > strategy.trail_tiers[i - 1].profit_pct;
+            if tier.profit_pct < Decimal::ZERO || tier.profit_pct >= Decimal::ONE || !above_previous
+            {
+                return Err(BotError::Config(format!(
+                    "strategy.trail_tiers[{}].profit_pct must be in [0, 1) and above the previous tier, got {}",
+                    i, tier.profit_pct
+                )));
+            }
+            if tier.callback <= Decimal::ZERO || tier.callback >= Decimal::ONE {
+                return Err(BotError::Config(format!(
+                    "strategy.trail_tiers[{}].callback must be in (0, 1), got {}",
+                    i, tier.callback
+                )));
+            }
+        }
+
+        if let Some(limit) = self.risk.max_daily_loss_usdt {
+            if limit <= Decimal::ZERO {
+                return Err(BotError::Config(format!(
+                    "risk.max_daily_loss_usdt must be > 0, got {}",
+                    limit
+                )));
+            }
+        }
+        if self.risk.max_concurrent_positions == Some(0) {
+            return Err(BotError::Config(
+                "risk.max_concurrent_positions must be > 0".to_string(),
+            ));
+        }
+        if let Some(floor) = self.risk.min_account_balance_usdt {
+            if floor < Decimal::ZERO {
+                return Err(BotError::Config(format!(
+                    "risk.min_account_balance_usdt must be >= 0, got {}",
+                    floor
+                )));
+            }
+        }
+        for group in &self.risk.correlation_groups {
+            if group.symbols.len() < 2 {
+                return Err(BotError::Config(format!(
+                    "risk.correlation_groups: a group needs at least two symbols, got {:?}",
+                    group.symbols
+                )));
+            }
+            if group.max_net_exposure_usdt < Decimal::ZERO {
+                return Err(BotError::Config(format!(
+                    "risk.correlation_groups: max_net_exposure_usdt must be >= 0, got {}",
+                    group.max_net_exposure_usdt
+                )));
+            }
+        }
+        if let Some(slippage) = self.risk.max_entry_slippage {
+            if slippage < Decimal::ZERO {
+                return Err(BotError::Config(format!(
+                    "risk.max_entry_slippage must be >= 0, got {}",
+                    slippage
+                )));
+            }
+        }
+        if let Some(slippage) = self.risk.max_slippage_bps {
+            if slippage < Decimal::ZERO {
+                return Err(BotError::Config(format!(
+                    "risk.max_slippage_bps must be >= 0, got {}",
+                    slippage
+                )));
+            }
+        }
+        if self.strategy.macd_filter {
+            let (fast, slow, signal) = (
+                self.strategy.macd_fast,
+                self.strategy.macd_slow,
+                self.strategy.macd_signal,
+            );
+            if fast == 0 || signal == 0 || fast >= slow {
+                return Err(BotError::Config(format!(
+                    "strategy.macd_fast/slow/signal must be > 0 with fast < slow, got {}/{}/{}",
+                    fast, slow, signal
+                )));
+            }
+        }
+        if self.strategy.vwap_entry
+            && (self.strategy.vwap_period < 2 || self.strategy.vwap_entry_std_dev <= 0.0)
+        {
+            return Err(BotError::Config(format!(
+                "strategy.vwap_period must be >= 2 and vwap_entry_std_dev > 0, got {}/{}",
+                self.strategy.vwap_period, self.strategy.vwap_entry_std_dev
+            )));
+        }
+        if self.strategy.divergence_filter
+            && (self.strategy.divergence_pivot == 0
+                || self.strategy.divergence_lookback <= self.strategy.divergence_pivot)
+        {
+            return Err(BotError::Config(format!(
+                "strategy.divergence_pivot must be > 0 and divergence_lookback > pivot, got {}/{}",
+                self.strategy.divergence_pivot, self.strategy.divergence_lookback
+            )));
+        }
+        if let Some(n) = self.strategy.seed_candles {
+            if n == 0 || n > 1500 {
+                return Err(BotError::Config(format!(
+                    "strategy.seed_candles must be in 1..=1500, got {}",
+                    n
+                )));
+            }
+        }
+        if self.exchange == Exchange::Okx
+            && self.mode != TradingMode::Paper
+            && self.okx_passphrase.is_empty()
+        {
+            return Err(BotError::Config(
+                "exchange = \"okx\" requires okx_passphrase (APP_OKX_PASSPHRASE)".to_string(),
+            ));
+        }
+        if self.exchange == Exchange::Coinbase
+            && self.strategy.trade_direction != TradeDirection::LongOnly
+        {
+            return Err(BotError::Config(format!(
+                "exchange = \"coinbase\" is spot only and requires strategy.trade_direction = \"long_only\", got {:?}",
+                self.strategy.trade_direction
+            )));
+        }
+        if self.market == Market::Spot {
+            if self.exchange != Exchange::Binance {
+                return Err(BotError::Config(
+                    "market = \"spot\" is supported only for exchange = \"binance\"".to_string(),
+                ));
+            }
+            // Продать на споте можно только то, что куплено: шортов нет
+            if self.strategy.trade_direction != TradeDirection::LongOnly {
+                return Err(BotError::Config(format!(
+                    "market = \"spot\" requires strategy.trade_direction = \"long_only\", got {:?}",
+                    self.strategy.trade_direction
+                )));
+            }
+        }
+        if self.order_timeout_seconds == Some(0) {
+            return Err(BotError::Config(
+                "order_timeout_seconds must be > 0 (omit it to disable)".to_string(),
+            ));
+        }
+        if self.risk.dead_man_timeout_seconds == Some(0) {
+            return Err(BotError::Config(
+                "risk.dead_man_timeout_seconds must be > 0 (omit it to disable)".to_string(),
+            ));
+        }
+        for (name, value) in [
+            ("max_tick_move_pct", self.risk.max_tick_move_pct),
+            ("flash_move_pct", self.risk.flash_move_pct),
+        ] {
+            if let Some(value) = value.filter(|v| *v <= Decimal::ZERO || *v >= Decimal::ONE) {
+                return Err(BotError::Config(format!(
+                    "risk.{} must be a fraction in (0, 1), got {}",
+                    name, value
+                )));
+            }
+        }
+        if let (Some(tick), Some(flash)) = (self.risk.max_tick_move_pct, self.risk.flash_move_pct) {
+            if flash <= tick {
+                return Err(BotError::Config(format!(
+                    "risk.flash_move_pct ({}) must be larger than risk.max_tick_move_pct ({})",
+                    flash, tick
+                )));
+            }
+        }
+        if self.risk.flash_window_seconds == 0 {
+            return Err(BotError::Config(
+                "risk.flash_window_seconds must be > 0".to_string(),
+            ));
+        }
+        if self.strategy_source == StrategySource::External && self.external_signal_file.is_none() {
+            return Err(BotError::Config(
+                "strategy_source = \"external\" requires external_signal_file".to_string(),
+            ));
+        }
+        if self.strategy_source == StrategySource::Grid {
+            let grid = &self.grid;
+            if grid.lower_price <= Decimal::ZERO || grid.upper_price <= grid.lower_price {
+                return Err(BotError::Config(format!(
+                    "grid: need 0 < lower_price < upper_price, got {} .. {}",
+                    grid.lower_price, grid.upper_price
+                )));
+            }
+            if grid.spacing <= Decimal::ZERO || grid.spacing > grid.upper_price - grid.lower_price {
+                return Err(BotError::Config(format!(
+                    "grid.spacing must be > 0 and fit into the range, got {}",
+                    grid.spacing
+                )));
+            }
+        }
+        if self.backtest.slippage_bps < Decimal::ZERO
+            || self.backtest.range_fraction < Decimal::ZERO
+        {
+            return Err(BotError::Config(format!(
+                "backtest.slippage_bps and backtest.range_fraction must be >= 0, got {}/{}",
+                self.backtest.slippage_bps, self.backtest.range_fraction
+            )));
+        }
+        if self.sweep.holdout_fraction < Decimal::ZERO
+            || self.sweep.holdout_fraction >= Decimal::ONE
+        {
+            return Err(BotError::Config(format!(
+                "sweep.holdout_fraction must be in [0, 1), got {}",
+                self.sweep.holdout_fraction
+            )));
+        }
+        if self.max_notional_bump < Decimal::ZERO {
+            return Err(BotError::Config(format!(
+                "max_notional_bump must be >= 0, got {}",
+                self.max_notional_bump
+            )));
+        }
+        if self.risk.depeg_threshold <= Decimal::ZERO {
+            return Err(BotError::Config(format!(
+                "risk.depeg_threshold must be > 0, got {}",
+                self.risk.depeg_threshold
+            )));
+        }
+        if self.risk.max_entries_per_candle == Some(0) {
+            return Err(BotError::Config(
+                "risk.max_entries_per_candle must be > 0".to_string(),
+            ));
+        }
+        if let Some(notional) = self.risk.max_entry_notional_per_candle {
+            if notional <= Decimal::ZERO {
+                return Err(BotError::Config(format!(
+                    "risk.max_entry_notional_per_candle must be > 0, got {}",
+                    notional
+                )));
+            }
+        }
+        for (name, asset) in [
+            ("quote_asset", &self.quote_asset),
+            ("base_asset", &self.base_asset),
+        ] {
+            if asset.as_deref().is_some_and(|a| a.trim().is_empty()) {
+                return Err(BotError::Config(format!("{} must not be empty", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// (базовый актив, актив расчётов) символа: BTCUSDC -> (BTC, USDC), BTC-USDT-SWAP -> (BTC, USDT).
+/// Контракты к USD (ETHUSD_PERP, BTC-USD-SWAP) coin-margined: маржа и PnL в базовой монете.
+/// Неизвестный суффикс — USDT, как было до появления настройки
+pub fn symbol_assets(symbol: &str) -> (String, String) {
+    let symbol = symbol.to_ascii_uppercase();
+    let (pair, contract) = match symbol.split_once('_') {
+        Some((pair, _)) => (pair.to_string(), true),
+        None => {
+            let mut parts = symbol.split('-');
+            match (parts.next(), parts.next()) {
+                (Some(base), Some(quote)) => {
+                    let contract = parts.next().is_some();
+                    return settlement(base, quote, contract);
+                }
+                _ => (symbol.clone(), false),
+            }
+        }
+    };
+    QUOTE_ASSETS
+        .iter()
+        .find_map(|quote| {
+            pair.strip_suffix(quote)
+                .filter(|base| !base.is_empty())
+                .map(|base| settlement(base, quote, contract))
+        })
+        .unwrap_or_else(|| (pair.clone(), "USDT".to_string()))
+}
+
+fn settlement(base: &str, quote: &str, contract: bool) -> (String, String) {
+    let settle = if contract && quote == "USD" {
+        base
+    } else {
+        quote
+    };
+    (base.to_string(), settle.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn valid_config() -> AppConfig {
+        serde_json::from_value(json!({
+            "api_key": "key",
+            "secret_key": "secret",
+            "symbol": "BTCUSDT",
+            "leverage": 5,
+            "order_size_usdt": 100.0,
+            "strategy": {
+                "rsi_period": 14,
+                "obi_threshold": 0.3,
+                "bb_period": 20,
+                "bb_std_dev": 2.0,
+                "min_volatility": "0.0005",
+                "atr_multiplier": 2.0,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn validate_accepts_minimal_config() {
+        valid_config().validate().unwrap();
+    }
+
+    type BreakConfig = fn(&mut AppConfig);
+
+    #[test]
+    fn validate_rejects_bad_configs() {
+        // (что ломаем, как, фрагмент ожидаемой ошибки)
+        let cases: Vec<(&str, BreakConfig, &str)> = vec![
+            (
+                "empty symbol",
+                |c| c.symbol = " ".to_string(),
+                "symbol must not be empty",
+            ),
+            (
+                "zero leverage",
+                |c| c.leverage = 0,
+                "leverage must be in 1..=125",
+            ),
+            (
+                "leverage above 125",
+                |c| c.leverage = 126,
+                "leverage must be in 1..=125",
+            ),
+            (
+                "symbol leverage",
+                |c| {
+                    c.symbol_leverage.insert("ethusdt".to_string(), 0);
+                },
+                "symbol_leverage.ethusdt",
+            ),
+            (
+                "zero order size",
+                |c| c.order_size_usdt = 0.0,
+                "order_size_usdt",
+            ),
+            (
+                "NaN order size",
+                |c| c.order_size_usdt = f64::NAN,
+                "order_size_usdt",
+            ),
+            (
+                "no order attempts",
+                |c| c.order_retry_attempts = 0,
+                "order_retry_attempts",
+            ),
+            (
+                "too many order attempts",
+                |c| c.order_retry_attempts = MAX_ORDER_RETRY_ATTEMPTS + 1,
+                "order_retry_attempts",
+            ),
+            (
+                "zero step size",
+                |c| c.symbol_step_size = Some(Decimal::ZERO),
+                "symbol_step_size",
+            ),
+            (
+                "negative tick size",
+                |c| c.symbol_tick_size = Some(dec("-0.1")),
+                "symbol_tick_size",
+            ),
+            (
+                "negative min notional",
+                |c| c.symbol_min_notional = Some(dec("-5")),
+                "symbol_min_notional",
+            ),
+            (
+                "RSI period 1",
+                |c| c.strategy.rsi_period = 1,
+                "strategy.rsi_period",
+            ),
+            (
+                "BB period 1",
+                |c| c.strategy.bb_period = 1,
+                "strategy.bb_period",
+            ),
+            (
+                "negative min volatility",
+                |c| c.strategy.min_volatility = dec("-0.001"),
+                "strategy.min_volatility",
+            ),
+            (
+                "zero hard stop",
+                |c| c.strategy.hard_stop_pct = Decimal::ZERO,
+                "hard_stop_pct",
+            ),
+            (
+                "trailing callback of 100%",
+                |c| c.strategy.trailing_callback = Decimal::ONE,
+                "trailing_callback",
+            ),
+            (
+                "trail activation of 100%",
+                |c| c.strategy.trail_activation_pct = Decimal::ONE,
+                "trail_activation_pct",
+            ),
+            (
+                "take-profit levels out of order",
+                |c| {
+                    c.strategy.take_profit_levels = vec![
+                        TakeProfitLevel {
+                            pct: dec("0.01"),
+                            close_fraction: dec("0.5"),
+                        },
+                        TakeProfitLevel {
+                            pct: dec("0.005"),
+                            close_fraction: dec("0.5"),
+                        },
+                    ]
+                },
+                "take_profit_levels[1].pct",
+            ),
+            (
+                "take-profit closes nothing",
+                |c| {
+                    c.strategy.take_profit_levels = vec![TakeProfitLevel {
+                        pct: dec("0.01"),
+                        close_fraction: Decimal::ZERO,
+                    }]
+                },
+                "take_profit_levels[0].close_fraction",
+            ),
+            (
+                "trail tiers out of order",
+                |c| {
+                    c.strategy.trail_tiers = vec![
+                        TrailTier {
+                            profit_pct: dec("0.01"),
+                            callback: dec("0.002"),
+                        },
+                        TrailTier {
+                            profit_pct: dec("0.01"),
+                            callback: dec("0.001"),
+                        },
+                    ]
+                },
+                "trail_tiers[1].profit_pct",
+            ),
+            (
+                "trail tier without callback",
+                |c| {
+                    c.strategy.trail_tiers = vec![TrailTier {
+                        profit_pct: dec("0.01"),
+                        callback: Decimal::ZERO,
+                    }]
+                },
+                "trail_tiers[0].callback",
+            ),
+            (
+                "zero daily loss limit",
+                |c| c.risk.max_daily_loss_usdt = Some(Decimal::ZERO),
+                "risk.max_daily_loss_usdt",
+            ),
+            (
+                "zero concurrent positions",
+                |c| c.risk.max_concurrent_positions = Some(0),
+                "risk.max_concurrent_positions",
+            ),
+            (
+                "negative balance floor",
+                |c| c.risk.min_account_balance_usdt = Some(dec("-1")),
+                "risk.min_account_balance_usdt",
+            ),
+            (
+                "correlation group of one symbol",
+                |c| {
+                    c.risk.correlation_groups = vec![CorrelationGroup {
+                        symbols: vec!["BTCUSDT".to_string()],
+                        max_net_exposure_usdt: Decimal::from(100),
+                    }]
+                },
+                "at least two symbols",
+            ),
+            (
+                "negative correlated exposure",
+                |c| {
+                    c.risk.correlation_groups = vec![CorrelationGroup {
+                        symbols: vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()],
+                        max_net_exposure_usdt: dec("-1"),
+                    }]
+                },
+                "max_net_exposure_usdt",
+            ),
+            (
+                "negative entry slippage",
+                |c| c.risk.max_entry_slippage = Some(dec("-0.001")),
+                "risk.max_entry_slippage",
+            ),
+            (
+                "negative fill slippage",
+                |c| c.risk.max_slippage_bps = Some(dec("-1")),
+                "risk.max_slippage_bps",
+            ),
+            (
+                "MACD fast not below slow",
+                |c| {
+                    c.strategy.macd_filter = true;
+                    c.strategy.macd_fast = 26;
+                },
+                "strategy.macd_fast/slow/signal",
+            ),
+            (
+                "VWAP period 1",
+                |c| {
+                    c.strategy.vwap_entry = true;
+                    c.strategy.vwap_period = 1;
+                },
+                "strategy.vwap_period",
+            ),
+            (
+                "divergence lookback within pivot",
+                |c| {
+                    c.strategy.divergence_filter = true;
+                    c.strategy.divergence_lookback = c.strategy.divergence_pivot;
+                },
+                "strategy.divergence_pivot",
+            ),
+            (
+                "zero seed candles",
+                |c| c.strategy.seed_candles = Some(0),
+                "strategy.seed_candles",
+            ),
+            (
+                "too many seed candles",
+                |c| c.strategy.seed_candles = Some(1501),
+                "strategy.seed_candles",
+            ),
+            (
+                "OKX live without passphrase",
+                |c| {
+                    c.exchange = Exchange::Okx;
+                    c.mode = TradingMode::Live;
+                },
+                "okx_passphrase",
+            ),
+            (
+                "Coinbase shorts",
+                |c| {
+                    c.exchange = Exchange::Coinbase;
+                    c.strategy.trade_direction = TradeDirection::Both;
+                },
+                "exchange = \"coinbase\" is spot only",
+            ),
+            (
+                "spot on OKX",
+                |c| {
+                    c.exchange = Exchange::Okx;
+                    c.okx_passphrase = "pass".to_string();
+                    c.market = Market::Spot;
+                },
+                "supported only for exchange = \"binance\"",
+            ),
+            (
+                "spot shorts",
+                |c| {
+                    c.market = Market::Spot;
+                    c.strategy.trade_direction = TradeDirection::ShortOnly;
+                },
+                "market = \"spot\" requires",
+            ),
+            (
+                "zero order timeout",
+                |c| c.order_timeout_seconds = Some(0),
+                "order_timeout_seconds",
+            ),
+            (
+                "zero dead man timeout",
+                |c| c.risk.dead_man_timeout_seconds = Some(0),
+                "risk.dead_man_timeout_seconds",
+            ),
+            (
+                "tick move of 100%",
+                |c| c.risk.max_tick_move_pct = Some(Decimal::ONE),
+                "risk.max_tick_move_pct",
+            ),
+            (
+                "flash move within tick move",
+                |c| {
+                    c.risk.max_tick_move_pct = Some(dec("0.02"));
+                    c.risk.flash_move_pct = Some(dec("0.01"));
+                },
+                "must be larger than risk.max_tick_move_pct",
+            ),
+            (
+                "zero flash window",
+                |c| c.risk.flash_window_seconds = 0,
+                "risk.flash_window_seconds",
+            ),
+            (
+                "external source without file",
+                |c| c.strategy_source = StrategySource::External,
+                "external_signal_file",
+            ),
+            (
+                "grid with inverted range",
+                |c| {
+                    c.strategy_source = StrategySource::Grid;
+                    c.grid.lower_price = Decimal::from(200);
+                    c.grid.upper_price = Decimal::from(100);
+                    c.grid.spacing = Decimal::from(10);
+                },
+                "lower_price < upper_price",
+            ),
+            (
+                "grid spacing wider than range",
+                |c| {
+                    c.strategy_source = StrategySource::Grid;
+                    c.grid.lower_price = Decimal::from(100);
+                    c.grid.upper_price = Decimal::from(110);
+                    c.grid.spacing = Decimal::from(20);
+                },
+                "grid.spacing",
+            ),
+            (
+                "negative backtest slippage",
+                |c| c.backtest.slippage_bps = dec("-1"),
+                "backtest.slippage_bps",
+            ),
+            (
+                "whole recording held out",
+                |c| c.sweep.holdout_fraction = Decimal::ONE,
+                "sweep.holdout_fraction",
+            ),
+            (
+                "negative notional bump",
+                |c| c.max_notional_bump = dec("-0.1"),
+                "max_notional_bump",
+            ),
+            (
+                "zero depeg threshold",
+                |c| c.risk.depeg_threshold = Decimal::ZERO,
+                "risk.depeg_threshold",
+            ),
+            (
+                "zero entries per candle",
+                |c| c.risk.max_entries_per_candle = Some(0),
+                "risk.max_entries_per_candle",
+            ),
+            (
+                "zero notional per candle",
+                |c| c.risk.max_entry_notional_per_candle = Some(Decimal::ZERO),
+                "risk.max_entry_notional_per_candle",
+            ),
+            (
+                "empty quote asset",
+                |c| c.quote_asset = Some(String::new()),
+                "quote_asset",
+            ),
+            (
+                "empty base asset",
+                |c| c.base_asset = Some(" ".to_string()),
+                "base_asset",
+            ),
+        ];
+
+        for (name, break_config, expected) in cases {
+            let mut config = valid_config();
+            break_config(&mut config);
+            match config.validate() {
+                Ok(()) => panic!("{}: config accepted", name),
+                Err(e) => assert!(
+                    e.to_string().contains(expected),
+                    "{}: expected {:?} in {:?}",
+                    name,
+                    expected,
+                    e.to_string()
+                ),
+            }
+        }
+    }
 }
@@ -0,0 +1,79 @@
+// src/config_watcher.rs
+use crate::config::{AppConfig, ConfigSource, StrategyConfig};
+use anyhow::{Context, Result};
+use notify::{Event, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+// Редакторы пишут файл в несколько приёмов: ждём, пока события утихнут
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// Следит за файлами конфига (база и профиль) и при изменении отдаёт заново прочитанный
+/// `StrategyConfig`. Невалидный конфиг в канал не попадает — остаются прежние параметры.
+pub fn watch_strategy_config(source: ConfigSource) -> Result<mpsc::Receiver<StrategyConfig>> {
+    let files: Vec<PathBuf> = source.files().into_iter().map(PathBuf::from).collect();
+    let file_names = files
+        .iter()
+        .map(|file| file.file_name().map(|name| name.to_os_string()))
+        .collect::<Option<Vec<_>>>()
+        .context("Config path has no file name")?;
+    // Следим за каталогом: многие редакторы сохраняют через rename, и watch на сам файл теряется.
+    // Профиль лежит рядом с базовым файлом
+    let dir = match files[0].parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel::<()>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        if let Ok(event) = res {
+            let touches_config = event.paths.iter().any(|p| {
+                p.file_name()
+                    .is_some_and(|name| file_names.iter().any(|f| f == name))
+            });
+            if touches_config && (event.kind.is_modify() || event.kind.is_create()) {
+                let _ = event_tx.send(());
+            }
+        }
+    })
+    .context("Failed to create config watcher")?;
+    watcher
+        .watch(Path::new(&dir), RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+    let (sender, receiver) = mpsc::channel(4);
+    info!("👀 Watching {} for strategy changes", source);
+
+    tokio::spawn(async move {
+        // watcher живёт, пока жива задача
+        let _watcher = watcher;
+        while event_rx.recv().await.is_some() {
+            sleep(SETTLE_DELAY).await;
+            while event_rx.try_recv().is_ok() {}
+
+            let reloaded = AppConfig::load(&source)
+                .map_err(anyhow::Error::from)
+                .and_then(|config| {
+                    config
+                        .validate()
+                        .map(|_| config)
+                        .map_err(anyhow::Error::from)
+                });
+            match reloaded {
+                Ok(config) => {
+                    if sender.send(config.strategy).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => error!(
+                    "⚠️ Config reload rejected, keeping current settings: {:#}",
+                    e
+                ),
+            }
+        }
+    });
+
+    Ok(receiver)
+}
@@ -1,32 +1,115 @@
-use crate::connectors::messages::BookTickerEvent;
-use crate::connectors::traits::{ExecutionHandler, StreamClient};
-use crate::types::{OrderResponse, Side, Ticker};
-use anyhow::{anyhow, Context, Result};
+use crate::config::{symbol_assets, DataStream, Market};
+use crate::connectors::messages::{AggTradeEvent, MarketEvent, TickerMerger, UserDataEvent};
+use crate::connectors::parse_stats::ParseFailureMonitor;
+use crate::connectors::recorder::Recorder;
+use crate::connectors::traits::{ExecutionHandler, HistoryProvider, StreamClient};
+use crate::connectors::ws_status::WsStatusReporter;
+use crate::metrics::METRICS;
+use crate::types::{
+    AccountEvent, ExchangePosition, FundingInfo, Kline, OrderRequest, OrderResponse, OrderStatus,
+    OrderType, Side, Ticker, TimeInForce,
+};
+use crate::utils::error::{BotError, Result};
+use crate::utils::fees::FeeModel;
+use crate::utils::precision::SymbolPrecision;
+use crate::utils::ring_channel::RingSender;
+use crate::utils::serde_decimal;
+use crate::utils::trade_volume::TradeVolume;
 use async_trait::async_trait;
 use chrono::Utc;
 use futures_util::StreamExt;
 use hmac::{Hmac, Mac};
-use reqwest::{Client, Method};
-use rust_decimal::prelude::*;
+use reqwest::{Client, Method, Response};
 use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio::time::{Duration, Instant};
 use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
 use tracing::{error, info, warn};
 use url::Url;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// Вместо error_for_status: не выбрасываем тело ошибки, а поднимаем код и сообщение Binance
+#[async_trait]
+trait BinanceResponseExt: Sized {
+    async fn binance_ok(self) -> Result<Self>;
+
+    /// `binance_ok` и разбор тела. Ошибка называет поле: `assets[0].walletBalance: ...`
+    async fn binance_json<T: DeserializeOwned>(self) -> Result<T>;
+}
+
+#[async_trait]
+impl BinanceResponseExt for Response {
+    async fn binance_ok(self) -> Result<Self> {
+        let status = self.status();
+        if status.is_success() {
+            return Ok(self);
+        }
+        let body = self.text().await.unwrap_or_default();
+        Err(BotError::from_binance_body(status.as_u16(), &body))
+    }
+
+    async fn binance_json<T: DeserializeOwned>(self) -> Result<T> {
+        let body = self.binance_ok().await?.text().await?;
+        parse_response(&body)
+    }
+}
+
+fn parse_response<T: DeserializeOwned>(body: &str) -> Result<T> {
+    let mut deserializer = serde_json::Deserializer::from_str(body);
+    serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        BotError::Parse(format!(
+            "Binance response field `{}`: {}",
+            e.path(),
+            e.inner()
+        ))
+    })
+}
+
+const MAINNET_REST_URL: &str = "https://fapi.binance.com";
+const MAINNET_WS_URL: &str = "wss://fstream.binance.com";
+// Testnet фьючерсов: отдельные ключи, ненастоящие средства
+const TESTNET_REST_URL: &str = "https://testnet.binancefuture.com";
+const TESTNET_WS_URL: &str = "wss://fstream.binancefuture.com";
+const SPOT_REST_URL: &str = "https://api.binance.com";
+const SPOT_WS_URL: &str = "wss://stream.binance.com:9443";
+const SPOT_TESTNET_REST_URL: &str = "https://testnet.binance.vision";
+const SPOT_TESTNET_WS_URL: &str = "wss://testnet.binance.vision";
+
+// listenKey живёт 60 минут без продления
+const LISTEN_KEY_KEEPALIVE: std::time::Duration = std::time::Duration::from_secs(50 * 60);
+// Как часто напоминать в логе об отставании движка от потока тиков
+const DROP_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
 #[derive(Clone)]
 pub struct BinanceClient {
     api_key: String,
     secret_key: String,
     http_client: Client,
     base_rest_url: String,
-    // Exchange Info cache
-    tick_size: Decimal,
-    step_size: Decimal,
+    base_ws_url: String,
+    market: Market,
+    testnet: bool,
+    // Потоки, из которых собирается Ticker
+    data_stream: DataStream,
+    // Exchange Info cache: фильтры и базовый актив (BTC для BTCUSDT) по символам
+    precision: HashMap<String, SymbolPrecision>,
+    base_assets: HashMap<String, String>,
+    // Запись сырых WS-сообщений на диск (record mode)
+    recorder: Option<Recorder>,
+    ws_status: WsStatusReporter,
+    // Задача потока котировок из subscribe_ticker (снимается при смене символа)
+    ws_task: Option<AbortHandle>,
+    // Объём свечей: из @trade (data_stream = trade | both) или отдельной подписки на @aggTrade
+    trade_volume: Option<Arc<TradeVolume>>,
+    agg_trade_task: Option<AbortHandle>,
 }
 
 impl BinanceClient {
@@ -35,14 +118,90 @@ impl BinanceClient {
             api_key,
             secret_key,
             http_client: Client::new(),
-            base_rest_url: "https://fapi.binance.com".to_string(),
-            tick_size: Decimal::new(1, 2), // Default 0.01
-            step_size: Decimal::new(1, 3), // Default 0.001
+            base_rest_url: MAINNET_REST_URL.to_string(),
+            base_ws_url: MAINNET_WS_URL.to_string(),
+            market: Market::Futures,
+            testnet: false,
+            data_stream: DataStream::BookTicker,
+            precision: HashMap::new(),
+            base_assets: HashMap::new(),
+            recorder: None,
+            ws_status: WsStatusReporter::default(),
+            ws_task: None,
+            trade_volume: None,
+            agg_trade_task: None,
         }
     }
 
-    /// Fetches exchange info to get real Precision/tickSize/stepSize
-    pub async fn fetch_exchange_info(&mut self, symbol: &str) -> Result<()> {
+    /// Состояние WS-потока котировок -> индикатор в TUI; исчерпанные переподключения -> движок
+    pub fn with_ws_status(mut self, ws_status: WsStatusReporter) -> Self {
+        self.ws_status = ws_status;
+        self
+    }
+
+    pub fn with_data_stream(mut self, data_stream: DataStream) -> Self {
+        self.data_stream = data_stream;
+        self
+    }
+
+    /// Копить объём сделок по свечам: из `@trade`, если он уже в data_stream, иначе
+    /// отдельной подпиской на `@aggTrade`
+    pub fn with_trade_volume(mut self, volume: Arc<TradeVolume>) -> Self {
+        self.trade_volume = Some(volume);
+        self
+    }
+
+    /// REST и WebSocket testnet вместо боевых адресов
+    pub fn with_testnet(mut self) -> Self {
+        self.testnet = true;
+        self.apply_endpoints();
+        self
+    }
+
+    /// Спот вместо фьючерсов: другие хосты и пути REST. Funding, плечо и user-data остаются
+    /// только фьючерсными
+    pub fn with_market(mut self, market: Market) -> Self {
+        self.market = market;
+        self.apply_endpoints();
+        self
+    }
+
+    fn apply_endpoints(&mut self) {
+        let (rest, ws) = match (self.market, self.testnet) {
+            (Market::Futures, false) => (MAINNET_REST_URL, MAINNET_WS_URL),
+            (Market::Futures, true) => (TESTNET_REST_URL, TESTNET_WS_URL),
+            (Market::Spot, false) => (SPOT_REST_URL, SPOT_WS_URL),
+            (Market::Spot, true) => (SPOT_TESTNET_REST_URL, SPOT_TESTNET_WS_URL),
+        };
+        self.base_rest_url = rest.to_string();
+        self.base_ws_url = ws.to_string();
+    }
+
+    /// Путь REST для текущего рынка
+    fn endpoint(&self, futures: &'static str, spot: &'static str) -> &'static str {
+        match self.market {
+            Market::Futures => futures,
+            Market::Spot => spot,
+        }
+    }
+
+    /// Every raw bookTicker message received by `subscribe_ticker` is also written to the recorder
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Фильтры символа из последнего успешного `fetch_exchange_info`
+    pub fn precision(&self, symbol: &str) -> Option<SymbolPrecision> {
+        self.precision.get(symbol).copied()
+    }
+
+    /// Fetches exchange info to get real tickSize/stepSize for every symbol.
+    /// Один запрос на все символы: exchangeInfo отдаёт весь рынок
+    pub async fn fetch_exchange_info(
+        &mut self,
+        symbols: &[String],
+    ) -> Result<HashMap<String, SymbolPrecision>> {
         #[derive(Deserialize)]
         struct ExchangeInfo {
             symbols: Vec<SymbolInfo>,
@@ -50,50 +209,285 @@ impl BinanceClient {
         #[derive(Deserialize)]
         struct SymbolInfo {
             symbol: String,
+            #[serde(rename = "baseAsset")]
+            base_asset: String,
+            // TRADING, иначе символ делистнут или на паузе
+            #[serde(default)]
+            status: String,
             filters: Vec<serde_json::Value>,
         }
 
-        info!("🔍 Fetching Exchange Info for {}...", symbol);
+        info!("🔍 Fetching Exchange Info for {}...", symbols.join(", "));
         let resp: ExchangeInfo = self
             .http_client
-            .get(format!("{}/fapi/v1/exchangeInfo", self.base_rest_url))
+            .get(format!(
+                "{}{}",
+                self.base_rest_url,
+                self.endpoint("/fapi/v1/exchangeInfo", "/api/v3/exchangeInfo")
+            ))
             .send()
             .await?
-            .json()
+            .binance_json()
             .await?;
 
-        let symbol_info = resp
-            .symbols
-            .into_iter()
-            .find(|s| s.symbol == symbol)
-            .ok_or_else(|| anyhow!("Symbol {} not found in exchange info", symbol))?;
-
-        for filter in symbol_info.filters {
-            if let Some(filter_type) = filter.get("filterType").and_then(|v| v.as_str()) {
-                match filter_type {
-                    "PRICE_FILTER" => {
-                        if let Some(tick) = filter.get("tickSize").and_then(|v| v.as_str()) {
-                            self.tick_size = Decimal::from_str(tick).unwrap_or(self.tick_size);
-                            info!("✅ Set Tick Size: {}", self.tick_size);
-                        }
-                    }
-                    "LOT_SIZE" => {
-                        if let Some(step) = filter.get("stepSize").and_then(|v| v.as_str()) {
-                            self.step_size = Decimal::from_str(step).unwrap_or(self.step_size);
-                            info!("✅ Set Step Size: {}", self.step_size);
-                        }
+        for symbol_info in resp.symbols {
+            if !symbols.contains(&symbol_info.symbol) {
+                continue;
+            }
+            if !symbol_info.status.is_empty() && symbol_info.status != "TRADING" {
+                return Err(BotError::Config(format!(
+                    "Symbol {} is not trading (status {})",
+                    symbol_info.symbol, symbol_info.status
+                )));
+            }
+            let (mut tick_size, mut step_size, mut min_notional) = (None, None, None);
+            for filter in &symbol_info.filters {
+                // Нет поля или ноль — как нет фильтра; нечисло — ошибка с именем поля
+                let field = |name: &str| -> Result<Option<Decimal>> {
+                    let Some(value) = filter.get(name) else {
+                        return Ok(None);
+                    };
+                    let value = serde_decimal::deserialize(value).map_err(|e| {
+                        BotError::Parse(format!(
+                            "Symbol {}: exchange info field `{}`: {}",
+                            symbol_info.symbol, name, e
+                        ))
+                    })?;
+                    Ok(Some(value).filter(|v| !v.is_zero()))
+                };
+                match filter.get("filterType").and_then(|v| v.as_str()) {
+                    Some("PRICE_FILTER") => tick_size = field("tickSize")?,
+                    Some("LOT_SIZE") => step_size = field("stepSize")?,
+                    // Фьючерсы: MIN_NOTIONAL.notional; спот: NOTIONAL.minNotional
+                    // (у старых спотовых символов — MIN_NOTIONAL.minNotional)
+                    Some("MIN_NOTIONAL") => {
+                        min_notional = field("notional")?.or(field("minNotional")?)
                     }
+                    Some("NOTIONAL") => min_notional = field("minNotional")?,
                     _ => {}
                 }
             }
+            // Шаги по умолчанию наугад: ордера с ними биржа отклонит или округлит не туда
+            let (Some(tick_size), Some(step_size)) = (tick_size, step_size) else {
+                let missing = match (tick_size, step_size) {
+                    (None, None) => "PRICE_FILTER and LOT_SIZE",
+                    (None, _) => "PRICE_FILTER",
+                    _ => "LOT_SIZE",
+                };
+                return Err(BotError::Config(format!(
+                    "Symbol {}: exchange info has no {} filter",
+                    symbol_info.symbol, missing
+                )));
+            };
+            let precision = SymbolPrecision {
+                tick_size,
+                step_size,
+                min_notional: min_notional.unwrap_or_default(),
+            };
+            info!(
+                "✅ {}: tick size {}, step size {}, min notional {}",
+                symbol_info.symbol,
+                precision.tick_size,
+                precision.step_size,
+                precision.min_notional
+            );
+            self.base_assets
+                .insert(symbol_info.symbol.clone(), symbol_info.base_asset);
+            self.precision.insert(symbol_info.symbol, precision);
         }
+
+        if let Some(missing) = symbols.iter().find(|s| !self.precision.contains_key(*s)) {
+            return Err(BotError::Config(format!(
+                "Symbol {} not found in exchange info",
+                missing
+            )));
+        }
+        Ok(self.precision.clone())
+    }
+
+    /// Current funding rate and next funding timestamp for a perpetual
+    pub async fn fetch_funding_rate(&self, symbol: &str) -> Result<FundingInfo> {
+        #[derive(Deserialize)]
+        struct PremiumIndex {
+            #[serde(rename = "lastFundingRate", with = "serde_decimal")]
+            last_funding_rate: Decimal,
+            #[serde(rename = "nextFundingTime")]
+            next_funding_time: u64,
+        }
+
+        let resp: PremiumIndex = self
+            .http_client
+            .get(format!("{}/fapi/v1/premiumIndex", self.base_rest_url))
+            .query(&[("symbol", symbol)])
+            .send()
+            .await?
+            .binance_json()
+            .await?;
+
+        Ok(FundingInfo {
+            rate: resp.last_funding_rate,
+            next_funding_time: resp.next_funding_time,
+        })
+    }
+
+    /// Last traded price of a symbol on the client's market (public endpoint)
+    pub async fn fetch_price(&self, symbol: &str) -> Result<Decimal> {
+        #[derive(Deserialize)]
+        struct TickerPrice {
+            #[serde(with = "serde_decimal")]
+            price: Decimal,
+        }
+
+        let resp: TickerPrice = self
+            .http_client
+            .get(format!(
+                "{}{}",
+                self.base_rest_url,
+                self.endpoint("/fapi/v1/ticker/price", "/api/v3/ticker/price")
+            ))
+            .query(&[("symbol", symbol)])
+            .send()
+            .await?
+            .binance_json()
+            .await?;
+
+        Ok(resp.price)
+    }
+
+    /// Creates (or returns the active) user-data stream listenKey
+    pub async fn create_listen_key(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct ListenKey {
+            #[serde(rename = "listenKey")]
+            listen_key: String,
+        }
+
+        let resp: ListenKey = self
+            .http_client
+            .post(format!("{}/fapi/v1/listenKey", self.base_rest_url))
+            .header("X-MBX-APIKEY", &self.api_key)
+            .send()
+            .await?
+            .binance_json()
+            .await?;
+        Ok(resp.listen_key)
+    }
+
+    /// Extends the listenKey validity by 60 minutes
+    pub async fn keepalive_listen_key(&self) -> Result<()> {
+        self.http_client
+            .put(format!("{}/fapi/v1/listenKey", self.base_rest_url))
+            .header("X-MBX-APIKEY", &self.api_key)
+            .send()
+            .await?
+            .binance_ok()
+            .await?;
         Ok(())
     }
 
-    pub async fn init_futures_settings(&self, symbol: &str, leverage: u8) -> Result<()> {
-        info!("⚙️ Configuring Futures: Leverage {}x, Isolated", leverage);
-        let _ = self
-            .send_signed_request::<serde_json::Value>(
+    /// User-data stream: позиции и ордера аккаунта, включая ручные сделки и ликвидации.
+    /// Переподключается с новым listenKey; keepalive раз в LISTEN_KEY_KEEPALIVE
+    pub async fn subscribe_user_data(&self, sender: mpsc::Sender<AccountEvent>) -> Result<()> {
+        // Первый ключ берём сразу, чтобы ошибка авторизации всплыла на старте
+        let mut listen_key = Some(self.create_listen_key().await?);
+        let client = self.clone();
+
+        info!("🔌 Initializing user-data stream...");
+
+        tokio::spawn(async move {
+            loop {
+                let key = match listen_key.take() {
+                    Some(key) => key,
+                    None => match client.create_listen_key().await {
+                        Ok(key) => key,
+                        Err(e) => {
+                            error!("❌ listenKey request failed: {}. Retrying in 5s...", e);
+                            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                            continue;
+                        }
+                    },
+                };
+                let url = format!("{}/ws/{}", client.base_ws_url, key);
+
+                match connect_async(url.as_str()).await {
+                    Ok((ws_stream, _)) => {
+                        info!("✅ User-data stream connected");
+                        let (_, mut read) = ws_stream.split();
+                        let mut keepalive = tokio::time::interval(LISTEN_KEY_KEEPALIVE);
+                        keepalive.tick().await; // первый тик срабатывает сразу
+
+                        loop {
+                            tokio::select! {
+                                _ = keepalive.tick() => {
+                                    if let Err(e) = client.keepalive_listen_key().await {
+                                        warn!("⚠️ listenKey keepalive failed: {}", e);
+                                    }
+                                }
+                                msg = read.next() => {
+                                    let text = match msg {
+                                        Some(Ok(msg)) => match msg.to_text() {
+                                            Ok(text) => text.to_string(),
+                                            Err(_) => continue,
+                                        },
+                                        Some(Err(e)) => {
+                                            error!("❌ User-data WS Read Error: {}. Reconnecting...", e);
+                                            break;
+                                        }
+                                        None => break,
+                                    };
+                                    let event = match serde_json::from_str::<UserDataEvent>(&text) {
+                                        Ok(event) => event,
+                                        Err(e) => {
+                                            warn!("Unparsed user-data message: {} ({})", text, e);
+                                            continue;
+                                        }
+                                    };
+                                    if matches!(event, UserDataEvent::ListenKeyExpired) {
+                                        warn!("⚠️ listenKey expired. Reconnecting with a new key...");
+                                        break;
+                                    }
+                                    for account_event in event.into_account_events() {
+                                        if sender.send(account_event).await.is_err() {
+                                            warn!("Account receiver closed. Stopping user-data task");
+                                            return;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        warn!("⚠️ User-data stream ended. Reconnecting...");
+                    }
+                    Err(e) => {
+                        error!(
+                            "❌ User-data WS Connection Failed: {}. Retrying in 5s...",
+                            e
+                        );
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Ставит изолированную маржу и плечо. Возвращает плечо, которое биржа реально применила:
+    /// оно может быть ниже запрошенного из-за лимитов тира символа
+    pub async fn init_futures_settings(&self, symbol: &str, leverage: u8) -> Result<u8> {
+        info!(
+            "⚙️ Configuring Futures {}: Leverage {}x, Isolated",
+            symbol, leverage
+        );
+        let max_leverage = self.fetch_max_leverage(symbol).await?;
+        if leverage > max_leverage {
+            return Err(BotError::Config(format!(
+                "Requested leverage {}x exceeds {} maximum of {}x",
+                leverage, symbol, max_leverage
+            )));
+        }
+
+        match self
+            .send_signed_request::<MarginTypeResponse>(
                 Method::POST,
                 "/fapi/v1/marginType",
                 vec![
@@ -101,10 +495,19 @@ impl BinanceClient {
                     ("marginType", "ISOLATED".to_string()),
                 ],
             )
-            .await;
+            .await
+        {
+            Ok(response) => info!("⚙️ Margin type for {}: ISOLATED ({})", symbol, response.msg),
+            // -4046 No need to change margin type
+            Err(BotError::Api { code: -4046, .. }) => {
+                info!("⚙️ Margin type for {} is already ISOLATED", symbol)
+            }
+            // Например, -4048: есть открытая позиция. Плечо всё равно выставляем
+            Err(e) => error!("⚠️ Failed to set ISOLATED margin for {}: {}", symbol, e),
+        }
 
-        let _ = self
-            .send_signed_request::<serde_json::Value>(
+        let response = self
+            .send_signed_request::<LeverageResponse>(
                 Method::POST,
                 "/fapi/v1/leverage",
                 vec![
@@ -113,18 +516,132 @@ impl BinanceClient {
                 ],
             )
             .await?;
-        Ok(())
+        info!(
+            "⚙️ Leverage applied for {}: {}x (max notional {} {})",
+            response.symbol,
+            response.leverage,
+            response.max_notional_value,
+            symbol_assets(&response.symbol).1
+        );
+        Ok(response.leverage)
+    }
+
+    /// Максимальное плечо символа: initialLeverage первого (самого маленького) notional-бракета
+    pub async fn fetch_max_leverage(&self, symbol: &str) -> Result<u8> {
+        // С параметром symbol Binance отвечает то массивом из одного элемента, то объектом
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BracketsResponse {
+            Many(Vec<SymbolBrackets>),
+            One(SymbolBrackets),
+        }
+        #[derive(Deserialize)]
+        struct SymbolBrackets {
+            brackets: Vec<Bracket>,
+        }
+        #[derive(Deserialize)]
+        struct Bracket {
+            #[serde(rename = "initialLeverage")]
+            initial_leverage: u8,
+        }
+
+        let response: BracketsResponse = self
+            .send_signed_request(
+                Method::GET,
+                "/fapi/v1/leverageBracket",
+                vec![("symbol", symbol.to_string())],
+            )
+            .await?;
+        let brackets = match response {
+            BracketsResponse::Many(list) => list.into_iter().next().map(|s| s.brackets),
+            BracketsResponse::One(single) => Some(single.brackets),
+        };
+        brackets
+            .and_then(|b| b.iter().map(|bracket| bracket.initial_leverage).max())
+            .ok_or_else(|| BotError::Parse(format!("No leverage brackets for {}", symbol)))
+    }
+
+    /// Ставки комиссий аккаунта по символу (с учётом VIP-уровня и скидки BNB). Только USDT-M
+    pub async fn fetch_commission_rate(&self, symbol: &str, fees: FeeModel) -> Result<FeeModel> {
+        #[derive(Deserialize)]
+        struct CommissionRate {
+            #[serde(rename = "makerCommissionRate", with = "serde_decimal")]
+            maker: Decimal,
+            #[serde(rename = "takerCommissionRate", with = "serde_decimal")]
+            taker: Decimal,
+        }
+
+        if self.market == Market::Spot {
+            return Err(BotError::Config(
+                "fees.auto_detect is supported only for USDT-M futures".to_string(),
+            ));
+        }
+        let rate: CommissionRate = self
+            .send_signed_request(
+                Method::GET,
+                "/fapi/v1/commissionRate",
+                vec![("symbol", symbol.to_string())],
+            )
+            .await?;
+        Ok(fees.with_rates(rate.maker, rate.taker))
+    }
+
+    /// Свободный остаток актива на спотовом кошельке
+    async fn get_spot_balance(&self, asset: &str) -> Result<Decimal> {
+        #[derive(Deserialize)]
+        struct Balance {
+            asset: String,
+            #[serde(with = "serde_decimal")]
+            free: Decimal,
+        }
+        #[derive(Deserialize)]
+        struct SpotAccount {
+            balances: Vec<Balance>,
+        }
+
+        let resp: SpotAccount = self
+            .send_signed_request(Method::GET, "/api/v3/account", vec![])
+            .await?;
+
+        resp.balances
+            .into_iter()
+            .find(|b| b.asset == asset)
+            .map(|b| b.free)
+            .ok_or_else(|| BotError::Config(format!("Asset {} not found in Spot wallet", asset)))
+    }
+
+    /// Спот удерживает комиссию покупки в базовом активе: на кошельке чуть меньше, чем
+    /// купили. Выход урезается до свободного остатка, иначе продажа падает на балансе
+    async fn spot_exit_quantity(&self, request: &OrderRequest) -> Result<Decimal> {
+        let base_asset = match self.base_assets.get(&request.symbol) {
+            Some(asset) if self.market == Market::Spot && request.reduce_only => asset,
+            _ => return Ok(request.quantity),
+        };
+        let free =
+            self.normalize_quantity(&request.symbol, self.get_spot_balance(base_asset).await?);
+        if free < request.quantity {
+            warn!(
+                "⚠️ Spot exit {} {} capped to free balance {}",
+                request.quantity, base_asset, free
+            );
+        }
+        Ok(request.quantity.min(free))
     }
 
     fn sign_and_build_query(&self, params: Vec<(&str, String)>) -> Result<String> {
+        self.signed_query(params, Utc::now().timestamp_millis())
+    }
+
+    /// Query string с `timestamp` (мс) и HMAC-SHA256 подписью всего, что перед ней
+    fn signed_query(&self, params: Vec<(&str, String)>, timestamp: i64) -> Result<String> {
         let mut params = params;
-        let timestamp = Utc::now().timestamp_millis().to_string();
-        params.push(("timestamp", timestamp));
+        params.push(("timestamp", timestamp.to_string()));
 
-        let query_string = serde_urlencoded::to_string(&params)?;
+        let query_string =
+            serde_urlencoded::to_string(&params).map_err(|e| BotError::Parse(e.to_string()))?;
 
         let mut mac = HmacSha256::new_from_slice(self.secret_key.as_bytes())
-            .context("Invalid secret key length")?;
+            .map_err(|_| BotError::Config("Invalid secret key length".to_string()))?;
         mac.update(query_string.as_bytes());
         let result = mac.finalize();
         let signature = hex::encode(result.into_bytes());
@@ -132,7 +649,7 @@ impl BinanceClient {
         Ok(format!("{}&signature={}", query_string, signature))
     }
 
-    async fn send_signed_request<T: for<'de> Deserialize<'de>>(
+    async fn send_signed_request<T: DeserializeOwned>(
         &self,
         method: Method,
         endpoint: &str,
@@ -147,35 +664,108 @@ impl BinanceClient {
             .header("X-MBX-APIKEY", &self.api_key)
             .send()
             .await?
-            .error_for_status()?;
+            .binance_json()
+            .await?;
 
-        let json_resp = response.json::<T>().await?;
-        Ok(json_resp)
+        Ok(response)
+    }
+}
+
+impl BinanceClient {
+    /// Поток `@aggTrade`: объём каждой сделки в свечу по времени сделки.
+    /// Свой переподключающийся цикл: обрыв не трогает поток котировок
+    fn spawn_agg_trade_stream(
+        &self,
+        symbol: &str,
+        volume: Arc<TradeVolume>,
+    ) -> Result<AbortHandle> {
+        let ws_url = format!("{}/ws/{}@aggTrade", self.base_ws_url, symbol.to_lowercase());
+        let url = Url::parse(&ws_url)
+            .map_err(|e| BotError::Config(format!("Bad WS URL {}: {}", ws_url, e)))?;
+        let symbol = symbol.to_string();
+        // Объём прошлого символа к новому не относится
+        volume.clear();
+
+        let task = tokio::spawn(async move {
+            let mut parse_stats = ParseFailureMonitor::new(format!("{}@aggTrade", symbol));
+            loop {
+                match connect_async(url.clone()).await {
+                    Ok((ws_stream, _)) => {
+                        info!("✅ WS aggTrade Connected: {}", symbol);
+                        let (_, mut read) = ws_stream.split();
+                        while let Some(msg_result) = read.next().await {
+                            match msg_result {
+                                Ok(Message::Text(text)) => {
+                                    match serde_json::from_str::<AggTradeEvent>(&text) {
+                                        Ok(event) => {
+                                            parse_stats.record_ok();
+                                            volume.add(event.trade_time, event.quantity);
+                                        }
+                                        Err(e) => parse_stats.record_failure(&text, &e),
+                                    }
+                                }
+                                Ok(_) => {}
+                                Err(e) => {
+                                    error!("❌ WS aggTrade Read Error: {}. Reconnecting...", e);
+                                    break;
+                                }
+                            }
+                        }
+                        warn!("⚠️ WS aggTrade Stream ended. Reconnecting...");
+                    }
+                    Err(e) => {
+                        error!("❌ WS aggTrade Connection Failed: {}. Retrying in 5s...", e);
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            }
+        });
+        Ok(task.abort_handle())
     }
 }
 
 #[async_trait]
 impl ExecutionHandler for BinanceClient {
-    fn normalize_price(&self, price: Decimal) -> Decimal {
-        if self.tick_size.is_zero() {
-            return price.round_dp(2);
+    async fn load_symbol(&mut self, symbol: &str, leverage: Option<u8>) -> Result<SymbolPrecision> {
+        self.fetch_exchange_info(&[symbol.to_string()]).await?;
+        if let (Some(leverage), Market::Futures) = (leverage, self.market) {
+            let applied = self.init_futures_settings(symbol, leverage).await?;
+            if applied != leverage {
+                warn!(
+                    "⚠️ Exchange applied leverage {}x instead of requested {}x for {}",
+                    applied, leverage, symbol
+                );
+            }
+        }
+        self.precision(symbol)
+            .ok_or_else(|| BotError::Config(format!("{}: no filters in exchange info", symbol)))
+    }
+
+    fn normalize_price(&self, symbol: &str, price: Decimal) -> Decimal {
+        // Фильтры не загружены — округлять не по чему
+        match self.precision(symbol) {
+            Some(precision) => (price / precision.tick_size).floor() * precision.tick_size,
+            None => price,
         }
-        (price / self.tick_size).floor() * self.tick_size
     }
 
-    fn normalize_quantity(&self, quantity: Decimal) -> Decimal {
-        if self.step_size.is_zero() {
-            return quantity.round_dp(3);
+    fn normalize_quantity(&self, symbol: &str, quantity: Decimal) -> Decimal {
+        match self.precision(symbol) {
+            Some(precision) => (quantity / precision.step_size).floor() * precision.step_size,
+            None => quantity,
         }
-        (quantity / self.step_size).floor() * self.step_size
     }
 
     async fn get_balance(&self, asset: &str) -> Result<Decimal> {
+        if self.market == Market::Spot {
+            return self.get_spot_balance(asset).await;
+        }
+
         #[derive(Deserialize)]
         struct Asset {
             asset: String,
-            #[serde(rename = "walletBalance")]
-            wallet_balance: String,
+            #[serde(rename = "walletBalance", with = "serde_decimal")]
+            wallet_balance: Decimal,
         }
         #[derive(Deserialize)]
         struct AccountInfo {
@@ -190,68 +780,93 @@ impl ExecutionHandler for BinanceClient {
             .assets
             .iter()
             .find(|b| b.asset == asset)
-            .ok_or_else(|| anyhow!("Asset {} not found in Futures wallet", asset))?;
+            .ok_or_else(|| {
+                BotError::Config(format!("Asset {} not found in Futures wallet", asset))
+            })?;
 
-        balance
-            .wallet_balance
-            .parse::<Decimal>()
-            .map_err(|e| anyhow!(e))
+        Ok(balance.wallet_balance)
     }
 
-    async fn place_order(
-        &self,
-        symbol: &str,
-        side: Side,
-        amount: Decimal,
-        price: Option<Decimal>,
-    ) -> Result<OrderResponse> {
-        let side_str = match side {
+    async fn place_order(&self, request: &OrderRequest) -> Result<OrderResponse> {
+        let side_str = match request.side {
             Side::Buy => "BUY",
             Side::Sell => "SELL",
         };
 
-        let (type_str, time_in_force, price_val) = match price {
-            Some(p) => ("LIMIT", Some("IOC"), Some(p)),
-            None => ("MARKET", None, None),
+        let spot = self.market == Market::Spot;
+        // (type, price, timeInForce, stopPrice). На споте условные ордера называются иначе,
+        // а post-only — отдельный тип LIMIT_MAKER без timeInForce (GTX там нет)
+        let (type_str, price, tif, stop_price) = match request.order_type {
+            OrderType::Market => ("MARKET", None, None, None),
+            OrderType::Limit {
+                price,
+                time_in_force: TimeInForce::Gtx,
+            } if spot => ("LIMIT_MAKER", Some(price), None, None),
+            OrderType::Limit {
+                price,
+                time_in_force,
+            } => ("LIMIT", Some(price), Some(time_in_force), None),
+            OrderType::StopMarket { trigger } if spot => ("STOP_LOSS", None, None, Some(trigger)),
+            OrderType::StopMarket { trigger } => ("STOP_MARKET", None, None, Some(trigger)),
+            OrderType::TakeProfitMarket { trigger } if spot => {
+                ("TAKE_PROFIT", None, None, Some(trigger))
+            }
+            OrderType::TakeProfitMarket { trigger } => {
+                ("TAKE_PROFIT_MARKET", None, None, Some(trigger))
+            }
         };
 
+        let quantity = self.spot_exit_quantity(request).await?;
+
         let mut params = vec![
-            ("symbol", symbol.to_string()),
+            ("symbol", request.symbol.clone()),
             ("side", side_str.to_string()),
             ("type", type_str.to_string()),
-            ("quantity", amount.to_string()),
+            ("quantity", quantity.to_string()),
         ];
 
-        if let Some(p) = price_val {
+        if let Some(p) = price {
             params.push(("price", p.to_string()));
         }
-        if let Some(tif) = time_in_force {
-            params.push(("timeInForce", tif.to_string()));
+        if let Some(tif) = tif {
+            params.push(("timeInForce", tif.as_str().to_string()));
         }
-
-        #[derive(Deserialize)]
-        struct BinanceOrderResponse {
-            #[serde(rename = "orderId")]
-            order_id: u64,
-            symbol: String,
-            status: String,
+        if let Some(stop) = stop_price {
+            params.push(("stopPrice", stop.to_string()));
+        }
+        match self.market {
+            Market::Futures if request.reduce_only => {
+                params.push(("reduceOnly", "true".to_string()));
+            }
+            // Без RESULT спот может ответить ACK — без статуса и исполненного объёма
+            Market::Spot => params.push(("newOrderRespType", "RESULT".to_string())),
+            Market::Futures => {}
         }
 
-        let resp: BinanceOrderResponse = self
-            .send_signed_request(Method::POST, "/fapi/v1/order", params)
-            .await?;
+        params.push(("newClientOrderId", request.client_order_id.clone()));
 
-        match resp.status.as_str() {
-            "FILLED" | "PARTIALLY_FILLED" => Ok(OrderResponse {
-                id: resp.order_id.to_string(),
-                symbol: resp.symbol,
-                status: resp.status,
-            }),
-            _ => Err(anyhow!(
-                "Order not filled (Slippage/IOC). Status: {}",
-                resp.status
-            )),
+        METRICS.orders_placed.inc();
+        let order = match self
+            .send_signed_request::<BinanceOrderResponse>(
+                Method::POST,
+                self.endpoint("/fapi/v1/order", "/api/v3/order"),
+                params,
+            )
+            .await
+            .and_then(BinanceOrderResponse::into_order_response)
+        {
+            Ok(order) => order,
+            Err(e) => {
+                METRICS.orders_rejected.inc();
+                return Err(e.into_order_rejection());
+            }
+        };
+        if order.status.is_filled() {
+            METRICS.orders_filled.inc();
+        } else if !order.status.is_resting() {
+            METRICS.orders_rejected.inc();
         }
+        Ok(order)
     }
 
     async fn cancel_order(&self, symbol: &str, order_id: &str) -> Result<()> {
@@ -260,56 +875,346 @@ impl ExecutionHandler for BinanceClient {
             ("orderId", order_id.to_string()),
         ];
         let _: serde_json::Value = self
-            .send_signed_request(Method::DELETE, "/fapi/v1/order", params)
+            .send_signed_request(
+                Method::DELETE,
+                self.endpoint("/fapi/v1/order", "/api/v3/order"),
+                params,
+            )
             .await?;
         Ok(())
     }
+
+    async fn query_order(&self, symbol: &str, order_id: &str) -> Result<OrderResponse> {
+        let params = vec![
+            ("symbol", symbol.to_string()),
+            ("orderId", order_id.to_string()),
+        ];
+        let resp: BinanceOrderResponse = self
+            .send_signed_request(
+                Method::GET,
+                self.endpoint("/fapi/v1/order", "/api/v3/order"),
+                params,
+            )
+            .await?;
+        resp.into_order_response()
+    }
+
+    async fn fetch_position(&self, symbol: &str) -> Result<Option<ExchangePosition>> {
+        #[derive(Deserialize)]
+        struct PositionRisk {
+            #[serde(rename = "positionAmt", with = "serde_decimal")]
+            position_amt: Decimal,
+            #[serde(rename = "entryPrice", with = "serde_decimal")]
+            entry_price: Decimal,
+        }
+
+        if self.market == Market::Spot {
+            return Ok(None);
+        }
+        // One-way режим: одна запись (positionSide BOTH). В hedge — лонг и шорт отдельно, суммируем
+        let risks: Vec<PositionRisk> = self
+            .send_signed_request(
+                Method::GET,
+                "/fapi/v2/positionRisk",
+                vec![("symbol", symbol.to_string())],
+            )
+            .await?;
+        let open: Vec<&PositionRisk> = risks.iter().filter(|r| !r.position_amt.is_zero()).collect();
+        Ok(Some(ExchangePosition {
+            amount: open.iter().map(|r| r.position_amt).sum(),
+            entry_price: open.first().map(|r| r.entry_price).unwrap_or_default(),
+        }))
+    }
+
+    async fn query_order_by_client_id(
+        &self,
+        symbol: &str,
+        client_order_id: &str,
+    ) -> Result<Option<OrderResponse>> {
+        let params = vec![
+            ("symbol", symbol.to_string()),
+            ("origClientOrderId", client_order_id.to_string()),
+        ];
+        let resp: BinanceOrderResponse = match self
+            .send_signed_request(
+                Method::GET,
+                self.endpoint("/fapi/v1/order", "/api/v3/order"),
+                params,
+            )
+            .await
+        {
+            Ok(resp) => resp,
+            // -2013 Order does not exist
+            Err(BotError::Api { code: -2013, .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        resp.into_order_response().map(Some)
+    }
+}
+
+impl BinanceClient {
+    /// Страница истории: до `limit` свечей с open_time в [start_ms, end_ms], старые первыми
+    pub async fn fetch_klines_page(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_ms: u64,
+        end_ms: u64,
+        limit: usize,
+    ) -> Result<Vec<Kline>> {
+        let rows: Vec<Vec<serde_json::Value>> = self
+            .http_client
+            .get(format!(
+                "{}{}",
+                self.base_rest_url,
+                self.endpoint("/fapi/v1/klines", "/api/v3/klines")
+            ))
+            .query(&[
+                ("symbol", symbol.to_string()),
+                ("interval", interval.to_string()),
+                ("startTime", start_ms.to_string()),
+                ("endTime", end_ms.to_string()),
+                ("limit", limit.to_string()),
+            ])
+            .send()
+            .await?
+            .binance_json()
+            .await?;
+
+        rows.iter().map(|row| parse_kline(row)).collect()
+    }
+}
+
+#[async_trait]
+impl HistoryProvider for BinanceClient {
+    async fn fetch_klines(&self, symbol: &str, interval: &str, limit: usize) -> Result<Vec<Kline>> {
+        // Ответ: массив массивов [openTime, "open", "high", "low", "close", "volume", closeTime, ...]
+        let rows: Vec<Vec<serde_json::Value>> = self
+            .http_client
+            .get(format!(
+                "{}{}",
+                self.base_rest_url,
+                self.endpoint("/fapi/v1/klines", "/api/v3/klines")
+            ))
+            .query(&[
+                ("symbol", symbol.to_string()),
+                ("interval", interval.to_string()),
+                ("limit", limit.to_string()),
+            ])
+            .send()
+            .await?
+            .binance_json()
+            .await?;
+
+        rows.iter().map(|row| parse_kline(row)).collect()
+    }
+}
+
+// Ответ /fapi/v1/marginType: {"code":200,"msg":"success"}
+#[derive(Deserialize)]
+struct MarginTypeResponse {
+    msg: String,
+}
+
+// Ответ /fapi/v1/leverage: {"leverage":20,"maxNotionalValue":"1000000","symbol":"BTCUSDT"}
+#[derive(Deserialize)]
+struct LeverageResponse {
+    leverage: u8,
+    #[serde(rename = "maxNotionalValue", with = "serde_decimal")]
+    max_notional_value: Decimal,
+    symbol: String,
+}
+
+#[derive(Deserialize)]
+struct BinanceOrderResponse {
+    #[serde(rename = "orderId")]
+    order_id: u64,
+    #[serde(rename = "clientOrderId", default)]
+    client_order_id: String,
+    symbol: String,
+    status: String,
+    #[serde(rename = "executedQty", with = "serde_decimal")]
+    executed_qty: Decimal,
+    // Фьючерсы: средняя цена ("0" до исполнения)
+    #[serde(rename = "avgPrice", with = "serde_decimal::option", default)]
+    avg_price: Option<Decimal>,
+    // Спот: средней цены нет, только потраченная котировка
+    #[serde(
+        rename = "cummulativeQuoteQty",
+        with = "serde_decimal::option",
+        default
+    )]
+    cumulative_quote_qty: Option<Decimal>,
+}
+
+impl BinanceOrderResponse {
+    fn into_order_response(self) -> Result<OrderResponse> {
+        let status = OrderStatus::parse(&self.status).ok_or_else(|| BotError::OrderRejected {
+            code: 0,
+            msg: format!(
+                "Order {} has unexpected status: {}",
+                self.order_id, self.status
+            ),
+        })?;
+        let avg_price = self
+            .avg_price
+            .filter(|price| !price.is_zero())
+            .or_else(|| {
+                self.cumulative_quote_qty
+                    .filter(|_| !self.executed_qty.is_zero())
+                    .map(|quote| quote / self.executed_qty)
+            })
+            .filter(|price| !price.is_zero());
+        Ok(OrderResponse {
+            id: self.order_id.to_string(),
+            client_order_id: self.client_order_id,
+            symbol: self.symbol,
+            status,
+            executed_qty: self.executed_qty,
+            avg_price,
+        })
+    }
+}
+
+fn parse_kline(row: &[serde_json::Value]) -> Result<Kline> {
+    let time_at = |idx: usize| -> Result<u64> {
+        row.get(idx).and_then(|v| v.as_u64()).ok_or_else(|| {
+            BotError::Parse(format!(
+                "Kline field #{} is not a timestamp: {:?}",
+                idx, row
+            ))
+        })
+    };
+    let decimal_at = |idx: usize| -> Result<Decimal> {
+        row.get(idx)
+            .and_then(|v| serde_decimal::deserialize(v).ok())
+            .ok_or_else(|| {
+                BotError::Parse(format!("Kline field #{} is not a decimal: {:?}", idx, row))
+            })
+    };
+
+    Ok(Kline {
+        open_time: time_at(0)?,
+        open: decimal_at(1)?,
+        high: decimal_at(2)?,
+        low: decimal_at(3)?,
+        close: decimal_at(4)?,
+        volume: decimal_at(5)?,
+        close_time: time_at(6)?,
+    })
 }
 
 #[async_trait]
 impl StreamClient for BinanceClient {
-    async fn subscribe_ticker(&mut self, symbol: &str, sender: mpsc::Sender<Ticker>) -> Result<()> {
-        let ws_url = format!(
-            "wss://fstream.binance.com/ws/{}@bookTicker",
-            symbol.to_lowercase()
-        );
-        let url = Url::parse(&ws_url)?;
+    async fn subscribe_ticker(&mut self, symbol: &str, sender: RingSender<Ticker>) -> Result<()> {
+        let lower = symbol.to_lowercase();
+        let (ws_url, stream_name) = match self.data_stream {
+            DataStream::BookTicker => (
+                format!("{}/ws/{}@bookTicker", self.base_ws_url, lower),
+                format!("{}@bookTicker", symbol),
+            ),
+            DataStream::Trade => (
+                format!("{}/ws/{}@trade", self.base_ws_url, lower),
+                format!("{}@trade", symbol),
+            ),
+            // Combined stream: сообщения обоих потоков в одном соединении и по порядку
+            DataStream::Both => (
+                format!(
+                    "{}/stream?streams={}@bookTicker/{}@trade",
+                    self.base_ws_url, lower, lower
+                ),
+                format!("{}@bookTicker+trade", symbol),
+            ),
+        };
+        let url = Url::parse(&ws_url)
+            .map_err(|e| BotError::Config(format!("Bad WS URL {}: {}", ws_url, e)))?;
         let symbol_clone = symbol.to_string();
+        let recorder = self.recorder.clone();
+        let ws_status = self.ws_status.clone();
+        // Объём идёт из @trade этого же соединения: отдельный @aggTrade посчитал бы его дважды
+        let trade_volume = self
+            .trade_volume
+            .clone()
+            .filter(|_| self.data_stream.has_trades());
+        if let Some(volume) = &trade_volume {
+            volume.clear();
+        }
 
-        info!("🔌 Initializing WebSocket connection for {}...", symbol);
+        info!(
+            "🔌 Initializing WebSocket connection for {}...",
+            stream_name
+        );
 
-        tokio::spawn(async move {
+        self.unsubscribe();
+        let task = tokio::spawn(async move {
+            let mut first_attempt = true;
+            // Вытеснения считаются всегда, а в лог попадают не чаще DROP_LOG_INTERVAL
+            let mut dropped_since_log = 0u64;
+            let mut last_drop_log = Instant::now();
+            let mut parse_stats = ParseFailureMonitor::new(stream_name.clone());
+            let mut reconnects = ws_status.reconnect_budget();
             loop {
+                if !first_attempt {
+                    METRICS.ws_reconnects.inc();
+                }
+                first_attempt = false;
+                ws_status.reconnecting();
                 info!("Connecting to WS: {}", url);
                 match connect_async(url.clone()).await {
                     Ok((ws_stream, _)) => {
                         info!("✅ WS Connected: {}", symbol_clone);
+                        reconnects.connected();
                         let (_, mut read) = ws_stream.split();
+                        // После переподключения стакан и последняя сделка придут заново
+                        let mut merger = TickerMerger::default();
 
                         while let Some(msg_result) = read.next().await {
                             match msg_result {
-                                Ok(msg) => {
-                                    if let Ok(text) = msg.to_text() {
-                                        if let Ok(event) =
-                                            serde_json::from_str::<BookTickerEvent>(text)
-                                        {
-                                            let mid_price = (event.best_bid_price
-                                                + event.best_ask_price)
-                                                / Decimal::from(2);
-
-                                            let ticker = Ticker {
-                                                symbol: symbol_clone.clone(),
-                                                price: mid_price,
-                                                bid_price: event.best_bid_price,
-                                                ask_price: event.best_ask_price,
-                                                bid_qty: event.best_bid_qty,
-                                                ask_qty: event.best_ask_qty,
-                                                timestamp: event.event_time,
-                                            };
-                                            if sender.try_send(ticker).is_err() {}
+                                Ok(Message::Text(text)) => {
+                                    if let Some(recorder) = &recorder {
+                                        recorder.record(&text);
+                                    }
+                                    let event = match MarketEvent::parse(&text) {
+                                        Ok(event) => {
+                                            parse_stats.record_ok();
+                                            event
+                                        }
+                                        Err(e) => {
+                                            parse_stats.record_failure(&text, &e);
+                                            continue;
+                                        }
+                                    };
+                                    if let (MarketEvent::Trade(trade), Some(volume)) =
+                                        (&event, &trade_volume)
+                                    {
+                                        volume.add(trade.trade_time, trade.quantity);
+                                    }
+                                    let recv_ts = Utc::now().timestamp_millis() as u64;
+                                    let ticker = merger.on_event(event, &symbol_clone, recv_ts);
+                                    // При отставании движка вытесняется самый старый тик,
+                                    // так что движок всегда видит свежую цену
+                                    match sender.send(ticker) {
+                                        Ok(true) => {
+                                            METRICS.ticks_dropped.inc();
+                                            dropped_since_log += 1;
+                                            if last_drop_log.elapsed() >= DROP_LOG_INTERVAL {
+                                                warn!(
+                                                    "🐢 Engine lagging: {} stale ticks for {} dropped",
+                                                    dropped_since_log, symbol_clone
+                                                );
+                                                dropped_since_log = 0;
+                                                last_drop_log = Instant::now();
+                                            }
+                                        }
+                                        Ok(false) => {}
+                                        Err(_) => {
+                                            warn!("Ticker receiver closed. Stopping WS task");
+                                            return;
                                         }
                                     }
                                 }
+                                // Ping/Pong отвечает сама библиотека
+                                Ok(_) => {}
                                 Err(e) => {
                                     error!("❌ WS Read Error: {}. Reconnecting...", e);
                                     break;
@@ -322,10 +1227,398 @@ impl StreamClient for BinanceClient {
                         error!("❌ WS Connection Failed: {}. Retrying in 5s...", e);
                     }
                 }
+                if !reconnects.lost(&stream_name) {
+                    return;
+                }
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
             }
         });
+        self.ws_task = Some(task.abort_handle());
+
+        if let Some(volume) = self
+            .trade_volume
+            .clone()
+            .filter(|_| !self.data_stream.has_trades())
+        {
+            self.agg_trade_task = Some(self.spawn_agg_trade_stream(symbol, volume)?);
+        }
 
         Ok(())
     }
+
+    fn unsubscribe(&mut self) {
+        if let Some(task) = self.ws_task.take() {
+            task.abort();
+            info!("🔌 WS ticker stream closed");
+        }
+        if let Some(task) = self.agg_trade_task.take() {
+            task.abort();
+            info!("🔌 WS aggTrade stream closed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leverage_response_reports_the_applied_leverage() {
+        // Запрошено 50x, тир символа ограничил до 20x
+        let response: LeverageResponse = serde_json::from_str(
+            r#"{"leverage":20,"maxNotionalValue":"1000000","symbol":"BTCUSDT"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.leverage, 20);
+        assert_eq!(response.max_notional_value, Decimal::from(1_000_000));
+        assert_eq!(response.symbol, "BTCUSDT");
+    }
+
+    fn order(body: &str) -> Result<OrderResponse> {
+        parse_response::<BinanceOrderResponse>(body)?.into_order_response()
+    }
+
+    #[test]
+    fn signed_query_matches_the_binance_docs_example() {
+        // Пример подписи из документации Binance (SIGNED endpoint, HMAC SHA256)
+        let client = BinanceClient::new(
+            "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A".to_string(),
+            "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j".to_string(),
+        );
+        let query = client
+            .signed_query(
+                vec![
+                    ("symbol", "LTCBTC".to_string()),
+                    ("side", "BUY".to_string()),
+                    ("type", "LIMIT".to_string()),
+                    ("timeInForce", "GTC".to_string()),
+                    ("quantity", "1".to_string()),
+                    ("price", "0.1".to_string()),
+                    ("recvWindow", "5000".to_string()),
+                ],
+                1_499_827_319_559,
+            )
+            .unwrap();
+        assert_eq!(
+            query,
+            "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1\
+             &recvWindow=5000&timestamp=1499827319559\
+             &signature=c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+
+    #[test]
+    fn futures_order_uses_avg_price() {
+        let response = order(
+            r#"{"orderId":42,"clientOrderId":"sniper-1","symbol":"BTCUSDT","status":"FILLED",
+                "executedQty":"0.010","avgPrice":"64000.50"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.id, "42");
+        assert_eq!(response.client_order_id, "sniper-1");
+        assert_eq!(response.status, OrderStatus::Filled);
+        assert_eq!(response.executed_qty, Decimal::new(10, 3));
+        assert_eq!(response.avg_price, Some(Decimal::new(6400050, 2)));
+    }
+
+    #[test]
+    fn spot_order_derives_avg_price_from_quote_quantity() {
+        let response = order(
+            r#"{"orderId":7,"symbol":"BTCUSDT","status":"PARTIALLY_FILLED",
+                "executedQty":"0.5","cummulativeQuoteQty":"50"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.status, OrderStatus::PartiallyFilled);
+        assert_eq!(response.avg_price, Some(Decimal::from(100)));
+    }
+
+    #[test]
+    fn resting_order_has_no_avg_price() {
+        // Фьючерсы отдают "0" до первого исполнения
+        let response = order(
+            r#"{"orderId":8,"symbol":"BTCUSDT","status":"NEW","executedQty":"0","avgPrice":"0.00"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.status, OrderStatus::New);
+        assert_eq!(response.avg_price, None);
+    }
+
+    #[test]
+    fn unexpected_order_status_is_a_rejection() {
+        let err =
+            order(r#"{"orderId":9,"symbol":"BTCUSDT","status":"REJECTED","executedQty":"0"}"#)
+                .unwrap_err();
+        assert!(matches!(err, BotError::OrderRejected { code: 0, .. }));
+        assert!(err.to_string().contains("REJECTED"));
+    }
+
+    #[test]
+    fn parse_response_names_the_bad_field() {
+        let err = parse_response::<BinanceOrderResponse>(
+            r#"{"orderId":1,"symbol":"BTCUSDT","status":"NEW","executedQty":"abc"}"#,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, BotError::Parse(_)));
+        assert!(err.to_string().contains("executedQty"), "{}", err);
+    }
+
+    // HTTP-заглушка REST API на локальном порту. Отвечает по префиксу пути (status, тело)
+    // и запоминает заголовки каждого запроса: метод, путь с query и X-MBX-APIKEY
+    struct MockServer {
+        url: String,
+        requests: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl MockServer {
+        async fn start(routes: Vec<(&'static str, u16, &'static str)>) -> Self {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let seen = requests.clone();
+            tokio::spawn(async move {
+                while let Ok((mut socket, _)) = listener.accept().await {
+                    let mut head = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => head.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let head = String::from_utf8_lossy(&head).into_owned();
+                    let path = head.split(' ').nth(1).unwrap_or_default().to_string();
+                    seen.lock().unwrap().push(head);
+                    let (status, body) = routes
+                        .iter()
+                        .find(|(prefix, _, _)| path.starts_with(prefix))
+                        .map(|&(_, status, body)| (status, body))
+                        .unwrap_or((404, r#"{"code":-1,"msg":"no route"}"#));
+                    let response = format!(
+                        "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                }
+            });
+            Self { url, requests }
+        }
+
+        fn client(&self, market: Market) -> BinanceClient {
+            let mut client =
+                BinanceClient::new("api-key".to_string(), "secret".to_string()).with_market(market);
+            client.base_rest_url = self.url.clone();
+            client
+        }
+
+        // Параметры query последнего запроса
+        fn last_params(&self) -> HashMap<String, String> {
+            let requests = self.requests.lock().unwrap();
+            let head = requests.last().expect("no requests");
+            let target = head.split(' ').nth(1).unwrap();
+            let query = target.split_once('?').map(|(_, q)| q).unwrap_or_default();
+            serde_urlencoded::from_str(query).unwrap()
+        }
+
+        fn last_request(&self) -> String {
+            self.requests.lock().unwrap().last().cloned().unwrap()
+        }
+    }
+
+    const FILLED_ORDER: &str = r#"{"orderId":42,"clientOrderId":"sniper-1","symbol":"BTCUSDT",
+        "status":"FILLED","executedQty":"0.010","avgPrice":"64000.50"}"#;
+
+    fn order_request(order_type: OrderType, reduce_only: bool) -> OrderRequest {
+        OrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: Side::Sell,
+            quantity: Decimal::new(10, 3),
+            order_type,
+            reduce_only,
+            client_order_id: "sniper-1".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn futures_limit_order_sends_type_tif_reduce_only_and_client_id() {
+        let server = MockServer::start(vec![("/fapi/v1/order", 200, FILLED_ORDER)]).await;
+        let client = server.client(Market::Futures);
+        let order_type = OrderType::Limit {
+            price: Decimal::new(6400050, 2),
+            time_in_force: TimeInForce::Ioc,
+        };
+
+        let response = client
+            .place_order(&order_request(order_type, true))
+            .await
+            .unwrap();
+        assert_eq!(response.status, OrderStatus::Filled);
+
+        let request = server.last_request();
+        assert!(request.starts_with("POST /fapi/v1/order?"));
+        assert!(request.to_lowercase().contains("x-mbx-apikey: api-key"));
+        let params = server.last_params();
+        assert_eq!(params["symbol"], "BTCUSDT");
+        assert_eq!(params["side"], "SELL");
+        assert_eq!(params["type"], "LIMIT");
+        assert_eq!(params["timeInForce"], "IOC");
+        assert_eq!(params["price"], "64000.50");
+        assert_eq!(params["quantity"], "0.010");
+        assert_eq!(params["reduceOnly"], "true");
+        assert_eq!(params["newClientOrderId"], "sniper-1");
+        assert!(params.contains_key("timestamp"));
+        assert_eq!(params["signature"].len(), 64);
+    }
+
+    #[tokio::test]
+    async fn stop_and_take_profit_orders_send_a_stop_price() {
+        let server = MockServer::start(vec![(
+            "/fapi/v1/order",
+            200,
+            r#"{"orderId":43,"symbol":"BTCUSDT","status":"NEW","executedQty":"0","avgPrice":"0"}"#,
+        )])
+        .await;
+        let client = server.client(Market::Futures);
+        let trigger = Decimal::from(63_000);
+
+        for (order_type, expected) in [
+            (OrderType::StopMarket { trigger }, "STOP_MARKET"),
+            (
+                OrderType::TakeProfitMarket { trigger },
+                "TAKE_PROFIT_MARKET",
+            ),
+        ] {
+            client
+                .place_order(&order_request(order_type, true))
+                .await
+                .unwrap();
+            let params = server.last_params();
+            assert_eq!(params["type"], expected);
+            assert_eq!(params["stopPrice"], "63000");
+            assert!(!params.contains_key("price"));
+            assert!(!params.contains_key("timeInForce"));
+        }
+    }
+
+    #[tokio::test]
+    async fn spot_post_only_order_is_limit_maker_with_full_response() {
+        let server = MockServer::start(vec![(
+            "/api/v3/order",
+            200,
+            r#"{"orderId":7,"symbol":"BTCUSDT","status":"NEW","executedQty":"0","cummulativeQuoteQty":"0"}"#,
+        )])
+        .await;
+        let client = server.client(Market::Spot);
+        let order_type = OrderType::Limit {
+            price: Decimal::from(64_000),
+            time_in_force: TimeInForce::Gtx,
+        };
+
+        client
+            .place_order(&order_request(order_type, false))
+            .await
+            .unwrap();
+        assert!(server.last_request().starts_with("POST /api/v3/order?"));
+        let params = server.last_params();
+        assert_eq!(params["type"], "LIMIT_MAKER");
+        assert!(!params.contains_key("timeInForce"));
+        assert!(!params.contains_key("reduceOnly"));
+        assert_eq!(params["newOrderRespType"], "RESULT");
+    }
+
+    #[tokio::test]
+    async fn rejected_order_carries_the_binance_code() {
+        let server = MockServer::start(vec![(
+            "/fapi/v1/order",
+            400,
+            r#"{"code":-2019,"msg":"Margin is insufficient."}"#,
+        )])
+        .await;
+        let err = server
+            .client(Market::Futures)
+            .place_order(&order_request(OrderType::Market, false))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BotError::OrderRejected { code: -2019, .. }));
+    }
+
+    #[tokio::test]
+    async fn balance_is_taken_for_the_requested_asset() {
+        let server = MockServer::start(vec![
+            (
+                "/fapi/v2/account",
+                200,
+                r#"{"assets":[{"asset":"USDT","walletBalance":"1000.5"},
+                              {"asset":"BNB","walletBalance":"2.25"}]}"#,
+            ),
+            (
+                "/api/v3/account",
+                200,
+                r#"{"balances":[{"asset":"BTC","free":"0.1","locked":"0"},
+                                {"asset":"USDC","free":"250","locked":"10"}]}"#,
+            ),
+        ])
+        .await;
+
+        let futures = server.client(Market::Futures);
+        assert_eq!(
+            futures.get_balance("BNB").await.unwrap(),
+            Decimal::new(225, 2)
+        );
+        assert_eq!(
+            futures.get_balance("USDT").await.unwrap(),
+            Decimal::new(10005, 1)
+        );
+        assert!(matches!(
+            futures.get_balance("USDC").await,
+            Err(BotError::Config(_))
+        ));
+
+        let spot = server.client(Market::Spot);
+        assert_eq!(spot.get_balance("USDC").await.unwrap(), Decimal::from(250));
+        assert!(server.last_request().starts_with("GET /api/v3/account?"));
+    }
+
+    #[tokio::test]
+    async fn exchange_info_reads_the_min_notional_filter() {
+        let server = MockServer::start(vec![
+            (
+                "/fapi/v1/exchangeInfo",
+                200,
+                r#"{"symbols":[{"symbol":"BTCUSDT","baseAsset":"BTC","status":"TRADING","filters":[
+                    {"filterType":"PRICE_FILTER","tickSize":"0.10"},
+                    {"filterType":"LOT_SIZE","stepSize":"0.001"},
+                    {"filterType":"MIN_NOTIONAL","notional":"100"}]}]}"#,
+            ),
+            (
+                "/api/v3/exchangeInfo",
+                200,
+                r#"{"symbols":[{"symbol":"ETHBTC","baseAsset":"ETH","status":"TRADING","filters":[
+                    {"filterType":"PRICE_FILTER","tickSize":"0.00001"},
+                    {"filterType":"LOT_SIZE","stepSize":"0.0001"},
+                    {"filterType":"NOTIONAL","minNotional":"0.0001","applyMinToMarket":true}]}]}"#,
+            ),
+        ])
+        .await;
+
+        let precision = server
+            .client(Market::Futures)
+            .fetch_exchange_info(&["BTCUSDT".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(precision["BTCUSDT"].min_notional, Decimal::from(100));
+
+        // Спотовый символ с котировкой в BTC: минимум в BTC, а не в USDT
+        let precision = server
+            .client(Market::Spot)
+            .fetch_exchange_info(&["ETHBTC".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(precision["ETHBTC"].min_notional, Decimal::new(1, 4));
+        assert_eq!(precision["ETHBTC"].step_size, Decimal::new(1, 4));
+    }
 }
@@ -1,9 +1,10 @@
 // src/connectors/messages.rs
+use crate::types::{AccountEvent, OrderStatus, Side, Ticker};
 use rust_decimal::Decimal;
 use serde::Deserialize;
 
 // Для чтения потока @bookTicker (OBI - Order Book Imbalance)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct BookTickerEvent {
     #[serde(rename = "b")]
     pub best_bid_price: Decimal,
@@ -13,21 +14,248 @@ pub struct BookTickerEvent {
     pub best_ask_price: Decimal,
     #[serde(rename = "A")]
     pub best_ask_qty: Decimal,
-    #[serde(rename = "E")]
+    // Только у фьючерсов: spot bookTicker приходит без времени события (0)
+    #[serde(rename = "E", default)]
     pub event_time: u64,
 }
 
-// Старая структура (можно оставить для совместимости, если вдруг понадобится)
+impl BookTickerEvent {
+    /// `recv_ts` — время получения сообщения, мс: метка тика, если биржа не прислала `E`
+    pub fn to_ticker(&self, symbol: &str, recv_ts: u64) -> Ticker {
+        let mid_price = (self.best_bid_price + self.best_ask_price) / Decimal::from(2);
+        Ticker {
+            symbol: symbol.to_string(),
+            price: mid_price,
+            bid_price: self.best_bid_price,
+            ask_price: self.best_ask_price,
+            bid_qty: self.best_bid_qty,
+            ask_qty: self.best_ask_qty,
+            timestamp: if self.event_time > 0 {
+                self.event_time
+            } else {
+                recv_ts
+            },
+        }
+    }
+}
+
+// --- User Data Stream (/ws/<listenKey>) ---
+
 #[derive(Debug, Deserialize)]
-pub struct BinanceTradeEvent {
-    #[serde(rename = "e")]
-    pub event_type: String,
-    #[serde(rename = "E")]
-    pub event_time: u64,
+#[serde(tag = "e")]
+pub enum UserDataEvent {
+    #[serde(rename = "ACCOUNT_UPDATE")]
+    AccountUpdate {
+        #[serde(rename = "a")]
+        account: AccountUpdateData,
+    },
+    #[serde(rename = "ORDER_TRADE_UPDATE")]
+    OrderTradeUpdate {
+        #[serde(rename = "o")]
+        order: OrderTradeUpdateData,
+    },
+    #[serde(rename = "listenKeyExpired")]
+    ListenKeyExpired,
+    // MARGIN_CALL, ACCOUNT_CONFIG_UPDATE и т.п. не используем
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountUpdateData {
+    #[serde(rename = "P", default)]
+    pub positions: Vec<AccountPosition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountPosition {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    // Знаковый объём: > 0 лонг, < 0 шорт
+    #[serde(rename = "pa")]
+    pub amount: Decimal,
+    #[serde(rename = "ep")]
+    pub entry_price: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderTradeUpdateData {
     #[serde(rename = "s")]
     pub symbol: String,
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[serde(rename = "c", default)]
+    pub client_order_id: String,
+    #[serde(rename = "S")]
+    pub side: String,
+    #[serde(rename = "X")]
+    pub status: String,
+    // Накопленный исполненный объём
+    #[serde(rename = "z")]
+    pub filled_qty: Decimal,
+    #[serde(rename = "ap")]
+    pub avg_price: Decimal,
+}
+
+impl UserDataEvent {
+    /// Переводит сообщение биржи в события движка (пусто — неинтересное сообщение)
+    pub fn into_account_events(self) -> Vec<AccountEvent> {
+        match self {
+            UserDataEvent::AccountUpdate { account } => account
+                .positions
+                .into_iter()
+                .map(|p| AccountEvent::PositionUpdate {
+                    symbol: p.symbol,
+                    amount: p.amount,
+                    entry_price: p.entry_price,
+                })
+                .collect(),
+            UserDataEvent::OrderTradeUpdate { order } => {
+                let side = match order.side.as_str() {
+                    "BUY" => Side::Buy,
+                    _ => Side::Sell,
+                };
+                match OrderStatus::parse(&order.status) {
+                    Some(status) => vec![AccountEvent::OrderUpdate {
+                        symbol: order.symbol,
+                        order_id: order.order_id.to_string(),
+                        client_order_id: order.client_order_id,
+                        side,
+                        status,
+                        filled_qty: order.filled_qty,
+                        avg_price: order.avg_price,
+                    }],
+                    None => Vec::new(),
+                }
+            }
+            UserDataEvent::ListenKeyExpired | UserDataEvent::Other => Vec::new(),
+        }
+    }
+}
+
+/// Сделка (`<symbol>@trade`): цена последней сделки для data_stream = trade | both
+#[derive(Debug, Deserialize)]
+pub struct TradeEvent {
     #[serde(rename = "p")]
     pub price: Decimal,
     #[serde(rename = "q")]
     pub quantity: Decimal,
+    // Время сделки, мс
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+}
+
+/// Сообщение потока котировок: @bookTicker или @trade, в том числе в обёртке
+/// combined stream (`{"stream": ..., "data": {...}}`) при data_stream = both
+#[derive(Debug)]
+pub enum MarketEvent {
+    Book(BookTickerEvent),
+    Trade(TradeEvent),
+}
+
+impl MarketEvent {
+    pub fn parse(text: &str) -> serde_json::Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(text)?;
+        if let Some(data) = value.get_mut("data") {
+            value = data.take();
+        }
+        // Spot bookTicker приходит без "e", фьючерсный — с "e": "bookTicker"
+        if value.get("e").and_then(|e| e.as_str()) == Some("trade") {
+            TradeEvent::deserialize(value).map(MarketEvent::Trade)
+        } else {
+            BookTickerEvent::deserialize(value).map(MarketEvent::Book)
+        }
+    }
+}
+
+/// Собирает Ticker из событий обоих потоков. Bid/ask и их объёмы — из последнего bookTicker,
+/// price — последняя сделка (mid, пока сделок не было). Сделка до первого bookTicker даёт
+/// тик с bid = ask = цене сделки и нулевыми объёмами (OBI = 0)
+#[derive(Debug, Default)]
+pub struct TickerMerger {
+    book: Option<BookTickerEvent>,
+    last_trade_price: Option<Decimal>,
+}
+
+impl TickerMerger {
+    /// `recv_ts` — когда сообщение получено (в записи — колонка recv_ts), мс
+    pub fn on_event(&mut self, event: MarketEvent, symbol: &str, recv_ts: u64) -> Ticker {
+        match event {
+            MarketEvent::Book(book) => {
+                let mut ticker = book.to_ticker(symbol, recv_ts);
+                if let Some(price) = self.last_trade_price {
+                    ticker.price = price;
+                }
+                self.book = Some(book);
+                ticker
+            }
+            MarketEvent::Trade(trade) => {
+                self.last_trade_price = Some(trade.price);
+                let mut ticker = match &self.book {
+                    Some(book) => book.to_ticker(symbol, recv_ts),
+                    None => Ticker {
+                        symbol: symbol.to_string(),
+                        price: trade.price,
+                        bid_price: trade.price,
+                        ask_price: trade.price,
+                        bid_qty: Decimal::ZERO,
+                        ask_qty: Decimal::ZERO,
+                        timestamp: 0,
+                    },
+                };
+                ticker.price = trade.price;
+                ticker.timestamp = trade.trade_time;
+                ticker
+            }
+        }
+    }
+}
+
+/// Агрегированная сделка (`<symbol>@aggTrade`): источник объёма для свечей
+#[derive(Debug, Deserialize)]
+pub struct AggTradeEvent {
+    #[serde(rename = "q")]
+    pub quantity: Decimal,
+    // Время сделки, мс
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::recorder::parse_record_line;
+
+    const SPOT_BOOK: &str = r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+    const FUTURES_BOOK: &str = r#"{"e":"bookTicker","u":400900217,"E":1568014460893,"T":1568014460891,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+
+    fn replay(lines: &[String]) -> Vec<Ticker> {
+        let mut merger = TickerMerger::default();
+        lines
+            .iter()
+            .filter_map(|line| parse_record_line(line))
+            .map(|(recv_ts, event)| merger.on_event(event, "BNBUSDT", recv_ts))
+            .collect()
+    }
+
+    #[test]
+    fn spot_book_ticker_is_stamped_with_the_recorded_receive_time() {
+        let lines = [
+            format!("1700000000000\t{}", SPOT_BOOK),
+            format!("1700000000250\t{}", SPOT_BOOK),
+        ];
+        let ticks = replay(&lines);
+        assert_eq!(
+            ticks.iter().map(|t| t.timestamp).collect::<Vec<_>>(),
+            vec![1_700_000_000_000, 1_700_000_000_250]
+        );
+        // Повторный прогон той же записи даёт те же метки
+        assert_eq!(replay(&lines)[1].timestamp, ticks[1].timestamp);
+    }
+
+    #[test]
+    fn futures_book_ticker_keeps_the_exchange_event_time() {
+        let ticks = replay(&[format!("1700000000000\t{}", FUTURES_BOOK)]);
+        assert_eq!(ticks[0].timestamp, 1_568_014_460_893);
+    }
 }
@@ -1,3 +1,8 @@
 pub mod binance;
+pub mod coinbase;
 pub mod messages;
+pub mod okx;
+pub mod parse_stats;
+pub mod recorder;
 pub mod traits;
+pub mod ws_status;
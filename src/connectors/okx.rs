@@ -0,0 +1,875 @@
+// src/connectors/okx.rs
+use crate::connectors::traits::{ExecutionHandler, HistoryProvider, StreamClient};
+use crate::connectors::ws_status::WsStatusReporter;
+use crate::metrics::METRICS;
+use crate::types::{
+    ExchangePosition, Kline, OrderRequest, OrderResponse, OrderStatus, OrderType, Side, Ticker,
+    TimeInForce,
+};
+use crate::utils::error::{BotError, Result};
+use crate::utils::precision::SymbolPrecision;
+use crate::utils::ring_channel::RingSender;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method};
+use rust_decimal::prelude::*;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::Sha256;
+use tokio::task::AbortHandle;
+use tokio::time::{interval, sleep, Duration};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, warn};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const REST_URL: &str = "https://www.okx.com";
+const PUBLIC_WS_URL: &str = "wss://ws.okx.com:8443/ws/v5/public";
+// Demo trading: те же REST-адреса с заголовком x-simulated-trading, отдельный WS
+const DEMO_PUBLIC_WS_URL: &str = "wss://wspap.okx.com:8443/ws/v5/public";
+
+// OKX рвёт соединение после 30 с тишины; "ping" текстом раньше этого срока
+const WS_PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Коннектор OKX для USDT-маржинальных перпетуалов (instType SWAP).
+///
+/// Движок везде считает объём в базовом активе (BTC), а OKX принимает `sz` в контрактах:
+/// пересчёт через `ctVal` инструмента делается здесь, наружу уходят только базовые единицы.
+#[derive(Clone)]
+pub struct OkxClient {
+    api_key: String,
+    secret_key: String,
+    passphrase: String,
+    http_client: Client,
+    base_rest_url: String,
+    base_ws_url: String,
+    demo: bool,
+    // Instrument cache (уже в базовом активе, кроме ct_val)
+    tick_size: Decimal,
+    step_size: Decimal,
+    // Базового актива в одном контракте (BTC-USDT-SWAP: 0.01 BTC)
+    ct_val: Decimal,
+    // Максимальное плечо инструмента (поле lever); None — инструмент ещё не загружен
+    max_leverage: Option<u8>,
+    // instId загруженного инструмента: шаги и ct_val относятся к нему
+    instrument: Option<String>,
+    ws_status: WsStatusReporter,
+    // Задача потока котировок из subscribe_ticker (снимается при смене символа)
+    ws_task: Option<AbortHandle>,
+}
+
+impl OkxClient {
+    pub fn new(api_key: String, secret_key: String, passphrase: String) -> Self {
+        Self {
+            api_key,
+            secret_key,
+            passphrase,
+            http_client: Client::new(),
+            base_rest_url: REST_URL.to_string(),
+            base_ws_url: PUBLIC_WS_URL.to_string(),
+            demo: false,
+            tick_size: Decimal::new(1, 1), // Default 0.1
+            step_size: Decimal::new(1, 4), // Default 0.0001 (0.01 контракта по 0.01 BTC)
+            ct_val: Decimal::new(1, 2),
+            max_leverage: None,
+            instrument: None,
+            ws_status: WsStatusReporter::default(),
+            ws_task: None,
+        }
+    }
+
+    /// Состояние WS-потока котировок -> индикатор в TUI; исчерпанные переподключения -> движок
+    pub fn with_ws_status(mut self, ws_status: WsStatusReporter) -> Self {
+        self.ws_status = ws_status;
+        self
+    }
+
+    /// Demo trading вместо боевого счёта (нужны demo-ключи)
+    pub fn with_demo(mut self) -> Self {
+        self.base_ws_url = DEMO_PUBLIC_WS_URL.to_string();
+        self.demo = true;
+        self
+    }
+
+    /// Фильтры инструмента; шаг объёма — в базовом активе (lotSz * ctVal)
+    pub fn precision(&self) -> SymbolPrecision {
+        SymbolPrecision {
+            tick_size: self.tick_size,
+            step_size: self.step_size,
+            // Минимум OKX — объём (minSz), а не нотионал: его держит шаг объёма
+            min_notional: Decimal::ZERO,
+        }
+    }
+
+    /// Загружает tickSz/lotSz/ctVal инструмента (/api/v5/public/instruments)
+    pub async fn fetch_instrument(&mut self, symbol: &str) -> Result<()> {
+        #[derive(Deserialize)]
+        struct Instrument {
+            #[serde(rename = "tickSz")]
+            tick_sz: Decimal,
+            #[serde(rename = "lotSz")]
+            lot_sz: Decimal,
+            #[serde(rename = "ctVal")]
+            ct_val: Decimal,
+            lever: String,
+        }
+
+        let inst_id = okx_inst_id(symbol)?;
+        info!("🔍 Fetching OKX instrument {}...", inst_id);
+        let instruments: Vec<Instrument> = self
+            .send_public_request(
+                "/api/v5/public/instruments",
+                &[("instType", "SWAP"), ("instId", &inst_id)],
+            )
+            .await?;
+        let instrument = instruments
+            .into_iter()
+            .next()
+            .ok_or_else(|| BotError::Config(format!("Instrument {} not found on OKX", inst_id)))?;
+
+        if instrument.ct_val <= Decimal::ZERO {
+            return Err(BotError::Parse(format!(
+                "Instrument {} has invalid ctVal {}",
+                inst_id, instrument.ct_val
+            )));
+        }
+        self.tick_size = instrument.tick_sz;
+        self.ct_val = instrument.ct_val;
+        self.step_size = instrument.lot_sz * instrument.ct_val;
+        self.max_leverage = instrument.lever.parse().ok();
+        self.instrument = Some(inst_id.clone());
+        info!(
+            "✅ OKX {}: tick {}, step {} (lot {} x ctVal {})",
+            inst_id, self.tick_size, self.step_size, instrument.lot_sz, self.ct_val
+        );
+        Ok(())
+    }
+
+    /// Изолированная маржа и плечо. Возвращает плечо, которое биржа реально применила
+    pub async fn init_futures_settings(&self, symbol: &str, leverage: u8) -> Result<u8> {
+        #[derive(Deserialize)]
+        struct LeverageInfo {
+            lever: String,
+        }
+
+        info!("⚙️ Configuring OKX: Leverage {}x, Isolated", leverage);
+        if let Some(max_leverage) = self.max_leverage {
+            if leverage > max_leverage {
+                return Err(BotError::Config(format!(
+                    "Requested leverage {}x exceeds {} maximum of {}x",
+                    leverage,
+                    okx_inst_id(symbol)?,
+                    max_leverage
+                )));
+            }
+        }
+        let applied: Vec<LeverageInfo> = self
+            .send_signed_request(
+                Method::POST,
+                "/api/v5/account/set-leverage",
+                Some(json!({
+                    "instId": okx_inst_id(symbol)?,
+                    "lever": leverage.to_string(),
+                    "mgnMode": "isolated",
+                })),
+            )
+            .await?;
+        let lever = applied
+            .first()
+            .ok_or_else(|| BotError::Parse("Empty set-leverage response".to_string()))?
+            .lever
+            .parse::<Decimal>()?;
+        lever
+            .to_u8()
+            .ok_or_else(|| BotError::Parse(format!("Unexpected leverage from OKX: {}", lever)))
+    }
+
+    fn contracts(&self, quantity: Decimal) -> Decimal {
+        (quantity / self.ct_val).normalize()
+    }
+
+    fn base_qty(&self, contracts: Decimal) -> Decimal {
+        contracts * self.ct_val
+    }
+
+    /// Подпись OKX: base64(HMAC-SHA256(secret, timestamp + METHOD + requestPath + body))
+    fn sign(
+        &self,
+        timestamp: &str,
+        method: &Method,
+        request_path: &str,
+        body: &str,
+    ) -> Result<String> {
+        let mut mac = HmacSha256::new_from_slice(self.secret_key.as_bytes())
+            .map_err(|_| BotError::Config("Invalid secret key length".to_string()))?;
+        mac.update(timestamp.as_bytes());
+        mac.update(method.as_str().as_bytes());
+        mac.update(request_path.as_bytes());
+        mac.update(body.as_bytes());
+        Ok(BASE64.encode(mac.finalize().into_bytes()))
+    }
+
+    async fn send_public_request<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let response = self
+            .http_client
+            .get(format!("{}{}", self.base_rest_url, endpoint))
+            .query(params)
+            .send()
+            .await?;
+        parse_envelope(response).await
+    }
+
+    /// GET — параметры в query (и в подписи вместе с "?"), POST — JSON-тело
+    async fn send_signed_request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<Vec<T>> {
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+        let signature = self.sign(&timestamp, &method, endpoint, &body)?;
+
+        let mut request = self
+            .http_client
+            .request(method, format!("{}{}", self.base_rest_url, endpoint))
+            .header("OK-ACCESS-KEY", &self.api_key)
+            .header("OK-ACCESS-SIGN", signature)
+            .header("OK-ACCESS-TIMESTAMP", timestamp)
+            .header("OK-ACCESS-PASSPHRASE", &self.passphrase)
+            .header("Content-Type", "application/json");
+        if self.demo {
+            request = request.header("x-simulated-trading", "1");
+        }
+        if !body.is_empty() {
+            request = request.body(body);
+        }
+
+        parse_envelope(request.send().await?).await
+    }
+}
+
+/// Ответ OKX: {"code":"0","msg":"","data":[...]}. HTTP 200 не гарантирует успех — смотрим code
+async fn parse_envelope<T: DeserializeOwned>(response: reqwest::Response) -> Result<Vec<T>> {
+    #[derive(Deserialize)]
+    struct Envelope {
+        code: String,
+        #[serde(default)]
+        msg: String,
+        #[serde(default)]
+        data: serde_json::Value,
+    }
+
+    let status = response.status();
+    let text = response.text().await?;
+    let status = status.as_u16();
+    let envelope: Envelope = serde_json::from_str(&text).map_err(|_| {
+        BotError::Parse(format!(
+            "Unexpected OKX response (HTTP {}): {}",
+            status,
+            text.chars().take(200).collect::<String>()
+        ))
+    })?;
+    if envelope.code != "0" {
+        // Для ордеров настоящая причина лежит в data[0].sCode / sMsg
+        let (code, msg) = envelope
+            .data
+            .get(0)
+            .and_then(|d| Some((d.get("sCode")?.as_str()?, d.get("sMsg")?.as_str()?)))
+            .map(|(code, msg)| (code.to_string(), msg.to_string()))
+            .unwrap_or((envelope.code, envelope.msg));
+        let code_num = code.parse::<i64>().unwrap_or_default();
+        return Err(match (status, code_num) {
+            // 50100..50119 — ключ, подпись, passphrase, timestamp
+            (401, _) | (_, 50100..=50119) => BotError::Auth {
+                code: code_num,
+                msg,
+            },
+            (429, _) | (_, 50011 | 50061) => BotError::RateLimited { status, msg },
+            _ => BotError::Api {
+                status,
+                code: code_num,
+                msg,
+            },
+        });
+    }
+    Ok(serde_json::from_value(envelope.data)?)
+}
+
+/// BTCUSDT -> BTC-USDT-SWAP, BTCUSDC -> BTC-USDC-SWAP. Уже готовый instId (с дефисом)
+/// не трогаем. Другие котировки (в т.ч. инверсные BTCUSD) — ошибка, а не чужой инструмент
+pub fn okx_inst_id(symbol: &str) -> Result<String> {
+    if symbol.contains('-') {
+        return Ok(symbol.to_string());
+    }
+    for quote in ["USDT", "USDC"] {
+        if let Some(base) = symbol.strip_suffix(quote).filter(|b| !b.is_empty()) {
+            return Ok(format!("{}-{}-SWAP", base, quote));
+        }
+    }
+    Err(BotError::Config(format!(
+        "Cannot map {} to an OKX swap: expected a USDT or USDC quote, or an instId like BTC-USDT-SWAP",
+        symbol
+    )))
+}
+
+impl OkxClient {
+    fn order_response(&self, symbol: &str, order: OkxOrder) -> Result<OrderResponse> {
+        Ok(OrderResponse {
+            id: order.ord_id,
+            client_order_id: order.cl_ord_id,
+            symbol: symbol.to_string(),
+            status: okx_order_status(&order.state)?,
+            executed_qty: self.base_qty(order.acc_fill_sz),
+            avg_price: order
+                .avg_px
+                .parse::<Decimal>()
+                .ok()
+                .filter(|price| !price.is_zero()),
+        })
+    }
+}
+
+#[async_trait]
+impl ExecutionHandler for OkxClient {
+    async fn load_symbol(&mut self, symbol: &str, leverage: Option<u8>) -> Result<SymbolPrecision> {
+        self.fetch_instrument(symbol).await?;
+        if let Some(leverage) = leverage {
+            let applied = self.init_futures_settings(symbol, leverage).await?;
+            if applied != leverage {
+                warn!(
+                    "⚠️ Exchange applied leverage {}x instead of requested {}x for {}",
+                    applied,
+                    leverage,
+                    okx_inst_id(symbol)?
+                );
+            }
+        }
+        Ok(self.precision())
+    }
+
+    // Коннектор OKX держит один инструмент из fetch_instrument
+    fn normalize_price(&self, _symbol: &str, price: Decimal) -> Decimal {
+        if self.tick_size.is_zero() {
+            return price.round_dp(2);
+        }
+        (price / self.tick_size).floor() * self.tick_size
+    }
+
+    fn normalize_quantity(&self, _symbol: &str, quantity: Decimal) -> Decimal {
+        if self.step_size.is_zero() {
+            return quantity.round_dp(4);
+        }
+        (quantity / self.step_size).floor() * self.step_size
+    }
+
+    async fn get_balance(&self, asset: &str) -> Result<Decimal> {
+        #[derive(Deserialize)]
+        struct Detail {
+            ccy: String,
+            #[serde(rename = "cashBal")]
+            cash_bal: Decimal,
+        }
+        #[derive(Deserialize)]
+        struct Balance {
+            details: Vec<Detail>,
+        }
+
+        let endpoint = format!("/api/v5/account/balance?ccy={}", asset);
+        let balances: Vec<Balance> = self
+            .send_signed_request(Method::GET, &endpoint, None)
+            .await?;
+
+        balances
+            .iter()
+            .flat_map(|b| b.details.iter())
+            .find(|d| d.ccy == asset)
+            .map(|d| d.cash_bal)
+            .ok_or_else(|| {
+                BotError::Config(format!("Asset {} not found in OKX trading account", asset))
+            })
+    }
+
+    async fn place_order(&self, request: &OrderRequest) -> Result<OrderResponse> {
+        #[derive(Deserialize)]
+        struct Placed {
+            #[serde(rename = "ordId")]
+            ord_id: String,
+        }
+
+        let side = match request.side {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        };
+        // У OKX time-in-force — это сам тип ордера
+        // Стопы и тейк-профиты у OKX — алгоритмические ордера с отдельным API
+        let ord_type = match request.order_type {
+            OrderType::Market => "market",
+            OrderType::Limit { time_in_force, .. } => match time_in_force {
+                TimeInForce::Ioc => "ioc",
+                TimeInForce::Fok => "fok",
+                TimeInForce::Gtx => "post_only",
+                TimeInForce::Gtc => "limit",
+            },
+            OrderType::StopMarket { .. } | OrderType::TakeProfitMarket { .. } => {
+                return Err(BotError::Config(format!(
+                    "{:?} orders are not supported on OKX",
+                    request.order_type
+                )));
+            }
+        };
+
+        let mut body = json!({
+            "instId": okx_inst_id(&request.symbol)?,
+            "tdMode": "isolated",
+            "side": side,
+            "ordType": ord_type,
+            "sz": self.contracts(request.quantity).to_string(),
+            "clOrdId": request.client_order_id,
+        });
+        if let Some(price) = request.order_type.limit_price() {
+            body["px"] = json!(price.to_string());
+        }
+        if request.reduce_only {
+            body["reduceOnly"] = json!(true);
+        }
+
+        METRICS.orders_placed.inc();
+        let placed = self
+            .send_signed_request::<Placed>(Method::POST, "/api/v5/trade/order", Some(body))
+            .await
+            .and_then(|placed| {
+                placed
+                    .into_iter()
+                    .next()
+                    .map(|p| p.ord_id)
+                    .ok_or_else(|| BotError::Parse("Empty OKX order response".to_string()))
+            });
+        let ord_id = match placed {
+            Ok(ord_id) => ord_id,
+            Err(e) => {
+                METRICS.orders_rejected.inc();
+                return Err(e.into_order_rejection());
+            }
+        };
+
+        // OKX отвечает только ordId: статус и исполненный объём берём запросом ордера
+        let order = self
+            .query_order(&request.symbol, &ord_id)
+            .await
+            .inspect_err(|_| METRICS.orders_rejected.inc())?;
+        if order.status.is_filled() {
+            METRICS.orders_filled.inc();
+        } else if !order.status.is_resting() {
+            METRICS.orders_rejected.inc();
+        }
+        Ok(order)
+    }
+
+    async fn cancel_order(&self, symbol: &str, order_id: &str) -> Result<()> {
+        let _: Vec<serde_json::Value> = self
+            .send_signed_request(
+                Method::POST,
+                "/api/v5/trade/cancel-order",
+                Some(json!({ "instId": okx_inst_id(symbol)?, "ordId": order_id })),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn query_order(&self, symbol: &str, order_id: &str) -> Result<OrderResponse> {
+        let endpoint = format!(
+            "/api/v5/trade/order?instId={}&ordId={}",
+            okx_inst_id(symbol)?,
+            order_id
+        );
+        let orders: Vec<OkxOrder> = self
+            .send_signed_request(Method::GET, &endpoint, None)
+            .await?;
+        let order = orders
+            .into_iter()
+            .next()
+            .ok_or_else(|| BotError::Parse(format!("Order {} not found on OKX", order_id)))?;
+        self.order_response(symbol, order)
+    }
+
+    async fn fetch_position(&self, symbol: &str) -> Result<Option<ExchangePosition>> {
+        #[derive(Deserialize)]
+        struct OkxPosition {
+            // Контракты; в net-режиме знаковые, в long/short — по posSide
+            pos: Decimal,
+            #[serde(rename = "posSide")]
+            pos_side: String,
+            #[serde(rename = "avgPx", default)]
+            avg_px: String,
+        }
+
+        let endpoint = format!(
+            "/api/v5/account/positions?instType=SWAP&instId={}",
+            okx_inst_id(symbol)?
+        );
+        let positions: Vec<OkxPosition> = self
+            .send_signed_request(Method::GET, &endpoint, None)
+            .await?;
+        let mut amount = Decimal::ZERO;
+        let mut entry_price = Decimal::ZERO;
+        for p in positions.iter().filter(|p| !p.pos.is_zero()) {
+            let contracts = if p.pos_side == "short" {
+                -p.pos.abs()
+            } else {
+                p.pos
+            };
+            amount += self.base_qty(contracts);
+            entry_price = Decimal::from_str(&p.avg_px).unwrap_or_default();
+        }
+        Ok(Some(ExchangePosition {
+            amount,
+            entry_price,
+        }))
+    }
+
+    async fn query_order_by_client_id(
+        &self,
+        symbol: &str,
+        client_order_id: &str,
+    ) -> Result<Option<OrderResponse>> {
+        let endpoint = format!(
+            "/api/v5/trade/order?instId={}&clOrdId={}",
+            okx_inst_id(symbol)?,
+            client_order_id
+        );
+        let orders: Vec<OkxOrder> =
+            match self.send_signed_request(Method::GET, &endpoint, None).await {
+                Ok(orders) => orders,
+                // 51603 Order does not exist
+                Err(BotError::Api { code: 51603, .. }) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+        orders
+            .into_iter()
+            .next()
+            .map(|order| self.order_response(symbol, order))
+            .transpose()
+    }
+}
+
+#[derive(Deserialize)]
+struct OkxOrder {
+    #[serde(rename = "ordId")]
+    ord_id: String,
+    #[serde(rename = "clOrdId", default)]
+    cl_ord_id: String,
+    state: String,
+    // Исполнено в контрактах
+    #[serde(rename = "accFillSz")]
+    acc_fill_sz: Decimal,
+    // Средняя цена исполнения; "" — ничего не исполнено
+    #[serde(rename = "avgPx", default)]
+    avg_px: String,
+}
+
+fn okx_order_status(state: &str) -> Result<OrderStatus> {
+    match state {
+        "live" => Ok(OrderStatus::New),
+        "partially_filled" => Ok(OrderStatus::PartiallyFilled),
+        "filled" => Ok(OrderStatus::Filled),
+        "canceled" | "mmp_canceled" => Ok(OrderStatus::Canceled),
+        other => Err(BotError::OrderRejected {
+            code: 0,
+            msg: format!("OKX order has unexpected state: {}", other),
+        }),
+    }
+}
+
+#[async_trait]
+impl HistoryProvider for OkxClient {
+    async fn fetch_klines(&self, symbol: &str, interval: &str, limit: usize) -> Result<Vec<Kline>> {
+        // Строки: [ts, o, h, l, c, vol, volCcy, volCcyQuote, confirm], новые первыми
+        let rows: Vec<Vec<String>> = self
+            .send_public_request(
+                "/api/v5/market/candles",
+                &[
+                    ("instId", &okx_inst_id(symbol)?),
+                    ("bar", &okx_bar(interval)),
+                    ("limit", &limit.min(300).to_string()),
+                ],
+            )
+            .await?;
+        let bar_ms = interval_ms(interval)?;
+
+        let mut klines = rows
+            .iter()
+            .map(|row| {
+                let field = |idx: usize| {
+                    row.get(idx).ok_or_else(|| {
+                        BotError::Parse(format!("Candle field #{} missing: {:?}", idx, row))
+                    })
+                };
+                let decimal_at =
+                    |idx: usize| -> Result<Decimal> { Ok(Decimal::from_str(field(idx)?)?) };
+                let open_time: u64 = field(0)?.parse()?;
+                Ok(Kline {
+                    open_time,
+                    open: decimal_at(1)?,
+                    high: decimal_at(2)?,
+                    low: decimal_at(3)?,
+                    close: decimal_at(4)?,
+                    volume: self.base_qty(decimal_at(5)?),
+                    close_time: open_time + bar_ms - 1,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        klines.reverse();
+        Ok(klines)
+    }
+}
+
+// Binance-интервалы ("1m", "1h", "1d") -> OKX bar ("1m", "1H", "1D")
+fn okx_bar(interval: &str) -> String {
+    if interval.ends_with('m') {
+        interval.to_string()
+    } else {
+        interval.to_uppercase()
+    }
+}
+
+fn interval_ms(interval: &str) -> Result<u64> {
+    let (count, unit) = interval.split_at(interval.len().saturating_sub(1));
+    let bad_interval = || BotError::Config(format!("Unsupported kline interval {}", interval));
+    let count: u64 = count.parse().map_err(|_| bad_interval())?;
+    let unit_ms = match unit {
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return Err(bad_interval()),
+    };
+    Ok(count * unit_ms)
+}
+
+// Канал tickers: лучший bid/ask с объёмами, как bookTicker у Binance
+#[derive(Debug, Deserialize)]
+struct OkxTickerPush {
+    data: Vec<OkxTickerData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OkxTickerData {
+    #[serde(rename = "bidPx")]
+    bid_px: Decimal,
+    #[serde(rename = "bidSz")]
+    bid_sz: Decimal,
+    #[serde(rename = "askPx")]
+    ask_px: Decimal,
+    #[serde(rename = "askSz")]
+    ask_sz: Decimal,
+    ts: String,
+}
+
+impl OkxTickerData {
+    fn to_ticker(&self, symbol: &str, ct_val: Decimal) -> Option<Ticker> {
+        Some(Ticker {
+            symbol: symbol.to_string(),
+            price: (self.bid_px + self.ask_px) / Decimal::from(2),
+            bid_price: self.bid_px,
+            ask_price: self.ask_px,
+            // Объёмы в контрактах -> базовый актив
+            bid_qty: self.bid_sz * ct_val,
+            ask_qty: self.ask_sz * ct_val,
+            timestamp: self.ts.parse().ok()?,
+        })
+    }
+}
+
+#[async_trait]
+impl StreamClient for OkxClient {
+    async fn subscribe_ticker(&mut self, symbol: &str, sender: RingSender<Ticker>) -> Result<()> {
+        // Объёмы в тикере пересчитываются через ctVal: при смене символа нужен новый инструмент
+        if self.instrument.as_deref() != Some(okx_inst_id(symbol)?.as_str()) {
+            self.fetch_instrument(symbol).await?;
+        }
+        let subscribe = json!({
+            "op": "subscribe",
+            "args": [{ "channel": "tickers", "instId": okx_inst_id(symbol)? }],
+        })
+        .to_string();
+        let url = self.base_ws_url.clone();
+        let symbol = symbol.to_string();
+        let ws_status = self.ws_status.clone();
+        let ct_val = self.ct_val;
+
+        info!("🔌 Initializing OKX WebSocket connection for {}...", symbol);
+
+        self.unsubscribe();
+        let task = tokio::spawn(async move {
+            let mut first_attempt = true;
+            let mut reconnects = ws_status.reconnect_budget();
+            loop {
+                if !first_attempt {
+                    METRICS.ws_reconnects.inc();
+                }
+                first_attempt = false;
+                ws_status.reconnecting();
+                info!("Connecting to WS: {}", url);
+                match connect_async(url.as_str()).await {
+                    Ok((ws_stream, _)) => {
+                        let (mut write, mut read) = ws_stream.split();
+                        if let Err(e) = write.send(Message::Text(subscribe.clone())).await {
+                            error!("❌ OKX subscribe failed: {}. Reconnecting...", e);
+                            if !reconnects.lost(&format!("OKX {} tickers", symbol)) {
+                                return;
+                            }
+                            sleep(Duration::from_secs(5)).await;
+                            continue;
+                        }
+                        info!("✅ OKX WS Connected: {}", symbol);
+                        reconnects.connected();
+                        let mut ping = interval(WS_PING_INTERVAL);
+                        ping.tick().await; // первый тик срабатывает сразу
+
+                        loop {
+                            tokio::select! {
+                                _ = ping.tick() => {
+                                    if let Err(e) = write.send(Message::Text("ping".to_string())).await {
+                                        error!("❌ OKX ping failed: {}. Reconnecting...", e);
+                                        break;
+                                    }
+                                }
+                                msg = read.next() => {
+                                    let text = match msg {
+                                        Some(Ok(msg)) => match msg.to_text() {
+                                            Ok(text) => text.to_string(),
+                                            Err(_) => continue,
+                                        },
+                                        Some(Err(e)) => {
+                                            error!("❌ OKX WS Read Error: {}. Reconnecting...", e);
+                                            break;
+                                        }
+                                        None => break,
+                                    };
+                                    // "pong" и события subscribe/error приходят без data
+                                    let Ok(push) = serde_json::from_str::<OkxTickerPush>(&text) else {
+                                        if text.contains("\"event\":\"error\"") {
+                                            warn!("⚠️ OKX WS error: {}", text);
+                                        }
+                                        continue;
+                                    };
+                                    for data in &push.data {
+                                        let Some(ticker) = data.to_ticker(&symbol, ct_val) else {
+                                            continue;
+                                        };
+                                        match sender.send(ticker) {
+                                            Ok(true) => METRICS.ticks_dropped.inc(),
+                                            Ok(false) => {}
+                                            Err(_) => {
+                                                warn!("Ticker receiver closed. Stopping OKX WS task");
+                                                return;
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        warn!("⚠️ OKX WS Stream ended. Reconnecting...");
+                    }
+                    Err(e) => {
+                        error!("❌ OKX WS Connection Failed: {}. Retrying in 5s...", e);
+                    }
+                }
+                if !reconnects.lost(&format!("OKX {} tickers", symbol)) {
+                    return;
+                }
+                sleep(Duration::from_secs(5)).await;
+            }
+        });
+        self.ws_task = Some(task.abort_handle());
+
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self) {
+        if let Some(task) = self.ws_task.take() {
+            task.abort();
+            info!("🔌 WS ticker stream closed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_matches_the_okx_docs_prehash() {
+        // Прехеш из документации OKX: timestamp + method + requestPath + body.
+        // Ожидаемые подписи посчитаны отдельно (HMAC-SHA256, base64)
+        let client = OkxClient::new(
+            "key".to_string(),
+            "22582BD0CFF14C41EDBF1AB98506286D".to_string(),
+            "passphrase".to_string(),
+        );
+        let timestamp = "2020-12-08T09:08:57.715Z";
+        assert_eq!(
+            client
+                .sign(
+                    timestamp,
+                    &Method::GET,
+                    "/api/v5/account/balance?ccy=BTC",
+                    ""
+                )
+                .unwrap(),
+            "HiZhvSfMtWJA3uUIVXV3a/bSXNPCWvYFXoGCVS8V4zY="
+        );
+        let body = r#"{"instId":"BTC-USDT-SWAP","tdMode":"isolated","side":"buy","ordType":"limit","px":"64000","sz":"1"}"#;
+        assert_eq!(
+            client
+                .sign(timestamp, &Method::POST, "/api/v5/trade/order", body)
+                .unwrap(),
+            "hJJeSYPfnOGtP+MTWj2XwjnXDDx5DTriEWISgbRxarU="
+        );
+    }
+
+    #[test]
+    fn okx_state_maps_to_order_status() {
+        let cases = [
+            ("live", OrderStatus::New),
+            ("partially_filled", OrderStatus::PartiallyFilled),
+            ("filled", OrderStatus::Filled),
+            ("canceled", OrderStatus::Canceled),
+            ("mmp_canceled", OrderStatus::Canceled),
+        ];
+        for (state, expected) in cases {
+            assert_eq!(okx_order_status(state).unwrap(), expected, "{}", state);
+        }
+        assert!(matches!(
+            okx_order_status("unknown"),
+            Err(BotError::OrderRejected { .. })
+        ));
+    }
+
+    #[test]
+    fn symbol_maps_to_a_swap_with_the_same_quote() {
+        assert_eq!(okx_inst_id("BTCUSDT").unwrap(), "BTC-USDT-SWAP");
+        assert_eq!(okx_inst_id("BTCUSDC").unwrap(), "BTC-USDC-SWAP");
+        assert_eq!(okx_inst_id("ETH-USDC-SWAP").unwrap(), "ETH-USDC-SWAP");
+
+        // Инверсный BTCUSD и прочие котировки не угадываем
+        for symbol in ["BTCUSD", "ETHBTC", "USDT"] {
+            assert!(
+                matches!(okx_inst_id(symbol), Err(BotError::Config(_))),
+                "{}",
+                symbol
+            );
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// src/connectors/parse_stats.rs
+use crate::metrics::METRICS;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+// Образец непрочитанного сообщения в debug-логе — не чаще раза в интервал
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+// Окно, за которое считается доля ошибок разбора
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+// Доля ошибок в окне, после которой поток, похоже, сменил формат
+const ALERT_FAILURE_RATE: f64 = 0.5;
+// Меньше сообщений в окне — доля ничего не говорит (пара служебных сообщений после коннекта)
+const MIN_WINDOW_MESSAGES: u64 = 20;
+// Длина образца в логе
+const SAMPLE_CHARS: usize = 300;
+
+/// Счётчик ошибок разбора WS-сообщений одного потока: метрика, редкие образцы
+/// в debug-логе и предупреждение, если не читается большая часть сообщений
+pub struct ParseFailureMonitor {
+    stream: String,
+    window_start: Instant,
+    messages: u64,
+    failures: u64,
+    last_sample: Option<Instant>,
+}
+
+impl ParseFailureMonitor {
+    pub fn new(stream: impl Into<String>) -> Self {
+        Self {
+            stream: stream.into(),
+            window_start: Instant::now(),
+            messages: 0,
+            failures: 0,
+            last_sample: None,
+        }
+    }
+
+    pub fn record_ok(&mut self) {
+        self.messages += 1;
+        self.roll_window();
+    }
+
+    pub fn record_failure(&mut self, payload: &str, error: &serde_json::Error) {
+        METRICS.ws_parse_failures.inc();
+        self.messages += 1;
+        self.failures += 1;
+
+        if self
+            .last_sample
+            .is_none_or(|at| at.elapsed() >= SAMPLE_INTERVAL)
+        {
+            self.last_sample = Some(Instant::now());
+            debug!(
+                stream = %self.stream,
+                error = %error,
+                "Unparsed WS message: {}",
+                payload.chars().take(SAMPLE_CHARS).collect::<String>()
+            );
+        }
+        self.roll_window();
+    }
+
+    /// По окончании окна проверяет долю ошибок и начинает новое
+    fn roll_window(&mut self) {
+        if self.window_start.elapsed() < RATE_WINDOW {
+            return;
+        }
+        if self.messages >= MIN_WINDOW_MESSAGES {
+            let rate = self.failures as f64 / self.messages as f64;
+            if rate > ALERT_FAILURE_RATE {
+                warn!(
+                    "⚠️ {}: {} of {} WS messages failed to parse in the last {}s. The stream format may have changed",
+                    self.stream,
+                    self.failures,
+                    self.messages,
+                    RATE_WINDOW.as_secs()
+                );
+            }
+        }
+        self.window_start = Instant::now();
+        self.messages = 0;
+        self.failures = 0;
+    }
+}
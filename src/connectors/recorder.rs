@@ -0,0 +1,160 @@
+// src/connectors/recorder.rs
+use crate::connectors::messages::{MarketEvent, TickerMerger};
+use crate::connectors::traits::StreamClient;
+use crate::types::Ticker;
+use crate::utils::error::{BotError, Result};
+use crate::utils::ring_channel::RingSender;
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, warn};
+
+// Формат записи: одна строка на WS-сообщение, `<recv_ts_ms>\t<raw json>`.
+// Сырой JSON не трогаем, чтобы при replay парсинг шёл тем же кодом, что и вживую.
+
+/// Пишет сырые WS-сообщения в файл. Запись идёт в отдельной задаче,
+/// поэтому WS-цикл не ждёт диск.
+#[derive(Clone)]
+pub struct Recorder {
+    sender: mpsc::UnboundedSender<String>,
+}
+
+impl Recorder {
+    /// Открывает файл на дозапись и запускает задачу-писатель
+    pub async fn open(path: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| BotError::io(format!("Failed to open record file {}", path), e))?;
+        let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+
+        info!("⏺️ Recording raw WS messages to {}", path);
+        let path = path.to_string();
+        tokio::spawn(async move {
+            let mut writer = BufWriter::new(file);
+            while let Some(line) = receiver.recv().await {
+                if let Err(e) = writer.write_all(line.as_bytes()).await {
+                    error!("Failed to write record file {}: {}", path, e);
+                    return;
+                }
+                // Сбрасываем, когда очередь опустела: при падении теряется минимум
+                if receiver.is_empty() {
+                    if let Err(e) = writer.flush().await {
+                        error!("Failed to flush record file {}: {}", path, e);
+                        return;
+                    }
+                }
+            }
+            let _ = writer.flush().await;
+        });
+
+        Ok(Self { sender })
+    }
+
+    pub fn record(&self, raw: &str) {
+        let line = format!("{}\t{}\n", Utc::now().timestamp_millis(), raw.trim_end());
+        let _ = self.sender.send(line);
+    }
+}
+
+/// Строка записи -> (время получения, событие). None — битая строка или служебное сообщение
+/// (вживую они так же игнорируются)
+pub fn parse_record_line(line: &str) -> Option<(u64, MarketEvent)> {
+    let (ts, raw) = line.split_once('\t')?;
+    let recv_ts = ts.parse::<u64>().ok()?;
+    let event = MarketEvent::parse(raw).ok()?;
+    Some((recv_ts, event))
+}
+
+/// Скорость воспроизведения: как записано или без пауз
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    // 1.0 = реальное время, 2.0 = вдвое быстрее
+    Realtime(f64),
+    AsFastAsPossible,
+}
+
+/// Источник тиков из файла записи вместо WebSocket.
+/// Тики отдаются без потерь (send_wait), так что стратегия видит ровно то, что прислала биржа.
+pub struct ReplayClient {
+    path: String,
+    speed: ReplaySpeed,
+}
+
+impl ReplayClient {
+    pub fn new(path: String, speed: ReplaySpeed) -> Self {
+        Self { path, speed }
+    }
+}
+
+#[async_trait]
+impl StreamClient for ReplayClient {
+    async fn subscribe_ticker(&mut self, symbol: &str, sender: RingSender<Ticker>) -> Result<()> {
+        let file = File::open(&self.path)
+            .await
+            .map_err(|e| BotError::io(format!("Failed to open replay file {}", self.path), e))?;
+        let symbol = symbol.to_string();
+        let path = self.path.clone();
+        let speed = self.speed;
+
+        info!("⏯️ Replaying {} for {} at {:?}", path, symbol, speed);
+
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(file).lines();
+            let mut prev_recv_ts: Option<u64> = None;
+            let mut replayed = 0u64;
+            let mut skipped = 0u64;
+            // Запись любого data_stream: bookTicker, trade или оба вперемешку
+            let mut merger = TickerMerger::default();
+
+            loop {
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Replay read error in {}: {}", path, e);
+                        break;
+                    }
+                };
+
+                let Some((recv_ts, event)) = parse_record_line(&line) else {
+                    skipped += 1;
+                    continue;
+                };
+
+                if let (ReplaySpeed::Realtime(factor), Some(prev)) = (speed, prev_recv_ts) {
+                    let gap_ms = recv_ts.saturating_sub(prev) as f64 / factor;
+                    if gap_ms >= 1.0 {
+                        sleep(Duration::from_millis(gap_ms as u64)).await;
+                    }
+                }
+                prev_recv_ts = Some(recv_ts);
+
+                if sender
+                    .send_wait(merger.on_event(event, &symbol, recv_ts))
+                    .await
+                    .is_err()
+                {
+                    warn!("Ticker receiver closed. Stopping replay");
+                    return;
+                }
+                replayed += 1;
+            }
+
+            info!(
+                "⏹️ Replay finished: {} tickers, {} lines skipped",
+                replayed, skipped
+            );
+        });
+
+        Ok(())
+    }
+
+    // Replay идёт по одному файлу от начала до конца, символ не меняется
+    fn unsubscribe(&mut self) {}
+}
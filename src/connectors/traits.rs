@@ -1,29 +1,52 @@
-use crate::types::{OrderResponse, Side, Ticker};
-use anyhow::Result;
+use crate::types::{ExchangePosition, Kline, OrderRequest, OrderResponse, Ticker};
+use crate::utils::error::Result;
+use crate::utils::precision::SymbolPrecision;
+use crate::utils::ring_channel::RingSender;
 use async_trait::async_trait;
 use rust_decimal::Decimal;
-use tokio::sync::mpsc;
 
 #[async_trait]
 pub trait StreamClient: Send + Sync {
-    async fn subscribe_ticker(&mut self, symbol: &str, sender: mpsc::Sender<Ticker>) -> Result<()>;
+    // Sender вытесняет старые тики при переполнении (latest-wins)
+    async fn subscribe_ticker(&mut self, symbol: &str, sender: RingSender<Ticker>) -> Result<()>;
+
+    // Останавливает задачу последней подписки (смена символа): старый поток не должен
+    // переподключаться и слать тики после перехода на новый символ
+    fn unsubscribe(&mut self);
 }
 
 #[async_trait]
 pub trait ExecutionHandler: Send + Sync {
     async fn get_balance(&self, asset: &str) -> Result<Decimal>;
 
-    async fn place_order(
+    async fn place_order(&self, request: &OrderRequest) -> Result<OrderResponse>;
+
+    async fn cancel_order(&self, symbol: &str, order_id: &str) -> Result<()>;
+
+    // Текущий статус ордера (для висящих maker-ордеров)
+    async fn query_order(&self, symbol: &str, order_id: &str) -> Result<OrderResponse>;
+
+    // Ордер по нашему client order id. None — биржа такого ордера не знает
+    async fn query_order_by_client_id(
         &self,
         symbol: &str,
-        side: Side,
-        amount: Decimal,
-        price: Option<Decimal>,
-    ) -> Result<OrderResponse>;
+        client_order_id: &str,
+    ) -> Result<Option<OrderResponse>>;
 
-    async fn cancel_order(&self, symbol: &str, order_id: &str) -> Result<()>;
+    // Открытая позиция по символу (объём 0 — позиции нет). None — у рынка нет позиций (спот)
+    async fn fetch_position(&self, symbol: &str) -> Result<Option<ExchangePosition>>;
+
+    // Смена символа на ходу: загружает фильтры нового символа и, если leverage задано,
+    // выставляет его на бирже (фьючерсы). Фильтры возвращаются без переопределений из конфига
+    async fn load_symbol(&mut self, symbol: &str, leverage: Option<u8>) -> Result<SymbolPrecision>;
 
-    // New helper methods for dynamic precision
-    fn normalize_price(&self, price: Decimal) -> Decimal;
-    fn normalize_quantity(&self, quantity: Decimal) -> Decimal;
+    // Округление под фильтры символа, загруженные коннектором
+    fn normalize_price(&self, symbol: &str, price: Decimal) -> Decimal;
+    fn normalize_quantity(&self, symbol: &str, quantity: Decimal) -> Decimal;
+}
+
+#[async_trait]
+pub trait HistoryProvider: Send + Sync {
+    // Last `limit` klines for the interval, oldest first (may include the still-forming one)
+    async fn fetch_klines(&self, symbol: &str, interval: &str, limit: usize) -> Result<Vec<Kline>>;
 }
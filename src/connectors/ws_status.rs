@@ -0,0 +1,114 @@
+// src/connectors/ws_status.rs
+use crate::types::{EngineCommand, UiEvent};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::error;
+
+// Соединение, прожившее столько, считается стабильным: счётчик неудачных попыток сбрасывается
+const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+
+/// Состояние потока котировок для индикатора в статус-баре TUI. Без получателя ничего не шлёт.
+/// try_send: цикл переподключения не ждёт занятый интерфейс
+#[derive(Clone, Default)]
+pub struct WsStatusReporter {
+    sender: Option<mpsc::Sender<UiEvent>>,
+    // Движок: получает StreamFailed, когда попытки переподключения исчерпаны
+    engine: Option<mpsc::Sender<EngineCommand>>,
+    // None — переподключаться бесконечно
+    max_reconnect_attempts: Option<u32>,
+}
+
+impl WsStatusReporter {
+    pub fn new(sender: mpsc::Sender<UiEvent>) -> Self {
+        Self {
+            sender: Some(sender),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_engine(mut self, engine: mpsc::Sender<EngineCommand>) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    pub fn with_max_reconnect_attempts(mut self, max_reconnect_attempts: Option<u32>) -> Self {
+        self.max_reconnect_attempts = max_reconnect_attempts;
+        self
+    }
+
+    /// Счётчик попыток для одной задачи потока
+    pub fn reconnect_budget(&self) -> ReconnectBudget {
+        ReconnectBudget {
+            status: self.clone(),
+            failures: 0,
+            connected_at: None,
+        }
+    }
+
+    pub fn connected(&self) {
+        self.send(UiEvent::WsConnected);
+    }
+
+    pub fn disconnected(&self) {
+        self.send(UiEvent::WsDisconnected);
+    }
+
+    pub fn reconnecting(&self) {
+        self.send(UiEvent::WsReconnecting);
+    }
+
+    /// Поток брошен: статус в TUI и команда остановки движку
+    fn failed(&self, reason: String) {
+        error!("💀 {}. Giving up", reason);
+        self.send(UiEvent::WsFailed);
+        if let Some(engine) = &self.engine {
+            let _ = engine.try_send(EngineCommand::StreamFailed(reason));
+        }
+    }
+
+    fn send(&self, event: UiEvent) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.try_send(event);
+        }
+    }
+}
+
+/// Неудачные подключения подряд (max_reconnect_attempts). Обрыв после стабильного
+/// соединения начинает счёт заново
+pub struct ReconnectBudget {
+    status: WsStatusReporter,
+    failures: u32,
+    connected_at: Option<Instant>,
+}
+
+impl ReconnectBudget {
+    pub fn connected(&mut self) {
+        self.connected_at = Some(Instant::now());
+        self.status.connected();
+    }
+
+    /// Соединение оборвалось или не установилось. false — попытки исчерпаны, движку
+    /// отправлен StreamFailed, задача потока должна завершиться
+    pub fn lost(&mut self, stream: &str) -> bool {
+        self.status.disconnected();
+        if self
+            .connected_at
+            .take()
+            .is_some_and(|at| at.elapsed() >= STABLE_CONNECTION)
+        {
+            self.failures = 0;
+        }
+        self.failures += 1;
+        match self.status.max_reconnect_attempts {
+            // Первый обрыв — ещё не попытка переподключения
+            Some(max) if self.failures > max => {
+                self.status.failed(format!(
+                    "{}: no stable connection after {} reconnect attempts",
+                    stream, max
+                ));
+                false
+            }
+            _ => true,
+        }
+    }
+}
@@ -1,19 +1,113 @@
 // src/core/engine.rs
-use crate::config::AppConfig;
-use crate::connectors::traits::ExecutionHandler;
+use crate::config::{
+    AppConfig, CorrelationGroup, OrderExecution, OrderTimeoutAction, StrategyConfig, TradingMode,
+};
+use crate::connectors::traits::{ExecutionHandler, StreamClient};
+use crate::core::ledger::{TradeLedger, TradeRecord};
+use crate::core::paper::{FillModel, PaperWallet};
+use crate::core::persistence::StatePersister;
+use crate::core::risk::{
+    entry_slippage_exceeded, fill_slippage_bps, funding_blocks_entry, hard_stop_hit, spread_bps,
+    DailyLossGuard, DepegGuard, DrawdownGuard, EntryThrottle, PriceSpikeGuard, TickCheck,
+};
+use crate::metrics::METRICS;
+use crate::storage::Store;
+use crate::strategies::scalper::CANDLE_MS;
 use crate::strategies::traits::Strategy;
-use crate::types::{Position, Side, Signal, Ticker, UiEvent};
-use crate::utils::precision::{normalize_price, normalize_quantity}; // Импорт утилит
-use anyhow::Result;
-use rust_decimal::prelude::{FromPrimitive, ToPrimitive}; // Добавлен ToPrimitive для логирования если нужно
+use crate::types::{
+    AccountEvent, EngineCommand, EngineEvent, FundingInfo, Inventory, OrderRequest, OrderResponse,
+    OrderStatus, OrderType, PendingOrder, Position, PositionSide, RiskSnapshot, Side, Signal,
+    Ticker, TimeInForce, UiEvent,
+};
+use crate::utils::clock::{Clock, RealClock};
+use crate::utils::error::{BinanceErrorKind, BotError, Result};
+use crate::utils::fees::Liquidity;
+use crate::utils::math::ONE_TENTH_PCT;
+use crate::utils::order_id::new_client_order_id;
+use crate::utils::precision::{
+    bump_to_min_notional, normalize_price, normalize_quantity, SymbolPrecision,
+}; // Импорт утилит
+use crate::utils::ring_channel::{ring_channel, RingReceiver};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
-use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+
+// Как часто опрашивать висящий maker-ордер
+const PENDING_POLL_MS: u64 = 1_000;
+// Сколько раз перевыставлять вход по таймауту, прежде чем отказаться
+const MAX_ENTRY_REPRICES: u32 = 3;
+// Запас к минимальному нотионалу символа: цена успеет сдвинуться до исполнения (+10%)
+const MIN_NOTIONAL_MARGIN: Decimal = Decimal::from_parts(11, 0, 0, false, 1);
+// Как часто dead man's switch проверяет поток тиков (и повторяет неудачное закрытие)
+const DEAD_MAN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// Сколько верить балансу счёта для risk.min_account_balance_usdt: не REST-запрос на каждый вход
+const BALANCE_CACHE_MS: u64 = 30_000;
+
+// Опциональный канал (аккаунт, перезагрузка конфига): без него ветка select никогда не срабатывает
+async fn next_event<T>(receiver: &mut Option<mpsc::Receiver<T>>) -> Option<T> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Ордер одной строкой для лога TUI: всё, что уходит на биржу, кроме client order id
+fn describe_order(request: &OrderRequest) -> String {
+    let order_type = match request.order_type {
+        OrderType::Limit {
+            price,
+            time_in_force,
+        } => format!("LIMIT {} {}", price, time_in_force.as_str()),
+        OrderType::Market => "MARKET".to_string(),
+        OrderType::StopMarket { trigger } => format!("STOP_MARKET @ {}", trigger),
+        OrderType::TakeProfitMarket { trigger } => format!("TAKE_PROFIT_MARKET @ {}", trigger),
+    };
+    format!(
+        "{} {:?} {} {}{}",
+        request.symbol,
+        request.side,
+        request.quantity,
+        order_type,
+        if request.reduce_only {
+            " reduce-only"
+        } else {
+            ""
+        }
+    )
+}
+
+fn new_spike_guard(config: &AppConfig) -> PriceSpikeGuard {
+    PriceSpikeGuard::new(
+        config.risk.max_tick_move_pct,
+        config.risk.flash_move_pct,
+        config.risk.flash_window_seconds * 1000,
+        config.risk.flash_halt_seconds * 1000,
+    )
+}
+
+/// Бумажный ордер в пути: исполнится по первому тику не раньше due_at (время тиков)
+#[derive(Debug)]
+struct DelayedPaperOrder {
+    side: Side,
+    quantity: Decimal,
+    is_exit: bool,
+    signal_price: Decimal,
+    due_at: u64,
+    // Локальное время отправки: таймаут, если тики перестали приходить
+    sent_at: Instant,
+}
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct EngineState {
+    #[serde(default)]
+    inventory: Inventory,
+    // Старый формат файла: одна позиция без инвентаря. Только читается
+    #[serde(default, skip_serializing)]
     active_position: Option<Position>,
 }
 
@@ -21,10 +115,66 @@ pub struct TradingEngine<S> {
     config: AppConfig,
     execution_handler: Box<dyn ExecutionHandler>,
     strategy: S,
-    ticker_receiver: mpsc::Receiver<Ticker>,
+    ticker_receiver: RingReceiver<Ticker>,
+    // Источник котировок для смены символа из TUI. None — символ фиксирован (replay)
+    stream_client: Option<Box<dyn StreamClient>>,
+    // Текущий символ для фоновых задач (опрос funding)
+    symbol_sender: Option<watch::Sender<String>>,
     ui_sender: mpsc::Sender<UiEvent>,
-    live_mode: bool,
+    command_receiver: mpsc::Receiver<EngineCommand>,
+    mode: TradingMode,
+    // Время для дневного лимита, funding и меток сделок: системное или по тикам (бэктест)
+    clock: Arc<dyn Clock>,
     state_file: String,
+    // Фоновая запись состояния, запускается в run()
+    persister: Option<StatePersister>,
+    // Позиции в sled (store_path): читаются при старте раньше файла состояния
+    store: Option<Arc<dyn Store>>,
+    // Журнал закрытых сделок (trades.jsonl)
+    trade_ledger: Option<TradeLedger>,
+    // Входы, выходы и остановки для уведомлений
+    event_sender: Option<broadcast::Sender<EngineEvent>>,
+    daily_loss: DailyLossGuard,
+    entry_throttle: EntryThrottle,
+    paper_wallet: PaperWallet,
+    // Модель исполнения бэктеста; None — как в paper (стакан + проскальзывание [paper])
+    fill_model: Option<Box<dyn FillModel>>,
+    funding_feed: Option<watch::Receiver<Option<FundingInfo>>>,
+    depeg_guard: DepegGuard,
+    drawdown_guard: DrawdownGuard,
+    spike_guard: PriceSpikeGuard,
+    // Equity на старте сессии и реализованный с тех пор PnL (для live, в paper есть кошелёк)
+    start_equity: Decimal,
+    session_realized: Decimal,
+    // Отказы биржи по марже (-2019) подряд; после лимита входы остановлены
+    margin_rejections: u32,
+    margin_halted: bool,
+    // Когда пришёл последний тик (локальное время: биржевое в тике стоит вместе с потоком)
+    last_tick_at: Instant,
+    // Dead man's switch сработал: входы остановлены до перезапуска
+    dead_man_halted: bool,
+    // Ручная пауза входов из TUI
+    paused: bool,
+    // Цена эталонного стейблкоина (risk.depeg_symbol)
+    depeg_feed: Option<watch::Receiver<Option<Decimal>>>,
+    strategy_enabled: bool,
+    last_ticker: Option<Ticker>,
+    // Портфель: баланс и позиции по символам. Источник истины для файла состояния,
+    // стратегия получает из него позицию своего символа
+    inventory: Inventory,
+    // Лимитный ордер, который стоит в стакане (maker-режим)
+    pending_order: Option<PendingOrder>,
+    last_pending_poll: u64,
+    // Когда движок последний раз отправил (или исполнил в paper) ордер, мс (risk.min_order_interval_ms)
+    last_order_at: Option<u64>,
+    // Бумажный ордер, который ещё "летит" до биржи (paper.simulated_latency_ms)
+    delayed_paper: Option<DelayedPaperOrder>,
+    // Баланс счёта (мс получения, в quote_asset) для risk.min_account_balance_usdt, только live/dry-run
+    balance_cache: Option<(u64, Decimal)>,
+    // Позиции и ордера с биржи (user-data stream), только в live
+    account_receiver: Option<mpsc::Receiver<AccountEvent>>,
+    // Новые параметры стратегии при изменении Settings.toml
+    config_receiver: Option<mpsc::Receiver<StrategyConfig>>,
 }
 
 impl<S> TradingEngine<S>
@@ -35,41 +185,234 @@ where
         config: AppConfig,
         execution_handler: Box<dyn ExecutionHandler>,
         strategy: S,
-        ticker_receiver: mpsc::Receiver<Ticker>,
+        ticker_receiver: RingReceiver<Ticker>,
         ui_sender: mpsc::Sender<UiEvent>,
-        live_mode: bool,
+        command_receiver: mpsc::Receiver<EngineCommand>,
+        mode: TradingMode,
     ) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(RealClock);
+        let daily_loss = DailyLossGuard::new(config.risk.max_daily_loss_usdt, clock.now());
+        let entry_throttle = EntryThrottle::new(
+            config.risk.max_entries_per_candle,
+            config.risk.max_entry_notional_per_candle,
+            CANDLE_MS,
+        );
+        let paper_wallet = PaperWallet::new(config.paper.clone());
+        let depeg_guard = DepegGuard::new(config.risk.depeg_threshold, config.risk.depeg_halt);
+        let drawdown_guard = DrawdownGuard::new(config.risk.max_session_drawdown);
+        let spike_guard = new_spike_guard(&config);
         Self {
             config,
             execution_handler,
             strategy,
             ticker_receiver,
+            stream_client: None,
+            symbol_sender: None,
             ui_sender,
-            live_mode,
+            command_receiver,
+            mode,
+            clock,
             state_file: "bot_state.json".to_string(),
+            persister: None,
+            store: None,
+            trade_ledger: None,
+            event_sender: None,
+            daily_loss,
+            entry_throttle,
+            paper_wallet,
+            fill_model: None,
+            funding_feed: None,
+            depeg_guard,
+            drawdown_guard,
+            spike_guard,
+            start_equity: Decimal::ZERO,
+            session_realized: Decimal::ZERO,
+            margin_rejections: 0,
+            margin_halted: false,
+            last_tick_at: Instant::now(),
+            dead_man_halted: false,
+            paused: false,
+            depeg_feed: None,
+            strategy_enabled: true,
+            last_ticker: None,
+            inventory: Inventory::default(),
+            pending_order: None,
+            last_pending_poll: 0,
+            last_order_at: None,
+            delayed_paper: None,
+            balance_cache: None,
+            account_receiver: None,
+            config_receiver: None,
         }
     }
 
+    /// Replay пишет состояние отдельно, чтобы не трогать позицию живой сессии
+    pub fn with_state_file(mut self, path: String) -> Self {
+        self.state_file = path;
+        self
+    }
+
+    /// Часы по тикам (`SimulatedClock`): прогон по записи не зависит от того, когда его запустили
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.daily_loss = DailyLossGuard::new(self.config.risk.max_daily_loss_usdt, clock.now());
+        self.clock = clock;
+        self
+    }
+
+    /// Бумажные ордера исполняет модель бэктеста, а не стакан тика
+    pub fn with_fill_model(mut self, model: Box<dyn FillModel>) -> Self {
+        self.fill_model = Some(model);
+        self
+    }
+
+    pub fn with_store(mut self, store: Arc<dyn Store>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn with_trade_ledger(mut self, ledger: TradeLedger) -> Self {
+        self.trade_ledger = Some(ledger);
+        self
+    }
+
+    /// Входы, выходы и остановки уходят в broadcast: подписчики (уведомления) читают сами
+    pub fn with_event_sender(mut self, sender: broadcast::Sender<EngineEvent>) -> Self {
+        self.event_sender = Some(sender);
+        self
+    }
+
+    pub fn with_account_feed(mut self, feed: mpsc::Receiver<AccountEvent>) -> Self {
+        self.account_receiver = Some(feed);
+        self
+    }
+
+    pub fn with_config_feed(mut self, feed: mpsc::Receiver<StrategyConfig>) -> Self {
+        self.config_receiver = Some(feed);
+        self
+    }
+
+    pub fn with_depeg_feed(mut self, feed: watch::Receiver<Option<Decimal>>) -> Self {
+        self.depeg_feed = Some(feed);
+        self
+    }
+
+    /// Поток ставки funding (None внутри — последний запрос не удался, фильтр не блокирует)
+    pub fn with_funding_feed(mut self, feed: watch::Receiver<Option<FundingInfo>>) -> Self {
+        self.funding_feed = Some(feed);
+        self
+    }
+
+    /// Клиент, через который подписан поток котировок: нужен, чтобы сменить символ на ходу
+    pub fn with_stream_client(mut self, client: Box<dyn StreamClient>) -> Self {
+        self.stream_client = Some(client);
+        self
+    }
+
+    /// Сюда движок пишет символ после смены, фоновые задачи переключаются следом
+    pub fn with_symbol_sender(mut self, sender: watch::Sender<String>) -> Self {
+        self.symbol_sender = Some(sender);
+        self
+    }
+
+    fn current_funding(&self) -> Option<FundingInfo> {
+        self.funding_feed
+            .as_ref()
+            .and_then(|feed| feed.borrow().clone())
+    }
+
     async fn load_state(&mut self) {
         if let Ok(data) = tokio::fs::read_to_string(&self.state_file).await {
-            if let Ok(state) = serde_json::from_str::<EngineState>(&data) {
+            if let Ok(mut state) = serde_json::from_str::<EngineState>(&data) {
                 info!("Restored state: {:?}", state);
-                self.strategy.update_position(state.active_position);
+                if let Some(pos) = state.active_position.take() {
+                    state.inventory.set_position(&pos.symbol.clone(), Some(pos));
+                }
+                self.inventory = state.inventory;
+            }
+        }
+        // Позиция из store главнее: JSON — экспорт для человека
+        if let Some(store) = &self.store {
+            match store.get_position(&self.config.symbol) {
+                Ok(Some(pos)) => {
+                    info!("Restored position from store: {:?}", pos);
+                    self.inventory.set_position(&self.config.symbol, Some(pos));
+                }
+                // Store пуст: позиция из JSON (если есть) переносится в него
+                Ok(None) => self.store_position(),
+                Err(e) => error!("Failed to read position from store: {}", e),
             }
         }
+        self.strategy
+            .update_position(self.inventory.position(&self.config.symbol));
+    }
+
+    /// Позиция текущего символа -> store. Открыта — запись, закрыта — удаление
+    fn store_position(&self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let position = self.inventory.position(&self.config.symbol);
+        if let Err(e) = store.put_position(&self.config.symbol, position.as_ref()) {
+            error!("Failed to save position to store: {}", e);
+        }
+    }
+
+    /// Новая позиция (или её закрытие): стратегия, инвентарь и файл состояния
+    fn set_position(&mut self, position: Option<Position>) {
+        self.strategy.update_position(position.clone());
+        self.inventory.set_position(&self.config.symbol, position);
+        self.save_state();
+        // Число позиций и экспозиция в статусе TUI
+        self.send_risk_snapshot();
+    }
+
+    /// Смена позиции: снимок инвентаря уходит в фоновую задачу, диск не ждём
+    fn save_state(&self) {
+        self.store_position();
+        if let (Some(persister), Some(data)) = (&self.persister, self.encode_state()) {
+            persister.save(data);
+        }
+    }
+
+    /// Промежуточное состояние (трейлинг): пишется с debounce
+    fn save_state_debounced(&self) {
+        self.store_position();
+        if let (Some(persister), Some(data)) = (&self.persister, self.encode_state()) {
+            persister.save_debounced(data);
+        }
     }
 
-    async fn save_state(&self, position: Option<Position>) {
+    fn encode_state(&self) -> Option<String> {
         let state = EngineState {
-            active_position: position,
+            inventory: self.inventory.clone(),
+            active_position: None,
         };
-        if let Ok(data) = serde_json::to_string_pretty(&state) {
-            if let Err(e) = tokio::fs::write(&self.state_file, data).await {
-                error!("Failed to save bot state: {}", e);
+        match serde_json::to_string_pretty(&state) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                error!("Failed to serialize bot state: {}", e);
+                None
             }
         }
     }
 
+    /// Пропущенный сигнал — в лог TUI, иначе бот выглядит просто простаивающим.
+    /// Подробности с полями пишутся в tracing на месте пропуска
+    fn report_skip(&self, reason: impl AsRef<str>) {
+        self.send_ui_event(UiEvent::Log(format!("SKIP: {}", reason.as_ref())));
+    }
+
+    /// Шаги цены и объёма символа. None — фильтры не загружены и не заданы в Settings.toml:
+    /// ордер не строим, с угаданным шагом биржа его отклонит
+    fn precision(&self, symbol: &str) -> Option<SymbolPrecision> {
+        let precision = self.config.precision_for(symbol);
+        if precision.is_none() {
+            error!(symbol = %symbol, "Order skipped: no tick/step size for symbol");
+            self.report_skip(format!("no tick/step size for {}", symbol));
+        }
+        precision
+    }
+
     fn send_ui_event(&self, event: UiEvent) {
         match self.ui_sender.try_send(event) {
             Ok(_) => {}
@@ -80,154 +423,2715 @@ where
         }
     }
 
+    /// Без подписчиков событие просто теряется — уведомления торговлю не блокируют
+    fn publish(&self, event: EngineEvent) {
+        if let Some(sender) = &self.event_sender {
+            let _ = sender.send(event);
+        }
+    }
+
+    fn publish_entry(&self, pos: &Position) {
+        self.publish(EngineEvent::Entry {
+            symbol: pos.symbol.clone(),
+            side: pos.side,
+            price: pos.entry_price,
+            quantity: pos.quantity,
+        });
+    }
+
+    fn publish_halt(&self, reason: impl Into<String>) {
+        self.publish(EngineEvent::Halt {
+            reason: reason.into(),
+        });
+    }
+
+    fn send_risk_snapshot(&self) {
+        self.send_ui_event(UiEvent::Risk(RiskSnapshot {
+            daily_pnl: self.daily_loss.realized_pnl(),
+            daily_loss_remaining: self.daily_loss.remaining(),
+            entries_blocked: self.daily_loss.is_breached()
+                || self.depeg_guard.halts_trading()
+                || self.drawdown_guard.is_halted()
+                || self.margin_halted
+                || self.dead_man_halted
+                || self.spike_guard.is_halted()
+                || self.paused,
+            funding_rate: self.current_funding().map(|f| f.rate),
+            depeg_deviation: self.depeg_guard.deviation(),
+            depegged: self.depeg_guard.is_depegged(),
+            portfolio_halted: self.drawdown_guard.is_halted(),
+            drawdown: self.drawdown_guard.drawdown(),
+            paused: self.paused,
+            open_positions: self.inventory.open_positions(),
+            max_positions: self.config.risk.max_concurrent_positions,
+            exposure: self.inventory.exposure(),
+        }));
+    }
+
+    /// Чья ликвидность у входов: бумага всегда бьёт в стакан, live — по order_execution.
+    /// Выходы всегда taker: post-only выход мог бы повиснуть, пока цена уходит за стоп
+    fn entry_liquidity(&self) -> Liquidity {
+        match (self.mode, self.config.order_execution) {
+            (TradingMode::Paper, _) | (_, OrderExecution::Taker) => Liquidity::Taker,
+            (_, OrderExecution::Maker) => Liquidity::Maker,
+        }
+    }
+
+    /// Учитывает реализованный PnL закрытого объёма (за вычетом комиссий входа и выхода)
+    /// в дневном лимите. Возвращает чистый PnL
+    fn record_realized_pnl(
+        &mut self,
+        position: &Position,
+        quantity: Decimal,
+        exit_price: Decimal,
+    ) -> Decimal {
+        let pnl = self.config.fees.net_pnl(
+            position.side,
+            position.entry_price,
+            exit_price,
+            quantity,
+            self.entry_liquidity(),
+            Liquidity::Taker,
+        );
+        let gross = position
+            .side
+            .pnl(position.entry_price, exit_price, quantity);
+        // Время выхода — по тику, чтобы replay и бэктест давали те же длительности
+        let closed_at = self
+            .last_ticker
+            .as_ref()
+            .map(|t| t.timestamp)
+            .unwrap_or_else(|| self.clock.now_ms());
+        let was_breached = self.daily_loss.is_breached();
+        self.daily_loss.record(pnl, self.clock.now());
+        self.session_realized += pnl;
+        if self.mode != TradingMode::Paper {
+            // Бумажный баланс берётся из кошелька после исполнения
+            self.inventory.quote_balance += pnl;
+        }
+        self.publish(EngineEvent::Exit {
+            symbol: position.symbol.clone(),
+            side: position.side,
+            entry_price: position.entry_price,
+            price: exit_price,
+            quantity,
+            pnl,
+            opened_at: position.opened_at,
+            closed_at,
+        });
+        if !was_breached && self.daily_loss.is_breached() {
+            self.publish_halt(format!(
+                "Daily loss limit hit: {} {} today",
+                self.daily_loss.realized_pnl().round_dp(2),
+                self.config.quote_asset()
+            ));
+        }
+        let trade = TradeRecord {
+            timestamp: closed_at,
+            symbol: position.symbol.clone(),
+            side: position.side,
+            entry_price: position.entry_price,
+            exit_price,
+            quantity,
+            fees: gross - pnl,
+            gross_pnl: gross,
+            net_pnl: pnl,
+            opened_at: position.opened_at,
+        };
+        if let Some(ledger) = &self.trade_ledger {
+            ledger.record(&trade);
+        }
+        self.send_ui_event(UiEvent::TradeClosed(trade));
+        METRICS.realized_pnl.add(pnl.to_f64().unwrap_or_default());
+        info!(
+            "Realized PnL: {} {} (gross {}, fees {}; today: {})",
+            pnl.round_dp(4),
+            self.config.quote_asset(),
+            gross.round_dp(4),
+            (gross - pnl).round_dp(4),
+            self.daily_loss.realized_pnl()
+        );
+        self.send_risk_snapshot();
+        pnl
+    }
+
+    /// Equity сессии: баланс + реализованный PnL + нереализованный PnL открытой позиции
+    fn current_equity(&self, price: Decimal) -> Decimal {
+        let unrealized = self
+            .strategy
+            .get_position()
+            .map(|pos| pos.side.pnl(pos.entry_price, price, pos.quantity))
+            .unwrap_or_default();
+        match self.mode {
+            // Бумажный кошелёк уже включает реализованный PnL
+            TradingMode::Paper => self.paper_wallet.balance() + unrealized,
+            _ => self.start_equity + self.session_realized + unrealized,
+        }
+    }
+
+    /// Баланс инвентаря на старте: бумажный кошелёк или баланс счёта в quote_asset.
+    /// false — баланс с биржи получить не удалось
+    async fn sync_quote_balance(&mut self) -> bool {
+        self.inventory.quote_balance = match self.mode {
+            TradingMode::Paper => self.paper_wallet.balance(),
+            _ => match self
+                .execution_handler
+                .get_balance(&self.config.quote_asset())
+                .await
+            {
+                Ok(balance) => balance,
+                Err(e) => {
+                    warn!("⚠️ Failed to fetch quote balance: {}", e);
+                    return false;
+                }
+            },
+        };
+        true
+    }
+
+    /// Баланс счёта и порог, если баланс ниже risk.min_account_balance_usdt.
+    /// Баланс с биржи кэшируется на BALANCE_CACHE_MS; не удалось получить — вход не блокируем
+    async fn balance_below_floor(&mut self) -> Option<(Decimal, Decimal)> {
+        let floor = self.config.risk.min_account_balance_usdt?;
+        let balance = match self.mode {
+            TradingMode::Paper => self.paper_wallet.balance(),
+            _ => {
+                let now_ms = self.clock.now_ms();
+                match self.balance_cache {
+                    Some((fetched_at, balance))
+                        if now_ms.saturating_sub(fetched_at) < BALANCE_CACHE_MS =>
+                    {
+                        balance
+                    }
+                    _ => match self
+                        .execution_handler
+                        .get_balance(&self.config.quote_asset())
+                        .await
+                    {
+                        Ok(balance) => {
+                            self.balance_cache = Some((now_ms, balance));
+                            balance
+                        }
+                        Err(e) => {
+                            warn!(
+                                "⚠️ Balance floor not checked: failed to fetch balance: {}",
+                                e
+                            );
+                            return None;
+                        }
+                    },
+                }
+            }
+        };
+        (balance < floor).then_some((balance, floor))
+    }
+
+    /// Стартовая точка для стопа по просадке: баланс инвентаря
+    async fn start_drawdown_guard(&mut self) {
+        let balance_synced = self.sync_quote_balance().await;
+        if self.config.risk.max_session_drawdown.is_none() {
+            return;
+        }
+        if !balance_synced {
+            warn!("⚠️ Portfolio stop disabled: quote balance is unknown");
+            return;
+        }
+        let equity = self.inventory.quote_balance;
+        self.start_equity = equity;
+        self.drawdown_guard.start(equity);
+        info!(
+            "Portfolio stop armed: session equity {} {}, max drawdown {:?}",
+            equity,
+            self.config.quote_asset(),
+            self.config.risk.max_session_drawdown
+        );
+    }
+
+    /// Обновляет unrealized_pnl позиции стратегии: он же попадает в файл состояния и снапшот
+    fn mark_position(&mut self, price: Decimal) {
+        if let Some(mut pos) = self.strategy.get_position() {
+            let exit_fee = self.config.fees.fee(price * pos.quantity, Liquidity::Taker);
+            pos.mark_to_market(price, exit_fee);
+            self.inventory
+                .set_position(&self.config.symbol, Some(pos.clone()));
+            self.strategy.update_position(Some(pos));
+        }
+    }
+
+    fn update_position_metrics(&self, price: Decimal) {
+        let (notional, unrealized) = match self.strategy.get_position() {
+            Some(pos) => (pos.quantity * price, pos.unrealized_pnl),
+            None => (Decimal::ZERO, Decimal::ZERO),
+        };
+        METRICS
+            .position_notional
+            .set(notional.to_f64().unwrap_or_default());
+        METRICS
+            .unrealized_pnl
+            .set(unrealized.to_f64().unwrap_or_default());
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         info!("Engine starting...");
         self.load_state().await;
-        self.strategy.init().await?;
-
-        info!("Engine loop running. Live Mode: {}", self.live_mode);
+        self.persister = Some(StatePersister::spawn(
+            self.state_file.clone(),
+            Duration::from_millis(self.config.state_save_debounce_ms),
+        ));
+        if self.mode == TradingMode::Live {
+            self.reconcile_startup_position().await?;
+        }
+        self.strategy.init().await.map_err(BotError::Strategy)?;
+        self.start_drawdown_guard().await;
 
-        while let Some(ticker) = self.ticker_receiver.recv().await {
-            self.send_ui_event(UiEvent::TickerUpdate(ticker.clone()));
+        info!("Engine loop running. Mode: {:?}", self.mode);
+        self.send_risk_snapshot();
 
-            let signal = self.strategy.on_tick(&ticker).await?;
+        self.send_strategy_state();
 
-            match signal {
-                Signal::Advice(side, price) => {
-                    self.handle_signal(side, price, &ticker).await?;
+        let mut dead_man_check = tokio::time::interval(DEAD_MAN_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                maybe_ticker = self.ticker_receiver.recv() => {
+                    match maybe_ticker {
+                        Some(ticker) => self.process_ticker(ticker).await?,
+                        None => {
+                            // Коннектор, бросая поток, сначала шлёт StreamFailed: не потерять его
+                            while let Ok(command) = self.command_receiver.try_recv() {
+                                self.handle_command(command).await?;
+                            }
+                            info!("Ticker stream closed. Engine stopping");
+                            // Запись кончилась раньше, чем ордер "долетел"
+                            self.fill_delayed_paper(None, true);
+                            if let Some(persister) = &self.persister {
+                                persister.flush().await;
+                            }
+                            break;
+                        }
+                    }
+                }
+                Some(command) = self.command_receiver.recv() => {
+                    self.handle_command(command).await?;
+                }
+                Some(event) = next_event(&mut self.account_receiver) => {
+                    self.handle_account_event(event).await;
                 }
-                Signal::StateChanged => {
-                    let current_pos = self.strategy.get_position();
-                    self.save_state(current_pos).await;
-                    info!("💾 State updated (highest_price tracked)");
+                Some(strategy_config) = next_event(&mut self.config_receiver) => {
+                    self.reload_strategy_config(strategy_config);
+                }
+                _ = dead_man_check.tick() => {
+                    self.check_dead_man().await;
+                    // Тики встали, а бумажный ордер ждёт следующего
+                    self.fill_delayed_paper(None, false);
                 }
-                Signal::Hold => {}
             }
         }
         Ok(())
     }
 
-    async fn handle_signal(
-        &mut self,
-        side: Side,
-        current_price: Decimal,
-        ticker: &Ticker,
-    ) -> Result<()> {
-        info!("Signal detected: {:?} @ {}", side, current_price);
-        self.send_ui_event(UiEvent::Signal(Signal::Advice(side, current_price)));
-
-        // 1. Расчет "сырого" объема
-        let order_usdt =
-            Decimal::from_f64(self.config.order_size_usdt).unwrap_or(Decimal::from(10));
-        let raw_qty = order_usdt / current_price;
+    async fn process_ticker(&mut self, ticker: Ticker) -> Result<()> {
+        METRICS.ticks_received.inc();
+        self.clock.observe(ticker.timestamp);
+        self.last_tick_at = Instant::now();
 
-        // 2. Нормализация объема (используем шаг из конфига)
-        let step_size = self.config.symbol_step_size;
-        let quantity = normalize_quantity(raw_qty, step_size);
+        // Выброс не доходит ни до стратегии, ни до стопов: по нему нельзя ни входить, ни выходить
+        match self.spike_guard.check(ticker.timestamp, ticker.price) {
+            TickCheck::Rejected => {
+                METRICS.ticks_rejected.inc();
+                warn!(
+                    symbol = %ticker.symbol,
+                    price = %ticker.price,
+                    "Tick rejected: price spike from last valid price"
+                );
+                return Ok(());
+            }
+            TickCheck::FlashHalt => {
+                let msg = format!(
+                    "FLASH MOVE on {}: entries paused for {}s",
+                    ticker.symbol, self.config.risk.flash_halt_seconds
+                );
+                self.send_ui_event(UiEvent::Log(msg.clone()));
+                self.publish_halt(msg);
+                self.send_risk_snapshot();
+            }
+            TickCheck::HaltLifted => {
+                self.send_ui_event(UiEvent::Log("Flash-move pause is over".to_string()));
+                self.send_risk_snapshot();
+            }
+            TickCheck::Accepted => {}
+        }
+        self.send_ui_event(UiEvent::TickerUpdate(ticker.clone()));
 
-        // 3. Проверка Min Notional (>$5.5)
-        let notional_value = quantity * current_price;
-        let min_notional = Decimal::from_str("5.5").unwrap(); // Безопасный парсинг без макроса dec!
+        let funding_changed = match self.funding_feed.as_mut() {
+            Some(feed) if feed.has_changed().unwrap_or(false) => {
+                feed.mark_unchanged();
+                true
+            }
+            _ => false,
+        };
+        let depeg_price = match self.depeg_feed.as_mut() {
+            Some(feed) if feed.has_changed().unwrap_or(false) => *feed.borrow_and_update(),
+            _ => None,
+        };
+        let depeg_transition = depeg_price.and_then(|price| self.depeg_guard.update(price));
+        if self.daily_loss.roll_over(self.clock.now()) || funding_changed || depeg_price.is_some() {
+            self.send_risk_snapshot();
+        }
+        match depeg_transition {
+            Some(true) => {
+                self.send_ui_event(UiEvent::Log(format!(
+                    "DEPEG ALERT: quote stablecoin off peg by {}",
+                    self.depeg_guard.deviation().unwrap_or_default()
+                )));
+                // Остановка: не держим позицию через депег
+                if self.depeg_guard.halts_trading() {
+                    self.publish_halt(format!(
+                        "Quote stablecoin depeg: off by {}",
+                        self.depeg_guard.deviation().unwrap_or_default()
+                    ));
+                    if let Some(pos) = self.strategy.get_position() {
+                        warn!("Flattening {} {} on depeg halt", pos.quantity, pos.symbol);
+                        self.handle_signal(
+                            pos.side.exit_side(),
+                            ticker.price,
+                            Decimal::ONE,
+                            &ticker,
+                        )
+                        .await?;
+                    }
+                }
+            }
+            Some(false) => self.send_ui_event(UiEvent::Log("Stablecoin peg restored".to_string())),
+            None => {}
+        }
 
-        if notional_value < min_notional {
-            warn!(
-                "Order skipped: Notional value ${:.2} < ${} (Min limit). Raw Qty: {}, Norm Qty: {}",
-                notional_value, min_notional, raw_qty, quantity
+        // Аварийный стоп портфеля: выше дневного лимита, закрывает всё и больше не открывает
+        if self
+            .drawdown_guard
+            .update(self.current_equity(ticker.price))
+        {
+            let msg = format!(
+                "PORTFOLIO STOP: drawdown {:.2}% from session peak. Trading halted",
+                self.drawdown_guard.drawdown() * Decimal::from(100)
             );
-            return Ok(());
+            self.send_ui_event(UiEvent::Log(msg.clone()));
+            self.publish_halt(msg);
+            self.send_risk_snapshot();
+            if let Some(pos) = self.strategy.get_position() {
+                warn!(
+                    "Flattening {} {} on portfolio stop",
+                    pos.quantity, pos.symbol
+                );
+                self.handle_signal(pos.side.exit_side(), ticker.price, Decimal::ONE, &ticker)
+                    .await?;
+            }
         }
 
-        if quantity.is_zero() {
-            warn!("⚠️ Quantity is zero after normalization. Not entering position.");
-            return Ok(());
+        self.fill_delayed_paper(Some(&ticker), false);
+        self.poll_pending_order(&ticker).await;
+
+        // Стратегия получает тики и в выключенном состоянии, чтобы индикаторы оставались прогретыми
+        let signal = self
+            .strategy
+            .on_tick(&ticker)
+            .await
+            .map_err(BotError::Strategy)?;
+
+        match signal {
+            Signal::Advice(side, price) if self.strategy_enabled => {
+                match side {
+                    Side::Buy => METRICS.signals_buy.inc(),
+                    Side::Sell => METRICS.signals_sell.inc(),
+                }
+                if !self.order_too_soon(side, price) {
+                    self.handle_signal(side, price, Decimal::ONE, &ticker)
+                        .await?;
+                }
+            }
+            Signal::PartialExit(side, price, fraction) if self.strategy_enabled => {
+                if !self.order_too_soon(side, price) {
+                    self.handle_signal(side, price, fraction, &ticker).await?;
+                }
+            }
+            Signal::Advice(side, price) | Signal::PartialExit(side, price, _) => {
+                debug!(
+                    "Strategy {} disabled: ignoring {:?} @ {}",
+                    self.strategy.name(),
+                    side,
+                    price
+                );
+            }
+            Signal::StateChanged => {
+                self.inventory
+                    .set_position(&self.config.symbol, self.strategy.get_position());
+                self.save_state_debounced();
+                info!("💾 State updated (highest_price tracked)");
+            }
+            Signal::Hold => {}
         }
 
-        // 4. Подготовка цены (для лимитных ордеров или симуляции)
-        // Для простоты берем tick_size из конфига
-        let tick_size = self.config.symbol_tick_size;
+        self.mark_position(ticker.price);
+        self.update_position_metrics(ticker.price);
+        self.send_snapshot(ticker.price);
+        self.last_ticker = Some(ticker);
+        Ok(())
+    }
 
-        // В Paper Mode мы "исполняем" по текущей цене (или с проскальзыванием), но нормализуем её
-        // В Live Mode ExecutionHandler сам может добавить slippage, но нам нужна базовая цена
-        let target_price = normalize_price(current_price, tick_size);
+    /// risk.min_order_interval_ms: сигнал стратегии пришёл слишком скоро после прошлого ордера.
+    /// Выход по жёсткому стопу не задерживается. Аварийные выходы движка идут мимо этой проверки
+    fn order_too_soon(&self, side: Side, price: Decimal) -> bool {
+        let (Some(interval), Some(last_order_at)) =
+            (self.config.risk.min_order_interval_ms, self.last_order_at)
+        else {
+            return false;
+        };
+        let elapsed = self.clock.now_ms().saturating_sub(last_order_at);
+        if elapsed >= interval {
+            return false;
+        }
+        if let Some(pos) = self.strategy.get_position() {
+            if side == pos.side.exit_side()
+                && hard_stop_hit(&pos, price, self.config.strategy.hard_stop_pct)
+            {
+                return false;
+            }
+        }
+        warn!(
+            side = ?side,
+            price = %price,
+            elapsed_ms = elapsed,
+            min_order_interval_ms = interval,
+            "Signal skipped: too soon after the last order"
+        );
+        self.report_skip(format!(
+            "{}ms since the last order (min {}ms)",
+            elapsed, interval
+        ));
+        true
+    }
 
-        if !self.live_mode {
-            // --- PAPER MODE ---
-            let fake_pos = match side {
-                Side::Buy => {
-                    info!(
-                        "Paper Buy: {} coins at ${} (Notional: ${:.2})",
-                        quantity, target_price, notional_value
-                    );
+    /// Индикаторы стратегии + реальная позиция (сторона, вход, объём) для TUI
+    fn send_snapshot(&self, price: Decimal) {
+        let mut snapshot = self.strategy.snapshot();
+        snapshot.session_pnl = self.session_realized + self.inventory.unrealized_pnl();
+        if let Some(pos) = self.strategy.get_position() {
+            snapshot.unrealized_pnl = pos.unrealized_pnl;
+            if !pos.entry_price.is_zero() {
+                snapshot.position_pnl =
+                    Some(pos.side.pnl(pos.entry_price, price, Decimal::ONE) / pos.entry_price);
+            }
+            snapshot.side = pos.side;
+            snapshot.entry_price = pos.entry_price;
+            snapshot.quantity = pos.quantity;
+        }
+        self.send_ui_event(UiEvent::Snapshot(snapshot));
+    }
 
-                    Some(Position {
-                        symbol: ticker.symbol.clone(),
-                        quantity,
-                        entry_price: target_price,
-                        unrealized_pnl: Decimal::ZERO,
-                        highest_price: target_price,
-                    })
+    /// Горячая перезагрузка: стратегия сама решает, что можно поменять на ходу
+    fn reload_strategy_config(&mut self, strategy_config: StrategyConfig) {
+        match self.strategy.reconfigure(strategy_config.clone()) {
+            Ok(()) => {
+                self.config.strategy = strategy_config;
+                info!("🔄 Strategy {} reconfigured", self.strategy.name());
+                self.send_ui_event(UiEvent::Log("Strategy config reloaded".to_string()));
+            }
+            Err(e) => {
+                warn!("⚠️ Strategy config reload rejected: {:#}", e);
+                self.send_ui_event(UiEvent::Log(format!("Config reload rejected: {}", e)));
+            }
+        }
+    }
+
+    fn send_strategy_state(&self) {
+        self.send_ui_event(UiEvent::StrategyState {
+            name: self.strategy.name(),
+            enabled: self.strategy_enabled,
+        });
+    }
+
+    async fn handle_command(&mut self, command: EngineCommand) -> Result<()> {
+        match command {
+            EngineCommand::EnableStrategy(id) | EngineCommand::DisableStrategy(id)
+                if id != self.strategy.name() =>
+            {
+                warn!("Unknown strategy id: {}", id);
+                self.send_ui_event(UiEvent::Log(format!("Unknown strategy: {}", id)));
+            }
+            EngineCommand::EnableStrategy(id) => {
+                self.strategy_enabled = true;
+                info!("▶️ Strategy {} enabled", id);
+                self.send_ui_event(UiEvent::Log(format!("Strategy {} enabled", id)));
+                self.send_strategy_state();
+            }
+            EngineCommand::DisableStrategy(id) => {
+                self.strategy_enabled = false;
+                info!("⏸️ Strategy {} disabled", id);
+                self.send_ui_event(UiEvent::Log(format!("Strategy {} disabled", id)));
+                self.send_strategy_state();
+
+                // Выключение закрывает позицию стратегии
+                if let (Some(pos), Some(ticker)) =
+                    (self.strategy.get_position(), self.last_ticker.clone())
+                {
+                    info!("Flattening {} {} on disable", pos.quantity, pos.symbol);
+                    self.handle_signal(pos.side.exit_side(), ticker.price, Decimal::ONE, &ticker)
+                        .await?;
                 }
-                Side::Sell => {
-                    info!("Paper Sell: Closing position at ${}", target_price);
-                    None
+            }
+            EngineCommand::TogglePause => {
+                self.paused = !self.paused;
+                let msg = if self.paused {
+                    warn!("⏸️ Entries PAUSED: open positions are still managed");
+                    "Entries paused (exits still active)"
+                } else {
+                    warn!("▶️ Entries RESUMED");
+                    "Entries resumed"
+                };
+                self.send_ui_event(UiEvent::Log(msg.to_string()));
+                self.send_risk_snapshot();
+            }
+            EngineCommand::Flatten => {
+                let Some(pos) = self.strategy.get_position() else {
+                    info!("Flatten requested, but there is no open position");
+                    self.send_ui_event(UiEvent::Log("Flatten: no open position".to_string()));
+                    return Ok(());
+                };
+                warn!(
+                    symbol = %pos.symbol,
+                    side = ?pos.side,
+                    qty = %pos.quantity,
+                    "✋ Manual flatten requested"
+                );
+                let msg = if self.flatten_at_market(pos.clone(), "Manual flatten").await {
+                    format!("Flattened {} {} at market", pos.quantity, pos.symbol)
+                } else {
+                    format!(
+                        "Flatten of {} {} failed, see logs",
+                        pos.quantity, pos.symbol
+                    )
+                };
+                self.send_ui_event(UiEvent::Log(msg));
+            }
+            EngineCommand::SwitchSymbol(symbol) => {
+                let symbol = symbol.trim().to_uppercase();
+                if let Err(e) = self.switch_symbol(&symbol).await {
+                    warn!("⚠️ Symbol switch to {} failed: {}", symbol, e);
+                    self.send_ui_event(UiEvent::Log(format!("Switch to {} failed: {}", symbol, e)));
                 }
-            };
-            self.strategy.update_position(fake_pos.clone());
-            self.save_state(fake_pos).await;
-            return Ok(());
+            }
+            EngineCommand::StreamFailed(reason) => {
+                error!("💀 Market data stream lost: {}. Engine stopping", reason);
+                let msg = format!("MARKET DATA LOST: {}. Engine stopped", reason);
+                self.send_ui_event(UiEvent::Log(msg.clone()));
+                self.publish_halt(msg);
+                // Без котировок трейлинг не работает: позицию не оставляем без присмотра
+                if let Some(pos) = self.strategy.get_position() {
+                    self.flatten_at_market(pos, "Stream failure").await;
+                }
+                if let Some(persister) = &self.persister {
+                    persister.flush().await;
+                }
+                return Err(BotError::Network(reason));
+            }
         }
+        Ok(())
+    }
 
-        // --- LIVE MODE ---
-        // Для Live режима мы передаем нормализованное количество.
-        // Цену execution_handler может пересчитать (slippage), но мы передадим ему "чистую"
-        // или позволим ему самому решать. В текущей реализации handler принимает option price.
+    /// Смена символа из TUI: закрыть позицию, загрузить фильтры нового символа,
+    /// переподписать поток котировок и прогреть стратегию заново.
+    /// Ошибка до переподписки оставляет движок на старом символе
+    async fn switch_symbol(&mut self, symbol: &str) -> Result<()> {
+        if symbol.is_empty() || symbol == self.config.symbol {
+            return Err(BotError::Config(format!(
+                "already trading {}",
+                self.config.symbol
+            )));
+        }
+        if self.stream_client.is_none() {
+            return Err(BotError::Config(
+                "symbol switch is not available in this mode".to_string(),
+            ));
+        }
+        // Переопределение шагов из Settings.toml относится к стартовому символу
+        if self.config.symbol_tick_size.is_some() || self.config.symbol_step_size.is_some() {
+            return Err(BotError::Config(
+                "symbol_tick_size/symbol_step_size override is set".to_string(),
+            ));
+        }
+        if self.pending_order.is_some() {
+            return Err(BotError::Config(
+                "a limit order is resting, wait for it to fill or expire".to_string(),
+            ));
+        }
+        if let Some(pos) = self.strategy.get_position() {
+            if !self.flatten_at_market(pos, "Symbol switch").await {
+                return Err(BotError::Config(
+                    "open position could not be flattened".to_string(),
+                ));
+            }
+        }
 
-        // Добавим проскальзывание для лимитного ордера, чтобы он сработал как маркет (taker)
-        // или оставим current_price если это Market Order (в зависимости от реализации handler).
-        // Предположим, мы шлем Limit ордер с агрессивной ценой.
+        let old_symbol = self.config.symbol.clone();
+        // Плечо выставляется только на боевом счёте: бумаге ключи не нужны
+        let leverage = (self.mode == TradingMode::Live).then(|| self.config.leverage_for(symbol));
+        let precision = self.execution_handler.load_symbol(symbol, leverage).await?;
 
-        let slippage_pct = Decimal::from_str("0.001").unwrap(); // 0.1%
-        let execution_price_raw = match side {
-            Side::Buy => current_price * (Decimal::ONE + slippage_pct),
-            Side::Sell => current_price * (Decimal::ONE - slippage_pct),
+        // Старая задача потока снимается до подписки: два потока в один движок — смесь цен
+        let (ticker_tx, ticker_rx) = ring_channel(self.config.ticker_buffer);
+        let Some(stream) = self.stream_client.as_mut() else {
+            return Err(BotError::Config(
+                "symbol switch is not available in this mode".to_string(),
+            ));
         };
-        let final_price = normalize_price(execution_price_raw, tick_size);
-
-        info!(
-            "Executing LIVE {:?}: Qty: {} @ Price: {} (Notional: ${:.2})",
-            side, quantity, final_price, notional_value
-        );
+        stream.unsubscribe();
+        if let Err(e) = stream.subscribe_ticker(symbol, ticker_tx).await {
+            // Без потока движок слеп: возвращаемся на старый символ
+            let (old_tx, old_rx) = ring_channel(self.config.ticker_buffer);
+            stream.subscribe_ticker(&old_symbol, old_tx).await?;
+            self.ticker_receiver = old_rx;
+            if let Err(e) = self.execution_handler.load_symbol(&old_symbol, None).await {
+                error!("Failed to reload {} filters: {}", old_symbol, e);
+            }
+            return Err(e);
+        }
+        self.ticker_receiver = ticker_rx;
 
-        match self
-            .execution_handler
-            .place_order(&ticker.symbol, side, quantity, Some(final_price))
+        self.config.symbol = symbol.to_string();
+        self.config.precision.insert(symbol.to_string(), precision);
+        // У нового символа может быть другой актив расчётов (BTCUSDT -> BTCUSDC)
+        self.balance_cache = None;
+        // Цены другого символа: без сброса первый же тик выглядел бы как выброс
+        self.spike_guard = new_spike_guard(&self.config);
+        self.last_ticker = None;
+        self.last_tick_at = Instant::now();
+        self.strategy
+            .switch_symbol(symbol)
             .await
-        {
+            .map_err(BotError::Strategy)?;
+        if let Some(sender) = &self.symbol_sender {
+            let _ = sender.send(symbol.to_string());
+        }
+        self.save_state();
+
+        warn!("🔀 Switched symbol {} -> {}", old_symbol, symbol);
+        self.send_ui_event(UiEvent::SymbolChanged(symbol.to_string()));
+        self.send_ui_event(UiEvent::Log(format!(
+            "Switched {} -> {}, warming up",
+            old_symbol, symbol
+        )));
+        self.send_risk_snapshot();
+        Ok(())
+    }
+
+    /// Dead man's switch: тики не приходят dead_man_timeout_seconds при открытой позиции —
+    /// трейлинг не обновляется, позиция без присмотра. Закрываем по рынку и больше не входим
+    async fn check_dead_man(&mut self) {
+        let Some(timeout) = self.config.risk.dead_man_timeout_seconds else {
+            return;
+        };
+        let silence = self.last_tick_at.elapsed();
+        if silence < Duration::from_secs(timeout) {
+            return;
+        }
+        let Some(pos) = self.strategy.get_position() else {
+            return;
+        };
+
+        if !self.dead_man_halted {
+            self.dead_man_halted = true;
+            error!(
+                symbol = %pos.symbol,
+                silence_secs = silence.as_secs(),
+                side = ?pos.side,
+                qty = %pos.quantity,
+                "💀 DEAD MAN'S SWITCH: no ticks, flattening at market. Entries halted until restart"
+            );
+            let msg = format!(
+                "DEAD MAN'S SWITCH: no ticks for {}s, flattening {} {}",
+                silence.as_secs(),
+                pos.quantity,
+                pos.symbol
+            );
+            self.send_ui_event(UiEvent::Log(msg.clone()));
+            self.publish_halt(msg);
+            self.send_risk_snapshot();
+        }
+        // Закрытие не прошло (сеть, скорее всего, та же) — повторим на следующей проверке
+        self.flatten_at_market(pos, "Dead man's switch").await;
+    }
+
+    /// Reduce-only MARKET на всю позицию: цена последнего тика могла устареть, лимит может не
+    /// исполниться. PnL считается по последней известной цене. true — позиция закрыта
+    async fn flatten_at_market(&mut self, pos: Position, reason: &str) -> bool {
+        let Some(ticker) = self.last_ticker.clone() else {
+            warn!("{}: no market data yet, cannot flatten", reason);
+            return false;
+        };
+        let side = pos.side.exit_side();
+        if self.mode != TradingMode::Live {
+            // Бумага и dry-run: обычный путь выхода по последнему стакану
+            if let Err(e) = self
+                .handle_signal(side, ticker.price, Decimal::ONE, &ticker)
+                .await
+            {
+                error!("{} flatten failed: {}", reason, e);
+            }
+            return self.strategy.get_position().is_none();
+        }
+
+        let request = OrderRequest {
+            symbol: pos.symbol.clone(),
+            side,
+            quantity: pos.quantity,
+            order_type: OrderType::Market,
+            reduce_only: true,
+            client_order_id: new_client_order_id(),
+        };
+        match self.submit_order(&request).await {
+            Ok(order) if order.status.is_filled() => {
+                let filled_qty = if order.executed_qty.is_zero() {
+                    pos.quantity
+                } else {
+                    order.executed_qty
+                };
+                // Маркет исполняется не по последнему тику: реальная цена в avgPrice
+                let fill_price = order.avg_price.unwrap_or(ticker.price);
+                warn!(
+                    order_id = %order.id,
+                    executed_qty = %filled_qty,
+                    price = %fill_price,
+                    "{}: position flattened", reason
+                );
+                self.apply_live_fill(side, filled_qty, fill_price, &ticker)
+                    .await;
+                true
+            }
             Ok(order) => {
-                info!("✅ Order Confirmed & Filled: {:?}", order);
-                match side {
-                    Side::Buy => {
-                        let pos = Position {
-                            symbol: ticker.symbol.clone(),
-                            quantity,
-                            entry_price: final_price, // В идеале брать из ответа биржи (avg_price)
-                            unrealized_pnl: Decimal::ZERO,
-                            highest_price: final_price,
-                        };
-                        self.strategy.update_position(Some(pos.clone()));
-                        self.save_state(Some(pos)).await;
-                    }
-                    Side::Sell => {
-                        self.strategy.update_position(None);
-                        self.save_state(None).await;
-                    }
-                }
+                warn!(
+                    order_id = %order.id,
+                    status = ?order.status,
+                    "{}: market exit not filled", reason
+                );
+                false
             }
             Err(e) => {
-                error!("⚠️ Execution Error: {}", e);
+                error!(symbol = %pos.symbol, error = %e, "{}: market exit failed", reason);
+                self.handle_order_error(&e, true);
+                false
             }
         }
+    }
 
-        Ok(())
+    /// Группа risk.correlation_groups с `symbol`, чей лимит превысит вход на `notional`,
+    /// и чистая экспозиция группы после него. Вход, уменьшающий |экспозицию|, не блокируется
+    fn correlation_breach(
+        &self,
+        symbol: &str,
+        side: Side,
+        notional: Decimal,
+    ) -> Option<(&CorrelationGroup, Decimal)> {
+        let signed = match PositionSide::from_entry(side) {
+            PositionSide::Long => notional,
+            PositionSide::Short => -notional,
+        };
+        self.config
+            .risk
+            .correlation_groups
+            .iter()
+            .filter(|group| group.symbols.iter().any(|s| s == symbol))
+            .find_map(|group| {
+                let before = self.inventory.net_exposure(&group.symbols);
+                let after = before + signed;
+                (after.abs() > group.max_net_exposure_usdt && after.abs() > before.abs())
+                    .then_some((group, after))
+            })
+    }
+
+    /// Объём входа: order_size_usdt с учётом лимита на свечу, шага и min notional.
+    /// None — вход пропущен (причина уже в логе)
+    fn entry_quantity(&mut self, current_price: Decimal, ticker: &Ticker) -> Option<Decimal> {
+        // 1. Расчет "сырого" объема
+        // NaN и бесконечность отсекает validate
+        let mut order_usdt = Decimal::from_f64(self.config.order_size_usdt).unwrap_or_default();
+
+        // Лимит новой экспозиции на свечу: лишний размер урезаем, сверх лимита входов — пропуск
+        let allowed = self
+            .entry_throttle
+            .allowed_notional(ticker.timestamp, order_usdt);
+        if allowed.is_zero() {
+            warn!(
+                symbol = %ticker.symbol,
+                candle_entries = self.entry_throttle.entries(),
+                candle_notional = %self.entry_throttle.notional(),
+                "Entry throttled: candle cap reached"
+            );
+            self.report_skip("candle entry cap reached");
+            return None;
+        }
+        if allowed < order_usdt {
+            info!(
+                "Entry downsized by candle cap: {} -> {} {}",
+                order_usdt,
+                allowed,
+                self.config.quote_asset()
+            );
+            order_usdt = allowed;
+        }
+        let raw_qty = order_usdt / current_price;
+
+        // 2. Нормализация объема по шагу символа
+        let precision = self.precision(&ticker.symbol)?;
+        let step_size = precision.step_size;
+        let mut quantity = normalize_quantity(raw_qty, step_size);
+
+        // 3. Проверка min notional символа (в котируемом активе, с запасом)
+        let notional_value = quantity * current_price;
+        let min_notional = precision.min_notional * MIN_NOTIONAL_MARGIN;
+
+        if notional_value < min_notional {
+            // Подъём до минимума: в пределах max_notional_bump и лимита на свечу
+            let bumped = if self.config.bump_to_min_notional {
+                let cap = self.entry_throttle.allowed_notional(
+                    ticker.timestamp,
+                    order_usdt * (Decimal::ONE + self.config.max_notional_bump),
+                );
+                bump_to_min_notional(current_price, min_notional, step_size, cap)
+            } else {
+                None
+            };
+
+            match bumped {
+                Some(bumped_qty) => {
+                    info!(
+                        symbol = %ticker.symbol,
+                        qty = %quantity,
+                        bumped_qty = %bumped_qty,
+                        notional = %(bumped_qty * current_price).round_dp(2),
+                        min_notional = %min_notional,
+                        "Entry size bumped to min notional"
+                    );
+                    quantity = bumped_qty;
+                }
+                None => {
+                    warn!(
+                        symbol = %ticker.symbol,
+                        notional = %notional_value.round_dp(2),
+                        min_notional = %min_notional,
+                        raw_qty = %raw_qty,
+                        qty = %quantity,
+                        "Order skipped: notional below exchange minimum"
+                    );
+                    self.report_skip(format!(
+                        "notional {} below minimum {}",
+                        notional_value.round_dp(2),
+                        min_notional
+                    ));
+                    return None;
+                }
+            }
+        }
+
+        if quantity.is_zero() {
+            warn!("⚠️ Quantity is zero after normalization. Not entering position.");
+            self.report_skip("quantity is zero after rounding to step size");
+            return None;
+        }
+
+        Some(quantity)
+    }
+
+    /// Объём выхода: доля `fraction` открытой позиции по шагу символа. None — закрывать нечего.
+    /// Если часть или остаток после неё меньше min notional, закрывается вся позиция:
+    /// такой огрызок потом не продать
+    fn exit_quantity(&self, ticker: &Ticker, price: Decimal, fraction: Decimal) -> Option<Decimal> {
+        match self.strategy.get_position() {
+            Some(pos) if pos.quantity > Decimal::ZERO && fraction >= Decimal::ONE => {
+                Some(pos.quantity)
+            }
+            Some(pos) if pos.quantity > Decimal::ZERO => {
+                let precision = self.precision(&ticker.symbol)?;
+                let quantity = normalize_quantity(pos.quantity * fraction, precision.step_size);
+                let remaining = pos.quantity - quantity;
+                let min_notional = precision.min_notional * MIN_NOTIONAL_MARGIN;
+                if quantity * price < min_notional || remaining * price < min_notional {
+                    info!(
+                        symbol = %ticker.symbol,
+                        qty = %pos.quantity,
+                        fraction = %fraction,
+                        partial_qty = %quantity,
+                        "Partial exit widened to full close: part or remainder below min notional"
+                    );
+                    return Some(pos.quantity);
+                }
+                Some(quantity)
+            }
+            _ => {
+                warn!(
+                    symbol = %ticker.symbol,
+                    "Exit skipped: no open position to reduce"
+                );
+                None
+            }
+        }
+    }
+
+    /// Бумажное исполнение по стакану тика с проскальзыванием (и частично, если так настроено).
+    /// signal_price — цена сигнала, для лога
+    fn fill_paper(
+        &mut self,
+        side: Side,
+        order_qty: Decimal,
+        is_exit: bool,
+        signal_price: Decimal,
+        ticker: &Ticker,
+    ) {
+        let Some(precision) = self.precision(&ticker.symbol) else {
+            return;
+        };
+        let (step_size, tick_size) = (precision.step_size, precision.tick_size);
+        let fill = match &self.fill_model {
+            Some(model) => model.fill(side, order_qty, ticker, step_size, tick_size),
+            None => self
+                .paper_wallet
+                .simulate_fill(side, order_qty, ticker, step_size, tick_size),
+        };
+        if fill.quantity.is_zero() {
+            warn!("Paper {:?}: nothing filled at top of book. Skipping", side);
+            return;
+        }
+        self.last_order_at = Some(self.clock.now_ms());
+
+        info!(
+            symbol = %ticker.symbol,
+            side = ?side,
+            filled_qty = %fill.quantity,
+            qty = %order_qty,
+            price = %fill.price,
+            mid = %signal_price,
+            slippage = %fill.slippage_cost.round_dp(4),
+            "Paper fill"
+        );
+        let paper_pos = match self.strategy.get_position() {
+            Some(mut pos) if is_exit => {
+                let pnl = self.record_realized_pnl(&pos, fill.quantity, fill.price);
+                self.paper_wallet.apply(&fill, pnl);
+                pos.quantity -= fill.quantity;
+                // Частичное исполнение: остаток позиции продолжаем вести
+                if pos.quantity > Decimal::ZERO {
+                    Some(pos)
+                } else {
+                    None
+                }
+            }
+            _ => {
+                self.paper_wallet.apply(&fill, Decimal::ZERO);
+                self.entry_throttle
+                    .record(ticker.timestamp, fill.price * fill.quantity);
+
+                let pos = self.position_after_entry(side, fill.quantity, fill.price, ticker);
+                self.publish_entry(&pos);
+                Some(pos)
+            }
+        };
+        info!(
+            balance = %self.paper_wallet.balance().round_dp(2),
+            slippage_paid = %self.paper_wallet.slippage_paid().round_dp(4),
+            "Paper wallet"
+        );
+        self.inventory.quote_balance = self.paper_wallet.balance();
+        self.set_position(paper_pos);
+    }
+
+    /// Бумажный ордер "долетел": исполняется по первому тику после задержки. Если тиков нет
+    /// дольше latency_timeout_ms (или force — поток закончился), по последней известной цене
+    fn fill_delayed_paper(&mut self, ticker: Option<&Ticker>, force: bool) {
+        let Some(order) = &self.delayed_paper else {
+            return;
+        };
+        let timeout = Duration::from_millis(
+            self.config.paper.simulated_latency_ms + self.config.paper.latency_timeout_ms,
+        );
+        let due = ticker.is_some_and(|t| t.timestamp >= order.due_at);
+        if !(due || force || order.sent_at.elapsed() >= timeout) {
+            return;
+        }
+        let Some(fill_ticker) = ticker.or(self.last_ticker.as_ref()).cloned() else {
+            return;
+        };
+        if ticker.is_none() {
+            warn!(
+                "Paper order: no tick after the latency window, filling at the last price {}",
+                fill_ticker.price
+            );
+        }
+        let Some(order) = self.delayed_paper.take() else {
+            return;
+        };
+        self.fill_paper(
+            order.side,
+            order.quantity,
+            order.is_exit,
+            order.signal_price,
+            &fill_ticker,
+        );
+    }
+
+    async fn handle_signal(
+        &mut self,
+        side: Side,
+        current_price: Decimal,
+        // Доля позиции для выхода; 1 — вся. На входы не влияет
+        fraction: Decimal,
+        ticker: &Ticker,
+    ) -> Result<()> {
+        info!(symbol = %ticker.symbol, side = ?side, price = %current_price, "Signal detected");
+        self.send_ui_event(UiEvent::Signal(Signal::Advice(side, current_price)));
+
+        if let Some(order) = &self.delayed_paper {
+            debug!(
+                "Signal skipped: paper {:?} {} is still in flight",
+                order.side, order.quantity
+            );
+            return Ok(());
+        }
+
+        if let Some(pending) = &self.pending_order {
+            info!(
+                "Signal skipped: order {} ({} {:?} {} @ {}) is still resting ({}s)",
+                pending.id,
+                pending.symbol,
+                pending.side,
+                pending.quantity,
+                pending.price,
+                ticker.timestamp.saturating_sub(pending.placed_at) / 1000
+            );
+            self.report_skip(format!("order {} is still resting", pending.id));
+            return Ok(());
+        }
+
+        // Сигнал против открытой позиции — выход, без позиции — вход в сторону сигнала
+        let position = self.strategy.get_position();
+        let is_exit = match &position {
+            Some(pos) if side == pos.side.exit_side() => true,
+            Some(_) if self.strategy.scales_in() => false,
+            Some(pos) => {
+                debug!(
+                    "Signal skipped: already {:?} {} {}",
+                    pos.side, pos.quantity, pos.symbol
+                );
+                return Ok(());
+            }
+            None => false,
+        };
+        let is_entry = !is_exit;
+
+        if is_entry && fraction < Decimal::ONE {
+            debug!(
+                symbol = %ticker.symbol,
+                "Partial exit skipped: no open position"
+            );
+            return Ok(());
+        }
+
+        if is_entry
+            && !self
+                .config
+                .strategy
+                .trade_direction
+                .allows(PositionSide::from_entry(side))
+        {
+            warn!(
+                symbol = %ticker.symbol,
+                side = ?side,
+                trade_direction = ?self.config.strategy.trade_direction,
+                "Entry skipped: direction not allowed"
+            );
+            self.report_skip(format!("{:?} entries not allowed by trade_direction", side));
+            return Ok(());
+        }
+
+        if is_entry && self.margin_halted {
+            warn!(
+                symbol = %ticker.symbol,
+                rejections = self.margin_rejections,
+                "Entry skipped: halted after repeated insufficient margin rejections"
+            );
+            self.report_skip("halted after repeated margin rejections");
+            return Ok(());
+        }
+
+        if is_entry && self.spike_guard.is_halted() {
+            warn!(
+                symbol = %ticker.symbol,
+                "Entry skipped: paused after a flash move"
+            );
+            self.report_skip("paused after a flash move");
+            return Ok(());
+        }
+
+        if is_entry
+            && self
+                .inventory
+                .at_limit(&ticker.symbol, self.config.risk.max_concurrent_positions)
+        {
+            warn!(
+                symbol = %ticker.symbol,
+                open_positions = self.inventory.open_positions(),
+                max_positions = ?self.config.risk.max_concurrent_positions,
+                "Entry skipped: max concurrent positions reached"
+            );
+            self.report_skip(format!(
+                "max positions reached ({}/{})",
+                self.inventory.open_positions(),
+                self.config
+                    .risk
+                    .max_concurrent_positions
+                    .unwrap_or_default()
+            ));
+            return Ok(());
+        }
+
+        if is_entry && self.paused {
+            info!(
+                symbol = %ticker.symbol,
+                side = ?side,
+                "Entry skipped: entries paused"
+            );
+            self.report_skip("entries paused (p)");
+            return Ok(());
+        }
+
+        if is_entry && self.dead_man_halted {
+            warn!(
+                symbol = %ticker.symbol,
+                "Entry skipped: halted by dead man's switch (tick stream was lost)"
+            );
+            self.report_skip("halted by dead man's switch");
+            return Ok(());
+        }
+
+        if is_entry && self.drawdown_guard.is_halted() {
+            warn!(
+                symbol = %ticker.symbol,
+                drawdown = %self.drawdown_guard.drawdown().round_dp(4),
+                "Entry skipped: portfolio stop is active"
+            );
+            self.report_skip("portfolio stop is active");
+            return Ok(());
+        }
+
+        if is_entry && self.depeg_guard.halts_trading() {
+            warn!(
+                symbol = %ticker.symbol,
+                deviation = %self.depeg_guard.deviation().unwrap_or_default(),
+                "Entry skipped: trading halted on stablecoin depeg"
+            );
+            self.report_skip("stablecoin depeg");
+            return Ok(());
+        }
+
+        // Дневной лимит блокирует только входы, выходы исполняются всегда
+        if is_entry && self.daily_loss.entries_blocked(self.clock.now()) {
+            warn!(
+                symbol = %ticker.symbol,
+                daily_pnl = %self.daily_loss.realized_pnl(),
+                "Entry skipped: daily loss limit reached"
+            );
+            self.report_skip("daily loss limit reached");
+            return Ok(());
+        }
+
+        if is_entry {
+            if let Some((balance, floor)) = self.balance_below_floor().await {
+                warn!(
+                    symbol = %ticker.symbol,
+                    balance = %balance.round_dp(2),
+                    min_balance = %floor,
+                    "Entry skipped: account balance below floor"
+                );
+                self.report_skip(format!(
+                    "balance {} below floor {} {}",
+                    balance.round_dp(2),
+                    floor,
+                    self.config.quote_asset()
+                ));
+                return Ok(());
+            }
+        }
+
+        // Широкий спред — гарантированное проскальзывание. Выход при этом не держим:
+        // закрыться важнее, чем сэкономить
+        if let (true, Some(max_spread)) = (is_entry, self.config.risk.max_spread_bps) {
+            if let Some(spread) = spread_bps(ticker.bid_price, ticker.ask_price) {
+                if spread > max_spread {
+                    warn!(
+                        symbol = %ticker.symbol,
+                        bid = %ticker.bid_price,
+                        ask = %ticker.ask_price,
+                        spread_bps = %spread.round_dp(2),
+                        max_spread_bps = %max_spread,
+                        "Entry skipped: spread too wide"
+                    );
+                    self.report_skip(format!(
+                        "spread {} bps > {} bps",
+                        spread.round_dp(2),
+                        max_spread
+                    ));
+                    return Ok(());
+                }
+            }
+        }
+
+        // Проскальзывание входа: берём лучшую цену, по которой реально можно войти
+        if let (true, Some(max_slippage)) = (is_entry, self.config.risk.max_entry_slippage) {
+            let entry_price = match side {
+                Side::Buy => ticker.ask_price,
+                Side::Sell => ticker.bid_price,
+            };
+            if entry_slippage_exceeded(side, current_price, entry_price, max_slippage) {
+                warn!(
+                    symbol = %ticker.symbol,
+                    signal_price = %current_price,
+                    achievable_price = %entry_price,
+                    max_slippage = %max_slippage,
+                    "Entry aborted: market ran away"
+                );
+                self.report_skip(format!(
+                    "price ran away: {} vs signal {}",
+                    entry_price, current_price
+                ));
+                return Ok(());
+            }
+        }
+
+        // Funding: не открываем позицию, которая вот-вот заплатит сильную ставку
+        if let (true, Some(window)) = (is_entry, self.config.risk.funding_avoid_window_minutes) {
+            if let Some(funding) = self.current_funding() {
+                let now_ms = self.clock.now_ms();
+                let threshold = self.config.risk.funding_rate_threshold;
+                if funding_blocks_entry(side, &funding, now_ms, window, threshold) {
+                    warn!(
+                        symbol = %ticker.symbol,
+                        funding_rate = %funding.rate,
+                        due_in_secs = funding.next_funding_time.saturating_sub(now_ms) / 1000,
+                        window_minutes = window,
+                        threshold = %threshold,
+                        "Entry skipped: funding payment ahead"
+                    );
+                    self.report_skip(format!("funding {} due soon", funding.rate));
+                    return Ok(());
+                }
+            }
+        }
+
+        // Вход: объём из order_size_usdt. Выход: открытая позиция или её доля (reduce-only),
+        // не больше позиции, иначе лишний объём на фьючерсах откроет позицию в обратную сторону
+        let Some(quantity) = (if is_exit {
+            self.exit_quantity(ticker, current_price, fraction)
+        } else {
+            self.entry_quantity(current_price, ticker)
+        }) else {
+            return Ok(());
+        };
+        let notional_value = quantity * current_price;
+
+        // Коррелированные символы: лонг ETH при открытом лонге BTC — та же ставка вдвое
+        if is_entry {
+            if let Some((group, net_after)) =
+                self.correlation_breach(&ticker.symbol, side, notional_value)
+            {
+                warn!(
+                    symbol = %ticker.symbol,
+                    side = ?side,
+                    group = ?group.symbols,
+                    net_exposure = %net_after.round_dp(2),
+                    max_net_exposure = %group.max_net_exposure_usdt,
+                    "Entry skipped: correlated exposure limit"
+                );
+                self.report_skip(format!(
+                    "net exposure {} > {} {} across {}",
+                    net_after.round_dp(2),
+                    group.max_net_exposure_usdt,
+                    self.config.quote_asset(),
+                    group.symbols.join("/")
+                ));
+                return Ok(());
+            }
+        }
+        // 4. Подготовка цены (для лимитных ордеров или симуляции)
+        let Some(precision) = self.precision(&ticker.symbol) else {
+            return Ok(());
+        };
+        let tick_size = precision.tick_size;
+
+        // В Paper Mode мы "исполняем" по текущей цене (или с проскальзыванием), но нормализуем её
+        // В Live Mode ExecutionHandler сам может добавить slippage, но нам нужна базовая цена
+        let target_price = normalize_price(current_price, tick_size);
+
+        if self.mode == TradingMode::Paper {
+            // --- PAPER MODE ---
+            // Задержка до биржи: исполнится тик, пришедший после неё (fill_delayed_paper)
+            let latency_ms = self.config.paper.simulated_latency_ms;
+            if latency_ms > 0 {
+                self.last_order_at = Some(self.clock.now_ms());
+                debug!(
+                    symbol = %ticker.symbol,
+                    side = ?side,
+                    qty = %quantity,
+                    latency_ms,
+                    "Paper order in flight"
+                );
+                self.delayed_paper = Some(DelayedPaperOrder {
+                    side,
+                    quantity,
+                    is_exit,
+                    signal_price: target_price,
+                    due_at: ticker.timestamp + latency_ms,
+                    sent_at: Instant::now(),
+                });
+                return Ok(());
+            }
+            self.fill_paper(side, quantity, is_exit, target_price, ticker);
+            return Ok(());
+        }
+
+        // --- LIVE MODE ---
+        // Для Live режима мы передаем нормализованное количество.
+        // Цену execution_handler может пересчитать (slippage), но мы передадим ему "чистую"
+        // или позволим ему самому решать. В текущей реализации handler принимает option price.
+
+        // Добавим проскальзывание для лимитного ордера, чтобы он сработал как маркет (taker)
+        // или оставим current_price если это Market Order (в зависимости от реализации handler).
+        // Предположим, мы шлем Limit ордер с агрессивной ценой.
+
+        // Maker: вход post-only на своей стороне стакана, ордер встаёт и ждёт исполнения (комиссия maker).
+        // Taker и любой выход: агрессивный IOC-лимит через спред. Выход ждать не может:
+        // висящий ордер блокирует следующие сигналы, в том числе жёсткий стоп
+        let execution = if is_exit {
+            OrderExecution::Taker
+        } else {
+            self.config.order_execution
+        };
+        let (execution_price_raw, time_in_force) = match execution {
+            OrderExecution::Maker => match side {
+                Side::Buy => (ticker.bid_price, TimeInForce::Gtx),
+                Side::Sell => (ticker.ask_price, TimeInForce::Gtx),
+            },
+            OrderExecution::Taker => {
+                let slippage_pct = ONE_TENTH_PCT; // 0.1%
+                match side {
+                    Side::Buy => (
+                        current_price * (Decimal::ONE + slippage_pct),
+                        TimeInForce::Ioc,
+                    ),
+                    Side::Sell => (
+                        current_price * (Decimal::ONE - slippage_pct),
+                        TimeInForce::Ioc,
+                    ),
+                }
+            }
+        };
+        let final_price = normalize_price(execution_price_raw, tick_size);
+
+        info!(
+            symbol = %ticker.symbol,
+            side = ?side,
+            qty = %quantity,
+            price = %final_price,
+            notional = %notional_value.round_dp(2),
+            margin = %(notional_value / Decimal::from(self.config.leverage.max(1))).round_dp(2),
+            leverage = self.config.leverage,
+            "Executing LIVE order"
+        );
+
+        let request = OrderRequest {
+            symbol: ticker.symbol.clone(),
+            side,
+            quantity,
+            order_type: OrderType::Limit {
+                price: final_price,
+                time_in_force,
+            },
+            reduce_only: is_exit,
+            client_order_id: new_client_order_id(),
+        };
+
+        // Dry-run считается как отправленный: интервал между ордерами тот же, что в live
+        self.last_order_at = Some(self.clock.now_ms());
+
+        // Dry-run: всё как в live, кроме отправки. Без симуляции исполнения и PnL
+        if self.mode == TradingMode::DryRun {
+            info!(
+                symbol = %request.symbol,
+                side = ?request.side,
+                order_type = ?request.order_type,
+                qty = %request.quantity,
+                reduce_only = request.reduce_only,
+                "🧪 DRY RUN: order not sent"
+            );
+            self.send_ui_event(UiEvent::Log(format!(
+                "DRY RUN: {}",
+                describe_order(&request)
+            )));
+            return Ok(());
+        }
+
+        let result = self.submit_order(&request).await;
+        if result.is_ok() {
+            self.margin_rejections = 0;
+        }
+        match result {
+            Ok(order) => match order.status {
+                OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::ExpiredInMatch
+                    if order.executed_qty.is_zero() =>
+                {
+                    warn!(
+                        "Order {} not filled ({:?}). Skipping {:?} signal",
+                        order.id, order.status, side
+                    );
+                }
+                // IOC, снятый биржей с частичным объёмом (EXPIRED у Binance, CANCELLED у Coinbase,
+                // canceled у OKX): исполненная часть уже на бирже, учитываем её как частичное исполнение
+                OrderStatus::Filled
+                | OrderStatus::PartiallyFilled
+                | OrderStatus::Canceled
+                | OrderStatus::Expired
+                | OrderStatus::ExpiredInMatch => {
+                    info!(
+                        order_id = %order.id,
+                        symbol = %order.symbol,
+                        status = ?order.status,
+                        executed_qty = %order.executed_qty,
+                        "✅ Order filled"
+                    );
+                    let filled_qty = if order.executed_qty.is_zero() {
+                        quantity
+                    } else {
+                        order.executed_qty
+                    };
+                    // Лимитная цена — только граница, реальная цена в avgPrice
+                    let fill_price = order.avg_price.unwrap_or(final_price);
+                    self.apply_live_fill(side, filled_qty, fill_price, ticker)
+                        .await;
+                    if is_entry {
+                        self.bail_on_fill_slippage(side, current_price, fill_price, filled_qty)
+                            .await;
+                    }
+                }
+                OrderStatus::New | OrderStatus::Accepted => {
+                    info!(
+                        "⏳ Order {} resting: {:?} {} @ {}",
+                        order.id, side, quantity, final_price
+                    );
+                    self.pending_order = Some(PendingOrder {
+                        id: order.id,
+                        client_order_id: request.client_order_id.clone(),
+                        symbol: order.symbol,
+                        side,
+                        quantity,
+                        price: final_price,
+                        placed_at: ticker.timestamp,
+                        reprices: 0,
+                    });
+                }
+            },
+            Err(e) => {
+                error!(symbol = %ticker.symbol, side = ?side, error = %e, "⚠️ Execution Error");
+                self.handle_order_error(&e, is_exit);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Вход исполнился хуже цены сигнала больше max_slippage_bps: исполненный объём сразу
+    /// закрывается по рынку. При частичном исполнении — только исполненная часть
+    async fn bail_on_fill_slippage(
+        &mut self,
+        side: Side,
+        signal_price: Decimal,
+        fill_price: Decimal,
+        filled_qty: Decimal,
+    ) {
+        let Some(max_slippage) = self.config.risk.max_slippage_bps else {
+            return;
+        };
+        let Some(slippage) = fill_slippage_bps(side, signal_price, fill_price) else {
+            return;
+        };
+        if slippage <= max_slippage {
+            return;
+        }
+        METRICS.slippage_bailouts.inc();
+        warn!(
+            symbol = %self.config.symbol,
+            side = ?side,
+            signal_price = %signal_price,
+            fill_price = %fill_price,
+            slippage_bps = %slippage.round_dp(2),
+            max_slippage_bps = %max_slippage,
+            qty = %filled_qty,
+            "🚨 Entry slippage exceeded, bailing out"
+        );
+        self.send_ui_event(UiEvent::Log(format!(
+            "SLIPPAGE {} bps: filled @ {} vs signal {}, closing {}",
+            slippage.round_dp(1),
+            fill_price,
+            signal_price,
+            filled_qty
+        )));
+        let Some(mut pos) = self.strategy.get_position() else {
+            return;
+        };
+        pos.quantity = pos.quantity.min(filled_qty);
+        // Box: flatten_at_market сам может вызвать handle_signal, откуда мы пришли
+        if !Box::pin(self.flatten_at_market(pos, "Slippage bail-out")).await {
+            self.send_ui_event(UiEvent::Log(
+                "Slippage bail-out failed, position is still open".to_string(),
+            ));
+        }
+    }
+
+    /// Отправка ордера с повторами при сетевых сбоях и 5xx (`order_retry_attempts`).
+    /// Ответ мог потеряться, а ордер — дойти до биржи: перед каждым повтором и после последней
+    /// попытки ищем его по client order id, чтобы не отправить второй и не потерять первый
+    async fn submit_order(&self, request: &OrderRequest) -> Result<OrderResponse> {
+        let mut attempt = 0;
+        let result = self
+            .config
+            .order_retry()
+            .run("Order request", || {
+                attempt += 1;
+                let retry = attempt > 1;
+                async move {
+                    if retry {
+                        if let Some(order) = self.find_sent_order(request).await? {
+                            return Ok(order);
+                        }
+                    }
+                    self.execution_handler.place_order(request).await
+                }
+            })
+            .await;
+        let error = match result {
+            Err(e) if e.is_transient() => e,
+            result => return result,
+        };
+        match self.find_sent_order(request).await {
+            Ok(Some(order)) => Ok(order),
+            Ok(None) => Err(error),
+            Err(e) => {
+                error!(
+                    client_order_id = %request.client_order_id,
+                    error = %e,
+                    "⚠️ Order request failed and its state is unknown. Check open orders on the exchange"
+                );
+                Err(error)
+            }
+        }
+    }
+
+    /// Ордер, который дошёл до биржи, хотя ответа на его отправку мы не получили
+    async fn find_sent_order(&self, request: &OrderRequest) -> Result<Option<OrderResponse>> {
+        let order = self
+            .execution_handler
+            .query_order_by_client_id(&request.symbol, &request.client_order_id)
+            .await?;
+        if let Some(order) = &order {
+            warn!(
+                client_order_id = %request.client_order_id,
+                order_id = %order.id,
+                status = ?order.status,
+                "Order request failed, but the order reached the exchange"
+            );
+        }
+        Ok(order)
+    }
+
+    /// Реакция на известные отказы биржи (код из тела ответа Binance)
+    fn handle_order_error(&mut self, error: &BotError, is_exit: bool) {
+        match error {
+            BotError::OrderRejected { .. } | BotError::Api { .. } => {}
+            BotError::RateLimited { .. } => {
+                warn!("Order hit the exchange rate limit. Next signals may be rejected too");
+                return;
+            }
+            BotError::Auth { .. } => {
+                error!("🔑 Order rejected: authentication failed. Check API keys and clock sync");
+                return;
+            }
+            _ => return,
+        }
+        match error.kind() {
+            BinanceErrorKind::InsufficientMargin => {
+                self.margin_rejections += 1;
+                let limit = self.config.risk.max_margin_rejections;
+                if !self.margin_halted && self.margin_rejections >= limit {
+                    self.margin_halted = true;
+                    error!(
+                        "🚨 {} insufficient margin rejections in a row. Entries halted until restart",
+                        self.margin_rejections
+                    );
+                    let msg = format!("HALT: margin insufficient x{}", self.margin_rejections);
+                    self.send_ui_event(UiEvent::Log(msg.clone()));
+                    self.publish_halt(msg);
+                    self.send_risk_snapshot();
+                }
+            }
+            // Биржа говорит, что уменьшать нечего: позиция уже закрыта (вручную или ликвидацией)
+            BinanceErrorKind::ReduceOnlyRejected if is_exit => {
+                warn!(
+                    "Reduce-only exit rejected: no position on exchange. Clearing local position"
+                );
+                self.set_position(None);
+            }
+            BinanceErrorKind::MinNotional => {
+                warn!(
+                    "Exchange rejected order notional: {}. Check order_size_usdt against the symbol minimum",
+                    error
+                );
+            }
+            BinanceErrorKind::ReduceOnlyRejected | BinanceErrorKind::Other => {}
+        }
+    }
+
+    /// Исполнение живого ордера: встречный позиции ордер уменьшает/закрывает её, иначе открывает новую
+    async fn apply_live_fill(
+        &mut self,
+        side: Side,
+        filled_qty: Decimal,
+        price: Decimal,
+        ticker: &Ticker,
+    ) {
+        match self.strategy.get_position() {
+            Some(mut pos) if side == pos.side.exit_side() => {
+                let closed = filled_qty.min(pos.quantity);
+                self.record_realized_pnl(&pos, closed, price);
+                pos.quantity -= closed;
+                // Частичное исполнение: остаток позиции продолжаем вести
+                let remaining = if pos.quantity > Decimal::ZERO {
+                    Some(pos)
+                } else {
+                    None
+                };
+                self.set_position(remaining);
+            }
+            _ => {
+                self.entry_throttle
+                    .record(ticker.timestamp, filled_qty * price);
+                let pos = self.position_after_entry(side, filled_qty, price, ticker);
+                self.publish_entry(&pos);
+                self.set_position(Some(pos));
+            }
+        }
+    }
+
+    /// Позиция после входа. Докупка к открытой позиции (`Strategy::scales_in`) усредняет
+    /// цену входа, время открытия остаётся от первого лота
+    fn position_after_entry(
+        &self,
+        side: Side,
+        quantity: Decimal,
+        price: Decimal,
+        ticker: &Ticker,
+    ) -> Position {
+        match self.strategy.get_position() {
+            Some(mut pos) if pos.side == PositionSide::from_entry(side) => {
+                let total = pos.quantity + quantity;
+                pos.entry_price = (pos.entry_price * pos.quantity + price * quantity) / total;
+                pos.quantity = total;
+                pos
+            }
+            _ => Position {
+                symbol: ticker.symbol.clone(),
+                side: PositionSide::from_entry(side),
+                quantity,
+                entry_price: price,
+                unrealized_pnl: Decimal::ZERO,
+                highest_price: price,
+                opened_at: ticker.timestamp,
+                armed: false,
+                tp_levels_hit: 0,
+                trail_tier: 0,
+            },
+        }
+    }
+
+    /// Опрос висящего maker-ордера (не чаще PENDING_POLL_MS по времени тиков).
+    /// Страховка на случай, если user-data stream не подключён или отстаёт
+    async fn poll_pending_order(&mut self, ticker: &Ticker) {
+        let Some(pending) = self.pending_order.clone() else {
+            return;
+        };
+        if ticker.timestamp.saturating_sub(self.last_pending_poll) < PENDING_POLL_MS {
+            return;
+        }
+        self.last_pending_poll = ticker.timestamp;
+
+        if let Some(timeout) = self.config.order_timeout_seconds {
+            if ticker.timestamp.saturating_sub(pending.placed_at) >= timeout * 1000 {
+                self.expire_pending_order(pending, ticker).await;
+                return;
+            }
+        }
+
+        match self
+            .execution_handler
+            .query_order(&pending.symbol, &pending.id)
+            .await
+        {
+            Ok(order) => self.resolve_pending_order(order, None, ticker).await,
+            Err(e) => warn!(order_id = %pending.id, error = %e, "Pending order poll failed"),
+        }
+    }
+
+    /// Таймаут висящего ордера: снять, затем запросить итог у биржи — ордер мог исполниться
+    /// между проверкой и отменой, такое исполнение применяется как обычно.
+    /// Выход перевыставляется всегда, вход — по order_timeout_action
+    async fn expire_pending_order(&mut self, pending: PendingOrder, ticker: &Ticker) {
+        warn!(
+            order_id = %pending.id,
+            side = ?pending.side,
+            qty = %pending.quantity,
+            price = %pending.price,
+            age_secs = ticker.timestamp.saturating_sub(pending.placed_at) / 1000,
+            "⌛ Order timed out, canceling"
+        );
+        if let Err(e) = self
+            .execution_handler
+            .cancel_order(&pending.symbol, &pending.id)
+            .await
+        {
+            // Например, "Unknown order": уже исполнен или снят. Итог покажет запрос ниже
+            warn!(order_id = %pending.id, error = %e, "Cancel failed, checking order state");
+        }
+
+        let order = match self
+            .execution_handler
+            .query_order(&pending.symbol, &pending.id)
+            .await
+        {
+            Ok(order) => order,
+            Err(e) => {
+                warn!(order_id = %pending.id, error = %e, "Order state unknown after cancel, retrying");
+                return;
+            }
+        };
+        if order.status.is_resting() {
+            warn!(order_id = %pending.id, status = ?order.status, "Order still resting after cancel, retrying");
+            return;
+        }
+        let filled = order.executed_qty;
+        self.resolve_pending_order(order, None, ticker).await;
+        if self.pending_order.is_some() {
+            return;
+        }
+
+        let position = self.strategy.get_position();
+        let is_exit = position
+            .as_ref()
+            .is_some_and(|pos| pending.side == pos.side.exit_side());
+        // Выход перевыставляется на неисполненный остаток ордера (частичный выход не растёт до полного)
+        let fraction = match &position {
+            Some(pos) if is_exit && !pos.quantity.is_zero() => {
+                ((pending.quantity - filled) / pos.quantity).min(Decimal::ONE)
+            }
+            _ => Decimal::ONE,
+        };
+        let reprice = if is_exit {
+            true
+        } else {
+            // Частично исполненный вход уже открыл позицию: добирать не будем
+            filled.is_zero()
+                && self.config.order_timeout_action == OrderTimeoutAction::Reprice
+                && pending.reprices < MAX_ENTRY_REPRICES
+        };
+        if !reprice {
+            self.send_ui_event(UiEvent::Log(format!(
+                "Order {} timed out and was canceled",
+                pending.id
+            )));
+            return;
+        }
+
+        info!(
+            order_id = %pending.id,
+            side = ?pending.side,
+            exit = is_exit,
+            "Re-pricing timed out order at current book"
+        );
+        if let Err(e) = self
+            .handle_signal(pending.side, ticker.price, fraction, ticker)
+            .await
+        {
+            error!("Re-pricing failed: {}", e);
+        }
+        if let Some(new_order) = self.pending_order.as_mut() {
+            new_order.reprices = pending.reprices + 1;
+        }
+    }
+
+    /// Применяет исполнение висящего ордера, когда он завершён:
+    /// FILLED или снят с частичным объёмом. fill_price = None — цена ордера
+    async fn resolve_pending_order(
+        &mut self,
+        order: OrderResponse,
+        fill_price: Option<Decimal>,
+        ticker: &Ticker,
+    ) {
+        let Some(pending) = self.pending_order.clone() else {
+            return;
+        };
+        // По client id — на случай, если id биржи мы так и не получили
+        if pending.id != order.id && pending.client_order_id != order.client_order_id {
+            return;
+        }
+        let price = fill_price.filter(|p| !p.is_zero()).unwrap_or(pending.price);
+
+        match order.status {
+            OrderStatus::New | OrderStatus::Accepted | OrderStatus::PartiallyFilled => {}
+            OrderStatus::Filled => {
+                info!(
+                    order_id = %order.id,
+                    side = ?pending.side,
+                    executed_qty = %order.executed_qty,
+                    price = %price,
+                    "✅ Maker order filled"
+                );
+                METRICS.orders_filled.inc();
+                self.pending_order = None;
+                self.apply_live_fill(pending.side, order.executed_qty, price, ticker)
+                    .await;
+            }
+            OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::ExpiredInMatch => {
+                self.pending_order = None;
+                if order.executed_qty.is_zero() {
+                    warn!(order_id = %order.id, status = ?order.status, "Maker order closed without fill");
+                } else {
+                    info!(
+                        order_id = %order.id,
+                        status = ?order.status,
+                        executed_qty = %order.executed_qty,
+                        "Maker order closed partially filled"
+                    );
+                    self.apply_live_fill(pending.side, order.executed_qty, price, ticker)
+                        .await;
+                }
+            }
+        }
+    }
+
+    async fn handle_account_event(&mut self, event: AccountEvent) {
+        match event {
+            AccountEvent::PositionUpdate {
+                symbol,
+                amount,
+                entry_price,
+            } => {
+                if symbol == self.config.symbol {
+                    self.reconcile_position(amount, entry_price).await;
+                }
+            }
+            AccountEvent::OrderUpdate {
+                symbol,
+                order_id,
+                client_order_id,
+                status,
+                filled_qty,
+                avg_price,
+                ..
+            } => {
+                // Без последнего тика не знаем время свечи; ордер подхватит опрос
+                let Some(ticker) = self.last_ticker.clone() else {
+                    return;
+                };
+                let order = OrderResponse {
+                    id: order_id,
+                    client_order_id,
+                    symbol,
+                    status,
+                    executed_qty: filled_qty,
+                    avg_price: Some(avg_price).filter(|price| !price.is_zero()),
+                };
+                self.resolve_pending_order(order, Some(avg_price), &ticker)
+                    .await;
+            }
+        }
+    }
+
+    /// Live: позиция из файла состояния сверяется с биржей до первого тика. Пока бот стоял,
+    /// позицию могли закрыть руками, ликвидировать или доисполнить. Без ответа биржи не стартуем:
+    /// торговать от позиции, которой, возможно, уже нет, хуже, чем не торговать
+    async fn reconcile_startup_position(&mut self) -> Result<()> {
+        let symbol = self.config.symbol.clone();
+        let exchange = match self.execution_handler.fetch_position(&symbol).await {
+            Ok(Some(exchange)) => exchange,
+            // Спот: позиций у биржи нет, остаётся сохранённое состояние
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                error!(symbol = %symbol, error = %e, "❌ Startup position check failed");
+                return Err(e);
+            }
+        };
+        info!(
+            symbol = %symbol,
+            exchange_qty = %exchange.amount,
+            exchange_entry = %exchange.entry_price,
+            local = ?self.strategy.get_position().map(|p| (p.side, p.quantity, p.entry_price)),
+            "Startup position check"
+        );
+        self.reconcile_position(exchange.amount, exchange.entry_price)
+            .await;
+        Ok(())
+    }
+
+    /// Биржа — источник истины: ручные сделки и ликвидации перезаписывают позицию бота
+    async fn reconcile_position(&mut self, amount: Decimal, entry_price: Decimal) {
+        let local = self.strategy.get_position();
+        // Знаковый объём, как у биржи: > 0 лонг, < 0 шорт
+        let local_qty = local
+            .as_ref()
+            .map(|p| match p.side {
+                PositionSide::Long => p.quantity,
+                PositionSide::Short => -p.quantity,
+            })
+            .unwrap_or_default();
+        if amount == local_qty {
+            // Объём сходится, но цена входа у биржи точнее: IOC мог исполниться лучше лимита
+            if let Some(mut pos) = local.filter(|p| p.entry_price != entry_price) {
+                if !entry_price.is_zero() {
+                    info!(
+                        symbol = %self.config.symbol,
+                        local_entry = %pos.entry_price,
+                        exchange_entry = %entry_price,
+                        "Entry price synced from exchange"
+                    );
+                    pos.entry_price = entry_price;
+                    self.set_position(Some(pos));
+                }
+            }
+            return;
+        }
+
+        warn!(
+            symbol = %self.config.symbol,
+            exchange_qty = %amount,
+            local_qty = %local_qty,
+            exchange_entry = %entry_price,
+            "⚠️ Position diverged from exchange. Adopting exchange position"
+        );
+        self.send_ui_event(UiEvent::Log(format!(
+            "Position resync: local {} -> exchange {}",
+            local_qty, amount
+        )));
+
+        let adopted = if amount.is_zero() {
+            None
+        } else {
+            let side = if amount > Decimal::ZERO {
+                PositionSide::Long
+            } else {
+                PositionSide::Short
+            };
+            // Трейлинг и отсчёт удержания продолжаем от локальной позиции, если направление то же
+            let same_side = local.filter(|p| p.side == side);
+            let best = match &same_side {
+                Some(p) if side == PositionSide::Long => p.highest_price.max(entry_price),
+                Some(p) => p.highest_price.min(entry_price),
+                None => entry_price,
+            };
+            let opened_at = same_side
+                .as_ref()
+                .map(|p| p.opened_at)
+                .unwrap_or_else(|| self.clock.now_ms());
+            let armed = same_side.as_ref().is_some_and(|p| p.armed);
+            let tp_levels_hit = same_side
+                .as_ref()
+                .map(|p| p.tp_levels_hit)
+                .unwrap_or_default();
+            let trail_tier = same_side.map(|p| p.trail_tier).unwrap_or_default();
+            Some(Position {
+                symbol: self.config.symbol.clone(),
+                side,
+                quantity: amount.abs(),
+                entry_price,
+                unrealized_pnl: Decimal::ZERO,
+                highest_price: best,
+                opened_at,
+                armed,
+                tp_levels_hit,
+                trail_tier,
+            })
+        };
+        self.set_position(adopted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ExchangePosition;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    const SYMBOL: &str = "BTCUSDT";
+
+    // Стратегия по сценарию: сигналы из очереди по одному на тик, позицию хранит как есть
+    struct ScriptedStrategy {
+        signals: VecDeque<Signal>,
+        position: Option<Position>,
+    }
+
+    #[async_trait]
+    impl Strategy for ScriptedStrategy {
+        fn name(&self) -> String {
+            "scripted".to_string()
+        }
+
+        async fn init(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn on_tick(&mut self, _ticker: &Ticker) -> anyhow::Result<Signal> {
+            Ok(self.signals.pop_front().unwrap_or(Signal::Hold))
+        }
+
+        fn update_position(&mut self, position: Option<Position>) {
+            self.position = position;
+        }
+
+        fn get_position(&self) -> Option<Position> {
+            self.position.clone()
+        }
+
+        async fn switch_symbol(&mut self, _symbol: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Биржа, которая отвечает на каждый ордер статусом `status` и запоминает запросы.
+    // Исполненный ордер — целиком по лимитной цене, `executed` задаёт исполненный объём явно.
+    // None — статус, которого бот не знает.
+    // `reject` возвращает тело ошибки Binance для ордеров, которые биржа отклоняет.
+    // `lost` — сколько следующих ответов теряется: ордер принят, но клиент видит 503
+    struct MockExchange {
+        orders: Arc<Mutex<Vec<OrderRequest>>>,
+        executed: Arc<Mutex<Option<Decimal>>>,
+        lost: Arc<Mutex<u32>>,
+        status: Option<OrderStatus>,
+        reject: Rejection,
+    }
+
+    type Rejection = fn(&OrderRequest) -> Option<&'static str>;
+
+    impl MockExchange {
+        // Ответ биржи на `number`-й принятый ордер
+        fn reply(&self, number: usize, request: &OrderRequest) -> Result<OrderResponse> {
+            let status = self.status.ok_or_else(|| BotError::OrderRejected {
+                code: 0,
+                msg: format!("Order {} has unexpected status: REJECTED", number),
+            })?;
+            let executed_qty = match *self.executed.lock().unwrap() {
+                Some(qty) => qty,
+                None if status.is_filled() => request.quantity,
+                None => Decimal::ZERO,
+            };
+            Ok(OrderResponse {
+                id: number.to_string(),
+                client_order_id: request.client_order_id.clone(),
+                symbol: request.symbol.clone(),
+                status,
+                executed_qty,
+                avg_price: request
+                    .order_type
+                    .limit_price()
+                    .filter(|_| !executed_qty.is_zero()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ExecutionHandler for MockExchange {
+        async fn get_balance(&self, _asset: &str) -> Result<Decimal> {
+            Ok(Decimal::from(10_000))
+        }
+
+        async fn place_order(&self, request: &OrderRequest) -> Result<OrderResponse> {
+            let number = {
+                let mut orders = self.orders.lock().unwrap();
+                orders.push(request.clone());
+                orders.len()
+            };
+            if let Some(body) = (self.reject)(request) {
+                return Err(BotError::from_binance_body(400, body).into_order_rejection());
+            }
+            let mut lost = self.lost.lock().unwrap();
+            if *lost > 0 {
+                *lost -= 1;
+                let body =
+                    r#"{"code":-1001,"msg":"Internal error; unable to process your request."}"#;
+                return Err(BotError::from_binance_body(503, body).into_order_rejection());
+            }
+            self.reply(number, request)
+        }
+
+        async fn cancel_order(&self, _symbol: &str, _order_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn query_order(&self, _symbol: &str, order_id: &str) -> Result<OrderResponse> {
+            Err(BotError::Parse(format!("unknown order {}", order_id)))
+        }
+
+        async fn query_order_by_client_id(
+            &self,
+            _symbol: &str,
+            client_order_id: &str,
+        ) -> Result<Option<OrderResponse>> {
+            let orders = self.orders.lock().unwrap().clone();
+            orders
+                .iter()
+                .position(|order| order.client_order_id == client_order_id)
+                .map(|index| self.reply(index + 1, &orders[index]))
+                .transpose()
+        }
+
+        async fn fetch_position(&self, _symbol: &str) -> Result<Option<ExchangePosition>> {
+            Ok(None)
+        }
+
+        async fn load_symbol(
+            &mut self,
+            symbol: &str,
+            _leverage: Option<u8>,
+        ) -> Result<SymbolPrecision> {
+            Err(BotError::Config(format!("no filters for {}", symbol)))
+        }
+
+        fn normalize_price(&self, _symbol: &str, price: Decimal) -> Decimal {
+            price
+        }
+
+        fn normalize_quantity(&self, _symbol: &str, quantity: Decimal) -> Decimal {
+            quantity
+        }
+    }
+
+    // Движок с биржей-заглушкой. Каналы UI и команд держим, чтобы движок не видел их закрытыми;
+    // из UI читаются строки лога
+    struct Harness {
+        engine: TradingEngine<ScriptedStrategy>,
+        orders: Arc<Mutex<Vec<OrderRequest>>>,
+        executed: Arc<Mutex<Option<Decimal>>>,
+        lost: Arc<Mutex<u32>>,
+        ui: mpsc::Receiver<UiEvent>,
+        _commands: mpsc::Sender<EngineCommand>,
+    }
+
+    impl Harness {
+        fn sent_orders(&self) -> Vec<OrderRequest> {
+            self.orders.lock().unwrap().clone()
+        }
+
+        // Следующие ордера биржа исполняет ровно на `qty`, какой бы ни была их заявка
+        fn execute_only(&self, qty: Decimal) {
+            *self.executed.lock().unwrap() = Some(qty);
+        }
+
+        // Биржа примет следующие `count` ордеров, но ответы на них потеряются
+        fn lose_responses(&self, count: u32) {
+            *self.lost.lock().unwrap() = count;
+        }
+
+        fn position(&self) -> Option<Position> {
+            self.engine.strategy.get_position()
+        }
+
+        fn ui_logs(&mut self) -> Vec<String> {
+            let mut logs = Vec::new();
+            while let Ok(event) = self.ui.try_recv() {
+                if let UiEvent::Log(line) = event {
+                    logs.push(line);
+                }
+            }
+            logs
+        }
+    }
+
+    fn test_config() -> AppConfig {
+        serde_json::from_value(json!({
+            "api_key": "key",
+            "secret_key": "secret",
+            "symbol": SYMBOL,
+            "leverage": 5,
+            "order_size_usdt": 100.0,
+            "symbol_tick_size": "0.01",
+            "symbol_step_size": "0.001",
+            "strategy": {
+                "rsi_period": 14,
+                "obi_threshold": 0.3,
+                "bb_period": 20,
+                "bb_std_dev": 2.0,
+                "min_volatility": "0",
+                "atr_multiplier": 2.0,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn harness(config: AppConfig, mode: TradingMode, signals: Vec<Signal>) -> Harness {
+        harness_with(config, mode, signals, Some(OrderStatus::Filled), |_| None)
+    }
+
+    fn harness_with(
+        config: AppConfig,
+        mode: TradingMode,
+        signals: Vec<Signal>,
+        status: Option<OrderStatus>,
+        reject: Rejection,
+    ) -> Harness {
+        let orders = Arc::new(Mutex::new(Vec::new()));
+        let executed = Arc::new(Mutex::new(None));
+        let lost = Arc::new(Mutex::new(0));
+        let exchange = MockExchange {
+            orders: orders.clone(),
+            executed: executed.clone(),
+            lost: lost.clone(),
+            status,
+            reject,
+        };
+        let strategy = ScriptedStrategy {
+            signals: signals.into(),
+            position: None,
+        };
+        let (_ticks, ticker_receiver) = ring_channel(16);
+        let (ui_sender, ui) = mpsc::channel(1024);
+        let (commands, command_receiver) = mpsc::channel(16);
+        let engine = TradingEngine::new(
+            config,
+            Box::new(exchange),
+            strategy,
+            ticker_receiver,
+            ui_sender,
+            command_receiver,
+            mode,
+        );
+        Harness {
+            engine,
+            orders,
+            executed,
+            lost,
+            ui,
+            _commands: commands,
+        }
+    }
+
+    fn tick(timestamp: u64, price: Decimal) -> Ticker {
+        Ticker {
+            symbol: SYMBOL.to_string(),
+            price,
+            bid_price: price,
+            ask_price: price,
+            bid_qty: Decimal::ONE,
+            ask_qty: Decimal::ONE,
+            timestamp,
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_strategy_signals_are_ignored_and_disable_flattens() {
+        let price = Decimal::from(100);
+        let buy = Signal::Advice(Side::Buy, price);
+        let mut h = harness(
+            test_config(),
+            TradingMode::Live,
+            vec![buy.clone(), buy.clone(), buy],
+        );
+
+        h.engine
+            .handle_command(EngineCommand::DisableStrategy("scripted".to_string()))
+            .await
+            .unwrap();
+        h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        assert!(h.sent_orders().is_empty());
+
+        // Чужой id стратегию не включает
+        h.engine
+            .handle_command(EngineCommand::EnableStrategy("other".to_string()))
+            .await
+            .unwrap();
+        h.engine.process_ticker(tick(2_000, price)).await.unwrap();
+        assert!(h.sent_orders().is_empty());
+        assert!(h.position().is_none());
+
+        h.engine
+            .handle_command(EngineCommand::EnableStrategy("scripted".to_string()))
+            .await
+            .unwrap();
+        h.engine.process_ticker(tick(3_000, price)).await.unwrap();
+        let orders = h.sent_orders();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].side, Side::Buy);
+        assert!(!orders[0].reduce_only);
+        assert!(h.position().is_some());
+
+        // Выключение с открытой позицией закрывает её reduce-only ордером
+        h.engine
+            .handle_command(EngineCommand::DisableStrategy("scripted".to_string()))
+            .await
+            .unwrap();
+        let orders = h.sent_orders();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[1].side, Side::Sell);
+        assert!(orders[1].reduce_only);
+        assert_eq!(orders[1].quantity, orders[0].quantity);
+        assert!(h.position().is_none());
+    }
+
+    #[tokio::test]
+    async fn three_entries_in_one_candle_open_only_the_capped_exposure() {
+        let price = Decimal::from(100);
+        let buy = Signal::Advice(Side::Buy, price);
+        let sell = Signal::Advice(Side::Sell, price);
+        let mut config = test_config();
+        config.risk.max_entry_notional_per_candle = Some(Decimal::from(150));
+        // Вход, выход, вход, выход, вход — всё в одной минутной свече
+        let mut h = harness(
+            config,
+            TradingMode::Live,
+            vec![buy.clone(), sell.clone(), buy.clone(), sell, buy],
+        );
+        for i in 0..5 {
+            h.engine
+                .process_ticker(tick(1_000 + i * 1_000, price))
+                .await
+                .unwrap();
+        }
+
+        let entries: Vec<_> = h
+            .sent_orders()
+            .into_iter()
+            .filter(|order| !order.reduce_only)
+            .collect();
+        assert_eq!(entries.len(), 2);
+        let exposure: Decimal = entries
+            .iter()
+            .map(|order| order.quantity * order.order_type.limit_price().unwrap())
+            .sum();
+        // Первый вход целиком, второй урезан до остатка лимита по цене исполнения, третий отклонён.
+        // Остаток считается по сигналу, исполнение на сдвиг IOC хуже: допуск 0.1%
+        assert_eq!(entries[0].quantity, Decimal::ONE);
+        assert_eq!(entries[1].quantity, Decimal::new(499, 3));
+        assert!(exposure <= Decimal::from(150) * (Decimal::ONE + ONE_TENTH_PCT));
+        assert!(h.position().is_none());
+    }
+
+    #[tokio::test]
+    async fn place_order_status_decides_position_and_resting_order() {
+        let price = Decimal::from(100);
+        let partial = Decimal::new(4, 1);
+        // (статус биржи, исполненный объём, открытая позиция, висит ли ордер)
+        let cases = [
+            (Some(OrderStatus::Filled), None, true, false),
+            (Some(OrderStatus::PartiallyFilled), None, true, false),
+            (Some(OrderStatus::New), None, false, true),
+            (Some(OrderStatus::Accepted), None, false, true),
+            (Some(OrderStatus::Canceled), None, false, false),
+            (Some(OrderStatus::Expired), None, false, false),
+            (Some(OrderStatus::ExpiredInMatch), None, false, false),
+            // IOC, снятый с частичным исполнением: исполненная часть — уже позиция
+            (Some(OrderStatus::Expired), Some(partial), true, false),
+            (Some(OrderStatus::Canceled), Some(partial), true, false),
+            (None, None, false, false),
+        ];
+        for (status, executed, opened, resting) in cases {
+            let mut h = harness_with(
+                test_config(),
+                TradingMode::Live,
+                vec![Signal::Advice(Side::Buy, price)],
+                status,
+                |_| None,
+            );
+            if let Some(qty) = executed {
+                h.execute_only(qty);
+            }
+            h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+            assert_eq!(h.sent_orders().len(), 1, "{:?}", status);
+            assert_eq!(h.position().is_some(), opened, "{:?}", status);
+            assert_eq!(h.engine.pending_order.is_some(), resting, "{:?}", status);
+            if let (Some(qty), Some(pos)) = (executed, h.position()) {
+                assert_eq!(pos.quantity, qty, "{:?}", status);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn depeg_beyond_threshold_flattens_and_blocks_entries() {
+        let price = Decimal::from(100);
+        let buy = Signal::Advice(Side::Buy, price);
+        let mut config = test_config();
+        config.risk.depeg_threshold = Decimal::new(1, 2);
+        config.risk.depeg_halt = true;
+        let (depeg, depeg_feed) = watch::channel(None);
+        let mut h = harness(
+            config,
+            TradingMode::Live,
+            vec![buy.clone(), Signal::Hold, buy],
+        );
+        h.engine = h.engine.with_depeg_feed(depeg_feed);
+
+        depeg.send(Some(Decimal::ONE)).unwrap();
+        h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        assert!(h.position().is_some());
+
+        // Эталон ушёл на 0.95: позиция закрывается, новые входы не проходят
+        depeg.send(Some(Decimal::new(95, 2))).unwrap();
+        h.engine.process_ticker(tick(2_000, price)).await.unwrap();
+        assert!(h.engine.depeg_guard.halts_trading());
+        assert!(h.position().is_none());
+        h.engine.process_ticker(tick(3_000, price)).await.unwrap();
+
+        let orders = h.sent_orders();
+        assert_eq!(orders.len(), 2);
+        assert!(orders[1].reduce_only);
+        assert!(h.position().is_none());
+    }
+
+    #[tokio::test]
+    async fn dry_run_logs_the_order_live_would_send() {
+        let price = Decimal::from(100);
+        let signals = vec![Signal::Advice(Side::Buy, price)];
+
+        let mut live = harness(test_config(), TradingMode::Live, signals.clone());
+        live.engine
+            .process_ticker(tick(1_000, price))
+            .await
+            .unwrap();
+        let sent = live.sent_orders();
+        assert_eq!(sent.len(), 1);
+
+        let mut dry = harness(test_config(), TradingMode::DryRun, signals);
+        dry.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        let logged: Vec<_> = dry
+            .ui_logs()
+            .into_iter()
+            .filter(|line| line.starts_with("DRY RUN: "))
+            .collect();
+        assert_eq!(
+            logged,
+            vec![format!("DRY RUN: {}", describe_order(&sent[0]))]
+        );
+        assert_eq!(logged[0], "DRY RUN: BTCUSDT Buy 1.000 LIMIT 100.10 IOC");
+
+        // Ничего не отправлено и не исполнено: ни позиции, ни записи в инвентаре
+        assert!(dry.sent_orders().is_empty());
+        assert!(dry.position().is_none());
+        assert_eq!(dry.engine.inventory.open_positions(), 0);
+    }
+
+    #[tokio::test]
+    async fn small_entry_is_bumped_to_min_notional_only_when_enabled() {
+        let price = Decimal::from(100);
+        let mut config = test_config();
+        // 5 USDT — меньше минимума символа 5 с запасом (5.5): 0.05 по цене 100
+        config.symbol_min_notional = Some(Decimal::from(5));
+        config.order_size_usdt = 5.0;
+        config.max_notional_bump = Decimal::new(2, 1);
+
+        config.bump_to_min_notional = false;
+        let mut h = harness(
+            config.clone(),
+            TradingMode::Live,
+            vec![Signal::Advice(Side::Buy, price)],
+        );
+        h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        assert!(h.sent_orders().is_empty());
+        assert!(h.position().is_none());
+
+        config.bump_to_min_notional = true;
+        let mut h = harness(
+            config,
+            TradingMode::Live,
+            vec![Signal::Advice(Side::Buy, price)],
+        );
+        h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        let orders = h.sent_orders();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].quantity, Decimal::new(55, 3));
+    }
+
+    #[tokio::test]
+    async fn wide_spread_blocks_entries_but_not_exits() {
+        let price = Decimal::from(100);
+        let mut config = test_config();
+        config.risk.max_spread_bps = Some(Decimal::from(10));
+        let wide = Ticker {
+            bid_price: Decimal::from(99),
+            ask_price: Decimal::from(101),
+            ..tick(2_000, price)
+        };
+        let mut h = harness(
+            config,
+            TradingMode::Live,
+            vec![
+                Signal::Advice(Side::Buy, price),
+                Signal::Advice(Side::Buy, price),
+                Signal::Advice(Side::Sell, price),
+            ],
+        );
+
+        // Спред 200 bps: вход пропущен
+        h.engine.process_ticker(wide.clone()).await.unwrap();
+        assert!(h.sent_orders().is_empty());
+
+        h.engine.process_ticker(tick(3_000, price)).await.unwrap();
+        assert!(h.position().is_some());
+
+        // Выход при том же широком спреде исполняется
+        h.engine
+            .process_ticker(Ticker {
+                timestamp: 4_000,
+                ..wide
+            })
+            .await
+            .unwrap();
+        assert_eq!(h.sent_orders().len(), 2);
+        assert!(h.position().is_none());
+    }
+
+    #[tokio::test]
+    async fn portfolio_stop_flattens_and_blocks_entries() {
+        let entry = Decimal::from(100);
+        let buy = Signal::Advice(Side::Buy, entry);
+        let mut config = test_config();
+        config.risk.max_session_drawdown = Some(Decimal::new(1, 2));
+        let mut h = harness(
+            config,
+            TradingMode::Live,
+            vec![buy.clone(), Signal::Hold, buy],
+        );
+        h.engine.start_drawdown_guard().await;
+
+        h.engine.process_ticker(tick(1_000, entry)).await.unwrap();
+        assert!(h.position().is_some());
+
+        // Лонг 1 по ~100 при equity 10000: на 80 просадка 0.2% (лимит 1%),
+        // почти у нуля — больше 1%
+        h.engine
+            .process_ticker(tick(2_000, Decimal::from(80)))
+            .await
+            .unwrap();
+        assert!(!h.engine.drawdown_guard.is_halted());
+        h.engine
+            .process_ticker(tick(3_000, Decimal::new(1, 2)))
+            .await
+            .unwrap();
+        assert!(h.engine.drawdown_guard.is_halted());
+        assert!(h.position().is_none());
+
+        h.engine.process_ticker(tick(4_000, entry)).await.unwrap();
+        let orders = h.sent_orders();
+        assert_eq!(orders.len(), 2);
+        assert!(orders[1].reduce_only);
+    }
+
+    #[tokio::test]
+    async fn repeated_margin_rejections_halt_entries() {
+        let price = Decimal::from(100);
+        let mut config = test_config();
+        config.risk.max_margin_rejections = 2;
+        let buy = Signal::Advice(Side::Buy, price);
+        let mut h = harness_with(
+            config,
+            TradingMode::Live,
+            vec![buy.clone(), buy.clone(), buy],
+            Some(OrderStatus::Filled),
+            |_| Some(r#"{"code":-2019,"msg":"Margin is insufficient."}"#),
+        );
+        for i in 1..=3 {
+            h.engine
+                .process_ticker(tick(i * 1_000, price))
+                .await
+                .unwrap();
+        }
+        // После двух отказов подряд третий вход даже не отправляется
+        assert_eq!(h.sent_orders().len(), 2);
+        assert!(h.engine.margin_halted);
+        assert!(h.position().is_none());
+    }
+
+    #[tokio::test]
+    async fn rejected_reduce_only_exit_clears_the_local_position() {
+        let price = Decimal::from(100);
+        let mut h = harness_with(
+            test_config(),
+            TradingMode::Live,
+            vec![
+                Signal::Advice(Side::Buy, price),
+                Signal::Advice(Side::Sell, price),
+            ],
+            Some(OrderStatus::Filled),
+            |request| {
+                request
+                    .reduce_only
+                    .then_some(r#"{"code":-2022,"msg":"ReduceOnly Order is rejected."}"#)
+            },
+        );
+        h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        assert!(h.position().is_some());
+
+        // Позицию на бирже уже закрыли (вручную или ликвидацией): локальную не держим
+        h.engine.process_ticker(tick(2_000, price)).await.unwrap();
+        assert_eq!(h.sent_orders().len(), 2);
+        assert!(h.position().is_none());
+    }
+
+    #[tokio::test]
+    async fn order_with_a_lost_response_is_found_by_client_id_not_resent() {
+        let price = Decimal::from(100);
+        // Ответ теряется на каждой попытке: без повторов ордер находит проверка после отказа,
+        // с повторами — проверка перед вторым запросом
+        for attempts in [1, 3] {
+            let mut config = test_config();
+            config.order_retry_attempts = attempts;
+            config.order_retry_backoff_ms = 0;
+            let mut h = harness(
+                config,
+                TradingMode::Live,
+                vec![Signal::Advice(Side::Buy, price)],
+            );
+            h.lose_responses(attempts);
+            h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+            assert_eq!(h.sent_orders().len(), 1, "attempts {}", attempts);
+            assert!(h.position().is_some(), "attempts {}", attempts);
+        }
+    }
+
+    #[tokio::test]
+    async fn min_notional_is_the_symbol_filter_in_quote_units() {
+        // ETHBTC: 0.002 BTC — меньше 5.5 любой валюты, но выше минимума символа 0.0001 BTC
+        let price = Decimal::new(5, 2);
+        let mut config = test_config();
+        config.order_size_usdt = 0.002;
+        config.symbol_min_notional = Some(Decimal::new(1, 4));
+        let mut h = harness(
+            config.clone(),
+            TradingMode::Live,
+            vec![Signal::Advice(Side::Buy, price)],
+        );
+        h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        assert_eq!(h.sent_orders().len(), 1);
+
+        // Минимум символа выше размера — вход пропускается
+        config.symbol_min_notional = Some(Decimal::new(1, 2));
+        let mut h = harness(
+            config,
+            TradingMode::Live,
+            vec![Signal::Advice(Side::Buy, price)],
+        );
+        h.engine.process_ticker(tick(1_000, price)).await.unwrap();
+        assert!(h.sent_orders().is_empty());
     }
 }
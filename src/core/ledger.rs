@@ -0,0 +1,169 @@
+// src/core/ledger.rs
+use crate::config::symbol_assets;
+use crate::types::PositionSide;
+use crate::utils::error::{BotError, Result};
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// Закрытая сделка (или закрытая часть позиции) — строка trades.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    // Время выхода, мс (по времени тика)
+    pub timestamp: u64,
+    pub symbol: String,
+    pub side: PositionSide,
+    pub entry_price: Decimal,
+    pub exit_price: Decimal,
+    pub quantity: Decimal,
+    pub fees: Decimal,
+    pub gross_pnl: Decimal,
+    pub net_pnl: Decimal,
+    // Время входа, мс. 0 — неизвестно (позиция из старого файла состояния)
+    pub opened_at: u64,
+}
+
+impl TradeRecord {
+    pub fn duration_secs(&self) -> Option<u64> {
+        (self.opened_at > 0).then(|| self.timestamp.saturating_sub(self.opened_at) / 1000)
+    }
+}
+
+/// Журнал сделок: JSONL на дозапись, пишется в отдельной задаче
+#[derive(Clone)]
+pub struct TradeLedger {
+    sender: mpsc::UnboundedSender<String>,
+}
+
+impl TradeLedger {
+    pub async fn open(path: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| BotError::io(format!("Failed to open trade ledger {}", path), e))?;
+        let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+
+        info!("📒 Trade ledger: {}", path);
+        let path = path.to_string();
+        tokio::spawn(async move {
+            let mut writer = BufWriter::new(file);
+            while let Some(line) = receiver.recv().await {
+                // Сделки редкие: сбрасываем каждую, чтобы не потерять при падении
+                let written = match writer.write_all(line.as_bytes()).await {
+                    Ok(()) => writer.flush().await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = written {
+                    error!("Failed to write trade ledger {}: {}", path, e);
+                    return;
+                }
+            }
+        });
+
+        Ok(Self { sender })
+    }
+
+    pub fn record(&self, trade: &TradeRecord) {
+        match serde_json::to_string(trade) {
+            Ok(json) => {
+                let _ = self.sender.send(format!("{}\n", json));
+            }
+            Err(e) => error!("Failed to encode trade record: {}", e),
+        }
+    }
+}
+
+/// Итоги по журналу для `--export-trades`
+#[derive(Debug, Default)]
+pub struct LedgerSummary {
+    pub trades: usize,
+    pub wins: usize,
+    pub gross_profit: Decimal,
+    pub gross_loss: Decimal,
+    pub net_pnl: Decimal,
+    // Активы расчётов символов журнала: подпись к net_pnl
+    pub quote_assets: BTreeSet<String>,
+}
+
+impl LedgerSummary {
+    pub fn win_rate(&self) -> Decimal {
+        if self.trades == 0 {
+            return Decimal::ZERO;
+        }
+        Decimal::from(self.wins) / Decimal::from(self.trades)
+    }
+
+    /// Прибыль / убыток. None — убыточных сделок не было
+    pub fn profit_factor(&self) -> Option<Decimal> {
+        (!self.gross_loss.is_zero()).then(|| self.gross_profit / self.gross_loss)
+    }
+}
+
+/// Переводит trades.jsonl в CSV. Только чтение журнала, к бирже не обращается
+pub async fn export_csv(ledger_path: &str, csv_path: &str) -> Result<LedgerSummary> {
+    let content = tokio::fs::read_to_string(ledger_path)
+        .await
+        .map_err(|e| BotError::io(format!("Failed to read trade ledger {}", ledger_path), e))?;
+
+    let mut csv =
+        String::from("timestamp,symbol,side,entry,exit,qty,fees,gross_pnl,net_pnl,duration_secs\n");
+    let mut summary = LedgerSummary::default();
+    for (line_no, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let trade: TradeRecord = match serde_json::from_str(line) {
+            Ok(trade) => trade,
+            Err(e) => {
+                warn!("Skipping ledger line {}: {}", line_no + 1, e);
+                continue;
+            }
+        };
+
+        let timestamp = Utc
+            .timestamp_millis_opt(trade.timestamp as i64)
+            .single()
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        let _ = writeln!(
+            csv,
+            "{},{},{:?},{},{},{},{},{},{},{}",
+            timestamp,
+            trade.symbol,
+            trade.side,
+            trade.entry_price,
+            trade.exit_price,
+            trade.quantity,
+            trade.fees,
+            trade.gross_pnl,
+            trade.net_pnl,
+            trade
+                .duration_secs()
+                .map(|d| d.to_string())
+                .unwrap_or_default()
+        );
+
+        summary.trades += 1;
+        summary.net_pnl += trade.net_pnl;
+        summary.quote_assets.insert(symbol_assets(&trade.symbol).1);
+        if trade.net_pnl > Decimal::ZERO {
+            summary.wins += 1;
+            summary.gross_profit += trade.net_pnl;
+        } else {
+            summary.gross_loss -= trade.net_pnl;
+        }
+    }
+
+    tokio::fs::write(csv_path, csv)
+        .await
+        .map_err(|e| BotError::io(format!("Failed to write {}", csv_path), e))?;
+    Ok(summary)
+}
@@ -1 +1,5 @@
 pub mod engine;
+pub mod ledger;
+pub mod paper;
+pub mod persistence;
+pub mod risk;
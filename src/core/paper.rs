@@ -0,0 +1,99 @@
+// src/core/paper.rs
+use crate::config::PaperConfig;
+use crate::types::{Side, Ticker};
+use crate::utils::math::BPS_PER_UNIT;
+use crate::utils::precision::{normalize_price, normalize_quantity};
+use rust_decimal::Decimal;
+
+/// Результат симулированного исполнения
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperFill {
+    pub price: Decimal,
+    pub quantity: Decimal,
+    // Потери на проскальзывании относительно mid-цены, в USDT
+    pub slippage_cost: Decimal,
+}
+
+/// Исполнение бумажного ордера в бэктесте вместо PaperWallet::simulate_fill
+/// (модели — в backtest::fill). Баланс всё равно ведёт кошелёк
+pub trait FillModel: Send + Sync {
+    fn fill(
+        &self,
+        side: Side,
+        quantity: Decimal,
+        ticker: &Ticker,
+        step_size: Decimal,
+        tick_size: Decimal,
+    ) -> PaperFill;
+}
+
+/// Бумажный кошелёк: исполняет ордера по стакану с проскальзыванием и ведёт баланс
+#[derive(Debug)]
+pub struct PaperWallet {
+    config: PaperConfig,
+    balance: Decimal,
+    slippage_paid: Decimal,
+}
+
+impl PaperWallet {
+    pub fn new(config: PaperConfig) -> Self {
+        Self {
+            balance: config.starting_balance_usdt,
+            slippage_paid: Decimal::ZERO,
+            config,
+        }
+    }
+
+    /// Симулирует исполнение рыночного ордера по top-of-book.
+    /// Buy берёт ask, Sell — bid; сверху фиксированные bps и импакт от размера к глубине уровня.
+    /// При `partial_fills` объём больше доступного на уровне исполняется частично.
+    pub fn simulate_fill(
+        &self,
+        side: Side,
+        quantity: Decimal,
+        ticker: &Ticker,
+        step_size: Decimal,
+        tick_size: Decimal,
+    ) -> PaperFill {
+        let (top_price, top_qty) = match side {
+            Side::Buy => (ticker.ask_price, ticker.ask_qty),
+            Side::Sell => (ticker.bid_price, ticker.bid_qty),
+        };
+
+        let filled_qty = if self.config.partial_fills && top_qty < quantity {
+            normalize_quantity(top_qty, step_size)
+        } else {
+            quantity
+        };
+
+        let mut impact_bps = self.config.slippage_bps;
+        if !top_qty.is_zero() {
+            impact_bps += self.config.depth_impact_bps * (filled_qty / top_qty);
+        }
+        let raw_price = match side {
+            Side::Buy => top_price * (Decimal::ONE + impact_bps / BPS_PER_UNIT),
+            Side::Sell => top_price * (Decimal::ONE - impact_bps / BPS_PER_UNIT),
+        };
+        let price = normalize_price(raw_price, tick_size);
+
+        PaperFill {
+            price,
+            quantity: filled_qty,
+            slippage_cost: (price - ticker.price).abs() * filled_qty,
+        }
+    }
+
+    /// Учитывает исполнение: реализованный PnL и проскальзывание
+    pub fn apply(&mut self, fill: &PaperFill, realized_pnl: Decimal) {
+        self.balance += realized_pnl;
+        self.slippage_paid += fill.slippage_cost;
+    }
+
+    pub fn balance(&self) -> Decimal {
+        self.balance
+    }
+
+    pub fn slippage_paid(&self) -> Decimal {
+        self.slippage_paid
+    }
+}
@@ -0,0 +1,181 @@
+// src/core/persistence.rs
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{sleep_until, Duration, Instant};
+use tracing::{debug, error, info};
+
+enum PersistCommand {
+    // Готовый JSON состояния; urgent — смена позиции, пишется без debounce
+    Save { data: String, urgent: bool },
+    Flush(oneshot::Sender<()>),
+}
+
+/// Запись состояния движка в отдельной задаче: торговый цикл только кладёт снимок в канал
+/// и никогда не ждёт диск.
+///
+/// Порядок сохраняется: снимки пишутся строго по очереди, а из накопившихся берётся
+/// последний, так что состояние после закрытия не перезапишет следующее открытие.
+/// Несрочные снимки (трейлинг highest_price) копятся `debounce` и пишутся одним разом;
+/// срочный снимок сбрасывает ожидание и уходит на диск сразу.
+#[derive(Clone)]
+pub struct StatePersister {
+    sender: mpsc::UnboundedSender<PersistCommand>,
+}
+
+impl StatePersister {
+    pub fn spawn(path: String, debounce: Duration) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        info!(
+            "💾 State persistence to {} (debounce {}ms)",
+            path,
+            debounce.as_millis()
+        );
+        tokio::spawn(run_writer(path, debounce, receiver));
+        Self { sender }
+    }
+
+    /// Смена позиции: пишется при первой возможности
+    pub fn save(&self, data: String) {
+        let _ = self
+            .sender
+            .send(PersistCommand::Save { data, urgent: true });
+    }
+
+    /// Промежуточное состояние: можно объединить с соседними снимками в пределах debounce
+    pub fn save_debounced(&self, data: String) {
+        let _ = self.sender.send(PersistCommand::Save {
+            data,
+            urgent: false,
+        });
+    }
+
+    /// Ждёт, пока всё отправленное ранее окажется на диске (перед остановкой)
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(PersistCommand::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+async fn run_writer(
+    path: String,
+    debounce: Duration,
+    mut receiver: mpsc::UnboundedReceiver<PersistCommand>,
+) {
+    // Последний ещё не записанный снимок и срок, до которого его можно придержать
+    let mut pending: Option<String> = None;
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let command = match deadline {
+            Some(at) => tokio::select! {
+                command = receiver.recv() => command,
+                _ = sleep_until(at) => {
+                    write_state(&path, pending.take()).await;
+                    deadline = None;
+                    continue;
+                }
+            },
+            None => receiver.recv().await,
+        };
+
+        match command {
+            Some(PersistCommand::Save { data, urgent }) => {
+                pending = Some(data);
+                // Уже накопившиеся снимки старше этого — пишем только последний
+                let mut flush_now = urgent || debounce.is_zero();
+                while let Ok(next) = receiver.try_recv() {
+                    match next {
+                        PersistCommand::Save { data, urgent } => {
+                            pending = Some(data);
+                            flush_now |= urgent;
+                        }
+                        PersistCommand::Flush(done) => {
+                            write_state(&path, pending.take()).await;
+                            deadline = None;
+                            let _ = done.send(());
+                        }
+                    }
+                }
+                if pending.is_none() {
+                    continue;
+                }
+                if flush_now {
+                    write_state(&path, pending.take()).await;
+                    deadline = None;
+                } else if deadline.is_none() {
+                    deadline = Some(Instant::now() + debounce);
+                }
+            }
+            Some(PersistCommand::Flush(done)) => {
+                write_state(&path, pending.take()).await;
+                deadline = None;
+                let _ = done.send(());
+            }
+            None => {
+                // Движок остановился: дописываем хвост
+                write_state(&path, pending.take()).await;
+                return;
+            }
+        }
+    }
+}
+
+async fn write_state(path: &str, data: Option<String>) {
+    let Some(data) = data else {
+        return;
+    };
+    match tokio::fs::write(path, data).await {
+        Ok(()) => debug!("State written to {}", path),
+        Err(e) => error!("Failed to save bot state: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("sniper-{}-{}.json", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn read(path: &str) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    #[tokio::test]
+    async fn save_returns_before_the_disk_write_and_keeps_order() {
+        let path = state_path("order");
+        let _ = std::fs::remove_file(&path);
+        let persister = StatePersister::spawn(path.clone(), Duration::from_secs(1));
+
+        // Закрытие и следующее открытие: оба вызова вернулись, а задача записи ещё не запускалась
+        persister.save("closed".to_string());
+        persister.save("opened".to_string());
+        assert_eq!(read(&path), None);
+
+        persister.flush().await;
+        assert_eq!(read(&path).as_deref(), Some("opened"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn debounced_save_waits_unless_an_urgent_one_follows() {
+        let path = state_path("debounce");
+        let _ = std::fs::remove_file(&path);
+        let persister = StatePersister::spawn(path.clone(), Duration::from_secs(60));
+
+        persister.save_debounced("trailing".to_string());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(read(&path), None);
+
+        // Срочный снимок (смена позиции) не ждёт debounce
+        persister.save("exit".to_string());
+        persister.flush().await;
+        assert_eq!(read(&path).as_deref(), Some("exit"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
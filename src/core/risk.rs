@@ -0,0 +1,773 @@
+// src/core/risk.rs
+use crate::types::{FundingInfo, Position, PositionSide, Side};
+use crate::utils::math::bps_diff;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use tracing::{error, info, warn};
+
+/// Проверка "рынок убежал": достижимая цена входа хуже цены сигнала больше допуска.
+/// Buy: fill > signal * (1 + max), Sell: fill < signal * (1 - max).
+pub fn entry_slippage_exceeded(
+    side: Side,
+    signal_price: Decimal,
+    fill_price: Decimal,
+    max_slippage: Decimal,
+) -> bool {
+    match side {
+        Side::Buy => fill_price > signal_price * (Decimal::ONE + max_slippage),
+        Side::Sell => fill_price < signal_price * (Decimal::ONE - max_slippage),
+    }
+}
+
+/// Проскальзывание исполнения против цены сигнала в bps: > 0 — исполнились хуже.
+/// None — нет цены сигнала
+pub fn fill_slippage_bps(
+    side: Side,
+    signal_price: Decimal,
+    fill_price: Decimal,
+) -> Option<Decimal> {
+    let diff = bps_diff(signal_price, fill_price)?;
+    Some(match side {
+        Side::Buy => diff,
+        Side::Sell => -diff,
+    })
+}
+
+/// Цена за жёстким стопом позиции: лонг ниже entry * (1 - pct), шорт выше entry * (1 + pct)
+pub fn hard_stop_hit(pos: &Position, price: Decimal, hard_stop_pct: Decimal) -> bool {
+    match pos.side {
+        PositionSide::Long => price < pos.entry_price * (Decimal::ONE - hard_stop_pct),
+        PositionSide::Short => price > pos.entry_price * (Decimal::ONE + hard_stop_pct),
+    }
+}
+
+/// Спред лучших цен в bps от mid: (ask - bid) / mid * 10000.
+/// None — пустая сторона стакана (нулевые цены), спред не определён.
+pub fn spread_bps(bid: Decimal, ask: Decimal) -> Option<Decimal> {
+    if bid <= Decimal::ZERO || ask <= Decimal::ZERO {
+        return None;
+    }
+    let mid = (bid + ask) / Decimal::TWO;
+    // ask отстоит от mid на половину спреда
+    bps_diff(mid, ask).map(|half| half * Decimal::TWO)
+}
+
+/// Фильтр funding: за `window_minutes` до выплаты не открываем позицию, которая будет платить.
+/// Лонги платят при положительной ставке, шорты — при отрицательной.
+pub fn funding_blocks_entry(
+    side: Side,
+    funding: &FundingInfo,
+    now_ms: u64,
+    window_minutes: u64,
+    threshold: Decimal,
+) -> bool {
+    let until_funding = funding.next_funding_time.saturating_sub(now_ms);
+    if funding.next_funding_time < now_ms || until_funding > window_minutes * 60_000 {
+        return false;
+    }
+    match side {
+        Side::Buy => funding.rate > threshold,
+        Side::Sell => funding.rate < -threshold,
+    }
+}
+
+/// Дневной лимит убытка: считает реализованный PnL с полуночи UTC
+/// и блокирует новые входы до следующего дня после пробоя лимита.
+#[derive(Debug)]
+pub struct DailyLossGuard {
+    max_daily_loss: Option<Decimal>,
+    day: NaiveDate,
+    realized_pnl: Decimal,
+    breached: bool,
+}
+
+impl DailyLossGuard {
+    pub fn new(max_daily_loss: Option<Decimal>, now: DateTime<Utc>) -> Self {
+        Self {
+            max_daily_loss,
+            day: now.date_naive(),
+            realized_pnl: Decimal::ZERO,
+            breached: false,
+        }
+    }
+
+    /// Сбрасывает счётчик при смене дня UTC. Возвращает true, если был сброс.
+    pub fn roll_over(&mut self, now: DateTime<Utc>) -> bool {
+        let today = now.date_naive();
+        if today == self.day {
+            return false;
+        }
+        info!(
+            "📅 UTC day rollover {} -> {}. Daily PnL reset (was {})",
+            self.day, today, self.realized_pnl
+        );
+        self.day = today;
+        self.realized_pnl = Decimal::ZERO;
+        self.breached = false;
+        true
+    }
+
+    pub fn record(&mut self, pnl: Decimal, now: DateTime<Utc>) {
+        self.roll_over(now);
+        self.realized_pnl += pnl;
+
+        if let Some(limit) = self.max_daily_loss {
+            if !self.breached && self.realized_pnl <= -limit {
+                self.breached = true;
+                warn!(
+                    "🧯 DAILY LOSS LIMIT HIT: PnL {} <= -{}. New entries blocked until UTC midnight",
+                    self.realized_pnl, limit
+                );
+            }
+        }
+    }
+
+    pub fn entries_blocked(&mut self, now: DateTime<Utc>) -> bool {
+        self.roll_over(now);
+        self.breached
+    }
+
+    pub fn is_breached(&self) -> bool {
+        self.breached
+    }
+
+    pub fn realized_pnl(&self) -> Decimal {
+        self.realized_pnl
+    }
+
+    /// Сколько ещё можно потерять сегодня (None — лимит не задан)
+    pub fn remaining(&self) -> Option<Decimal> {
+        self.max_daily_loss
+            .map(|limit| (limit + self.realized_pnl).max(Decimal::ZERO))
+    }
+}
+
+/// Лимит новой экспозиции в пределах одной свечи: по числу входов и/или по нотионалу.
+/// Сколько бы сигналов ни пришло за свечу, открыть можно не больше настроенного.
+#[derive(Debug)]
+pub struct EntryThrottle {
+    max_entries: Option<u32>,
+    max_notional: Option<Decimal>,
+    candle_ms: u64,
+    candle_open: u64,
+    entries: u32,
+    notional: Decimal,
+}
+
+impl EntryThrottle {
+    pub fn new(max_entries: Option<u32>, max_notional: Option<Decimal>, candle_ms: u64) -> Self {
+        Self {
+            max_entries,
+            max_notional,
+            candle_ms,
+            candle_open: 0,
+            entries: 0,
+            notional: Decimal::ZERO,
+        }
+    }
+
+    fn roll_over(&mut self, now_ms: u64) {
+        let candle_open = (now_ms / self.candle_ms) * self.candle_ms;
+        if candle_open != self.candle_open {
+            self.candle_open = candle_open;
+            self.entries = 0;
+            self.notional = Decimal::ZERO;
+        }
+    }
+
+    /// Сколько из `requested` USDT можно открыть в текущей свече (ноль — вход запрещён)
+    pub fn allowed_notional(&mut self, now_ms: u64, requested: Decimal) -> Decimal {
+        self.roll_over(now_ms);
+        if let Some(max_entries) = self.max_entries {
+            if self.entries >= max_entries {
+                return Decimal::ZERO;
+            }
+        }
+        match self.max_notional {
+            Some(max_notional) => requested.min((max_notional - self.notional).max(Decimal::ZERO)),
+            None => requested,
+        }
+    }
+
+    /// Учитывает исполненный вход
+    pub fn record(&mut self, now_ms: u64, notional: Decimal) {
+        self.roll_over(now_ms);
+        self.entries += 1;
+        self.notional += notional;
+    }
+
+    pub fn entries(&self) -> u32 {
+        self.entries
+    }
+
+    pub fn notional(&self) -> Decimal {
+        self.notional
+    }
+}
+
+/// Контроль депега квотируемого стейблкоина по цене эталона (например USDC/USDT ≈ 1.0).
+/// Отклонение больше порога — алерт, а при `halt` ещё и остановка входов.
+#[derive(Debug)]
+pub struct DepegGuard {
+    threshold: Decimal,
+    halt: bool,
+    deviation: Option<Decimal>,
+    depegged: bool,
+}
+
+impl DepegGuard {
+    pub fn new(threshold: Decimal, halt: bool) -> Self {
+        Self {
+            threshold,
+            halt,
+            deviation: None,
+            depegged: false,
+        }
+    }
+
+    /// Новая цена эталона. Some(true) — депег начался, Some(false) — привязка восстановилась
+    pub fn update(&mut self, reference_price: Decimal) -> Option<bool> {
+        let deviation = (reference_price - Decimal::ONE).abs();
+        self.deviation = Some(deviation);
+
+        let depegged = deviation > self.threshold;
+        if depegged == self.depegged {
+            return None;
+        }
+        self.depegged = depegged;
+        if depegged {
+            error!(
+                "🚨 STABLECOIN DEPEG: reference {} deviates {} from 1.0 (threshold {}){}",
+                reference_price,
+                deviation,
+                self.threshold,
+                if self.halt { ". Trading halted" } else { "" }
+            );
+        } else {
+            info!(
+                "✅ Stablecoin peg restored: reference {} (deviation {})",
+                reference_price, deviation
+            );
+        }
+        Some(depegged)
+    }
+
+    pub fn deviation(&self) -> Option<Decimal> {
+        self.deviation
+    }
+
+    pub fn is_depegged(&self) -> bool {
+        self.depegged
+    }
+
+    /// Депег и включена остановка торговли
+    pub fn halts_trading(&self) -> bool {
+        self.halt && self.depegged
+    }
+}
+
+/// Аварийный стоп портфеля: просадка equity (реализованный + нереализованный PnL)
+/// от пика сессии больше порога — закрыть всё и остановить торговлю до перезапуска.
+#[derive(Debug)]
+pub struct DrawdownGuard {
+    // Доля от пика (0.05 = 5%). None — выключен
+    max_drawdown: Option<Decimal>,
+    peak: Option<Decimal>,
+    drawdown: Decimal,
+    halted: bool,
+}
+
+impl DrawdownGuard {
+    pub fn new(max_drawdown: Option<Decimal>) -> Self {
+        Self {
+            max_drawdown,
+            peak: None,
+            drawdown: Decimal::ZERO,
+            halted: false,
+        }
+    }
+
+    /// Начальный equity сессии. До вызова guard ничего не проверяет
+    pub fn start(&mut self, equity: Decimal) {
+        if equity > Decimal::ZERO {
+            self.peak = Some(equity);
+        }
+    }
+
+    /// Текущий equity. true — порог пробит прямо сейчас (остановка срабатывает один раз)
+    pub fn update(&mut self, equity: Decimal) -> bool {
+        let Some(peak) = self.peak else {
+            return false;
+        };
+        let peak = peak.max(equity);
+        self.peak = Some(peak);
+        self.drawdown = (peak - equity) / peak;
+
+        match self.max_drawdown {
+            Some(max) if !self.halted && self.drawdown > max => {
+                self.halted = true;
+                error!(
+                    "🚨 PORTFOLIO STOP: equity {} is {}% below session peak {} (limit {}%). Flattening and halting",
+                    equity.round_dp(2),
+                    (self.drawdown * Decimal::from(100)).round_dp(2),
+                    peak.round_dp(2),
+                    max * Decimal::from(100)
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn drawdown(&self) -> Decimal {
+        self.drawdown
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+}
+
+/// Что делать с тиком после проверки на выброс
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickCheck {
+    Accepted,
+    // Одиночный скачок от последней валидной цены: считаем плохими данными
+    Rejected,
+    // Реальное резкое движение за окно: входы на паузе
+    FlashHalt,
+    // Пауза после резкого движения истекла
+    HaltLifted,
+}
+
+/// Защита от выбросов и flash crash.
+///
+/// Тик дальше `max_tick_move` от последней валидной цены отбрасывается как выброс.
+/// Если следующие тики остаются у новой цены (SPIKE_CONFIRM_TICKS подряд), движение
+/// настоящее: цена принимается. Вернулся к прежней — это был одиночный спайк.
+/// Размах валидных цен больше `flash_move` за окно останавливает входы на `halt_ms`.
+#[derive(Debug)]
+pub struct PriceSpikeGuard {
+    max_tick_move: Option<Decimal>,
+    flash_move: Option<Decimal>,
+    window_ms: u64,
+    halt_ms: u64,
+    last_valid: Option<Decimal>,
+    // Цена после скачка и сколько тиков подряд держатся рядом с ней
+    suspect: Option<(Decimal, u32)>,
+    // Валидные цены за окно (мс, цена), по возрастанию времени
+    window: VecDeque<(u64, Decimal)>,
+    halted_until: Option<u64>,
+}
+
+// Сколько тиков подряд у новой цены (вместе с первым) нужно, чтобы признать движение
+const SPIKE_CONFIRM_TICKS: u32 = 3;
+
+impl PriceSpikeGuard {
+    pub fn new(
+        max_tick_move: Option<Decimal>,
+        flash_move: Option<Decimal>,
+        window_ms: u64,
+        halt_ms: u64,
+    ) -> Self {
+        Self {
+            max_tick_move,
+            flash_move,
+            window_ms,
+            halt_ms,
+            last_valid: None,
+            suspect: None,
+            window: VecDeque::new(),
+            halted_until: None,
+        }
+    }
+
+    pub fn check(&mut self, timestamp_ms: u64, price: Decimal) -> TickCheck {
+        if !self.filter_spike(price) {
+            return TickCheck::Rejected;
+        }
+        self.last_valid = Some(price);
+
+        let Some(flash_move) = self.flash_move else {
+            return TickCheck::Accepted;
+        };
+        if let Some(until) = self.halted_until {
+            if timestamp_ms < until {
+                return TickCheck::Accepted;
+            }
+            self.halted_until = None;
+            info!("✅ Flash-move pause is over, entries allowed");
+            return TickCheck::HaltLifted;
+        }
+
+        while self
+            .window
+            .front()
+            .is_some_and(|(t, _)| t + self.window_ms < timestamp_ms)
+        {
+            self.window.pop_front();
+        }
+        self.window.push_back((timestamp_ms, price));
+        let (low, high) = self
+            .window
+            .iter()
+            .fold((price, price), |(low, high), (_, p)| {
+                (low.min(*p), high.max(*p))
+            });
+        let range = (high - low) / low;
+        if range <= flash_move {
+            return TickCheck::Accepted;
+        }
+
+        error!(
+            "🚨 FLASH MOVE: price range {}% within {}s ({} .. {}). Entries paused for {}s",
+            (range * Decimal::from(100)).round_dp(2),
+            self.window_ms / 1000,
+            low,
+            high,
+            self.halt_ms / 1000
+        );
+        self.halted_until = Some(timestamp_ms + self.halt_ms);
+        // Окно начинаем заново, иначе пауза продлится сразу же после окончания
+        self.window.clear();
+        TickCheck::FlashHalt
+    }
+
+    /// false — тик похож на выброс и отбрасывается
+    fn filter_spike(&mut self, price: Decimal) -> bool {
+        let (Some(max_move), Some(last_valid)) = (self.max_tick_move, self.last_valid) else {
+            return true;
+        };
+        if last_valid.is_zero() || ((price - last_valid) / last_valid).abs() <= max_move {
+            // Вернулись к прежней цене: скачок был одиночным
+            if let Some((suspect, _)) = self.suspect.take() {
+                warn!(
+                    "Price spike to {} reverted to {}, ignored as bad data",
+                    suspect, price
+                );
+            }
+            return true;
+        }
+
+        let confirmations = match self.suspect {
+            Some((suspect, count)) if ((price - suspect) / suspect).abs() <= max_move => count + 1,
+            _ => 1,
+        };
+        if confirmations >= SPIKE_CONFIRM_TICKS {
+            warn!(
+                "Large move {} -> {} held for {} ticks, accepting",
+                last_valid, price, confirmations
+            );
+            self.suspect = None;
+            return true;
+        }
+        self.suspect = Some((price, confirmations));
+        false
+    }
+
+    /// Входы стоят после резкого движения (снимается на первом тике после паузы)
+    pub fn is_halted(&self) -> bool {
+        self.halted_until.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn daily_loss_blocks_entries_until_utc_midnight() {
+        let mut guard = DailyLossGuard::new(Some(Decimal::from(50)), utc(1, 9));
+        guard.record(Decimal::from(-30), utc(1, 10));
+        assert!(!guard.entries_blocked(utc(1, 11)));
+        assert_eq!(guard.remaining(), Some(Decimal::from(20)));
+
+        guard.record(Decimal::from(-20), utc(1, 12));
+        assert!(guard.entries_blocked(utc(1, 23)));
+        assert_eq!(guard.remaining(), Some(Decimal::ZERO));
+
+        // Новый день UTC: счётчик и блокировка сброшены
+        assert!(!guard.entries_blocked(utc(2, 0)));
+        assert_eq!(guard.realized_pnl(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn daily_loss_without_limit_never_blocks() {
+        let mut guard = DailyLossGuard::new(None, utc(1, 9));
+        guard.record(Decimal::from(-1_000_000), utc(1, 10));
+        assert!(!guard.entries_blocked(utc(1, 11)));
+        assert_eq!(guard.remaining(), None);
+    }
+
+    #[test]
+    fn entry_beyond_slippage_tolerance_is_aborted() {
+        let signal = Decimal::from(100);
+        let max = Decimal::new(2, 3); // 0.2%
+
+        // Лонг: дороже 100.2 — рынок убежал
+        assert!(entry_slippage_exceeded(
+            Side::Buy,
+            signal,
+            Decimal::new(10025, 2),
+            max
+        ));
+        assert!(!entry_slippage_exceeded(
+            Side::Buy,
+            signal,
+            Decimal::new(10020, 2),
+            max
+        ));
+        assert!(!entry_slippage_exceeded(
+            Side::Buy,
+            signal,
+            Decimal::new(9990, 2),
+            max
+        ));
+
+        // Шорт — зеркально: дешевле 99.8
+        assert!(entry_slippage_exceeded(
+            Side::Sell,
+            signal,
+            Decimal::new(9975, 2),
+            max
+        ));
+        assert!(!entry_slippage_exceeded(
+            Side::Sell,
+            signal,
+            Decimal::new(9980, 2),
+            max
+        ));
+        assert!(!entry_slippage_exceeded(
+            Side::Sell,
+            signal,
+            Decimal::new(10010, 2),
+            max
+        ));
+    }
+
+    #[test]
+    fn funding_blocks_only_the_paying_side_inside_the_window() {
+        let funding = FundingInfo {
+            rate: Decimal::new(5, 4),
+            next_funding_time: 10 * 60_000,
+        };
+        let threshold = Decimal::new(3, 4);
+
+        // За 5 минут до выплаты при положительной ставке платят лонги
+        assert!(funding_blocks_entry(
+            Side::Buy,
+            &funding,
+            5 * 60_000,
+            15,
+            threshold
+        ));
+        assert!(!funding_blocks_entry(
+            Side::Sell,
+            &funding,
+            5 * 60_000,
+            15,
+            threshold
+        ));
+
+        // До окна и после выплаты фильтр не действует
+        assert!(!funding_blocks_entry(Side::Buy, &funding, 0, 5, threshold));
+        assert!(!funding_blocks_entry(
+            Side::Buy,
+            &funding,
+            11 * 60_000,
+            15,
+            threshold
+        ));
+
+        // При отрицательной ставке платят шорты; ставка в пределах порога никого не блокирует
+        let negative = FundingInfo {
+            rate: Decimal::new(-5, 4),
+            ..funding
+        };
+        assert!(funding_blocks_entry(
+            Side::Sell,
+            &negative,
+            5 * 60_000,
+            15,
+            threshold
+        ));
+        assert!(!funding_blocks_entry(
+            Side::Buy,
+            &negative,
+            5 * 60_000,
+            15,
+            threshold
+        ));
+        assert!(!funding_blocks_entry(
+            Side::Buy,
+            &funding,
+            5 * 60_000,
+            15,
+            Decimal::new(5, 4)
+        ));
+    }
+
+    #[test]
+    fn entry_throttle_caps_exposure_within_one_candle() {
+        let candle_ms = 60_000;
+        let mut throttle = EntryThrottle::new(Some(2), Some(Decimal::from(150)), candle_ms);
+        let size = Decimal::from(100);
+
+        // Три сигнала в одной свече: первый целиком, второй урезан до остатка, третий отклонён
+        let first = throttle.allowed_notional(1_000, size);
+        assert_eq!(first, size);
+        throttle.record(1_000, first);
+
+        let second = throttle.allowed_notional(2_000, size);
+        assert_eq!(second, Decimal::from(50));
+        throttle.record(2_000, second);
+
+        assert_eq!(throttle.allowed_notional(3_000, size), Decimal::ZERO);
+        assert_eq!(throttle.entries(), 2);
+        assert_eq!(throttle.notional(), Decimal::from(150));
+
+        // Новая свеча: лимиты снова полные
+        assert_eq!(throttle.allowed_notional(candle_ms, size), size);
+        assert_eq!(throttle.entries(), 0);
+    }
+
+    #[test]
+    fn entry_throttle_counts_entries_without_notional_cap() {
+        let mut throttle = EntryThrottle::new(Some(1), None, 60_000);
+        let size = Decimal::from(100);
+        assert_eq!(throttle.allowed_notional(1_000, size), size);
+        throttle.record(1_000, size);
+        assert_eq!(throttle.allowed_notional(2_000, size), Decimal::ZERO);
+
+        let mut unlimited = EntryThrottle::new(None, None, 60_000);
+        for ts in 0..5 {
+            unlimited.record(ts, size);
+        }
+        assert_eq!(unlimited.allowed_notional(5, size), size);
+    }
+
+    #[test]
+    fn depeg_beyond_threshold_alerts_once_and_halts() {
+        let mut guard = DepegGuard::new(Decimal::new(1, 2), true);
+        assert_eq!(guard.update(Decimal::new(9950, 4)), None);
+        assert!(!guard.halts_trading());
+
+        // 0.97: отклонение 3% больше порога 1% — тревога и остановка
+        assert_eq!(guard.update(Decimal::new(97, 2)), Some(true));
+        assert!(guard.is_depegged());
+        assert!(guard.halts_trading());
+        assert_eq!(guard.deviation(), Some(Decimal::new(3, 2)));
+        // Повторная цена вне привязки новой тревоги не даёт
+        assert_eq!(guard.update(Decimal::new(96, 2)), None);
+
+        assert_eq!(guard.update(Decimal::ONE), Some(false));
+        assert!(!guard.halts_trading());
+    }
+
+    #[test]
+    fn depeg_without_halt_only_alerts() {
+        let mut guard = DepegGuard::new(Decimal::new(1, 2), false);
+        assert_eq!(guard.update(Decimal::new(103, 2)), Some(true));
+        assert!(guard.is_depegged());
+        assert!(!guard.halts_trading());
+    }
+
+    #[test]
+    fn spread_bps_of_a_valid_book() {
+        // (101 - 100) / 100.5 = 99.5 bps
+        let spread = spread_bps(Decimal::from(100), Decimal::from(101)).unwrap();
+        assert_eq!(spread.round_dp(1), Decimal::new(995, 1));
+        assert_eq!(
+            spread_bps(Decimal::from(100), Decimal::from(100)),
+            Some(Decimal::ZERO)
+        );
+    }
+
+    #[test]
+    fn spread_bps_of_an_empty_book_is_unknown() {
+        assert_eq!(spread_bps(Decimal::ZERO, Decimal::from(100)), None);
+        assert_eq!(spread_bps(Decimal::from(100), Decimal::ZERO), None);
+    }
+
+    #[test]
+    fn drawdown_from_session_peak_halts_once() {
+        let mut guard = DrawdownGuard::new(Some(Decimal::new(5, 2)));
+        guard.start(Decimal::from(1000));
+
+        // Пик растёт до 1100, просадка считается от него, а не от старта
+        assert!(!guard.update(Decimal::from(1100)));
+        assert!(!guard.update(Decimal::from(1050)));
+        assert!(!guard.is_halted());
+
+        // 1100 -> 1040: 5.45% больше лимита 5%
+        assert!(guard.update(Decimal::from(1040)));
+        assert!(guard.is_halted());
+        assert!(guard.drawdown() > Decimal::new(5, 2));
+        assert!(!guard.update(Decimal::from(1000)));
+        assert!(guard.is_halted());
+    }
+
+    #[test]
+    fn drawdown_guard_is_idle_without_limit_or_start() {
+        let mut unstarted = DrawdownGuard::new(Some(Decimal::new(5, 2)));
+        assert!(!unstarted.update(Decimal::from(1)));
+        assert_eq!(unstarted.drawdown(), Decimal::ZERO);
+
+        let mut unlimited = DrawdownGuard::new(None);
+        unlimited.start(Decimal::from(1000));
+        assert!(!unlimited.update(Decimal::from(100)));
+        assert_eq!(unlimited.drawdown(), Decimal::new(9, 1));
+        assert!(!unlimited.is_halted());
+    }
+
+    #[test]
+    fn single_tick_spike_is_rejected_and_a_held_move_accepted() {
+        let mut guard = PriceSpikeGuard::new(Some(Decimal::new(5, 2)), None, 10_000, 60_000);
+        assert_eq!(guard.check(0, Decimal::from(100)), TickCheck::Accepted);
+
+        // Одиночный выброс на +20% и возврат к прежней цене
+        assert_eq!(guard.check(1, Decimal::from(120)), TickCheck::Rejected);
+        assert_eq!(guard.check(2, Decimal::from(101)), TickCheck::Accepted);
+
+        // Новая цена держится SPIKE_CONFIRM_TICKS тиков — это реальное движение
+        assert_eq!(guard.check(3, Decimal::from(130)), TickCheck::Rejected);
+        assert_eq!(guard.check(4, Decimal::from(131)), TickCheck::Rejected);
+        assert_eq!(guard.check(5, Decimal::from(130)), TickCheck::Accepted);
+        assert_eq!(guard.check(6, Decimal::from(129)), TickCheck::Accepted);
+    }
+
+    #[test]
+    fn flash_move_within_window_pauses_entries_until_halt_ends() {
+        let mut guard = PriceSpikeGuard::new(None, Some(Decimal::new(3, 2)), 10_000, 60_000);
+        assert_eq!(guard.check(0, Decimal::from(100)), TickCheck::Accepted);
+        assert_eq!(guard.check(5_000, Decimal::from(102)), TickCheck::Accepted);
+
+        // 100 -> 104 за 8 секунд: 4% больше порога 3%
+        assert_eq!(guard.check(8_000, Decimal::from(104)), TickCheck::FlashHalt);
+        assert!(guard.is_halted());
+        assert_eq!(guard.check(30_000, Decimal::from(104)), TickCheck::Accepted);
+        assert!(guard.is_halted());
+
+        assert_eq!(
+            guard.check(68_000, Decimal::from(104)),
+            TickCheck::HaltLifted
+        );
+        assert!(!guard.is_halted());
+    }
+
+    #[test]
+    fn slow_move_outside_window_is_not_a_flash_move() {
+        let mut guard = PriceSpikeGuard::new(None, Some(Decimal::new(3, 2)), 10_000, 60_000);
+        assert_eq!(guard.check(0, Decimal::from(100)), TickCheck::Accepted);
+        assert_eq!(guard.check(9_000, Decimal::from(102)), TickCheck::Accepted);
+        // 100 ушла из окна: диапазон 102..104 — меньше 3%
+        assert_eq!(guard.check(15_000, Decimal::from(104)), TickCheck::Accepted);
+        assert!(!guard.is_halted());
+    }
+}
@@ -0,0 +1,47 @@
+// src/logging.rs
+use anyhow::Context;
+use std::str::FromStr;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, Layer};
+
+/// Логи в `dir/bot.log.YYYY-MM-DD` (новый файл каждые сутки) и, если `console`, ещё в stderr.
+/// Пока TUI занимает экран, консоль выключена: строки логов поверх интерфейса его ломают.
+/// Guard держать до конца main, иначе хвост логов не допишется
+pub fn init(dir: &str, level: &str, console: bool) -> anyhow::Result<WorkerGuard> {
+    let level = LevelFilter::from_str(level).with_context(|| {
+        format!(
+            "log_level: expected error|warn|info|debug|trace, got {:?}",
+            level
+        )
+    })?;
+    let (file_writer, guard) = tracing_appender::non_blocking(rolling::daily(dir, "bot.log"));
+
+    // LOG_FORMAT=json — по строке JSON на событие, поля (symbol, price, ...) отдельными ключами
+    let json_logs = std::env::var("LOG_FORMAT")
+        .map(|v| v.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    let file_layer = if json_logs {
+        fmt::layer()
+            .json()
+            .with_writer(file_writer)
+            .with_ansi(false)
+            .boxed()
+    } else {
+        fmt::layer()
+            .with_writer(file_writer)
+            .with_ansi(false)
+            .boxed()
+    };
+    let console_layer = console.then(|| fmt::layer().with_writer(std::io::stderr).boxed());
+
+    tracing_subscriber::registry()
+        .with(file_layer)
+        .with(console_layer)
+        .with(level)
+        .init();
+    Ok(guard)
+}
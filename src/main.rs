@@ -1,69 +1,467 @@
+mod backtest;
+mod cli;
 mod config;
+mod config_watcher;
 mod connectors;
 mod core;
+mod logging;
+mod metrics;
+mod notify;
+mod storage;
 mod strategies;
 mod tui;
 mod types;
 mod utils;
 
-use crate::config::AppConfig;
+use crate::backtest::download;
+use crate::backtest::report::BacktestReport;
+use crate::backtest::sweep::{self, SweepGrid};
+use crate::cli::Cli;
+use crate::config::{
+    AppConfig, ConfigSource, Exchange, Market, StrategySource, SweepConfig, TradingMode,
+};
 use crate::connectors::binance::BinanceClient;
-use crate::connectors::traits::StreamClient;
+use crate::connectors::coinbase::CoinbaseClient;
+use crate::connectors::okx::OkxClient;
+use crate::connectors::recorder::{Recorder, ReplayClient, ReplaySpeed};
+use crate::connectors::traits::{ExecutionHandler, HistoryProvider, StreamClient};
+use crate::connectors::ws_status::WsStatusReporter;
 use crate::core::engine::TradingEngine;
-use crate::strategies::scalper::RsiBollingerStrategy;
+use crate::core::ledger::{self, TradeLedger};
+use crate::notify::discord::DiscordNotifier;
+use crate::storage::sled_store::SledStore;
+use crate::storage::Store;
+use crate::strategies::external::{tail_signal_file, ExternalSignalStrategy};
+use crate::strategies::grid::GridStrategy;
+use crate::strategies::scalper::{RsiBollingerStrategy, CANDLE_MS};
+use crate::strategies::traits::Strategy;
+use crate::utils::clock::{Clock, SimulatedClock};
+use crate::utils::error::BotError;
+use crate::utils::fees::{load_cached_fees, save_cached_fees};
+use crate::utils::precision::SymbolPrecision;
+use crate::utils::ring_channel::ring_channel;
+use crate::utils::trade_volume::TradeVolume;
+use anyhow::Context;
+use clap::Parser;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::signal;
-use tokio::sync::mpsc;
-use tracing::{error, info};
-use tracing_appender::rolling;
-use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tokio::sync::{broadcast, mpsc, watch};
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    // Офлайн-команда: конфиг, логи и биржа не нужны
+    if let Some(csv_path) = cli.export_trades.clone() {
+        let summary = ledger::export_csv(&cli.ledger, &csv_path).await?;
+        println!("Exported {} trades to {}", summary.trades, csv_path);
+        println!("Total trades:  {}", summary.trades);
+        println!(
+            "Win rate:      {:.2}%",
+            summary.win_rate() * rust_decimal::Decimal::from(100)
+        );
+        match summary.profit_factor() {
+            Some(pf) => println!("Profit factor: {:.2}", pf),
+            None => println!("Profit factor: n/a (no losing trades)"),
+        }
+        println!(
+            "Total net PnL: {:.4} {}",
+            summary.net_pnl,
+            summary
+                .quote_assets
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+        return Ok(());
+    }
+
     // 1. Загружаем .env файл
     dotenvy::dotenv().ok();
 
-    // 2. Настраиваем логи
-    let file_appender = rolling::daily("logs", "bot.log");
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-    tracing_subscriber::fmt()
-        .with_writer(non_blocking)
-        .with_ansi(false)
-        .init();
+    // 2. Загружаем конфиг: в нём каталог и уровень логов
+    let config_source = ConfigSource::new(cli.config.clone(), cli.profile.clone());
+    let mut config = AppConfig::load(&config_source).unwrap_or_else(|e| {
+        panic!(
+            "❌ Ошибка: Не удалось загрузить конфиг! Проверь {} и .env: {}",
+            config_source, e
+        )
+    });
 
-    // 3. Загружаем конфиг
-    let config = AppConfig::new()
-        .expect("❌ Ошибка: Не удалось загрузить конфиг! Проверь Settings.toml и .env");
+    // 3. Настраиваем логи. В консоль — только офлайн-командам без TUI
+    let headless = cli.backtest.is_some() || cli.sweep.is_some() || cli.download.is_some();
+    let _log_guard = logging::init(&config.log_dir, &config.log_level, headless)?;
 
-    info!("🚀 Starting Sniper Bot with Symbol: {}", config.symbol);
+    if let Err(e) = config.validate() {
+        error!("❌ Invalid config: {:#}", e);
+        return Err(e.into());
+    }
 
-    // 4. Инициализация компонентов
-    let mut binance_client = BinanceClient::new(config.api_key.clone(), config.secret_key.clone());
+    if let Some(fill_model) = cli.fill_model {
+        config.backtest.fill_model = fill_model;
+    }
 
-    // Fetch dynamic exchange info (Precision/StepSize)
-    if let Err(e) = binance_client.fetch_exchange_info(&config.symbol).await {
-        error!("⚠️ Failed to fetch exchange info: {}", e);
+    // Офлайн-прогонам фильтры биржи взять негде: шаги только из Settings.toml
+    let offline = cli.backtest.is_some() || cli.sweep.is_some() || cli.replay.is_some();
+    if offline && config.precision_for(&config.symbol).is_none() {
+        anyhow::bail!(
+            "{}: set symbol_tick_size and symbol_step_size for --backtest/--sweep/--replay",
+            config.symbol
+        );
     }
 
-    // Применяем настройки плеча
-    if let Err(e) = binance_client
-        .init_futures_settings(&config.symbol, config.leverage)
-        .await
-    {
-        error!("⚠️ Failed to set leverage: {}", e);
+    // Дальше везде одно число: плечо торгуемого символа
+    config.leverage = config.leverage_for(&config.symbol);
+
+    // Офлайн-прогоны считают комиссии по последним ставкам аккаунта, если они уже получены
+    if config.fees.auto_detect {
+        if let Some(fees) = load_cached_fees(&config.symbol, config.fees).await {
+            info!(
+                "💸 Cached commission rate for {}: maker {} bps / taker {} bps",
+                config.symbol,
+                fees.maker_bps.normalize(),
+                fees.taker_bps.normalize()
+            );
+            config.fees = fees;
+        }
+    }
+
+    // Загрузка истории для бэктеста: только публичный REST, без ключей и TUI
+    if let Some(args) = cli.download.clone() {
+        let [symbol, interval, start, end, outfile] = args.as_slice() else {
+            anyhow::bail!("--download expects SYMBOL INTERVAL START END OUTFILE");
+        };
+        let start_ms = download::parse_time(start)
+            .with_context(|| format!("--download: bad START {:?}", start))?;
+        let end_ms =
+            download::parse_time(end).with_context(|| format!("--download: bad END {:?}", end))?;
+        let mut client =
+            BinanceClient::new(String::new(), String::new()).with_market(config.market);
+        if config.testnet {
+            client = client.with_testnet();
+        }
+        let symbol = symbol.to_uppercase();
+        let summary =
+            download::download(&client, &symbol, interval, start_ms, end_ms, outfile).await?;
+        println!(
+            "Saved {} {} candles of {} to {} ({} duplicates dropped, {} gaps, {} candles filled)",
+            summary.candles,
+            interval,
+            symbol,
+            outfile,
+            summary.duplicates,
+            summary.gaps,
+            summary.filled
+        );
+        return Ok(());
+    }
+
+    // Бэктест и перебор параметров: офлайн по записи, без TUI и биржи
+    if let Some(recording) = cli.backtest.clone() {
+        let ticks = backtest::load_ticks(&recording, &config.symbol).await?;
+        let starting_equity = config.paper.starting_balance_usdt;
+        let result = backtest::run(config, Arc::new(ticks)).await?;
+        println!(
+            "{}",
+            BacktestReport::new(&result, starting_equity, cli.risk_free_rate)
+        );
+        return Ok(());
+    }
+    if let Some(recording) = cli.sweep.clone() {
+        let grid = sweep_grid(&cli, &config.sweep)?;
+        let ticks = backtest::load_ticks(&recording, &config.symbol).await?;
+        let rows = sweep::run(&config, ticks, &grid, cli.rank_by, cli.risk_free_rate).await;
+        sweep::print_table(&rows, cli.top);
+        return Ok(());
+    }
+
+    // Живые деньги — только с явным подтверждением, одной строчки в конфиге мало
+    if config.mode == TradingMode::Live && cli.replay.is_none() && !live_confirmed(&cli) {
+        let msg = format!(
+            "🛑 mode = \"live\" is not confirmed: pass --live or set {}=1. Running in PAPER mode",
+            LIVE_CONFIRM_ENV
+        );
+        // В лог и в терминал: TUI ещё не занял экран
+        error!("{}", msg);
+        eprintln!("{}", msg);
+        config.mode = TradingMode::Paper;
+    }
+
+    info!(
+        "🚀 Starting Sniper Bot with Symbol: {} ({:?}, {}x)",
+        config.symbol, config.mode, config.leverage
+    );
+
+    if let Some(addr) = config.metrics_addr.clone() {
+        if let Err(e) = metrics::serve(addr).await {
+            error!("⚠️ Failed to start metrics endpoint: {}", e);
+        }
+    }
+
+    // 4. Инициализация компонентов
+    let mut binance_client = BinanceClient::new(config.api_key.clone(), config.secret_key.clone())
+        .with_market(config.market)
+        .with_data_stream(config.data_stream);
+    if config.testnet {
+        info!("🧪 Using Binance futures TESTNET endpoints");
+        binance_client = binance_client.with_testnet();
+    }
+    if cli.replay.is_none() {
+        if let Some(path) = config.record_file.clone() {
+            binance_client = binance_client.with_recorder(Recorder::open(&path).await?);
+        }
     }
 
-    let strategy = RsiBollingerStrategy::new(config.symbol.clone(), config.strategy.clone());
-    let execution_handler = Box::new(binance_client.clone());
+    // Replay: без сети. Фильтры берём из конфига, история и funding не запрашиваются,
+    // чтобы решения стратегии зависели только от записанных тиков
+    if let Some(replay_path) = cli.replay.clone() {
+        info!("⏯️ Replay mode: {}", replay_path);
+        let clock: Arc<dyn Clock> = Arc::new(SimulatedClock::default());
+        let strategy = backtest::offline_strategy(&config, clock.clone());
+        let execution_handler = Box::new(binance_client);
+
+        let (ticker_tx, ticker_rx) = ring_channel(config.ticker_buffer);
+        let (ui_tx, ui_rx) = mpsc::channel(100);
+        let (command_tx, command_rx) = mpsc::channel(16);
+
+        let speed = if cli.replay_speed > 0.0 {
+            ReplaySpeed::Realtime(cli.replay_speed)
+        } else {
+            ReplaySpeed::AsFastAsPossible
+        };
+        ReplayClient::new(replay_path.clone(), speed)
+            .subscribe_ticker(&config.symbol, ticker_tx)
+            .await?;
+
+        // Каждый прогон начинается с чистого состояния, иначе повторы не сравнить
+        let state_file = format!("{}.state.json", replay_path);
+        let _ = tokio::fs::remove_file(&state_file).await;
+        let ledger_file = format!("{}.trades.jsonl", replay_path);
+        let _ = tokio::fs::remove_file(&ledger_file).await;
+        let trade_ledger = TradeLedger::open(&ledger_file).await?;
+
+        let engine_config = config.clone();
+        let engine_handle = tokio::spawn(async move {
+            let mut engine = TradingEngine::new(
+                engine_config,
+                execution_handler,
+                strategy,
+                ticker_rx,
+                ui_tx,
+                command_rx,
+                TradingMode::Paper, // Replay всегда бумажный
+            )
+            .with_clock(clock)
+            .with_state_file(state_file)
+            .with_trade_ledger(trade_ledger);
+            if let Err(e) = engine.run().await {
+                error!("❌ Engine CRITICAL error: {}", e);
+            }
+        });
 
-    // Каналы связи
-    let (ticker_tx, ticker_rx) = mpsc::channel(100);
+        let app = tui::App::new(ui_rx, command_tx, config.symbol.clone())
+            .with_fees(config.fees)
+            .with_order_size(config.order_size_usdt)
+            .with_assets(config.quote_asset.clone(), config.base_asset.clone());
+        if let Err(e) = app.run().await {
+            eprintln!("TUI Error: {}", e);
+        }
+
+        engine_handle.abort();
+        return Ok(());
+    }
+
+    // Каналы связи. UI-канал нужен раньше: в него же коннектор шлёт состояние WS
+    let (ticker_tx, ticker_rx) = ring_channel(config.ticker_buffer);
     let (ui_tx, ui_rx) = mpsc::channel(100);
+    let (command_tx, command_rx) = mpsc::channel(16);
+
+    // OKX и Coinbase: свои коннекторы за теми же трейтами. Funding, депег и user-data
+    // пока только у Binance
+    let is_binance = config.exchange == Exchange::Binance;
+    if config.data_stream.has_trades() && !is_binance {
+        warn!(
+            "⚠️ data_stream = {:?} is supported only for Binance. Using the exchange ticker feed",
+            config.data_stream
+        );
+    }
+    // Объём живых свечей: из @trade, если он уже в data_stream, иначе из @aggTrade —
+    // лишний поток, поэтому только по флагу. Только у Binance
+    let wants_volume = config.agg_trade_volume || config.data_stream.has_trades();
+    let trade_volume = match (wants_volume, is_binance) {
+        (true, true) => Some(Arc::new(TradeVolume::new(CANDLE_MS))),
+        (true, false) => {
+            if config.agg_trade_volume {
+                warn!(
+                    "⚠️ agg_trade_volume is supported only for Binance. Candles keep zero volume"
+                );
+            }
+            None
+        }
+        (false, _) => None,
+    };
+    let ws_status = WsStatusReporter::new(ui_tx.clone())
+        .with_engine(command_tx.clone())
+        .with_max_reconnect_attempts(config.max_reconnect_attempts);
+    let (history, execution_handler, mut stream_client): (
+        Box<dyn HistoryProvider>,
+        Box<dyn ExecutionHandler>,
+        Box<dyn StreamClient>,
+    ) = match config.exchange {
+        Exchange::Okx => {
+            let okx = connect_okx(&mut config).await?;
+            (
+                Box::new(okx.clone()),
+                Box::new(okx.clone()),
+                Box::new(okx.with_ws_status(ws_status)),
+            )
+        }
+        Exchange::Coinbase => {
+            let coinbase = connect_coinbase(&mut config).await?;
+            (
+                Box::new(coinbase.clone()),
+                Box::new(coinbase.clone()),
+                Box::new(coinbase.with_ws_status(ws_status)),
+            )
+        }
+        Exchange::Binance => {
+            prepare_binance(&mut binance_client, &mut config).await?;
+            let mut stream = binance_client.clone().with_ws_status(ws_status);
+            if let Some(volume) = trade_volume.clone() {
+                stream = stream.with_trade_volume(volume);
+            }
+            (
+                Box::new(binance_client.clone()),
+                Box::new(binance_client.clone()),
+                Box::new(stream),
+            )
+        }
+    };
+    let store: Option<Arc<dyn Store>> = match config.store_path.as_deref() {
+        Some(path) => Some(Arc::new(
+            SledStore::open(path).context("Failed to open store_path")?,
+        )),
+        None => None,
+    };
+    let strategy: Box<dyn Strategy> = match config.strategy_source {
+        StrategySource::Scalper => {
+            let mut scalper =
+                RsiBollingerStrategy::new(config.symbol.clone(), config.strategy.clone())
+                    .with_history(history);
+            if let Some(store) = store.clone() {
+                scalper = scalper.with_store(store);
+            }
+            if let Some(volume) = trade_volume {
+                scalper = scalper.with_trade_volume(volume);
+            }
+            Box::new(scalper)
+        }
+        StrategySource::External => {
+            let path = config
+                .external_signal_file
+                .clone()
+                .context("external_signal_file is not set")?;
+            Box::new(ExternalSignalStrategy::new(
+                config.symbol.clone(),
+                tail_signal_file(path).await?,
+            ))
+        }
+        StrategySource::Grid => Box::new(GridStrategy::new(config.symbol.clone(), &config.grid)),
+    };
 
     // 5. Запуск потока данных (WebSocket)
-    binance_client
+    stream_client
         .subscribe_ticker(&config.symbol, ticker_tx)
         .await?;
 
+    // Текущий символ: движок меняет его по команде из TUI
+    let (symbol_tx, symbol_rx) = watch::channel(config.symbol.clone());
+
+    // Funding rate: опрос раз в минуту и сразу после смены символа.
+    // Ошибка не блокирует торговлю — просто нет данных
+    let (funding_tx, funding_rx) = watch::channel(None);
+    if is_binance && config.market == Market::Futures {
+        let client = binance_client.clone();
+        let mut symbol_rx = symbol_rx.clone();
+        tokio::spawn(async move {
+            loop {
+                let symbol = symbol_rx.borrow_and_update().clone();
+                match client.fetch_funding_rate(&symbol).await {
+                    Ok(funding) => {
+                        let _ = funding_tx.send(Some(funding));
+                    }
+                    Err(e) => {
+                        warn!("⚠️ Funding rate fetch failed: {}", e);
+                        let _ = funding_tx.send(None);
+                    }
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(60)) => {}
+                    Ok(()) = symbol_rx.changed() => {
+                        // Ставка старого символа к новому не относится
+                        let _ = funding_tx.send(None);
+                    }
+                }
+            }
+        });
+    }
+
+    // Депег: цена эталонного стейблкоина раз в минуту. None — контроль выключен или нет данных
+    let (depeg_tx, depeg_rx) = watch::channel(None);
+    if let Some(depeg_symbol) = config.risk.depeg_symbol.clone().filter(|_| is_binance) {
+        let client = binance_client.clone();
+        tokio::spawn(async move {
+            loop {
+                match client.fetch_price(&depeg_symbol).await {
+                    Ok(price) => {
+                        let _ = depeg_tx.send(Some(price));
+                    }
+                    Err(e) => warn!("⚠️ Depeg reference {} fetch failed: {}", depeg_symbol, e),
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+            }
+        });
+    }
+
+    // User-data stream: только для живой торговли, бумажная позиция на бирже не существует
+    let (account_tx, account_rx) = mpsc::channel(64);
+    // Спотовый user-data stream другого формата, а позиции там — просто остатки на кошельке
+    if config.mode == TradingMode::Live && is_binance && config.market == Market::Futures {
+        if let Err(e) = binance_client.subscribe_user_data(account_tx).await {
+            error!(
+                "⚠️ User-data stream unavailable, positions won't be reconciled: {}",
+                e
+            );
+        }
+    }
+
+    // Горячая перезагрузка параметров стратегии из файлов конфига
+    let config_rx = match config_watcher::watch_strategy_config(config_source.clone()) {
+        Ok(rx) => rx,
+        Err(e) => {
+            warn!("⚠️ Config hot reload disabled: {:#}", e);
+            mpsc::channel(1).1
+        }
+    };
+
+    let trade_ledger = match config.trade_ledger_file.clone() {
+        Some(path) => Some(TradeLedger::open(&path).await?),
+        None => None,
+    };
+
+    // События движка для уведомлений; без подписчиков отправка ничего не стоит
+    let (event_tx, _) = broadcast::channel(64);
+    if let Some(url) = config.discord_webhook_url.clone() {
+        DiscordNotifier::new(url)
+            .with_quote_asset(config.quote_asset.clone())
+            .spawn(event_tx.subscribe());
+    }
+
     // 6. Запуск движка (в фоне)
     // We clone config here to pass it into the engine
     let engine_config = config.clone();
@@ -75,8 +473,22 @@ async fn main() -> anyhow::Result<()> {
             strategy,
             ticker_rx,
             ui_tx,
-            false, // Live Mode
-        );
+            command_rx,
+            config.mode,
+        )
+        .with_funding_feed(funding_rx)
+        .with_depeg_feed(depeg_rx)
+        .with_account_feed(account_rx)
+        .with_config_feed(config_rx)
+        .with_event_sender(event_tx)
+        .with_stream_client(stream_client)
+        .with_symbol_sender(symbol_tx);
+        if let Some(ledger) = trade_ledger {
+            engine = engine.with_trade_ledger(ledger);
+        }
+        if let Some(store) = store {
+            engine = engine.with_store(store);
+        }
         if let Err(e) = engine.run().await {
             error!("❌ Engine CRITICAL error: {}", e);
         }
@@ -86,11 +498,16 @@ async fn main() -> anyhow::Result<()> {
     tokio::spawn(async move {
         signal::ctrl_c().await.unwrap();
         info!("🛑 Shutdown signal received.");
+        // exit не запускает Drop: терминал TUI восстанавливаем вручную
+        tui::restore_terminal();
         std::process::exit(0);
     });
 
     // 8. Запуск TUI (Интерфейс)
-    let app = tui::App::new(ui_rx, config.symbol.clone());
+    let app = tui::App::new(ui_rx, command_tx, config.symbol.clone())
+        .with_fees(config.fees)
+        .with_order_size(config.order_size_usdt)
+        .with_assets(config.quote_asset.clone(), config.base_asset.clone());
     if let Err(e) = app.run().await {
         eprintln!("TUI Error: {}", e);
     }
@@ -98,3 +515,293 @@ async fn main() -> anyhow::Result<()> {
     let _ = engine_handle.await;
     Ok(())
 }
+
+/// Значения параметров из `--rsi-period` и т.п., а без флага — из секции [sweep]
+fn sweep_grid(cli: &Cli, config: &SweepConfig) -> anyhow::Result<SweepGrid> {
+    let values = |flag: &str,
+                  from_cli: &Option<String>,
+                  from_config: &Option<String>|
+     -> anyhow::Result<Vec<Decimal>> {
+        let (source, spec) = match (from_cli, from_config) {
+            (Some(spec), _) => (format!("--{}", flag), spec),
+            (None, Some(spec)) => (format!("sweep.{}", flag.replace('-', "_")), spec),
+            (None, None) => return Ok(Vec::new()),
+        };
+        sweep::parse_values(spec).map_err(|e| anyhow::anyhow!("{}: {}", source, e))
+    };
+    Ok(SweepGrid {
+        rsi_period: values("rsi-period", &cli.rsi_period, &config.rsi_period)?
+            .iter()
+            .map(|v| {
+                v.fract()
+                    .is_zero()
+                    .then(|| v.to_usize())
+                    .flatten()
+                    .context("rsi_period: expected whole numbers")
+            })
+            .collect::<anyhow::Result<_>>()?,
+        obi_threshold: values("obi-threshold", &cli.obi_threshold, &config.obi_threshold)?
+            .iter()
+            .filter_map(|v| v.to_f64())
+            .collect(),
+        min_volatility: values(
+            "min-volatility",
+            &cli.min_volatility,
+            &config.min_volatility,
+        )?,
+        trailing_callback: values(
+            "trailing-callback",
+            &cli.trailing_callback,
+            &config.trailing_callback,
+        )?,
+        take_profit_pct: values(
+            "take-profit-pct",
+            &cli.take_profit_pct,
+            &config.take_profit_pct,
+        )?,
+    })
+}
+
+const LIVE_CONFIRM_ENV: &str = "I_UNDERSTAND_LIVE_TRADING";
+
+fn live_confirmed(cli: &Cli) -> bool {
+    cli.live || std::env::var(LIVE_CONFIRM_ENV).is_ok_and(|v| v.trim() == "1")
+}
+
+/// Фильтры символа и плечо на Binance. Реальные значения биржи важнее конфига
+async fn prepare_binance(client: &mut BinanceClient, config: &mut AppConfig) -> anyhow::Result<()> {
+    // Фильтры всех символов конфига. Без них живую торговлю начинать нельзя,
+    // если шаги не заданы в Settings.toml вручную
+    let symbols = config.symbols();
+    match client.fetch_exchange_info(&symbols).await {
+        Ok(precision) => adopt_precision(config, precision),
+        Err(e) => match config.precision_for(&config.symbol) {
+            Some(fallback) => error!(
+                "⚠️ Failed to fetch exchange info: {}. Using Settings.toml tick {} / step {}",
+                e, fallback.tick_size, fallback.step_size
+            ),
+            None => {
+                return Err(anyhow::Error::from(e).context(
+                    "Failed to fetch exchange info (set symbol_tick_size/symbol_step_size to override)",
+                ));
+            }
+        },
+    }
+
+    if config.fees.auto_detect {
+        detect_binance_fees(client, config).await;
+    }
+
+    if config.market == Market::Spot {
+        info!("🪙 Binance SPOT market: no leverage, long only");
+        config.leverage = 1;
+        return Ok(());
+    }
+
+    // Применяем настройки плеча. Дальше везде используется плечо, которое реально стоит на бирже
+    let applied = client
+        .init_futures_settings(&config.symbol, config.leverage)
+        .await;
+    adopt_leverage(config, applied)
+}
+
+/// Реальные ставки комиссий аккаунта: с биржи, а если запрос не прошёл — последние из кэша
+async fn detect_binance_fees(client: &BinanceClient, config: &mut AppConfig) {
+    match client
+        .fetch_commission_rate(&config.symbol, config.fees)
+        .await
+    {
+        Ok(fees) => {
+            info!(
+                "💸 Commission rate for {}: maker {} bps / taker {} bps",
+                config.symbol,
+                fees.maker_bps.normalize(),
+                fees.taker_bps.normalize()
+            );
+            save_cached_fees(&config.symbol, &fees).await;
+            config.fees = fees;
+        }
+        Err(e) => {
+            // Кэш уже применён при старте; иначе остаются ставки из Settings.toml
+            warn!(
+                "⚠️ Failed to fetch commission rate: {}. Using maker {} bps / taker {} bps",
+                e,
+                config.fees.maker_bps.normalize(),
+                config.fees.taker_bps.normalize()
+            );
+        }
+    }
+}
+
+/// Инструмент и плечо на OKX. Без метаданных инструмента торговать нельзя: объём в контрактах
+async fn connect_okx(config: &mut AppConfig) -> anyhow::Result<OkxClient> {
+    info!("🏦 Exchange: OKX");
+    let mut client = OkxClient::new(
+        config.api_key.clone(),
+        config.secret_key.clone(),
+        config.okx_passphrase.clone(),
+    );
+    if config.testnet {
+        info!("🧪 Using OKX demo trading");
+        client = client.with_demo();
+    }
+    if config.record_file.is_some() {
+        warn!("⚠️ record_file is supported only for Binance bookTicker. Not recording");
+    }
+
+    client
+        .fetch_instrument(&config.symbol)
+        .await
+        .context("Failed to load OKX instrument")?;
+    let precision = HashMap::from([(config.symbol.clone(), client.precision())]);
+    adopt_precision(config, precision);
+
+    if config.mode == TradingMode::Paper {
+        // Бумажный режим не должен требовать ключей
+        return Ok(client);
+    }
+    let applied = client
+        .init_futures_settings(&config.symbol, config.leverage)
+        .await;
+    adopt_leverage(config, applied)?;
+    Ok(client)
+}
+
+/// Продукт на Coinbase: только спот, плеча нет. Шаги цены и объёма — из метаданных продукта
+async fn connect_coinbase(config: &mut AppConfig) -> anyhow::Result<CoinbaseClient> {
+    info!("🏦 Exchange: Coinbase Advanced Trade (spot)");
+    let mut client = CoinbaseClient::new(config.api_key.clone(), config.secret_key.clone())
+        .context("Failed to load Coinbase API key")?;
+    if config.testnet {
+        warn!("⚠️ Coinbase Advanced Trade has no testnet. Ignoring testnet = true");
+    }
+    if config.record_file.is_some() {
+        warn!("⚠️ record_file is supported only for Binance bookTicker. Not recording");
+    }
+
+    match client.fetch_product(&config.symbol).await {
+        Ok(()) => {
+            let precision = HashMap::from([(config.symbol.clone(), client.precision())]);
+            adopt_precision(config, precision);
+        }
+        Err(e) => match config.precision_for(&config.symbol) {
+            Some(fallback) => error!(
+                "⚠️ Failed to load Coinbase product: {}. Using Settings.toml tick {} / step {}",
+                e, fallback.tick_size, fallback.step_size
+            ),
+            None => {
+                return Err(anyhow::Error::from(e).context(
+                    "Failed to load Coinbase product (set symbol_tick_size/symbol_step_size to override)",
+                ));
+            }
+        },
+    }
+
+    info!("🪙 Coinbase SPOT: no leverage, long only");
+    config.leverage = 1;
+    Ok(client)
+}
+
+fn adopt_precision(config: &mut AppConfig, precision: HashMap<String, SymbolPrecision>) {
+    for (symbol, exchange) in &precision {
+        let effective = SymbolPrecision {
+            tick_size: config.symbol_tick_size.unwrap_or(exchange.tick_size),
+            step_size: config.symbol_step_size.unwrap_or(exchange.step_size),
+            min_notional: config.symbol_min_notional.unwrap_or(exchange.min_notional),
+        };
+        if effective != *exchange {
+            warn!(
+                "⚠️ {}: Settings.toml overrides exchange precision: tick {} -> {}, step {} -> {}, min notional {} -> {}",
+                symbol,
+                exchange.tick_size,
+                effective.tick_size,
+                exchange.step_size,
+                effective.step_size,
+                exchange.min_notional,
+                effective.min_notional
+            );
+        }
+    }
+    config.precision = precision;
+}
+
+fn adopt_leverage(config: &mut AppConfig, applied: Result<u8, BotError>) -> anyhow::Result<()> {
+    match applied {
+        Ok(applied) if applied != config.leverage => {
+            if config.strict_leverage {
+                anyhow::bail!(
+                    "Exchange applied leverage {}x instead of requested {}x (strict_leverage = true)",
+                    applied,
+                    config.leverage
+                );
+            }
+            warn!(
+                "⚠️ Exchange applied leverage {}x instead of requested {}x. Using {}x",
+                applied, config.leverage, applied
+            );
+            config.leverage = applied;
+        }
+        Ok(_) => {}
+        // Запрошено больше, чем биржа разрешает символу: не стартуем с чужим риском
+        Err(e @ BotError::Config(_)) => return Err(e.into()),
+        Err(e) => error!("⚠️ Failed to set leverage: {}", e),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn leverage_config(leverage: u8, strict: bool) -> AppConfig {
+        serde_json::from_value(json!({
+            "api_key": "key",
+            "secret_key": "secret",
+            "symbol": "BTCUSDT",
+            "leverage": leverage,
+            "strict_leverage": strict,
+            "order_size_usdt": 100.0,
+            "strategy": {
+                "rsi_period": 14,
+                "obi_threshold": 0.3,
+                "bb_period": 20,
+                "bb_std_dev": 2.0,
+                "min_volatility": "0",
+                "atr_multiplier": 2.0,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn capped_leverage_replaces_the_requested_one() {
+        // Запрошено 50x, тир символа разрешил 20x: дальше везде 20x
+        let mut config = leverage_config(50, false);
+        adopt_leverage(&mut config, Ok(20)).unwrap();
+        assert_eq!(config.leverage, 20);
+
+        let mut config = leverage_config(20, false);
+        adopt_leverage(&mut config, Ok(20)).unwrap();
+        assert_eq!(config.leverage, 20);
+    }
+
+    #[test]
+    fn capped_leverage_is_an_error_in_strict_mode() {
+        let mut config = leverage_config(50, true);
+        let err = adopt_leverage(&mut config, Ok(20)).unwrap_err();
+        assert!(err.to_string().contains("strict_leverage"));
+        assert_eq!(config.leverage, 50);
+    }
+
+    #[test]
+    fn failed_leverage_request_keeps_the_requested_value() {
+        let mut config = leverage_config(10, false);
+        adopt_leverage(&mut config, Err(BotError::Network("timeout".to_string()))).unwrap();
+        assert_eq!(config.leverage, 10);
+
+        // Плечо выше допустимого для символа — не стартуем
+        let rejected = BotError::Config("leverage 10x not allowed".to_string());
+        assert!(adopt_leverage(&mut config, Err(rejected)).is_err());
+    }
+}
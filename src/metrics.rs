@@ -0,0 +1,227 @@
+// src/metrics.rs
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+/// Монотонный счётчик (Prometheus counter)
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Произвольное значение (Prometheus gauge). f64 хранится битами в AtomicU64
+pub struct Gauge(AtomicU64);
+
+impl Gauge {
+    const fn new() -> Self {
+        Self(AtomicU64::new(0)) // 0u64 == 0.0f64
+    }
+
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn add(&self, delta: f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + delta).to_bits())
+            });
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+pub struct Metrics {
+    pub ticks_received: Counter,
+    // Тики, вытесненные из ring_channel более свежими, пока движок был занят
+    pub ticks_dropped: Counter,
+    // Тики-выбросы, отброшенные защитой от спайков
+    pub ticks_rejected: Counter,
+    pub signals_buy: Counter,
+    pub signals_sell: Counter,
+    pub orders_placed: Counter,
+    pub orders_filled: Counter,
+    pub orders_rejected: Counter,
+    pub ws_reconnects: Counter,
+    // WS-сообщения, которые не удалось разобрать (формат потока мог измениться)
+    pub ws_parse_failures: Counter,
+    // Входы, закрытые сразу из-за проскальзывания исполнения (risk.max_slippage_bps)
+    pub slippage_bailouts: Counter,
+    pub position_notional: Gauge,
+    pub realized_pnl: Gauge,
+    pub unrealized_pnl: Gauge,
+}
+
+pub static METRICS: Metrics = Metrics {
+    ticks_received: Counter::new(),
+    ticks_dropped: Counter::new(),
+    ticks_rejected: Counter::new(),
+    signals_buy: Counter::new(),
+    signals_sell: Counter::new(),
+    orders_placed: Counter::new(),
+    orders_filled: Counter::new(),
+    orders_rejected: Counter::new(),
+    ws_reconnects: Counter::new(),
+    ws_parse_failures: Counter::new(),
+    slippage_bailouts: Counter::new(),
+    position_notional: Gauge::new(),
+    realized_pnl: Gauge::new(),
+    unrealized_pnl: Gauge::new(),
+};
+
+impl Metrics {
+    /// Текстовый формат экспозиции Prometheus (version 0.0.4)
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut counter = |name: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+        counter(
+            "sniper_ticks_received_total",
+            "Tickers received by the engine",
+            self.ticks_received.get(),
+        );
+        counter(
+            "sniper_ticks_dropped_total",
+            "Stale tickers evicted because the engine lagged behind the feed",
+            self.ticks_dropped.get(),
+        );
+        counter(
+            "sniper_ticks_rejected_total",
+            "Tickers rejected as single-tick price spikes",
+            self.ticks_rejected.get(),
+        );
+        counter(
+            "sniper_orders_placed_total",
+            "Orders sent to the exchange",
+            self.orders_placed.get(),
+        );
+        counter(
+            "sniper_orders_filled_total",
+            "Orders filled (fully or partially)",
+            self.orders_filled.get(),
+        );
+        counter(
+            "sniper_orders_rejected_total",
+            "Orders rejected or not filled",
+            self.orders_rejected.get(),
+        );
+        counter(
+            "sniper_ws_reconnects_total",
+            "WebSocket reconnect attempts",
+            self.ws_reconnects.get(),
+        );
+        counter(
+            "sniper_ws_parse_failures_total",
+            "WebSocket messages that failed to parse",
+            self.ws_parse_failures.get(),
+        );
+        counter(
+            "sniper_slippage_bailouts_total",
+            "Entries closed right after the fill because of excessive slippage",
+            self.slippage_bailouts.get(),
+        );
+
+        let _ = writeln!(out, "# HELP sniper_signals_total Strategy signals by side");
+        let _ = writeln!(out, "# TYPE sniper_signals_total counter");
+        let _ = writeln!(
+            out,
+            "sniper_signals_total{{side=\"buy\"}} {}",
+            self.signals_buy.get()
+        );
+        let _ = writeln!(
+            out,
+            "sniper_signals_total{{side=\"sell\"}} {}",
+            self.signals_sell.get()
+        );
+
+        let mut gauge = |name: &str, help: &str, value: f64| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+        gauge(
+            "sniper_position_notional_usdt",
+            "Notional of the open position",
+            self.position_notional.get(),
+        );
+        gauge(
+            "sniper_realized_pnl_usdt",
+            "Realized PnL since start",
+            self.realized_pnl.get(),
+        );
+        gauge(
+            "sniper_unrealized_pnl_usdt",
+            "Unrealized PnL of the open position",
+            self.unrealized_pnl.get(),
+        );
+        out
+    }
+}
+
+/// Минимальный HTTP-сервер: отдаёт `GET /metrics`, на всё остальное 404
+pub async fn serve(addr: String) -> Result<()> {
+    let listener = TcpListener::bind(&addr).await?;
+    info!("📈 Metrics endpoint: http://{}/metrics", addr);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!("Metrics accept error: {}", e);
+                    continue;
+                }
+            };
+
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let n = match socket.read(&mut buf).await {
+                    Ok(n) => n,
+                    Err(e) => {
+                        warn!("Metrics read error from {}: {}", peer, e);
+                        return;
+                    }
+                };
+                let request = String::from_utf8_lossy(&buf[..n]);
+
+                let response = if request.starts_with("GET /metrics") {
+                    let body = METRICS.render();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+
+                if let Err(e) = socket.write_all(response.as_bytes()).await {
+                    warn!("Metrics write error to {}: {}", peer, e);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
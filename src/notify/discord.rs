@@ -0,0 +1,152 @@
+// src/notify/discord.rs
+use crate::config::symbol_assets;
+use crate::types::{EngineEvent, PositionSide};
+use chrono::Utc;
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, warn};
+
+// Попыток на одно сообщение, пауза растёт: 1с, 2с
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+// Цвета embed (0xRRGGBB)
+const GREEN: u32 = 0x2ecc71;
+const RED: u32 = 0xe74c3c;
+const BLUE: u32 = 0x3498db;
+const YELLOW: u32 = 0xf1c40f;
+
+/// Уведомления в Discord через webhook: входы, выходы, стопы и остановки торговли
+pub struct DiscordNotifier {
+    http_client: Client,
+    webhook_url: String,
+    // quote_asset из конфига для подписи PnL; None — по символу события
+    quote_asset: Option<String>,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: String) -> Self {
+        Self {
+            http_client: Client::new(),
+            webhook_url,
+            quote_asset: None,
+        }
+    }
+
+    pub fn with_quote_asset(mut self, quote_asset: Option<String>) -> Self {
+        self.quote_asset = quote_asset;
+        self
+    }
+
+    /// Читает события движка в своей задаче. Если Discord тормозит, broadcast вытесняет
+    /// старые события — движок от этого не ждёт
+    pub fn spawn(self, mut events: broadcast::Receiver<EngineEvent>) {
+        info!("🔔 Discord notifications enabled");
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => self.post(&self.embed(&event)).await,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Discord notifier lagging: {} events skipped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+
+    async fn post(&self, embed: &Value) {
+        let body = json!({ "embeds": [embed] });
+        for attempt in 1..=MAX_ATTEMPTS {
+            let result = self
+                .http_client
+                .post(&self.webhook_url)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|resp| resp.error_for_status());
+            match result {
+                Ok(_) => return,
+                Err(e) if attempt < MAX_ATTEMPTS => {
+                    warn!("Discord webhook failed (attempt {}): {}", attempt, e);
+                    sleep(RETRY_DELAY * attempt).await;
+                }
+                Err(e) => error!(
+                    "❌ Discord webhook failed after {} attempts, notification dropped: {}",
+                    MAX_ATTEMPTS, e
+                ),
+            }
+        }
+    }
+
+    fn embed(&self, event: &EngineEvent) -> Value {
+        let timestamp = Utc::now().to_rfc3339();
+        match event {
+            EngineEvent::Entry {
+                symbol,
+                side,
+                price,
+                quantity,
+            } => json!({
+                "title": format!("Entry {}", side_label(*side)),
+                "color": GREEN,
+                "fields": [
+                    field("Symbol", symbol),
+                    field("Price", price),
+                    field("Quantity", quantity),
+                ],
+                "timestamp": timestamp,
+            }),
+            EngineEvent::Exit {
+                symbol,
+                side,
+                price,
+                quantity,
+                pnl,
+                ..
+            } => {
+                let quote = self
+                    .quote_asset
+                    .clone()
+                    .unwrap_or_else(|| symbol_assets(symbol).1);
+                // Скальпер выходит только по стопам: убыточный выход — это сработавший стоп-лосс
+                let (title, color) = if *pnl < Decimal::ZERO {
+                    ("Stop loss", RED)
+                } else {
+                    ("Exit", BLUE)
+                };
+                json!({
+                    "title": format!("{} {}", title, side_label(*side)),
+                    "color": color,
+                    "fields": [
+                        field("Symbol", symbol),
+                        field("Price", price),
+                        field("Quantity", quantity),
+                        field("PnL", format!("{} {}", pnl.round_dp(4), quote)),
+                    ],
+                    "timestamp": timestamp,
+                })
+            }
+            EngineEvent::Halt { reason } => json!({
+                "title": "Trading halted",
+                "color": YELLOW,
+                "description": reason,
+                "timestamp": timestamp,
+            }),
+        }
+    }
+}
+
+fn field(name: &str, value: impl ToString) -> Value {
+    json!({ "name": name, "value": value.to_string(), "inline": true })
+}
+
+fn side_label(side: PositionSide) -> &'static str {
+    match side {
+        PositionSide::Long => "LONG",
+        PositionSide::Short => "SHORT",
+    }
+}
@@ -0,0 +1,2 @@
+// src/notify/mod.rs
+pub mod discord;
@@ -1 +1,20 @@
+// src/storage/mod.rs
 pub mod sled_store;
+
+use crate::types::{Kline, Position};
+use crate::utils::error::Result;
+
+/// Хранилище между перезапусками: позиция и последние закрытые свечи по символу.
+/// Файл состояния JSON пишется как раньше — для чтения человеком
+pub trait Store: Send + Sync {
+    fn get_position(&self, symbol: &str) -> Result<Option<Position>>;
+
+    // None — позиции нет, запись удаляется
+    fn put_position(&self, symbol: &str, position: Option<&Position>) -> Result<()>;
+
+    // Последние `limit` свечей символа, от старых к новым
+    fn get_candles(&self, symbol: &str, limit: usize) -> Result<Vec<Kline>>;
+
+    // Свеча с тем же open_time перезаписывается
+    fn put_candle(&self, symbol: &str, candle: &Kline) -> Result<()>;
+}
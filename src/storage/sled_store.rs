@@ -0,0 +1,109 @@
+// src/storage/sled_store.rs
+use crate::storage::Store;
+use crate::types::{Kline, Position};
+use crate::utils::error::{BotError, Result};
+use tracing::info;
+
+// Сколько истории хранить на символ: 1500 минутных свечей, как биржа отдаёт за один запрос
+const CANDLE_RETENTION_MS: u64 = 1500 * 60_000;
+
+/// Store на sled. Деревья: `positions` (символ -> JSON позиции) и `candles`
+/// (символ, 0, open_time big-endian -> JSON свечи): ключи одного символа идут по времени
+/// На диск sled сбрасывает сам, в фоне (раз в 500 мс)
+pub struct SledStore {
+    positions: sled::Tree,
+    candles: sled::Tree,
+}
+
+impl SledStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let db = sled::open(path).map_err(|e| storage_error(path, e))?;
+        let positions = db
+            .open_tree("positions")
+            .map_err(|e| storage_error(path, e))?;
+        let candles = db
+            .open_tree("candles")
+            .map_err(|e| storage_error(path, e))?;
+        info!("🗄️ Store opened at {}", path);
+        Ok(Self { positions, candles })
+    }
+}
+
+fn storage_error(context: &str, e: sled::Error) -> BotError {
+    BotError::io(format!("Store error ({})", context), e.into())
+}
+
+fn candle_prefix(symbol: &str) -> Vec<u8> {
+    let mut prefix = symbol.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+fn candle_key(symbol: &str, open_time: u64) -> Vec<u8> {
+    let mut key = candle_prefix(symbol);
+    key.extend_from_slice(&open_time.to_be_bytes());
+    key
+}
+
+impl Store for SledStore {
+    fn get_position(&self, symbol: &str) -> Result<Option<Position>> {
+        match self
+            .positions
+            .get(symbol)
+            .map_err(|e| storage_error(symbol, e))?
+        {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put_position(&self, symbol: &str, position: Option<&Position>) -> Result<()> {
+        match position {
+            Some(position) => self
+                .positions
+                .insert(symbol, serde_json::to_vec(position)?)
+                .map(|_| ()),
+            None => self.positions.remove(symbol).map(|_| ()),
+        }
+        .map_err(|e| storage_error(symbol, e))
+    }
+
+    fn get_candles(&self, symbol: &str, limit: usize) -> Result<Vec<Kline>> {
+        let mut candles = self
+            .candles
+            .scan_prefix(candle_prefix(symbol))
+            .rev()
+            .take(limit)
+            .map(|entry| {
+                let (_, data) = entry.map_err(|e| storage_error(symbol, e))?;
+                Ok(serde_json::from_slice(&data)?)
+            })
+            .collect::<Result<Vec<Kline>>>()?;
+        candles.reverse();
+        Ok(candles)
+    }
+
+    fn put_candle(&self, symbol: &str, candle: &Kline) -> Result<()> {
+        self.candles
+            .insert(
+                candle_key(symbol, candle.open_time),
+                serde_json::to_vec(candle)?,
+            )
+            .map_err(|e| storage_error(symbol, e))?;
+
+        // Более старые свечи не нужны ни прогреву, ни человеку. Диапазон по ключу:
+        // обычно пуст или в нём одна свеча, весь символ не сканируется
+        let cutoff = candle.open_time.saturating_sub(CANDLE_RETENTION_MS);
+        for key in self
+            .candles
+            .range(candle_prefix(symbol)..candle_key(symbol, cutoff))
+            .keys()
+        {
+            let key = key.map_err(|e| storage_error(symbol, e))?;
+            self.candles
+                .remove(key)
+                .map_err(|e| storage_error(symbol, e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+// src/strategies/divergence.rs
+use std::collections::VecDeque;
+
+// Сколько свечей после подтверждения второй вершины дивергенция считается актуальной
+const MAX_AGE_CANDLES: usize = 5;
+
+/// Классическая дивергенция цены и RSI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    // Цена обновила минимум, RSI — нет: продавцы выдыхаются
+    Bullish,
+    // Цена обновила максимум, RSI — нет: покупатели выдыхаются
+    Bearish,
+}
+
+// Подтверждённая вершина или впадина: номер свечи, цена экстремума, RSI на этой свече
+#[derive(Debug, Clone, Copy)]
+struct Swing {
+    index: usize,
+    price: f64,
+    rsi: f64,
+}
+
+// Свеча в окне поиска экстремумов
+#[derive(Debug, Clone, Copy)]
+struct Bar {
+    high: f64,
+    low: f64,
+    rsi: f64,
+}
+
+/// Ищет дивергенцию RSI на закрытиях свечей. Вершина — свеча, чей high строго выше
+/// high `pivot` свечей слева и справа (впадина — так же по low), поэтому подтверждается
+/// она с опозданием на `pivot` свечей. Дивергенция — две соседние вершины (впадины)
+/// не дальше `lookback` свечей друг от друга, где цена и RSI пошли в разные стороны
+#[derive(Debug, Clone)]
+pub struct DivergenceDetector {
+    pivot: usize,
+    lookback: usize,
+    // Последние 2 * pivot + 1 свечей: средняя — кандидат в экстремум
+    window: VecDeque<Bar>,
+    // Номер следующей закрытой свечи
+    next_index: usize,
+    last_high: Option<Swing>,
+    last_low: Option<Swing>,
+    // Последняя найденная дивергенция и номер свечи, на которой она подтвердилась
+    detected: Option<(Divergence, usize)>,
+}
+
+impl DivergenceDetector {
+    pub fn new(pivot: usize, lookback: usize) -> Self {
+        Self {
+            pivot,
+            lookback,
+            window: VecDeque::with_capacity(2 * pivot + 1),
+            next_index: 0,
+            last_high: None,
+            last_low: None,
+            detected: None,
+        }
+    }
+
+    /// Закрытие свечи. Some — на этой свече подтвердилась новая дивергенция
+    pub fn on_candle_close(&mut self, high: f64, low: f64, rsi: f64) -> Option<Divergence> {
+        let index = self.next_index;
+        self.next_index += 1;
+        if self.window.len() == 2 * self.pivot + 1 {
+            self.window.pop_front();
+        }
+        self.window.push_back(Bar { high, low, rsi });
+        if self.window.len() < 2 * self.pivot + 1 {
+            return None;
+        }
+
+        let center = self.window[self.pivot];
+        let center_index = index - self.pivot;
+        let others = || {
+            self.window
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != self.pivot)
+                .map(|(_, bar)| bar)
+        };
+        let is_high = others().all(|bar| center.high > bar.high);
+        let is_low = others().all(|bar| center.low < bar.low);
+
+        let mut found = None;
+        if is_high {
+            let swing = Swing {
+                index: center_index,
+                price: center.high,
+                rsi: center.rsi,
+            };
+            if let Some(prev) = self.last_high.filter(|prev| self.in_range(prev, &swing)) {
+                if swing.price > prev.price && swing.rsi < prev.rsi {
+                    found = Some(Divergence::Bearish);
+                }
+            }
+            self.last_high = Some(swing);
+        }
+        if is_low {
+            let swing = Swing {
+                index: center_index,
+                price: center.low,
+                rsi: center.rsi,
+            };
+            if let Some(prev) = self.last_low.filter(|prev| self.in_range(prev, &swing)) {
+                if swing.price < prev.price && swing.rsi > prev.rsi {
+                    found = Some(Divergence::Bullish);
+                }
+            }
+            self.last_low = Some(swing);
+        }
+
+        if let Some(divergence) = found {
+            self.detected = Some((divergence, index));
+        }
+        found
+    }
+
+    fn in_range(&self, prev: &Swing, swing: &Swing) -> bool {
+        swing.index - prev.index <= self.lookback
+    }
+
+    /// Дивергенция, подтвердившаяся меньше MAX_AGE_CANDLES свечей назад. None — нет или устарела
+    pub fn current(&self) -> Option<Divergence> {
+        let (divergence, index) = self.detected?;
+        (self.next_index - 1 - index < MAX_AGE_CANDLES).then_some(divergence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Впадины 90 (свеча 2) и 88 (свеча 6), вершина между ними
+    const LOWS: [f64; 9] = [100.0, 98.0, 90.0, 97.0, 99.0, 96.0, 88.0, 95.0, 97.0];
+    // Вершины 110 (свеча 2) и 112 (свеча 6)
+    const HIGHS: [f64; 9] = [
+        100.0, 102.0, 110.0, 103.0, 101.0, 104.0, 112.0, 105.0, 103.0,
+    ];
+
+    /// Свечи с диапазоном 1 вокруг экстремумов и RSI по свечам. Результат каждого закрытия
+    fn feed_lows(detector: &mut DivergenceDetector, rsi: [f64; 9]) -> Vec<Option<Divergence>> {
+        LOWS.iter()
+            .zip(rsi)
+            .map(|(&low, rsi)| detector.on_candle_close(low + 1.0, low, rsi))
+            .collect()
+    }
+
+    fn feed_highs(detector: &mut DivergenceDetector, rsi: [f64; 9]) -> Vec<Option<Divergence>> {
+        HIGHS
+            .iter()
+            .zip(rsi)
+            .map(|(&high, rsi)| detector.on_candle_close(high, high - 1.0, rsi))
+            .collect()
+    }
+
+    #[test]
+    fn lower_low_with_higher_rsi_is_bullish() {
+        let mut detector = DivergenceDetector::new(2, 20);
+        let found = feed_lows(
+            &mut detector,
+            [40.0, 35.0, 22.0, 30.0, 38.0, 33.0, 28.0, 32.0, 36.0],
+        );
+        // Вторая впадина подтверждается через pivot свечей после неё
+        assert_eq!(found[8], Some(Divergence::Bullish));
+        assert!(found[..8].iter().all(Option::is_none));
+        assert_eq!(detector.current(), Some(Divergence::Bullish));
+    }
+
+    #[test]
+    fn higher_high_with_lower_rsi_is_bearish() {
+        let mut detector = DivergenceDetector::new(2, 20);
+        let found = feed_highs(
+            &mut detector,
+            [60.0, 65.0, 78.0, 70.0, 62.0, 66.0, 72.0, 68.0, 64.0],
+        );
+        assert_eq!(found[8], Some(Divergence::Bearish));
+        assert!(found[..8].iter().all(Option::is_none));
+        assert_eq!(detector.current(), Some(Divergence::Bearish));
+    }
+
+    #[test]
+    fn rsi_confirming_price_is_no_divergence() {
+        // Новый минимум цены и новый минимум RSI
+        let mut detector = DivergenceDetector::new(2, 20);
+        let found = feed_lows(
+            &mut detector,
+            [40.0, 35.0, 28.0, 30.0, 38.0, 33.0, 22.0, 32.0, 36.0],
+        );
+        assert!(found.iter().all(Option::is_none));
+        assert_eq!(detector.current(), None);
+
+        // Новый максимум цены и новый максимум RSI
+        let mut detector = DivergenceDetector::new(2, 20);
+        let found = feed_highs(
+            &mut detector,
+            [60.0, 65.0, 72.0, 70.0, 62.0, 66.0, 78.0, 68.0, 64.0],
+        );
+        assert!(found.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn swings_farther_than_lookback_are_not_compared() {
+        // Впадины в 4 свечах друг от друга, lookback 3
+        let mut detector = DivergenceDetector::new(2, 3);
+        let found = feed_lows(
+            &mut detector,
+            [40.0, 35.0, 22.0, 30.0, 38.0, 33.0, 28.0, 32.0, 36.0],
+        );
+        assert!(found.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn divergence_expires_after_max_age() {
+        let mut detector = DivergenceDetector::new(2, 20);
+        feed_lows(
+            &mut detector,
+            [40.0, 35.0, 22.0, 30.0, 38.0, 33.0, 28.0, 32.0, 36.0],
+        );
+        // Ровный рынок без новых экстремумов
+        for _ in 0..MAX_AGE_CANDLES - 1 {
+            detector.on_candle_close(98.0, 97.0, 50.0);
+            assert_eq!(detector.current(), Some(Divergence::Bullish));
+        }
+        detector.on_candle_close(98.0, 97.0, 50.0);
+        assert_eq!(detector.current(), None);
+    }
+}
@@ -0,0 +1,248 @@
+// src/strategies/external.rs
+use crate::strategies::traits::Strategy;
+use crate::types::{Position, Side, Signal, Ticker};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::io::SeekFrom;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tracing::{debug, info, warn};
+
+// Как часто проверять файл на новые строки
+const TAIL_POLL: Duration = Duration::from_millis(200);
+
+/// Сигнал внешнего генератора. Строка JSONL: {"symbol":"BTCUSDT","side":"buy","price":"64000.5"}
+/// price необязателен — тогда берётся текущая цена тика.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ExternalSignal {
+    pub symbol: String,
+    pub side: ExternalSide,
+    #[serde(default)]
+    pub price: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExternalSide {
+    Buy,
+    Sell,
+}
+
+/// Стратегия-исполнитель: сама ничего не считает, а переводит внешние сигналы в Advice.
+/// Источник — любой отправитель в канал (см. `tail_signal_file`), риск-менеджмент остаётся за движком.
+pub struct ExternalSignalStrategy {
+    symbol: String,
+    signals: mpsc::Receiver<ExternalSignal>,
+    position: Option<Position>,
+}
+
+impl ExternalSignalStrategy {
+    pub fn new(symbol: String, signals: mpsc::Receiver<ExternalSignal>) -> Self {
+        Self {
+            symbol,
+            signals,
+            position: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Strategy for ExternalSignalStrategy {
+    fn name(&self) -> String {
+        "External".to_string()
+    }
+
+    async fn init(&mut self) -> Result<()> {
+        info!(
+            "🚀 Strategy {} initialized for {}",
+            self.name(),
+            self.symbol
+        );
+        Ok(())
+    }
+
+    async fn on_tick(&mut self, ticker: &Ticker) -> Result<Signal> {
+        // Берём последний сигнал по нашему символу; накопившиеся до него устарели
+        let mut latest = None;
+        while let Ok(signal) = self.signals.try_recv() {
+            if signal.symbol.eq_ignore_ascii_case(&self.symbol) {
+                latest = Some(signal);
+            } else {
+                debug!("External signal for {} ignored", signal.symbol);
+            }
+        }
+
+        match latest {
+            Some(signal) => {
+                let side = match signal.side {
+                    ExternalSide::Buy => Side::Buy,
+                    ExternalSide::Sell => Side::Sell,
+                };
+                let price = signal.price.unwrap_or(ticker.price);
+                info!(symbol = %self.symbol, side = ?side, price = %price, "📨 External signal");
+                Ok(Signal::Advice(side, price))
+            }
+            None => Ok(Signal::Hold),
+        }
+    }
+
+    fn update_position(&mut self, position: Option<Position>) {
+        self.position = position;
+    }
+
+    fn get_position(&self) -> Option<Position> {
+        self.position.clone()
+    }
+
+    // Прогрева нет: дальше просто берутся сигналы по новому символу
+    async fn switch_symbol(&mut self, symbol: &str) -> Result<()> {
+        self.symbol = symbol.to_string();
+        self.position = None;
+        info!("🔀 Strategy {} switched to {}", self.name(), self.symbol);
+        Ok(())
+    }
+}
+
+/// Следит за JSONL-файлом (как `tail -f`) и отправляет новые сигналы в канал.
+/// Читает только строки, дописанные после старта; при усечении файла начинает сначала.
+pub async fn tail_signal_file(path: String) -> Result<mpsc::Receiver<ExternalSignal>> {
+    let mut file = File::open(&path)
+        .await
+        .with_context(|| format!("Failed to open signal file {}", path))?;
+    let mut offset = file.seek(SeekFrom::End(0)).await?;
+    let (sender, receiver) = mpsc::channel(64);
+
+    info!("📨 Tailing external signals from {}", path);
+
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line).await {
+                // Неполная строка (писатель ещё не дописал \n) — ждём и перечитываем с того же места
+                Ok(n) if n > 0 && line.ends_with('\n') => {
+                    offset += n as u64;
+                    let text = line.trim();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<ExternalSignal>(text) {
+                        Ok(signal) => {
+                            if sender.send(signal).await.is_err() {
+                                warn!("Signal receiver closed. Stopping tail of {}", path);
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Bad external signal line {:?}: {}", text, e),
+                    }
+                }
+                Ok(_) => {
+                    sleep(TAIL_POLL).await;
+                    let len = match tokio::fs::metadata(&path).await {
+                        Ok(meta) => meta.len(),
+                        Err(e) => {
+                            warn!("Signal file {} unavailable: {}", path, e);
+                            continue;
+                        }
+                    };
+                    if len < offset {
+                        warn!("Signal file {} truncated. Reading from the start", path);
+                        offset = 0;
+                    }
+                    if let Err(e) = reader.seek(SeekFrom::Start(offset)).await {
+                        warn!("Signal file {} seek failed: {}", path, e);
+                    }
+                }
+                Err(e) => {
+                    warn!("Signal file {} read error: {}", path, e);
+                    sleep(TAIL_POLL).await;
+                }
+            }
+        }
+    });
+
+    Ok(receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(price: Decimal) -> Ticker {
+        Ticker {
+            symbol: "BTCUSDT".to_string(),
+            price,
+            bid_price: price,
+            ask_price: price,
+            bid_qty: Decimal::ONE,
+            ask_qty: Decimal::ONE,
+            timestamp: 0,
+        }
+    }
+
+    fn signal(line: &str) -> ExternalSignal {
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[tokio::test]
+    async fn mock_source_signals_become_advice() {
+        let (source, receiver) = mpsc::channel(8);
+        let mut strategy = ExternalSignalStrategy::new("BTCUSDT".to_string(), receiver);
+        let price = Decimal::from(64_000);
+
+        assert_eq!(strategy.on_tick(&tick(price)).await.unwrap(), Signal::Hold);
+
+        // Цена из сигнала важнее цены тика
+        source
+            .send(signal(
+                r#"{"symbol":"BTCUSDT","side":"buy","price":"63990.5"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            strategy.on_tick(&tick(price)).await.unwrap(),
+            Signal::Advice(Side::Buy, Decimal::new(639905, 1))
+        );
+
+        // Без цены — по тику. Сигнал другого символа пропускается
+        source
+            .send(signal(r#"{"symbol":"btcusdt","side":"sell"}"#))
+            .await
+            .unwrap();
+        source
+            .send(signal(r#"{"symbol":"ETHUSDT","side":"buy"}"#))
+            .await
+            .unwrap();
+        assert_eq!(
+            strategy.on_tick(&tick(price)).await.unwrap(),
+            Signal::Advice(Side::Sell, price)
+        );
+        assert_eq!(strategy.on_tick(&tick(price)).await.unwrap(), Signal::Hold);
+    }
+
+    #[tokio::test]
+    async fn only_the_latest_queued_signal_is_used() {
+        let (source, receiver) = mpsc::channel(8);
+        let mut strategy = ExternalSignalStrategy::new("BTCUSDT".to_string(), receiver);
+        source
+            .send(signal(r#"{"symbol":"BTCUSDT","side":"buy"}"#))
+            .await
+            .unwrap();
+        source
+            .send(signal(r#"{"symbol":"BTCUSDT","side":"sell"}"#))
+            .await
+            .unwrap();
+
+        let price = Decimal::from(100);
+        assert_eq!(
+            strategy.on_tick(&tick(price)).await.unwrap(),
+            Signal::Advice(Side::Sell, price)
+        );
+        assert_eq!(strategy.on_tick(&tick(price)).await.unwrap(), Signal::Hold);
+    }
+}
@@ -0,0 +1,344 @@
+// src/strategies/grid.rs
+use crate::config::GridConfig;
+use crate::strategies::traits::Strategy;
+use crate::types::{Position, Side, Signal, StrategySnapshot, Ticker};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{debug, info, warn};
+
+// Сколько ждать исполнения отданной заявки (мс по времени тиков). Бумажный ордер с
+// simulated_latency_ms исполняется на одном из следующих тиков; дольше — движок её пропустил
+const PENDING_TIMEOUT_MS: u64 = 5_000;
+
+// Заявка, которую стратегия отдала движку и ждёт её исполнения (update_position)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GridOrder {
+    Buy(usize),
+    Sell(usize),
+}
+
+/// Сетка для бокового рынка. Уровни от lower_price до upper_price через spacing.
+/// Под ценой стоят покупки на каждом свободном уровне; купленный на уровне i
+/// лот продаётся на уровне i + 1, после чего покупка на i выставляется снова.
+///
+/// Сетка виртуальная: заявки на уровнях в стакане не стоят. Когда цена тика дошла до уровня,
+/// стратегия отдаёт сигнал, и движок исполняет его как любой другой вход или выход —
+/// по текущему стакану в режиме `execution`, с его комиссией и проскальзыванием, а не по
+/// цене уровня. Прокол уровня между тиками сетка не видит.
+/// Лоты складываются в одну позицию (`scales_in`), продаются долями через PartialExit.
+/// Только лонг: сетка накапливает базовый актив на спаде и раздаёт на росте
+pub struct GridStrategy {
+    symbol: String,
+    levels: Vec<Decimal>,
+    // Уровни с купленным лотом
+    lots: BTreeSet<usize>,
+    // Уровни, над которыми цена уже побывала: на них стоит покупка
+    armed: BTreeSet<usize>,
+    // Заявка и время тика, на котором она отдана
+    pending: Option<(GridOrder, u64)>,
+    position: Option<Position>,
+    out_of_range: bool,
+}
+
+impl GridStrategy {
+    pub fn new(symbol: String, config: &GridConfig) -> Self {
+        Self {
+            symbol,
+            levels: grid_levels(config),
+            lots: BTreeSet::new(),
+            armed: BTreeSet::new(),
+            pending: None,
+            position: None,
+            out_of_range: false,
+        }
+    }
+
+    /// Позиция из файла состояния, а лотов стратегия не помнит: считаем её одним лотом
+    /// уровня под ценой входа, чтобы у неё была заявка на продажу
+    fn adopt_position(&mut self) {
+        let Some(pos) = &self.position else {
+            return;
+        };
+        if !self.lots.is_empty() {
+            return;
+        }
+        let Some(level) = self.levels.iter().rposition(|l| *l <= pos.entry_price) else {
+            return;
+        };
+        let level = level.min(self.levels.len() - 2);
+        warn!(
+            symbol = %self.symbol,
+            entry = %pos.entry_price,
+            level = %self.levels[level],
+            "Grid adopted an open position as one lot"
+        );
+        self.lots.insert(level);
+    }
+
+    fn next_order(&self, price: Decimal) -> Option<GridOrder> {
+        // Продажа: лот уровня i, цена дошла до уровня i + 1. Первым — самый дешёвый лот
+        if let Some(&level) = self.lots.iter().find(|&&i| price >= self.levels[i + 1]) {
+            return Some(GridOrder::Sell(level));
+        }
+        // Покупка: цена опустилась до взведённого уровня. Первым — самый высокий
+        self.armed
+            .iter()
+            .rev()
+            .find(|&&i| price <= self.levels[i])
+            .map(|&i| GridOrder::Buy(i))
+    }
+}
+
+/// Уровни сетки снизу вверх. Верхний — только для продажи
+fn grid_levels(config: &GridConfig) -> Vec<Decimal> {
+    let mut levels = Vec::new();
+    if config.spacing <= Decimal::ZERO {
+        return levels;
+    }
+    let mut level = config.lower_price;
+    while level <= config.upper_price {
+        levels.push(level);
+        level += config.spacing;
+    }
+    levels
+}
+
+#[async_trait]
+impl Strategy for GridStrategy {
+    fn name(&self) -> String {
+        "Grid".to_string()
+    }
+
+    async fn init(&mut self) -> Result<()> {
+        if self.levels.len() < 2 {
+            bail!("Grid needs at least two levels, got {}", self.levels.len());
+        }
+        info!(
+            "🚀 Strategy {} initialized for {}: {} levels {} .. {}",
+            self.name(),
+            self.symbol,
+            self.levels.len(),
+            self.levels[0],
+            self.levels[self.levels.len() - 1]
+        );
+        Ok(())
+    }
+
+    async fn on_tick(&mut self, ticker: &Ticker) -> Result<Signal> {
+        self.adopt_position();
+
+        let price = ticker.price;
+        let (low, high) = (self.levels[0], self.levels[self.levels.len() - 1]);
+        // Запас в шаг сетки: у крайнего уровня цена ходит туда-сюда, лог не должен мигать
+        let spacing = self.levels[1] - self.levels[0];
+        if !self.out_of_range && (price < low - spacing || price > high + spacing) {
+            self.out_of_range = true;
+            warn!(symbol = %self.symbol, price = %price, "Price left the grid range {} .. {}", low, high);
+        } else if self.out_of_range && price >= low && price <= high {
+            self.out_of_range = false;
+            info!(symbol = %self.symbol, price = %price, "Price is back in the grid range");
+        }
+
+        // Покупка выставляется на каждом свободном уровне под ценой (кроме верхнего).
+        // Уровень с покупкой в пути уже занят
+        let buying = match self.pending {
+            Some((GridOrder::Buy(level), _)) => Some(level),
+            _ => None,
+        };
+        for (i, level) in self.levels[..self.levels.len() - 1].iter().enumerate() {
+            if *level < price && !self.lots.contains(&i) && buying != Some(i) {
+                self.armed.insert(i);
+            }
+        }
+
+        // Пока заявка в пути, новых не даём: иначе исполнение припишется не тому уровню
+        if let Some((order, sent_at)) = self.pending {
+            if ticker.timestamp.saturating_sub(sent_at) < PENDING_TIMEOUT_MS {
+                return Ok(Signal::Hold);
+            }
+            debug!(?order, "Grid order was not filled");
+            self.pending = None;
+        }
+        let Some(order) = self.next_order(price) else {
+            return Ok(Signal::Hold);
+        };
+        self.pending = Some((order, ticker.timestamp));
+        match order {
+            GridOrder::Buy(level) => {
+                // Одна попытка на уровень: если движок вход пропустил, покупка встанет снова,
+                // когда цена вернётся выше уровня
+                self.armed.remove(&level);
+                info!(symbol = %self.symbol, price = %price, level = %self.levels[level], "🪜 Grid buy");
+                Ok(Signal::Advice(Side::Buy, price))
+            }
+            GridOrder::Sell(level) => {
+                info!(
+                    symbol = %self.symbol,
+                    price = %price,
+                    level = %self.levels[level + 1],
+                    lots = self.lots.len(),
+                    "🪜 Grid sell"
+                );
+                if self.lots.len() > 1 {
+                    let fraction = Decimal::ONE / Decimal::from(self.lots.len());
+                    Ok(Signal::PartialExit(Side::Sell, price, fraction))
+                } else {
+                    Ok(Signal::Advice(Side::Sell, price))
+                }
+            }
+        }
+    }
+
+    fn update_position(&mut self, position: Option<Position>) {
+        let before = self
+            .position
+            .as_ref()
+            .map(|p| p.quantity)
+            .unwrap_or_default();
+        let after = position.as_ref().map(|p| p.quantity).unwrap_or_default();
+        // Движок зовёт update_position и для переоценки позиции: заявку снимает только
+        // изменение объёма, иначе её ждёт таймаут
+        match self.pending {
+            Some((GridOrder::Buy(level), _)) if after > before => {
+                self.lots.insert(level);
+                self.pending = None;
+            }
+            Some((GridOrder::Sell(level), _)) if after < before => {
+                self.lots.remove(&level);
+                self.pending = None;
+            }
+            _ => {}
+        }
+        // Позицию закрыли целиком (стоп движка, ручное закрытие): лотов больше нет
+        if position.is_none() {
+            self.lots.clear();
+        }
+        self.position = position;
+    }
+
+    fn get_position(&self) -> Option<Position> {
+        self.position.clone()
+    }
+
+    fn scales_in(&self) -> bool {
+        true
+    }
+
+    // Уровни остаются прежними: вне диапазона нового символа сетка просто стоит
+    async fn switch_symbol(&mut self, symbol: &str) -> Result<()> {
+        self.symbol = symbol.to_string();
+        self.lots.clear();
+        self.armed.clear();
+        self.pending = None;
+        self.position = None;
+        self.out_of_range = false;
+        warn!(
+            "🔀 Strategy {} switched to {}. Grid levels are unchanged, reconfigure [grid] for its prices",
+            self.name(),
+            self.symbol
+        );
+        Ok(())
+    }
+
+    fn snapshot(&self) -> StrategySnapshot {
+        let mut indicators =
+            BTreeMap::from([("Grid lots".to_string(), self.lots.len().to_string())]);
+        if let Some(&level) = self.armed.iter().next_back() {
+            indicators.insert("Next buy".to_string(), self.levels[level].to_string());
+        }
+        if let Some(&level) = self.lots.iter().next() {
+            indicators.insert("Next sell".to_string(), self.levels[level + 1].to_string());
+        }
+        StrategySnapshot {
+            indicators,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest;
+    use crate::config::AppConfig;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn grid_config() -> AppConfig {
+        serde_json::from_value(json!({
+            "api_key": "",
+            "secret_key": "",
+            "symbol": "BTCUSDT",
+            "leverage": 1,
+            "order_size_usdt": 100.0,
+            "symbol_tick_size": "0.01",
+            "symbol_step_size": "0.001",
+            "strategy_source": "grid",
+            "grid": { "lower_price": "98", "upper_price": "102", "spacing": "0.5" },
+            "strategy": {
+                "rsi_period": 14,
+                "obi_threshold": 0.3,
+                "bb_period": 20,
+                "bb_std_dev": 2.0,
+                "min_volatility": "0",
+                "atr_multiplier": 2.0,
+            },
+        }))
+        .unwrap()
+    }
+
+    // Боковик 100 ± 1.5: несколько полных качаний через уровни сетки
+    fn sideways_ticks(count: u64) -> Vec<Ticker> {
+        (0..count)
+            .map(|i| {
+                let price = Decimal::try_from(100.0 + 1.5 * (i as f64 / 40.0).sin())
+                    .unwrap()
+                    .round_dp(2);
+                let spread = Decimal::new(1, 2);
+                Ticker {
+                    symbol: "BTCUSDT".to_string(),
+                    price,
+                    bid_price: price - spread,
+                    ask_price: price + spread,
+                    bid_qty: Decimal::ONE,
+                    ask_qty: Decimal::ONE,
+                    timestamp: 1_700_000_000_000 + i * 1_000,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn levels_run_from_lower_to_upper_price() {
+        let levels = grid_levels(&grid_config().grid);
+        assert_eq!(levels.len(), 9);
+        assert_eq!(levels[0], Decimal::from(98));
+        assert_eq!(levels[8], Decimal::from(102));
+    }
+
+    #[tokio::test]
+    async fn paper_grid_profits_on_a_sideways_market() {
+        let result = backtest::run(grid_config(), Arc::new(sideways_ticks(2_000)))
+            .await
+            .unwrap();
+
+        // Лоты одной позиции (со средней ценой входа) делят время открытия. Частичная
+        // продажа нижнего лота по средней цене бывает в минусе, поэтому считаем цикл целиком:
+        // от первой покупки на спаде до продажи последнего лота на росте
+        let mut cycles: BTreeMap<u64, (usize, Decimal)> = BTreeMap::new();
+        for trade in &result.trades {
+            let cycle = cycles.entry(trade.opened_at).or_default();
+            cycle.0 += 1;
+            cycle.1 += trade.pnl;
+        }
+        // Последний цикл запись обрывает с открытой позицией
+        let closed: Vec<_> = cycles.values().take(cycles.len() - 1).collect();
+        assert!(closed.len() >= 5, "only {} grid cycles", closed.len());
+        for (lots, pnl) in closed {
+            assert!(*lots > 1, "the swing sold only {} lot", lots);
+            assert!(*pnl > Decimal::ZERO, "grid cycle lost {}", pnl);
+        }
+    }
+}
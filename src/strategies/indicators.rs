@@ -0,0 +1,232 @@
+// src/strategies/indicators.rs
+use crate::config::StrategyConfig;
+use crate::strategies::divergence::{Divergence, DivergenceDetector};
+use std::collections::VecDeque;
+use ta::indicators::{
+    AverageTrueRange, BollingerBands, MovingAverageConvergenceDivergence, RelativeStrengthIndex,
+};
+use ta::{Close, DataItem, High, Low, Next, Volume};
+use tracing::warn;
+
+// Период ATR для динамического стопа
+const ATR_PERIOD: usize = 14;
+// Запас свечей к самому длинному окну: RSI и MACD сглажены экспоненциально
+// и после первого полного окна ещё заметно плывут
+const WARMUP_MARGIN: usize = 10;
+
+/// Последние значения индикаторов (на закрытии последней свечи)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndicatorValues {
+    pub rsi: f64,
+    pub atr: f64,
+    // (lower, average, upper)
+    pub bb: Option<(f64, f64, f64)>,
+    // None — MACD выключен или ещё не было свечей
+    pub macd_histogram: Option<f64>,
+    pub prev_macd_histogram: Option<f64>,
+    // (VWAP, стандартное отклонение типичной цены от него). None — выключен или окно не набрано
+    pub vwap: Option<(f64, f64)>,
+    // Свежая дивергенция RSI. None — выключена или не найдена
+    pub divergence: Option<Divergence>,
+}
+
+impl Default for IndicatorValues {
+    fn default() -> Self {
+        Self {
+            rsi: 50.0,
+            atr: 0.0,
+            bb: None,
+            macd_histogram: None,
+            prev_macd_histogram: None,
+            vwap: None,
+            divergence: None,
+        }
+    }
+}
+
+impl IndicatorValues {
+    /// Гистограмма MACD растёт относительно предыдущей свечи (None — недостаточно данных)
+    pub fn macd_rising(&self) -> Option<bool> {
+        match (self.macd_histogram, self.prev_macd_histogram) {
+            (Some(current), Some(prev)) => Some(current > prev),
+            _ => None,
+        }
+    }
+}
+
+/// VWAP по скользящему окну свечей: типичная цена (high + low + close) / 3, взвешенная
+/// объёмом. Если в окне есть свеча без объёма (живые свечи из bookTicker), веса одинаковые
+struct RollingVwap {
+    period: usize,
+    // (типичная цена, объём)
+    window: VecDeque<(f64, f64)>,
+    // Последний расчёт был без объёма (предупреждение — только при переходе)
+    unweighted: bool,
+}
+
+impl RollingVwap {
+    fn new(period: usize) -> Self {
+        Self {
+            period,
+            window: VecDeque::with_capacity(period),
+            unweighted: false,
+        }
+    }
+
+    fn next(&mut self, item: &DataItem) -> Option<(f64, f64)> {
+        let typical = (item.high() + item.low() + item.close()) / 3.0;
+        if self.window.len() == self.period {
+            self.window.pop_front();
+        }
+        self.window.push_back((typical, item.volume()));
+        if self.window.len() < self.period {
+            return None;
+        }
+
+        let has_volume = self.window.iter().all(|&(_, volume)| volume > 0.0);
+        if !has_volume && !self.unweighted {
+            warn!("VWAP: volume unavailable, falling back to typical-price average");
+        }
+        self.unweighted = !has_volume;
+
+        let weight = |volume: f64| if has_volume { volume } else { 1.0 };
+        let total: f64 = self.window.iter().map(|&(_, v)| weight(v)).sum();
+        let vwap = self
+            .window
+            .iter()
+            .map(|&(price, v)| price * weight(v))
+            .sum::<f64>()
+            / total;
+        let variance = self
+            .window
+            .iter()
+            .map(|&(price, v)| weight(v) * (price - vwap).powi(2))
+            .sum::<f64>()
+            / total;
+        Some((vwap, variance.sqrt()))
+    }
+}
+
+/// Набор индикаторов стратегии с единым обновлением на закрытии свечи.
+/// RSI, Bollinger и ATR есть всегда; MACD, VWAP и дивергенция — только если включены в конфиге.
+pub struct IndicatorBundle {
+    rsi: RelativeStrengthIndex,
+    bb: BollingerBands,
+    atr: AverageTrueRange,
+    macd: Option<MovingAverageConvergenceDivergence>,
+    vwap: Option<RollingVwap>,
+    divergence: Option<DivergenceDetector>,
+    values: IndicatorValues,
+    // Сколько закрытых свечей нужно до торговли и сколько уже принято
+    warmup: usize,
+    closed: usize,
+    // Прогрев завершён: свечей достаточно и RSI/BB дали настоящие значения. Не сбрасывается
+    stable: bool,
+}
+
+impl IndicatorBundle {
+    pub fn new(config: &StrategyConfig) -> Self {
+        let macd = config.macd_filter.then(|| {
+            MovingAverageConvergenceDivergence::new(
+                config.macd_fast,
+                config.macd_slow,
+                config.macd_signal,
+            )
+            .unwrap()
+        });
+
+        Self {
+            rsi: RelativeStrengthIndex::new(config.rsi_period).unwrap(),
+            bb: BollingerBands::new(config.bb_period, config.bb_std_dev).unwrap(),
+            atr: AverageTrueRange::new(ATR_PERIOD).unwrap(),
+            macd,
+            vwap: config
+                .vwap_entry
+                .then(|| RollingVwap::new(config.vwap_period)),
+            divergence: config.divergence_filter.then(|| {
+                DivergenceDetector::new(config.divergence_pivot, config.divergence_lookback)
+            }),
+            values: IndicatorValues::default(),
+            warmup: Self::warmup_candles(config),
+            closed: 0,
+            stable: false,
+        }
+    }
+
+    /// Свечей прогрева: самое длинное окно включённых индикаторов + WARMUP_MARGIN
+    pub fn warmup_candles(config: &StrategyConfig) -> usize {
+        let mut longest = config.rsi_period.max(config.bb_period).max(ATR_PERIOD);
+        if config.macd_filter {
+            // Сигнальная линия считается по уже готовому MACD
+            longest = longest.max(config.macd_slow + config.macd_signal);
+        }
+        if config.vwap_entry {
+            longest = longest.max(config.vwap_period);
+        }
+        if config.divergence_filter {
+            // Обе сравниваемые вершины должны прийтись на уже посчитанный RSI
+            longest = longest.max(config.rsi_period + config.divergence_lookback);
+        }
+        longest + WARMUP_MARGIN
+    }
+
+    pub fn on_candle_close(&mut self, item: &DataItem) {
+        self.values.rsi = self.rsi.next(item);
+        self.values.atr = self.atr.next(item);
+
+        let bb_out = self.bb.next(item);
+        self.values.bb = Some((bb_out.lower, bb_out.average, bb_out.upper));
+
+        if let Some(macd) = self.macd.as_mut() {
+            let out = macd.next(item);
+            self.values.prev_macd_histogram = self.values.macd_histogram;
+            self.values.macd_histogram = Some(out.histogram);
+        }
+
+        if let Some(vwap) = self.vwap.as_mut() {
+            self.values.vwap = vwap.next(item);
+        }
+
+        if let Some(divergence) = self.divergence.as_mut() {
+            divergence.on_candle_close(item.high(), item.low(), self.values.rsi);
+            self.values.divergence = divergence.current();
+        }
+
+        self.closed += 1;
+        if !self.stable && self.closed >= self.warmup && self.produced_values() {
+            self.stable = true;
+        }
+    }
+
+    // RSI ушёл со стартовых 50 (на ровном ряду он там и стоит), полосы BB конечны
+    fn produced_values(&self) -> bool {
+        let default = IndicatorValues::default();
+        let rsi_ready = self.values.rsi.is_finite() && self.values.rsi != default.rsi;
+        let bb_ready = self.values.bb.is_some_and(|(lower, average, upper)| {
+            lower.is_finite() && average.is_finite() && upper.is_finite()
+        });
+        rsi_ready && bb_ready
+    }
+
+    /// Индикаторам можно верить: прогрев пройден
+    pub fn is_stable(&self) -> bool {
+        self.stable
+    }
+
+    /// (принято свечей, нужно свечей) для "warming up: 37/60"
+    pub fn warmup_progress(&self) -> (usize, usize) {
+        (self.closed, self.warmup)
+    }
+
+    pub fn values(&self) -> &IndicatorValues {
+        &self.values
+    }
+
+    pub fn macd_enabled(&self) -> bool {
+        self.macd.is_some()
+    }
+
+    pub fn divergence_enabled(&self) -> bool {
+        self.divergence.is_some()
+    }
+}
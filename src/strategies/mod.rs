@@ -1,2 +1,6 @@
+pub mod divergence;
+pub mod external;
+pub mod grid;
+pub mod indicators;
 pub mod scalper;
 pub mod traits; // Объявляем модуль scalper
@@ -1,14 +1,31 @@
 // src/strategies/scalper.rs
-use crate::config::StrategyConfig;
+use crate::config::{StrategyConfig, TakeProfitLevel, TradeDirection, TrailTier};
+use crate::connectors::traits::HistoryProvider;
+use crate::storage::Store;
+use crate::strategies::divergence::Divergence;
+use crate::strategies::indicators::IndicatorBundle;
 use crate::strategies::traits::Strategy;
-use crate::types::{Position, Side, Signal, Ticker};
-use anyhow::Result;
+use crate::types::{Kline, Position, PositionSide, Signal, StrategySnapshot, Ticker};
+use crate::utils::clock::{Clock, RealClock};
+use crate::utils::trade_volume::TradeVolume;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
-use ta::indicators::{AverageTrueRange, BollingerBands, RelativeStrengthIndex};
-use ta::{DataItem, Next};
-use tracing::{debug, info}; // Убрал warn
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use ta::DataItem;
+use tracing::{debug, info, warn};
+
+pub const CANDLE_MS: u64 = 60_000;
+
+/// Цена лучше опорной для трейлинга: выше у лонга, ниже у шорта
+fn improves(side: PositionSide, price: Decimal, best: Decimal) -> bool {
+    match side {
+        PositionSide::Long => price > best,
+        PositionSide::Short => price < best,
+    }
+}
 
 #[derive(Debug, Clone)]
 struct CandleBuilder {
@@ -17,17 +34,33 @@ struct CandleBuilder {
     high: Decimal,
     low: Decimal,
     close: Decimal,
+    // bookTicker сделок не несёт: у живых свечей объём берётся из @aggTrade при закрытии,
+    // без этого потока он нулевой
+    volume: Decimal,
 }
 
 impl CandleBuilder {
     fn new(tick: &Ticker) -> Self {
-        let open_time = (tick.timestamp / 60_000) * 60_000;
+        let open_time = (tick.timestamp / CANDLE_MS) * CANDLE_MS;
         Self {
             open_time,
             open: tick.price,
             high: tick.price,
             low: tick.price,
             close: tick.price,
+            volume: Decimal::ZERO,
+        }
+    }
+
+    fn to_kline(&self) -> Kline {
+        Kline {
+            open_time: self.open_time,
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            close_time: self.open_time + CANDLE_MS - 1,
         }
     }
 
@@ -42,53 +75,260 @@ impl CandleBuilder {
     }
 }
 
+impl From<&Kline> for CandleBuilder {
+    fn from(kline: &Kline) -> Self {
+        Self {
+            open_time: kline.open_time,
+            open: kline.open,
+            high: kline.high,
+            low: kline.low,
+            close: kline.close,
+            volume: kline.volume,
+        }
+    }
+}
+
 pub struct RsiBollingerStrategy {
     symbol: String,
-    rsi: RelativeStrengthIndex,
-    bb: BollingerBands,
-    atr: AverageTrueRange,
+    indicators: IndicatorBundle,
 
     current_candle: Option<CandleBuilder>,
 
-    // Состояние индикаторов
-    last_rsi_value: f64,
-    last_atr_value: f64, // <--- Значение ATR
-    last_bb_values: Option<(f64, f64, f64)>,
-
     position: Option<Position>,
 
-    // Warm-up Logic
-    warmup_period: usize,
-    processed_candles: usize,
+    // Warm-up Logic: сколько свечей нужно и готовность индикаторов — в IndicatorBundle
+    seed_from_history: bool,
+    seed_candles: usize,
+    history: Option<Box<dyn HistoryProvider>>,
+    // Закрытые свечи между перезапусками: прогрев без запроса истории
+    store: Option<Arc<dyn Store>>,
+    // Объём сделок по свечам из @aggTrade. Нет — живые свечи с нулевым объёмом
+    trade_volume: Option<Arc<TradeVolume>>,
+    // Текущее время для шва history -> live и возраста тика
+    clock: Arc<dyn Clock>,
+    // open_time последней исторической свечи (шов history -> live)
+    last_seeded_open_time: Option<u64>,
 
     // Strategy Parameters
     obi_threshold: Decimal,
+    // Порог VWAP-входа в σ (сам VWAP — в indicators, если vwap_entry)
+    vwap_entry_std_dev: f64,
     min_volatility: f64,
-    // Заменили trailing_callback на atr_multiplier
+    // Дистанция трейлинга: ATR * atr_multiplier, но не ближе trailing_callback от цены
     atr_multiplier: Decimal,
+    trailing_callback: Decimal,
+    // Порог взвода трейлинга от цены входа (0 — взведён сразу)
+    trail_activation_pct: Decimal,
+    take_profit_levels: Vec<TakeProfitLevel>,
+    // Profit-lock: ступени сужения трейлинга по прибыли
+    trail_tiers: Vec<TrailTier>,
+    hard_stop_pct: Decimal,
+    // Двигать highest_price только по закрытию свечи (а не на каждом тике)
+    trail_on_candle_close: bool,
+    // Предел возраста тика для входов по OBI (None — не проверяем)
+    max_tick_age_ms: Option<u64>,
+    trade_direction: TradeDirection,
+    // Анти-флип-флоп: минимальное удержание и пауза перед повторным входом (мс)
+    min_hold_ms: u64,
+    reentry_cooldown_ms: u64,
+    // Пауза входов после выхода по стопу (мс)
+    post_stop_cooldown_ms: u64,
+    // Время последнего тика и момент закрытия предыдущей позиции (по времени тиков)
+    last_tick_at: u64,
+    last_exit_at: Option<u64>,
+    // Когда сработал последний стоп (жёсткий или трейлинг), по времени тиков
+    last_stop_at: Option<u64>,
+    // Дисбаланс стакана последнего тика (для TUI)
+    last_obi: Decimal,
+    // Параметры, с которыми построены индикаторы (их на ходу менять нельзя)
+    indicator_config: StrategyConfig,
 }
 
 impl RsiBollingerStrategy {
     pub fn new(symbol: String, config: StrategyConfig) -> Self {
         Self {
             symbol,
-            rsi: RelativeStrengthIndex::new(config.rsi_period).unwrap(),
-            bb: BollingerBands::new(config.bb_period, config.bb_std_dev).unwrap(),
-            atr: AverageTrueRange::new(14).unwrap(),
+            indicators: IndicatorBundle::new(&config),
 
             current_candle: None,
-            last_rsi_value: 50.0,
-            last_atr_value: 0.0,
-            last_bb_values: None,
             position: None,
 
-            warmup_period: 50,
-            processed_candles: 0,
+            seed_from_history: config.seed_from_history,
+            seed_candles: config
+                .seed_candles
+                .unwrap_or_else(|| IndicatorBundle::warmup_candles(&config)),
+            history: None,
+            store: None,
+            trade_volume: None,
+            clock: Arc::new(RealClock),
+            last_seeded_open_time: None,
 
             obi_threshold: Decimal::from_f64(config.obi_threshold).unwrap_or(Decimal::ZERO),
+            vwap_entry_std_dev: config.vwap_entry_std_dev,
             min_volatility: config.min_volatility.to_f64().unwrap_or(0.003),
             // Инициализация множителя из конфига (default 2.0 если придет 0)
             atr_multiplier: Decimal::from_f64(config.atr_multiplier).unwrap_or(Decimal::from(2)),
+            trailing_callback: config.trailing_callback,
+            trail_activation_pct: config.trail_activation_pct,
+            take_profit_levels: config.take_profit_levels.clone(),
+            trail_tiers: config.trail_tiers.clone(),
+            hard_stop_pct: config.hard_stop_pct,
+            trail_on_candle_close: config.trail_on_candle_close,
+            max_tick_age_ms: config.max_tick_age_ms,
+            trade_direction: config.trade_direction,
+            min_hold_ms: config.min_hold_seconds * 1000,
+            reentry_cooldown_ms: config.reentry_cooldown_seconds * 1000,
+            post_stop_cooldown_ms: config.post_stop_cooldown_seconds * 1000,
+            last_tick_at: 0,
+            last_exit_at: None,
+            last_stop_at: None,
+            last_obi: Decimal::ZERO,
+            indicator_config: config,
+        }
+    }
+
+    /// Источник исторических свечей для прогрева (используется если `seed_from_history = true`)
+    pub fn with_history(mut self, provider: Box<dyn HistoryProvider>) -> Self {
+        self.history = Some(provider);
+        self
+    }
+
+    /// Те же часы, что у движка (в бэктесте — время тиков)
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_store(mut self, store: Arc<dyn Store>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Объём живых свечей из потока сделок (заполняет коннектор)
+    pub fn with_trade_volume(mut self, volume: Arc<TradeVolume>) -> Self {
+        self.trade_volume = Some(volume);
+        self
+    }
+
+    /// Прогрев из свечей прошлого запуска. Только если их хватает и последняя стоит
+    /// вплотную к текущей минуте: с пропуском индикаторы посчитаны по дырявому ряду.
+    /// Без истории с биржи берутся и такие — с предупреждением о шве
+    fn seed_from_store(&mut self) -> bool {
+        let Some(store) = self.store.clone() else {
+            return false;
+        };
+        let candles = match store.get_candles(&self.symbol, self.seed_candles) {
+            Ok(candles) if !candles.is_empty() => candles,
+            Ok(_) => return false,
+            Err(e) => {
+                warn!("⚠️ Stored candles unavailable: {}", e);
+                return false;
+            }
+        };
+        let now_ms = self.clock.now_ms();
+        let live_open_time = (now_ms / CANDLE_MS) * CANDLE_MS;
+        let fresh = candles.len() >= self.seed_candles
+            && candles
+                .last()
+                .is_some_and(|k| k.open_time + CANDLE_MS == live_open_time);
+        let exchange_available = self.seed_from_history && self.history.is_some();
+        if !fresh && exchange_available {
+            info!(
+                "🗄️ {} stored candles for {} are incomplete or stale, fetching history",
+                candles.len(),
+                self.symbol
+            );
+            return false;
+        }
+        let seeded = self.preload_history(candles, now_ms);
+        info!(
+            "🗄️ Warm-up seeded with {} stored candles for {}",
+            seeded, self.symbol
+        );
+        true
+    }
+
+    fn store_candles(&self, candles: &[Kline]) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        for candle in candles {
+            if let Err(e) = store.put_candle(&self.symbol, candle) {
+                warn!("⚠️ Failed to store candle: {}", e);
+                return;
+            }
+        }
+    }
+
+    /// Прогревает индикаторы закрытыми свечами так, чтобы последняя из них была
+    /// непосредственно перед текущей (формирующейся) живой свечой.
+    /// Формирующуюся свечу не берём — её соберёт live-поток. Возвращает число принятых свечей.
+    fn preload_history(&mut self, mut klines: Vec<Kline>, now_ms: u64) -> usize {
+        let live_open_time = (now_ms / CANDLE_MS) * CANDLE_MS;
+
+        klines.sort_by_key(|k| k.open_time);
+        klines.dedup_by_key(|k| k.open_time);
+
+        let before = klines.len();
+        klines.retain(|k| k.open_time < live_open_time);
+        if klines.len() < before {
+            debug!(
+                "Seam: dropped {} still-forming kline(s) at/after {}",
+                before - klines.len(),
+                live_open_time
+            );
+        }
+
+        let mut accepted = 0;
+        for kline in &klines {
+            if let Some(prev) = self.last_seeded_open_time {
+                if kline.open_time != prev + CANDLE_MS {
+                    warn!(
+                        "Seam: history gap between {} and {} ({} candles missing)",
+                        prev,
+                        kline.open_time,
+                        (kline.open_time - prev) / CANDLE_MS - 1
+                    );
+                }
+            }
+            self.close_candle(&CandleBuilder::from(kline));
+            self.last_seeded_open_time = Some(kline.open_time);
+            accepted += 1;
+        }
+
+        match self.last_seeded_open_time {
+            Some(last) if last + CANDLE_MS == live_open_time => info!(
+                "🧵 Seam aligned: last history candle {} -> live candle {}",
+                last, live_open_time
+            ),
+            Some(last) => warn!(
+                "🧵 Seam gap: last history candle {} but live candle is {} ({} candles missing)",
+                last,
+                live_open_time,
+                live_open_time.saturating_sub(last + CANDLE_MS) / CANDLE_MS
+            ),
+            None => warn!("🧵 No closed history candles before {}", live_open_time),
+        }
+
+        accepted
+    }
+
+    /// Режим trail_on_candle_close: опорная цена трейлинга двигается только по close свечи,
+    /// поэтому одиночная тень внутри свечи её не сдвигает. Возвращает true, если сдвинули.
+    fn advance_trailing_on_close(&mut self, candle_close: Decimal) -> bool {
+        if !self.trail_on_candle_close {
+            return false;
+        }
+        match &mut self.position {
+            Some(pos) if improves(pos.side, candle_close, pos.highest_price) => {
+                debug!(
+                    "Trailing reference advanced on close: {} -> {}",
+                    pos.highest_price, candle_close
+                );
+                pos.highest_price = candle_close;
+                true
+            }
+            _ => false,
         }
     }
 
@@ -98,17 +338,11 @@ impl RsiBollingerStrategy {
             .low(candle.low.to_f64().unwrap_or_default())
             .close(candle.close.to_f64().unwrap_or_default())
             .open(candle.open.to_f64().unwrap_or_default())
-            .volume(0.0)
+            .volume(candle.volume.to_f64().unwrap_or_default())
             .build()
             .unwrap();
 
-        self.last_rsi_value = self.rsi.next(&item);
-        self.last_atr_value = self.atr.next(&item); // Сохраняем актуальный ATR
-
-        let bb_out = self.bb.next(&item);
-        self.last_bb_values = Some((bb_out.lower, bb_out.average, bb_out.upper));
-
-        self.processed_candles += 1;
+        self.indicators.on_candle_close(&item);
     }
 }
 
@@ -119,22 +353,93 @@ impl Strategy for RsiBollingerStrategy {
     }
 
     async fn init(&mut self) -> Result<()> {
+        // Свечи из store — история с биржи уже не нужна
+        if !self.seed_from_store() && self.seed_from_history {
+            if let Some(history) = self.history.take() {
+                // +1: последняя свеча ответа обычно ещё формируется и будет отброшена
+                let limit = (self.seed_candles + 1).min(1500);
+                match history.fetch_klines(&self.symbol, "1m", limit).await {
+                    Ok(klines) => {
+                        let now_ms = self.clock.now_ms();
+                        let seeded = self.preload_history(klines.clone(), now_ms);
+                        // В store — только закрытые свечи, принятые в прогрев
+                        let stored: Vec<Kline> = klines
+                            .into_iter()
+                            .filter(|k| {
+                                self.last_seeded_open_time
+                                    .is_some_and(|last| k.open_time <= last)
+                            })
+                            .collect();
+                        self.store_candles(&stored);
+                        let (processed, warmup) = self.indicators.warmup_progress();
+                        if processed >= warmup {
+                            info!(
+                                "📚 Warm-up seeded with {} historical candles. Trading from the first tick",
+                                seeded
+                            );
+                        } else {
+                            warn!(
+                                "📚 Warm-up seeded with {} historical candles, {} more live candles needed",
+                                seeded,
+                                warmup - processed
+                            );
+                        }
+                    }
+                    Err(e) => warn!("⚠️ History preload failed, warming up live: {}", e),
+                }
+                self.history = Some(history);
+            }
+        }
+
         info!(
             "🚀 Strategy {} initialized. Warm-up target: {} candles. ATR Multiplier: {}",
             self.name(),
-            self.warmup_period,
+            self.indicators.warmup_progress().1,
             self.atr_multiplier
         );
         Ok(())
     }
 
     async fn on_tick(&mut self, tick: &Ticker) -> Result<Signal> {
+        self.last_tick_at = tick.timestamp;
+
         // 1. Candle Logic
-        let tick_minute_start = (tick.timestamp / 60_000) * 60_000;
+        let tick_minute_start = (tick.timestamp / CANDLE_MS) * CANDLE_MS;
+
+        // Шов history -> live: тик из уже учтённой исторической свечи не должен открыть её повторно
+        if let Some(last_seeded) = self.last_seeded_open_time {
+            if tick_minute_start <= last_seeded {
+                debug!(
+                    "Seam: skipping tick of already seeded candle {}",
+                    tick_minute_start
+                );
+                return Ok(Signal::Hold);
+            }
+            if tick_minute_start > last_seeded + CANDLE_MS {
+                warn!(
+                    "Seam: first live candle {} skips {} candle(s) after history",
+                    tick_minute_start,
+                    (tick_minute_start - last_seeded) / CANDLE_MS - 1
+                );
+            } else {
+                info!(
+                    "Seam: live candles continue from history at {}",
+                    tick_minute_start
+                );
+            }
+            self.last_seeded_open_time = None;
+        }
+
+        let mut trail_advanced = false;
         match self.current_candle.clone() {
             Some(mut candle) => {
                 if tick_minute_start > candle.open_time {
+                    if let Some(volume) = &self.trade_volume {
+                        candle.volume = volume.take(candle.open_time);
+                    }
                     self.close_candle(&candle);
+                    self.store_candles(&[candle.to_kline()]);
+                    trail_advanced = self.advance_trailing_on_close(candle.close);
                     self.current_candle = Some(CandleBuilder::new(tick));
                 } else {
                     candle.update(tick);
@@ -147,22 +452,33 @@ impl Strategy for RsiBollingerStrategy {
         }
 
         // 2. Warm-up Check
-        if self.processed_candles < self.warmup_period {
-            if self.processed_candles % 10 == 0 {
-                debug!(
-                    "Warming up: {} / {} candles",
-                    self.processed_candles, self.warmup_period
-                );
+        // Свечей может уже хватать, а RSI/BB ещё не ожили (ровный рынок): ждём дальше
+        if !self.indicators.is_stable() {
+            let (processed, warmup) = self.indicators.warmup_progress();
+            if processed.is_multiple_of(10) {
+                debug!("Warming up: {} / {} candles", processed, warmup);
             }
             return Ok(Signal::Hold);
         }
 
         // 3. Indicator Extraction
-        let (bb_lower_f, _, _) = match self.last_bb_values {
+        let indicators = *self.indicators.values();
+        let (bb_lower_f, _, bb_upper_f) = match indicators.bb {
             Some(vals) => vals,
             None => return Ok(Signal::Hold),
         };
         let bb_lower = Decimal::from_f64(bb_lower_f).unwrap_or_default();
+        let bb_upper = Decimal::from_f64(bb_upper_f).unwrap_or_default();
+        // MACD-фильтр (если включён): лонг при растущей гистограмме, шорт при падающей
+        let macd_enabled = self.indicators.macd_enabled();
+        let macd_confirms_long = !macd_enabled || indicators.macd_rising() == Some(true);
+        let macd_confirms_short = !macd_enabled || indicators.macd_rising() == Some(false);
+        // Фильтр дивергенции (если включён): лонг после бычьей, шорт после медвежьей
+        let divergence_enabled = self.indicators.divergence_enabled();
+        let divergence_confirms_long =
+            !divergence_enabled || indicators.divergence == Some(Divergence::Bullish);
+        let divergence_confirms_short =
+            !divergence_enabled || indicators.divergence == Some(Divergence::Bearish);
 
         // 4. OBI Calculation
         let total_qty = tick.bid_qty + tick.ask_qty;
@@ -171,12 +487,13 @@ impl Strategy for RsiBollingerStrategy {
         } else {
             Decimal::ZERO
         };
+        self.last_obi = obi;
 
         // 5. Entry/Exit Logic
         match &mut self.position {
             None => {
                 // --- VOLATILITY FILTER ---
-                let current_atr = self.last_atr_value;
+                let current_atr = indicators.atr;
                 let current_price = tick.price.to_f64().unwrap_or(1.0);
                 let vol_pct = current_atr / current_price;
 
@@ -184,50 +501,250 @@ impl Strategy for RsiBollingerStrategy {
                     return Ok(Signal::Hold);
                 }
 
-                // ENTRY LOGIC
-                if tick.price < bb_lower && self.last_rsi_value < 30.0 && obi > self.obi_threshold {
+                // Только что вышли — не прыгаем обратно на соседнем тике
+                if let Some(exit_at) = self.last_exit_at {
+                    if tick.timestamp.saturating_sub(exit_at) < self.reentry_cooldown_ms {
+                        return Ok(Signal::Hold);
+                    }
+                }
+
+                // ENTRY LOGIC: зеркально для лонга и шорта
+                let long_setup = tick.price < bb_lower
+                    && indicators.rsi < 30.0
+                    && obi > self.obi_threshold
+                    && macd_confirms_long;
+                let short_setup = tick.price > bb_upper
+                    && indicators.rsi > 70.0
+                    && obi < -self.obi_threshold
+                    && macd_confirms_short;
+                // VWAP-реверсия: цена далеко от VWAP, OBI и MACD подтверждают разворот
+                let (vwap_long, vwap_short) = match indicators.vwap {
+                    Some((vwap, std_dev)) => {
+                        let band = std_dev * self.vwap_entry_std_dev;
+                        (
+                            current_price < vwap - band
+                                && obi > self.obi_threshold
+                                && macd_confirms_long,
+                            current_price > vwap + band
+                                && obi < -self.obi_threshold
+                                && macd_confirms_short,
+                        )
+                    }
+                    None => (false, false),
+                };
+                let long_setup = (long_setup || vwap_long) && divergence_confirms_long;
+                let short_setup = (short_setup || vwap_short) && divergence_confirms_short;
+                let entry = if long_setup && self.trade_direction.allows(PositionSide::Long) {
+                    Some(PositionSide::Long)
+                } else if short_setup && self.trade_direction.allows(PositionSide::Short) {
+                    Some(PositionSide::Short)
+                } else {
+                    None
+                };
+
+                if let Some(side) = entry {
+                    if let Some(stop_at) = self.last_stop_at {
+                        let elapsed = tick.timestamp.saturating_sub(stop_at);
+                        if elapsed < self.post_stop_cooldown_ms {
+                            info!(
+                                symbol = %tick.symbol,
+                                side = ?side,
+                                remaining_s = (self.post_stop_cooldown_ms - elapsed).div_ceil(1000),
+                                "Entry skipped: post-stop cooldown"
+                            );
+                            return Ok(Signal::Hold);
+                        }
+                    }
+                    // OBI — снимок стакана в момент тика. Если тик старый, стакан уже другой
+                    if let Some(max_age) = self.max_tick_age_ms {
+                        let age_ms = self.clock.now_ms().saturating_sub(tick.timestamp);
+                        if age_ms > max_age {
+                            info!(
+                                symbol = %tick.symbol,
+                                tick_age_ms = age_ms,
+                                max_tick_age_ms = max_age,
+                                obi = %obi.round_dp(2),
+                                "Entry suppressed: stale tick, OBI unreliable"
+                            );
+                            return Ok(Signal::Hold);
+                        }
+                    }
                     info!(
-                        "⚡ LONG SIGNAL: RSI {:.2} < 30 & OBI {:.2} > {}. Volatility: {:.4}%",
-                        self.last_rsi_value,
-                        obi,
-                        self.obi_threshold,
-                        vol_pct * 100.0
+                        symbol = %tick.symbol,
+                        side = ?side,
+                        price = %tick.price,
+                        rsi = indicators.rsi,
+                        macd_histogram = ?indicators.macd_histogram,
+                        vwap = ?indicators.vwap.map(|(vwap, _)| vwap),
+                        divergence = ?indicators.divergence,
+                        obi = %obi.round_dp(2),
+                        obi_threshold = %self.obi_threshold,
+                        volatility_pct = vol_pct * 100.0,
+                        "⚡ ENTRY SIGNAL"
                     );
-                    return Ok(Signal::Advice(Side::Buy, tick.price));
+                    return Ok(Signal::Advice(side.entry_side(), tick.price));
                 }
             }
             Some(pos) => {
-                let mut state_changed = false;
+                let mut state_changed = trail_advanced;
 
-                if tick.price > pos.highest_price {
+                // Стоп проверяется на каждом тике, но опорная цена в candle-close режиме — только по закрытию
+                if !self.trail_on_candle_close && improves(pos.side, tick.price, pos.highest_price)
+                {
                     pos.highest_price = tick.price;
                     state_changed = true;
                 }
 
+                // Трейлинг взводится один раз, когда цена дошла до порога активации.
+                // Откат ниже порога его уже не снимает
+                if !pos.armed {
+                    let activation = match pos.side {
+                        PositionSide::Long => {
+                            pos.entry_price * (Decimal::ONE + self.trail_activation_pct)
+                        }
+                        PositionSide::Short => {
+                            pos.entry_price * (Decimal::ONE - self.trail_activation_pct)
+                        }
+                    };
+                    if self.trail_activation_pct.is_zero() {
+                        pos.armed = true;
+                        state_changed = true;
+                    } else if !improves(pos.side, activation, tick.price) {
+                        info!(
+                            symbol = %tick.symbol,
+                            side = ?pos.side,
+                            price = %tick.price,
+                            activation = %activation,
+                            "🎯 Trailing stop armed"
+                        );
+                        pos.armed = true;
+                        state_changed = true;
+                    }
+                }
+
                 // --- DYNAMIC TRAILING STOP (ATR BASED) ---
-                let current_atr_dec = Decimal::from_f64(self.last_atr_value).unwrap_or_default();
+                let current_atr_dec = Decimal::from_f64(indicators.atr).unwrap_or_default();
                 let mut stop_dist = current_atr_dec * self.atr_multiplier;
 
-                // Sanity Check: Минимальный стоп 0.1%, чтобы не выбивало шумом при нулевом ATR
-                let min_dist = tick.price * Decimal::from_str("0.001").unwrap();
+                // Sanity Check: минимальный стоп trailing_callback, чтобы не выбивало шумом при нулевом ATR
+                let min_dist = tick.price * self.trailing_callback;
                 if stop_dist < min_dist {
                     stop_dist = min_dist;
                 }
 
-                let trailing_stop_price = pos.highest_price - stop_dist;
+                // --- PROFIT-LOCK ---
+                // Ступень засчитывается по текущей прибыли и дальше не теряется: откат цены
+                // трейлинг не расширяет
+                let profit = match pos.side {
+                    PositionSide::Long => (tick.price - pos.entry_price) / pos.entry_price,
+                    PositionSide::Short => (pos.entry_price - tick.price) / pos.entry_price,
+                };
+                let reached = self
+                    .trail_tiers
+                    .iter()
+                    .take_while(|tier| profit >= tier.profit_pct)
+                    .count();
+                if reached > pos.trail_tier {
+                    pos.trail_tier = reached;
+                    state_changed = true;
+                    info!(
+                        symbol = %tick.symbol,
+                        side = ?pos.side,
+                        price = %tick.price,
+                        profit_pct = %(profit * Decimal::from(100)).round_dp(3),
+                        tier = reached,
+                        "🔒 Trailing stop tightened"
+                    );
+                }
+                let reached_tiers = &self.trail_tiers[..pos.trail_tier.min(self.trail_tiers.len())];
+                if let Some(callback) = reached_tiers.iter().map(|tier| tier.callback).min() {
+                    stop_dist = stop_dist.min(tick.price * callback);
+                }
+
+                // Шорт — зеркально: стоп над минимумом, выход при росте
+                let hard_stop_pct = self.hard_stop_pct;
+                let (trailing_stop_price, trailing_hit, hard_stop, hard_hit) = match pos.side {
+                    PositionSide::Long => {
+                        let trailing = pos.highest_price - stop_dist;
+                        let hard = pos.entry_price * (Decimal::ONE - hard_stop_pct);
+                        (trailing, tick.price < trailing, hard, tick.price < hard)
+                    }
+                    PositionSide::Short => {
+                        let trailing = pos.highest_price + stop_dist;
+                        let hard = pos.entry_price * (Decimal::ONE + hard_stop_pct);
+                        (trailing, tick.price > trailing, hard, tick.price > hard)
+                    }
+                };
+                // До активации выход только по жёсткому стопу
+                let trailing_hit = trailing_hit && pos.armed;
 
-                if tick.price < trailing_stop_price {
+                // Трейлинг ждёт min_hold; жёсткий стоп ниже — нет, иначе убыток не ограничен.
+                // opened_at = 0 (позиция из старого файла состояния) считаем давно открытой
+                let held_ms = tick.timestamp.saturating_sub(pos.opened_at);
+                if trailing_hit && held_ms < self.min_hold_ms {
+                    debug!(
+                        held_ms,
+                        min_hold_ms = self.min_hold_ms,
+                        "Trailing stop deferred: minimum hold not reached"
+                    );
+                } else if trailing_hit {
                     info!(
-                        "🛡️ DYNAMIC TRAILING: Price {} < Stop {} (High {} - Dist {})",
-                        tick.price, trailing_stop_price, pos.highest_price, stop_dist
+                        symbol = %tick.symbol,
+                        side = ?pos.side,
+                        price = %tick.price,
+                        stop = %trailing_stop_price,
+                        best = %pos.highest_price,
+                        stop_dist = %stop_dist,
+                        "🛡️ DYNAMIC TRAILING STOP"
                     );
-                    return Ok(Signal::Advice(Side::Sell, tick.price));
+                    self.last_stop_at = Some(tick.timestamp);
+                    return Ok(Signal::Advice(pos.side.exit_side(), tick.price));
                 }
 
-                let hard_stop = pos.entry_price * Decimal::from_str("0.99").unwrap();
-                if tick.price < hard_stop {
-                    info!("🛑 HARD STOP LOSS");
-                    return Ok(Signal::Advice(Side::Sell, tick.price));
+                if hard_hit {
+                    info!(
+                        symbol = %tick.symbol,
+                        side = ?pos.side,
+                        price = %tick.price,
+                        entry = %pos.entry_price,
+                        stop = %hard_stop,
+                        "🛑 HARD STOP LOSS"
+                    );
+                    self.last_stop_at = Some(tick.timestamp);
+                    return Ok(Signal::Advice(pos.side.exit_side(), tick.price));
+                }
+
+                // --- ЧАСТИЧНАЯ ФИКСАЦИЯ ПРИБЫЛИ ---
+                // Уровень отмечается сработавшим сразу: повторно по нему не выходим, даже если
+                // ордер не прошёл — остаток всё равно ведут трейлинг и жёсткий стоп.
+                // Как и трейлинг, ждёт min_hold: раньше него выходит только жёсткий стоп
+                let next_level = self
+                    .take_profit_levels
+                    .get(pos.tp_levels_hit)
+                    .copied()
+                    .filter(|_| held_ms >= self.min_hold_ms);
+                if let Some(level) = next_level {
+                    let target = match pos.side {
+                        PositionSide::Long => pos.entry_price * (Decimal::ONE + level.pct),
+                        PositionSide::Short => pos.entry_price * (Decimal::ONE - level.pct),
+                    };
+                    if !improves(pos.side, target, tick.price) {
+                        pos.tp_levels_hit += 1;
+                        info!(
+                            symbol = %tick.symbol,
+                            side = ?pos.side,
+                            price = %tick.price,
+                            target = %target,
+                            level = pos.tp_levels_hit,
+                            close_fraction = %level.close_fraction,
+                            "💰 TAKE PROFIT LEVEL"
+                        );
+                        return Ok(Signal::PartialExit(
+                            pos.side.exit_side(),
+                            tick.price,
+                            level.close_fraction,
+                        ));
+                    }
                 }
 
                 if state_changed {
@@ -240,10 +757,458 @@ impl Strategy for RsiBollingerStrategy {
     }
 
     fn update_position(&mut self, position: Option<Position>) {
+        if self.position.is_some() && position.is_none() {
+            self.last_exit_at = Some(self.last_tick_at);
+        }
         self.position = position;
     }
 
     fn get_position(&self) -> Option<Position> {
         self.position.clone()
     }
+
+    async fn switch_symbol(&mut self, symbol: &str) -> Result<()> {
+        // Свечи и индикаторы старого символа новому не подходят: прогрев с нуля (и из истории)
+        self.symbol = symbol.to_string();
+        self.indicators = IndicatorBundle::new(&self.indicator_config);
+        self.current_candle = None;
+        self.position = None;
+        self.last_seeded_open_time = None;
+        self.last_exit_at = None;
+        self.last_stop_at = None;
+        self.last_obi = Decimal::ZERO;
+        self.init().await
+    }
+
+    fn reconfigure(&mut self, config: StrategyConfig) -> Result<()> {
+        // Периоды определяют состояние индикаторов: смена на ходу = пересчёт с нуля
+        let current = &self.indicator_config;
+        if config.rsi_period != current.rsi_period
+            || config.bb_period != current.bb_period
+            || config.bb_std_dev != current.bb_std_dev
+            || config.macd_filter != current.macd_filter
+            || config.macd_fast != current.macd_fast
+            || config.macd_slow != current.macd_slow
+            || config.macd_signal != current.macd_signal
+            || config.vwap_entry != current.vwap_entry
+            || config.vwap_period != current.vwap_period
+            || config.divergence_filter != current.divergence_filter
+            || config.divergence_pivot != current.divergence_pivot
+            || config.divergence_lookback != current.divergence_lookback
+        {
+            bail!(
+                "RSI/BB/MACD/VWAP/divergence parameters changed: they invalidate indicator state, restart required"
+            );
+        }
+
+        self.obi_threshold = Decimal::from_f64(config.obi_threshold).unwrap_or(Decimal::ZERO);
+        self.vwap_entry_std_dev = config.vwap_entry_std_dev;
+        self.min_volatility = config.min_volatility.to_f64().unwrap_or(0.003);
+        self.atr_multiplier = Decimal::from_f64(config.atr_multiplier).unwrap_or(Decimal::from(2));
+        self.trailing_callback = config.trailing_callback;
+        self.trail_activation_pct = config.trail_activation_pct;
+        self.take_profit_levels = config.take_profit_levels.clone();
+        self.trail_tiers = config.trail_tiers.clone();
+        self.hard_stop_pct = config.hard_stop_pct;
+        self.trail_on_candle_close = config.trail_on_candle_close;
+        self.max_tick_age_ms = config.max_tick_age_ms;
+        self.trade_direction = config.trade_direction;
+        self.min_hold_ms = config.min_hold_seconds * 1000;
+        self.reentry_cooldown_ms = config.reentry_cooldown_seconds * 1000;
+        self.post_stop_cooldown_ms = config.post_stop_cooldown_seconds * 1000;
+        info!(
+            obi_threshold = %self.obi_threshold,
+            min_volatility = self.min_volatility,
+            atr_multiplier = %self.atr_multiplier,
+            trailing_callback = %self.trailing_callback,
+            trail_activation_pct = %self.trail_activation_pct,
+            take_profit_levels = self.take_profit_levels.len(),
+            trail_tiers = self.trail_tiers.len(),
+            hard_stop_pct = %self.hard_stop_pct,
+            trade_direction = ?self.trade_direction,
+            min_hold_ms = self.min_hold_ms,
+            reentry_cooldown_ms = self.reentry_cooldown_ms,
+            post_stop_cooldown_ms = self.post_stop_cooldown_ms,
+            "🔄 Scalper parameters updated"
+        );
+        self.indicator_config = config;
+        Ok(())
+    }
+
+    fn snapshot(&self) -> StrategySnapshot {
+        let values = self.indicators.values();
+        let mut indicators = BTreeMap::from([
+            ("RSI".to_string(), format!("{:.1}", values.rsi)),
+            ("OBI".to_string(), format!("{:.2}", self.last_obi)),
+            ("ATR".to_string(), format!("{:.2}", values.atr)),
+        ]);
+        if let Some((lower, average, upper)) = values.bb {
+            indicators.insert(
+                "BB".to_string(),
+                format!("{:.2} / {:.2} / {:.2}", lower, average, upper),
+            );
+        }
+        if let Some(histogram) = values.macd_histogram {
+            indicators.insert("MACD hist".to_string(), format!("{:.4}", histogram));
+        }
+        if let Some((vwap, std_dev)) = values.vwap {
+            indicators.insert("VWAP".to_string(), format!("{:.2} ± {:.2}", vwap, std_dev));
+        }
+        if self.indicators.divergence_enabled() {
+            let divergence = match values.divergence {
+                Some(Divergence::Bullish) => "bullish",
+                Some(Divergence::Bearish) => "bearish",
+                None => "-",
+            };
+            indicators.insert("Divergence".to_string(), divergence.to_string());
+        }
+        StrategySnapshot {
+            indicators,
+            warmup: (!self.indicators.is_stable()).then(|| self.indicators.warmup_progress()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    use crate::utils::clock::SimulatedClock;
+    use serde_json::json;
+
+    fn strategy_config() -> StrategyConfig {
+        serde_json::from_value(json!({
+            "rsi_period": 14,
+            "obi_threshold": 0.3,
+            "bb_period": 20,
+            "bb_std_dev": 2.0,
+            "min_volatility": "0",
+            "atr_multiplier": 2.0,
+        }))
+        .unwrap()
+    }
+
+    fn kline(open_time: u64, price: Decimal) -> Kline {
+        Kline {
+            open_time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Decimal::ONE,
+            close_time: open_time + CANDLE_MS - 1,
+        }
+    }
+
+    fn tick(timestamp: u64, price: Decimal) -> Ticker {
+        Ticker {
+            symbol: "BTCUSDT".to_string(),
+            price,
+            bid_price: price,
+            ask_price: price,
+            bid_qty: Decimal::ONE,
+            ask_qty: Decimal::ONE,
+            timestamp,
+        }
+    }
+
+    // Свечей прогрева в тестах: с запасом больше warmup_candles
+    const WARMUP_CANDLES: u64 = 60;
+    // Первая минута после прогрева
+    const LIVE: u64 = WARMUP_CANDLES * CANDLE_MS;
+
+    /// Прогрев по тику на свечу: цена от 100 меняется на step * 0.1 за свечу
+    async fn warmed_up(config: StrategyConfig, step: i64) -> RsiBollingerStrategy {
+        let mut strategy = RsiBollingerStrategy::new("BTCUSDT".to_string(), config);
+        for i in 0..WARMUP_CANDLES {
+            let price = Decimal::new(1000 + step * i as i64, 1);
+            strategy.on_tick(&tick(i * CANDLE_MS, price)).await.unwrap();
+        }
+        assert!(strategy.indicators.is_stable());
+        strategy
+    }
+
+    fn long_position(entry: Decimal) -> Position {
+        Position {
+            symbol: "BTCUSDT".to_string(),
+            side: PositionSide::Long,
+            quantity: Decimal::ONE,
+            entry_price: entry,
+            unrealized_pnl: Decimal::ZERO,
+            highest_price: entry,
+            opened_at: 0,
+            armed: true,
+            tp_levels_hit: 0,
+            trail_tier: 0,
+        }
+    }
+
+    fn best_price(strategy: &RsiBollingerStrategy) -> Decimal {
+        strategy.get_position().unwrap().highest_price
+    }
+
+    // История с биржи: отдаёт свечи как есть, вместе с формирующейся
+    struct StubHistory(Vec<Kline>);
+
+    #[async_trait]
+    impl HistoryProvider for StubHistory {
+        async fn fetch_klines(
+            &self,
+            _symbol: &str,
+            _interval: &str,
+            _limit: usize,
+        ) -> crate::utils::error::Result<Vec<Kline>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn history_to_live_seam_has_no_duplicate_or_skipped_candle() {
+        let live = 100 * CANDLE_MS;
+        // Свечи 0..=100: последняя ещё формируется, 99-я пришла дважды
+        let mut klines: Vec<Kline> = (0..=100)
+            .map(|i| kline(i * CANDLE_MS, Decimal::from(100 + i % 7)))
+            .collect();
+        klines.push(kline(99 * CANDLE_MS, Decimal::from(100)));
+
+        let clock = Arc::new(SimulatedClock::default());
+        clock.observe(live + 30_000);
+        let mut config = strategy_config();
+        config.seed_from_history = true;
+        let mut strategy = RsiBollingerStrategy::new("BTCUSDT".to_string(), config)
+            .with_history(Box::new(StubHistory(klines)))
+            .with_clock(clock);
+        strategy.init().await.unwrap();
+
+        // Приняты только закрытые 0..=99, каждая один раз
+        assert_eq!(strategy.indicators.warmup_progress().0, 100);
+        assert_eq!(strategy.last_seeded_open_time, Some(99 * CANDLE_MS));
+
+        // Запоздавший тик последней исторической свечи не открывает её заново
+        strategy
+            .on_tick(&tick(live - 1_000, Decimal::from(101)))
+            .await
+            .unwrap();
+        assert!(strategy.current_candle.is_none());
+        assert_eq!(strategy.indicators.warmup_progress().0, 100);
+
+        // Первый живой тик начинает свечу сразу за историей
+        strategy
+            .on_tick(&tick(live + 30_000, Decimal::from(102)))
+            .await
+            .unwrap();
+        assert_eq!(
+            strategy.current_candle.as_ref().map(|c| c.open_time),
+            Some(live)
+        );
+        assert_eq!(strategy.indicators.warmup_progress().0, 100);
+
+        // Следующая минута закрывает её: ровно одна новая свеча
+        strategy
+            .on_tick(&tick(live + CANDLE_MS, Decimal::from(103)))
+            .await
+            .unwrap();
+        assert_eq!(strategy.indicators.warmup_progress().0, 101);
+        assert_eq!(
+            strategy.current_candle.as_ref().map(|c| c.open_time),
+            Some(live + CANDLE_MS)
+        );
+    }
+
+    #[tokio::test]
+    async fn intra_candle_wick_does_not_advance_trailing_in_candle_close_mode() {
+        let mut config = strategy_config();
+        config.trail_on_candle_close = true;
+        let mut strategy = warmed_up(config, -1).await;
+        let entry = Decimal::from(100);
+        strategy.update_position(Some(long_position(entry)));
+
+        for (offset, price) in [(1_000, "100.2"), (2_000, "103"), (3_000, "100.3")] {
+            let signal = strategy
+                .on_tick(&tick(LIVE + offset, price.parse().unwrap()))
+                .await
+                .unwrap();
+            assert!(!matches!(signal, Signal::Advice(..)));
+        }
+        // Тень до 103 внутри свечи опорную цену не сдвинула
+        assert_eq!(best_price(&strategy), entry);
+
+        // Закрытие свечи сдвигает её до close, а не до максимума тени
+        strategy
+            .on_tick(&tick(LIVE + CANDLE_MS, "100.3".parse().unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(best_price(&strategy), "100.3".parse().unwrap());
+    }
+
+    /// Тик с заданным стаканом: bid_qty > ask_qty даёт положительный OBI
+    fn book_tick(timestamp: u64, price: Decimal, bid_qty: i64, ask_qty: i64) -> Ticker {
+        Ticker {
+            bid_qty: Decimal::from(bid_qty),
+            ask_qty: Decimal::from(ask_qty),
+            ..tick(timestamp, price)
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_tick_suppresses_obi_entry() {
+        let mut config = strategy_config();
+        config.max_tick_age_ms = Some(1_000);
+        // Цена падает весь прогрев: RSI перепродан, свежий тик ниже нижней полосы — вход в лонг
+        let oversold = book_tick(LIVE, Decimal::from(90), 10, 1);
+
+        let clock = Arc::new(SimulatedClock::default());
+        clock.observe(LIVE + 500);
+        let mut fresh = warmed_up(config.clone(), -1).await.with_clock(clock);
+        assert_eq!(
+            fresh.on_tick(&oversold).await.unwrap(),
+            Signal::Advice(Side::Buy, Decimal::from(90))
+        );
+
+        // Тот же тик, но движок отстал на 5 секунд: стакан уже другой, входа нет
+        let clock = Arc::new(SimulatedClock::default());
+        clock.observe(LIVE + 5_000);
+        let mut lagging = warmed_up(config, -1).await.with_clock(clock);
+        assert_eq!(lagging.on_tick(&oversold).await.unwrap(), Signal::Hold);
+    }
+
+    #[tokio::test]
+    async fn trade_direction_decides_which_entries_fire() {
+        let long_price = Decimal::from(90);
+        let short_price = Decimal::from(115);
+        // Перепроданность с покупателями в стакане и перекупленность с продавцами
+        let oversold = book_tick(LIVE, long_price, 10, 1);
+        let overbought = book_tick(LIVE, short_price, 1, 10);
+        // (режим, вход по перепроданности, вход по перекупленности)
+        let cases = [
+            (
+                TradeDirection::LongOnly,
+                Signal::Advice(Side::Buy, long_price),
+                Signal::Hold,
+            ),
+            (
+                TradeDirection::ShortOnly,
+                Signal::Hold,
+                Signal::Advice(Side::Sell, short_price),
+            ),
+            (
+                TradeDirection::Both,
+                Signal::Advice(Side::Buy, long_price),
+                Signal::Advice(Side::Sell, short_price),
+            ),
+        ];
+        for (direction, on_oversold, on_overbought) in cases {
+            let mut config = strategy_config();
+            config.trade_direction = direction;
+
+            let mut falling = warmed_up(config.clone(), -1).await;
+            assert_eq!(
+                falling.on_tick(&oversold).await.unwrap(),
+                on_oversold,
+                "{:?}",
+                direction
+            );
+            let mut rising = warmed_up(config, 1).await;
+            assert_eq!(
+                rising.on_tick(&overbought).await.unwrap(),
+                on_overbought,
+                "{:?}",
+                direction
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn short_position_exits_with_a_buy_when_price_turns_up() {
+        let mut strategy = warmed_up(strategy_config(), 1).await;
+        strategy.update_position(Some(Position {
+            side: PositionSide::Short,
+            ..long_position(Decimal::from(100))
+        }));
+
+        // Для шорта лучшая цена — минимум
+        strategy
+            .on_tick(&tick(LIVE, Decimal::from(95)))
+            .await
+            .unwrap();
+        assert_eq!(best_price(&strategy), Decimal::from(95));
+
+        assert_eq!(
+            strategy
+                .on_tick(&tick(LIVE + 1_000, Decimal::from(110)))
+                .await
+                .unwrap(),
+            Signal::Advice(Side::Buy, Decimal::from(110))
+        );
+    }
+
+    /// Трейлинг только по trailing_callback (ATR выключен) и две ступени profit-lock
+    async fn tiered_strategy() -> RsiBollingerStrategy {
+        let mut config = strategy_config();
+        config.atr_multiplier = 0.0;
+        config.trailing_callback = Decimal::new(1, 2);
+        config.trail_tiers = vec![
+            TrailTier {
+                profit_pct: Decimal::new(3, 3),
+                callback: Decimal::new(5, 3),
+            },
+            TrailTier {
+                profit_pct: Decimal::new(6, 3),
+                callback: Decimal::new(2, 3),
+            },
+        ];
+        let mut strategy = warmed_up(config, 0).await;
+        strategy.update_position(Some(long_position(Decimal::from(100))));
+        strategy
+    }
+
+    fn trail_tier(strategy: &RsiBollingerStrategy) -> usize {
+        strategy.get_position().unwrap().trail_tier
+    }
+
+    fn is_exit(signal: &Signal) -> bool {
+        matches!(signal, Signal::Advice(..) | Signal::PartialExit(..))
+    }
+
+    #[tokio::test]
+    async fn trail_tiers_are_crossed_in_order_and_only_tighten() {
+        let mut strategy = tiered_strategy().await;
+        // (цена, ступень после тика): 0.2%, 0.4%, 0.7% прибыли, затем откат до 0.55%
+        let path = [
+            (Decimal::new(1002, 1), 0),
+            (Decimal::new(1004, 1), 1),
+            (Decimal::new(1007, 1), 2),
+            (Decimal::new(10055, 2), 2),
+        ];
+        for (i, (price, tier)) in path.into_iter().enumerate() {
+            let signal = strategy
+                .on_tick(&tick(LIVE + i as u64 * 1_000, price))
+                .await
+                .unwrap();
+            assert!(!is_exit(&signal), "{}: {:?}", price, signal);
+            assert_eq!(trail_tier(&strategy), tier, "{}", price);
+        }
+
+        // Откат не вернул широкий стоп первой ступени (100.7 - 0.5% = 100.2):
+        // стоп второй ступени 100.7 - 0.2% ≈ 100.5 срабатывает
+        assert_eq!(
+            strategy
+                .on_tick(&tick(LIVE + 10_000, Decimal::new(10045, 2)))
+                .await
+                .unwrap(),
+            Signal::Advice(Side::Sell, Decimal::new(10045, 2))
+        );
+    }
+
+    #[tokio::test]
+    async fn jump_past_several_tiers_reaches_all_of_them() {
+        let mut strategy = tiered_strategy().await;
+        let signal = strategy
+            .on_tick(&tick(LIVE, Decimal::new(1008, 1)))
+            .await
+            .unwrap();
+        assert!(!is_exit(&signal));
+        assert_eq!(trail_tier(&strategy), 2);
+    }
 }
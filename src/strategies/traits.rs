@@ -1,5 +1,6 @@
 // src/strategies/traits.rs
-use crate::types::{Position, Signal, Ticker};
+use crate::config::StrategyConfig;
+use crate::types::{Position, Signal, StrategySnapshot, Ticker};
 use anyhow::Result;
 use async_trait::async_trait;
 
@@ -18,4 +19,64 @@ pub trait Strategy: Send + Sync {
 
     // <--- НОВОЕ: Метод для получения текущего состояния (нужен Engine для сохранения)
     fn get_position(&self) -> Option<Position>;
+
+    // Смена символа на ходу (позиция уже закрыта движком): состояние старого символа
+    // сбрасывается, прогрев начинается заново
+    async fn switch_symbol(&mut self, symbol: &str) -> Result<()>;
+
+    // Индикаторы для TUI (любые, по имени). Поля позиции заполняет движок
+    fn snapshot(&self) -> StrategySnapshot {
+        StrategySnapshot::default()
+    }
+
+    // Докупка к открытой позиции (сетка): вход в её сторону движок не пропускает,
+    // а усредняет цену входа. Выходы по лотам — PartialExit
+    fn scales_in(&self) -> bool {
+        false
+    }
+
+    // Горячая перезагрузка параметров. Err — изменение нельзя применить на ходу, стратегия не тронута
+    fn reconfigure(&mut self, _config: StrategyConfig) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Выбор стратегии в рантайме (конфиг) без дженериков в движке
+#[async_trait]
+impl Strategy for Box<dyn Strategy> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    async fn init(&mut self) -> Result<()> {
+        (**self).init().await
+    }
+
+    async fn on_tick(&mut self, ticker: &Ticker) -> Result<Signal> {
+        (**self).on_tick(ticker).await
+    }
+
+    fn update_position(&mut self, position: Option<Position>) {
+        (**self).update_position(position)
+    }
+
+    fn get_position(&self) -> Option<Position> {
+        (**self).get_position()
+    }
+
+    async fn switch_symbol(&mut self, symbol: &str) -> Result<()> {
+        (**self).switch_symbol(symbol).await
+    }
+
+    fn snapshot(&self) -> StrategySnapshot {
+        (**self).snapshot()
+    }
+
+    fn scales_in(&self) -> bool {
+        (**self).scales_in()
+    }
+
+    fn reconfigure(&mut self, config: StrategyConfig) -> Result<()> {
+        (**self).reconfigure(config)
+    }
 }
@@ -1,8 +1,12 @@
 // src/tui/mod.rs
-use crate::types::{Signal, UiEvent};
+use crate::config::symbol_assets;
+use crate::core::ledger::TradeRecord;
+use crate::types::{EngineCommand, PositionSide, RiskSnapshot, Signal, UiEvent};
+use crate::utils::fees::{FeeModel, Liquidity};
 use anyhow::Result;
 use chrono::{DateTime, Local};
 use crossterm::{
+    cursor::Show,
     event::{self, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -11,48 +15,183 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols,
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
+    widgets::{
+        Axis, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, GraphType, List, ListItem,
+        Paragraph, Row, Table,
+    },
     Frame, Terminal,
 };
-use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use rust_decimal::Decimal;
+use std::collections::{BTreeMap, VecDeque};
 use std::io;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+// Сколько последних снапшотов хранит кривая equity
+const EQUITY_POINTS: usize = 600;
+// Сколько последних закрытых сделок хранит панель сделок (итог сессии считается по всем)
+const TRADE_HISTORY: usize = 100;
+// Гипотетические движения цены для панели what-if, в процентах
+const WHAT_IF_MOVES_PCT: [(i64, u32); 5] = [(-10, 1), (-5, 1), (5, 1), (10, 1), (20, 1)];
+
+// Состояние WS-потока котировок по событиям коннектора
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WsStatus {
+    Connected,
+    Disconnected,
+    Reconnecting,
+    Failed,
+}
+
+/// Возвращает терминал из raw-режима и alternate screen. Ошибки глотаются:
+/// вызывается из паники и при выходе, когда терминал может быть уже восстановлен
+pub fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+/// Восстанавливает терминал при любом выходе из App::run, включая ошибку `?`
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Паника в любой задаче: сначала вернуть терминал, потом напечатать сообщение —
+/// иначе оно останется на alternate screen, а shell после выхода будет в raw-режиме
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+}
+
 pub struct App {
     receiver: mpsc::Receiver<UiEvent>,
+    commands: mpsc::Sender<EngineCommand>,
     symbol: String,
     // State
     price: Decimal,
-    rsi: f64,
-    obi: Decimal,
+    // Индикаторы активной стратегии из снапшота: имя -> значение
+    indicators: BTreeMap<String, String>,
+    // Прогрев стратегии (принято свечей, нужно); None — торгует
+    warmup: Option<(usize, usize)>,
     // PnL in decimal percentage (e.g. 0.01 for 1%)
     pnl: Option<Decimal>,
+    // Реальная позиция из снапшота движка (валидна, когда pnl = Some)
+    side: PositionSide,
+    entry_price: Decimal,
+    quantity: Decimal,
+    // PnL позиции от движка: валовый минус комиссия выхода
+    unrealized_pnl: Decimal,
     logs: Vec<String>,
     active_signal: String, // "BUY", "SELL", "WAITING"
     start_time: Instant,
+    risk: RiskSnapshot,
+    // (name, enabled) стратегий движка
+    strategies: Vec<(String, bool)>,
+    fees: FeeModel,
+    // Кривая PnL сессии: (номер снапшота, PnL в quote_asset), не больше EQUITY_POINTS точек
+    equity_curve: VecDeque<(f64, f64)>,
+    equity_samples: u64,
+    show_equity: bool,
+    // Размер входа (order_size_usdt) для панели what-if
+    order_size: Decimal,
+    // quote_asset/base_asset из конфига; None — по текущему символу
+    quote_asset: Option<String>,
+    base_asset: Option<String>,
+    show_what_if: bool,
+    // None — коннектор состояние не сообщает (replay)
+    ws_status: Option<WsStatus>,
+    // Ввод нового символа ('s'); None — окно ввода закрыто
+    symbol_input: Option<String>,
+    // Закрытые сделки сессии, новые первыми; прокрутка — сколько новых пропущено сверху
+    trades: VecDeque<TradeRecord>,
+    trade_scroll: usize,
+    session_trades: usize,
+    session_trades_pnl: Decimal,
 }
 
 impl App {
-    pub fn new(receiver: mpsc::Receiver<UiEvent>, symbol: String) -> Self {
+    pub fn new(
+        receiver: mpsc::Receiver<UiEvent>,
+        commands: mpsc::Sender<EngineCommand>,
+        symbol: String,
+    ) -> Self {
         Self {
             receiver,
+            commands,
             symbol,
             price: Decimal::ZERO,
-            rsi: 50.0,
-            obi: Decimal::ZERO,
+            indicators: BTreeMap::new(),
+            warmup: None,
             pnl: None,
+            side: PositionSide::Long,
+            entry_price: Decimal::ZERO,
+            quantity: Decimal::ZERO,
+            unrealized_pnl: Decimal::ZERO,
             logs: vec![],
             active_signal: "WAITING".to_string(),
             start_time: Instant::now(),
+            risk: RiskSnapshot::default(),
+            strategies: vec![],
+            fees: FeeModel::default(),
+            equity_curve: VecDeque::with_capacity(EQUITY_POINTS),
+            equity_samples: 0,
+            show_equity: true,
+            order_size: Decimal::ZERO,
+            quote_asset: None,
+            base_asset: None,
+            show_what_if: false,
+            ws_status: None,
+            symbol_input: None,
+            trades: VecDeque::with_capacity(TRADE_HISTORY),
+            trade_scroll: 0,
+            session_trades: 0,
+            session_trades_pnl: Decimal::ZERO,
         }
     }
 
+    pub fn with_fees(mut self, fees: FeeModel) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn with_order_size(mut self, order_size_usdt: f64) -> Self {
+        self.order_size = Decimal::from_f64(order_size_usdt).unwrap_or_default();
+        self
+    }
+
+    pub fn with_assets(mut self, quote_asset: Option<String>, base_asset: Option<String>) -> Self {
+        self.quote_asset = quote_asset;
+        self.base_asset = base_asset;
+        self
+    }
+
+    /// Актив подписей PnL и размера: из конфига или по символу (меняется клавишей 's')
+    fn quote_asset(&self) -> String {
+        self.quote_asset
+            .clone()
+            .unwrap_or_else(|| symbol_assets(&self.symbol).1)
+    }
+
+    fn base_asset(&self) -> String {
+        self.base_asset
+            .clone()
+            .unwrap_or_else(|| symbol_assets(&self.symbol).0)
+    }
+
     pub async fn run(mut self) -> Result<()> {
         // Setup Terminal
+        install_panic_hook();
         enable_raw_mode()?;
+        let _guard = TerminalGuard;
         let mut stdout = io::stdout();
         execute!(stdout, EnterAlternateScreen)?;
         let backend = CrosstermBackend::new(stdout);
@@ -65,8 +204,23 @@ impl App {
             // Input (Non-blocking check)
             if event::poll(Duration::from_millis(10))? {
                 if let Event::Key(key) = event::read()? {
-                    if let KeyCode::Char('q') = key.code {
-                        break;
+                    match key.code {
+                        // Пока открыт ввод символа, все клавиши идут в него
+                        code if self.symbol_input.is_some() => self.handle_symbol_input(code),
+                        KeyCode::Char('q') => break,
+                        KeyCode::Char('d') => self.toggle_strategies(),
+                        KeyCode::Char('e') => self.show_equity = !self.show_equity,
+                        KeyCode::Char('x') => self.request_flatten(),
+                        KeyCode::Char('p') => self.toggle_pause(),
+                        KeyCode::Char('w') => self.show_what_if = !self.show_what_if,
+                        KeyCode::Char('s') => self.symbol_input = Some(String::new()),
+                        // Прокрутка таблицы сделок: вниз — к более старым
+                        KeyCode::Down => {
+                            self.trade_scroll =
+                                (self.trade_scroll + 1).min(self.trades.len().saturating_sub(1));
+                        }
+                        KeyCode::Up => self.trade_scroll = self.trade_scroll.saturating_sub(1),
+                        _ => {}
                     }
                 }
             }
@@ -82,26 +236,151 @@ impl App {
                             let msg = format!("SIGNAL: {:?} @ {}", side, price);
                             self.add_log(msg);
                         }
+                        Signal::PartialExit(side, price, fraction) => {
+                            self.active_signal = format!("{:?} PART", side).to_uppercase();
+                            let msg = format!(
+                                "SIGNAL: {:?} {}% @ {}",
+                                side,
+                                (fraction * Decimal::from(100)).normalize(),
+                                price
+                            );
+                            self.add_log(msg);
+                        }
                         Signal::StateChanged => {} // Игнорируем внутренние изменения
                         Signal::Hold => self.active_signal = "HOLD".to_string(),
                     },
+                    UiEvent::Risk(risk) => self.risk = risk,
+                    UiEvent::StrategyState { name, enabled } => {
+                        match self.strategies.iter_mut().find(|(n, _)| *n == name) {
+                            Some(entry) => entry.1 = enabled,
+                            None => self.strategies.push((name, enabled)),
+                        }
+                    }
                     UiEvent::Log(l) => self.add_log(l),
+                    UiEvent::WsConnected => self.ws_status = Some(WsStatus::Connected),
+                    UiEvent::WsDisconnected => self.ws_status = Some(WsStatus::Disconnected),
+                    UiEvent::WsReconnecting => self.ws_status = Some(WsStatus::Reconnecting),
+                    UiEvent::WsFailed => {
+                        self.ws_status = Some(WsStatus::Failed);
+                        self.add_log("Market data stream lost, engine stopped".to_string());
+                    }
+                    UiEvent::SymbolChanged(symbol) => {
+                        self.symbol = symbol;
+                        // Цена и индикаторы старого символа больше не актуальны
+                        self.price = Decimal::ZERO;
+                        self.indicators.clear();
+                        self.warmup = None;
+                    }
+                    UiEvent::TradeClosed(trade) => self.push_trade(trade),
                     UiEvent::Snapshot(snap) => {
-                        self.rsi = snap.rsi;
-                        self.obi = snap.obi;
+                        self.indicators = snap.indicators;
+                        self.warmup = snap.warmup;
                         self.pnl = snap.position_pnl;
+                        self.side = snap.side;
+                        self.entry_price = snap.entry_price;
+                        self.quantity = snap.quantity;
+                        self.unrealized_pnl = snap.unrealized_pnl;
+                        self.push_equity(snap.session_pnl);
                     }
                 }
             }
         }
 
-        // Cleanup
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-        terminal.show_cursor()?;
+        // Cleanup делает _guard: и здесь, и при выходе по `?` или панике выше
         Ok(())
     }
 
+    /// 'd': включает/выключает стратегии (выключение закрывает их позицию)
+    fn toggle_strategies(&mut self) {
+        let commands: Vec<EngineCommand> = self
+            .strategies
+            .iter()
+            .map(|(name, enabled)| {
+                if *enabled {
+                    EngineCommand::DisableStrategy(name.clone())
+                } else {
+                    EngineCommand::EnableStrategy(name.clone())
+                }
+            })
+            .collect();
+        for command in commands {
+            self.send_command(command);
+        }
+    }
+
+    /// 'x': закрыть позицию по рынку. Есть ли что закрывать, решает движок
+    fn request_flatten(&mut self) {
+        if self.send_command(EngineCommand::Flatten) {
+            self.add_log("Flatten requested".to_string());
+        }
+    }
+
+    /// Окно ввода символа: Enter — переключиться, Esc — отмена
+    fn handle_symbol_input(&mut self, code: KeyCode) {
+        let Some(input) = self.symbol_input.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) if c.is_ascii_alphanumeric() && input.len() < 20 => {
+                input.push(c.to_ascii_uppercase());
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.symbol_input = None,
+            KeyCode::Enter => {
+                let symbol = self.symbol_input.take().unwrap_or_default();
+                if symbol.is_empty() || symbol == self.symbol {
+                    return;
+                }
+                if self.send_command(EngineCommand::SwitchSymbol(symbol.clone())) {
+                    self.add_log(format!(
+                        "Switching to {} (open position will be closed)",
+                        symbol
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 'p': пауза/продолжение новых входов. Состояние приходит обратно в RiskSnapshot
+    fn toggle_pause(&mut self) {
+        self.send_command(EngineCommand::TogglePause);
+    }
+
+    fn send_command(&mut self, command: EngineCommand) -> bool {
+        let sent = self.commands.try_send(command).is_ok();
+        if !sent {
+            self.add_log("Error: engine command channel unavailable".to_string());
+        }
+        sent
+    }
+
+    fn push_equity(&mut self, session_pnl: Decimal) {
+        if self.equity_curve.len() == EQUITY_POINTS {
+            self.equity_curve.pop_front();
+        }
+        self.equity_curve.push_back((
+            self.equity_samples as f64,
+            session_pnl.to_f64().unwrap_or_default(),
+        ));
+        self.equity_samples += 1;
+    }
+
+    fn push_trade(&mut self, trade: TradeRecord) {
+        self.session_trades += 1;
+        self.session_trades_pnl += trade.net_pnl;
+        if self.trades.len() == TRADE_HISTORY {
+            self.trades.pop_back();
+        }
+        self.trades.push_front(trade);
+        // Прокрученная таблица остаётся на тех же сделках
+        if self.trade_scroll > 0 {
+            self.trade_scroll = (self.trade_scroll + 1).min(self.trades.len() - 1);
+        }
+    }
+
     fn add_log(&mut self, message: String) {
         let timestamp = Local::now().format("%H:%M:%S");
         self.logs.push(format!("[{}] {}", timestamp, message));
@@ -117,17 +396,77 @@ impl App {
             .margin(1)
             .constraints(
                 [
-                    Constraint::Length(3), // Status Bar
-                    Constraint::Min(10),   // Position Monitor (Flexible)
-                    Constraint::Length(8), // System Logs
+                    Constraint::Length(3),  // Status Bar
+                    Constraint::Min(10),    // Position Monitor (Flexible)
+                    Constraint::Length(12), // System Logs + Trades
                 ]
                 .as_ref(),
             )
             .split(f.size());
 
         self.render_status_bar(f, chunks[0]);
-        self.render_position_monitor(f, chunks[1]);
-        self.render_logs(f, chunks[2]);
+        if self.show_equity {
+            // 'e' прячет график, монитор позиции тогда занимает его место
+            let middle = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(40),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(40),
+                ])
+                .split(chunks[1]);
+            self.render_position_monitor(f, middle[0]);
+            self.render_indicators(f, middle[1]);
+            self.render_equity_curve(f, middle[2]);
+        } else {
+            let middle = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .split(chunks[1]);
+            self.render_position_monitor(f, middle[0]);
+            self.render_indicators(f, middle[1]);
+        }
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[2]);
+        self.render_logs(f, bottom[0]);
+        self.render_trades(f, bottom[1]);
+        if self.show_what_if {
+            self.render_what_if(f, chunks[1]);
+        }
+        if let Some(input) = &self.symbol_input {
+            self.render_symbol_input(f, chunks[1], input);
+        }
+    }
+
+    /// Окно ввода нового символа поверх монитора
+    fn render_symbol_input(&self, f: &mut Frame, area: Rect, input: &str) {
+        let width = area.width.min(40);
+        let height = area.height.min(4);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(Span::styled(
+                " SWITCH SYMBOL (Enter / Esc) ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        let lines = vec![
+            Line::from(format!("{} -> {}_", self.symbol, input)),
+            Line::from(Span::styled(
+                "Open position will be closed at market",
+                Style::default().fg(Color::DarkGray),
+            )),
+        ];
+        f.render_widget(Clear, popup);
+        f.render_widget(Paragraph::new(lines).block(block), popup);
     }
 
     fn render_status_bar(&self, f: &mut Frame, area: Rect) {
@@ -164,7 +503,15 @@ impl App {
         f.render_widget(title, chunks[0]);
 
         // 2. Market Status
-        let market_info = format!(" {} | ${:.2}", self.symbol, self.price);
+        let market_info = match self.risk.funding_rate {
+            Some(rate) => format!(
+                " {} | ${:.2} | Funding: {:.4}%",
+                self.symbol,
+                self.price,
+                rate * Decimal::from(100)
+            ),
+            None => format!(" {} | ${:.2}", self.symbol, self.price),
+        };
         let center_widget = Paragraph::new(Span::raw(market_info))
             .alignment(Alignment::Center)
             .block(
@@ -175,9 +522,68 @@ impl App {
             );
         f.render_widget(center_widget, chunks[1]);
 
-        // 3. System Status
-        let status = format!(" Uptime: {} ", uptime);
-        let right_widget = Paragraph::new(Span::raw(status))
+        // 3. System Status (+ дневной PnL и остаток лимита)
+        let daily = match self.risk.daily_loss_remaining {
+            Some(left) => format!("Day: {:.2} (left {:.2})", self.risk.daily_pnl, left),
+            None => format!("Day: {:.2}", self.risk.daily_pnl),
+        };
+        let disabled: Vec<&str> = self
+            .strategies
+            .iter()
+            .filter(|(_, enabled)| !enabled)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let mut status = if disabled.is_empty() {
+            format!(" {} | Uptime: {} ", daily, uptime)
+        } else {
+            format!(
+                " OFF: {} | {} | Uptime: {} ",
+                disabled.join(","),
+                daily,
+                uptime
+            )
+        };
+        if !self.risk.exposure.is_zero() {
+            status = format!(" Exp: {:.2} |{}", self.risk.exposure, status);
+        }
+        if let Some(max) = self.risk.max_positions {
+            status = format!(" Pos {}/{} |{}", self.risk.open_positions, max, status);
+        }
+        if self.risk.portfolio_halted {
+            status = format!(
+                " HALTED: DD {:.2}% |{}",
+                self.risk.drawdown * Decimal::from(100),
+                status
+            );
+        }
+        if self.risk.paused {
+            status = format!(" PAUSED (p) |{}", status);
+        }
+        if self.risk.depegged {
+            let deviation = self.risk.depeg_deviation.unwrap_or_default() * Decimal::from(100);
+            status = format!(" DEPEG {:.2}% |{}", deviation, status);
+        }
+        let status_style =
+            if self.risk.entries_blocked || self.risk.depegged || !disabled.is_empty() {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+        let mut spans = Vec::with_capacity(2);
+        if let Some(ws) = self.ws_status {
+            let (label, color) = match ws {
+                WsStatus::Connected => ("● WS", Color::Green),
+                WsStatus::Disconnected => ("● WS DOWN", Color::Red),
+                WsStatus::Reconnecting => ("● WS ...", Color::Yellow),
+                WsStatus::Failed => ("● WS FAILED", Color::Red),
+            };
+            spans.push(Span::styled(
+                label,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ));
+        }
+        spans.push(Span::styled(status, status_style));
+        let right_widget = Paragraph::new(Line::from(spans))
             .alignment(Alignment::Right)
             .block(
                 Block::default()
@@ -193,53 +599,47 @@ impl App {
             .borders(Borders::ALL)
             .border_type(BorderType::Double) // Двойная рамка для важности
             .title(Span::styled(
-                " POSITION MONITOR ",
+                " POSITION MONITOR (x: flatten) ",
                 Style::default().add_modifier(Modifier::BOLD),
             ));
 
         let inner_area = block.inner(area);
         f.render_widget(block, area);
 
-        if let Some(pnl_pct) = self.pnl {
+        if self.pnl.is_some() {
             // --- ACTIVE POSITION LOGIC ---
+            let entry_price = self.entry_price;
+            let qty = self.quantity;
+            let entry_notional = entry_price * qty;
 
-            // 1. Reverse Engineering Entry Price (Приблизительно, т.к. нет Qty в Event)
-            // PnL% = (Current - Entry) / Entry  => Entry = Current / (1 + PnL%)
-            // Для Short позиций логика инвертируется, но пока считаем как Long для простоты визуализации,
-            // либо если PnL отрицательный на росте - это шорт.
-            // *Для точности лучше добавить side в Snapshot в будущем.*
+            // 1. Calc Metrics
+            let gross_pnl = self.side.pnl(entry_price, self.price, qty);
 
-            let one = Decimal::new(1, 0);
-            let entry_price = if !pnl_pct.is_zero() {
-                self.price / (one + pnl_pct)
+            // Fees: taker на входе и выходе (оценка)
+            let total_fees = self.fees.round_trip(
+                entry_notional,
+                self.price * qty,
+                Liquidity::Taker,
+                Liquidity::Taker,
+            );
+            let round_trip_pct = self.fees.rate(Liquidity::Taker) * Decimal::new(200, 0);
+
+            // Комиссию выхода движок уже вычел из unrealized_pnl, остаётся комиссия входа
+            let net_pnl = self.unrealized_pnl - self.fees.fee(entry_notional, Liquidity::Taker);
+            let net_pnl_pct = if entry_notional.is_zero() {
+                Decimal::ZERO
             } else {
-                self.price
+                net_pnl / entry_notional * Decimal::new(100, 0)
             };
 
-            // 2. Estimating Logic (Hardcoded 10 USDT Order Size for visualization purpose)
-            let estimated_balance = Decimal::new(10, 0);
-            let qty = estimated_balance / entry_price;
-
-            // 3. Calc Metrics
-            let gross_pnl = (self.price - entry_price) * qty;
-
-            // Fees: 0.05% Entry + 0.05% Exit (Taker)
-            let fee_rate = Decimal::from_f64_retain(0.0005).unwrap_or(Decimal::ZERO);
-            let entry_fee = entry_price * qty * fee_rate;
-            let exit_fee = self.price * qty * fee_rate;
-            let total_fees = entry_fee + exit_fee;
-
-            let net_pnl = gross_pnl - total_fees;
-            let net_pnl_pct = net_pnl / estimated_balance * Decimal::new(100, 0);
-
-            // 4. Styling
+            // 2. Styling
             let pnl_color = if net_pnl >= Decimal::ZERO {
                 Color::Green
             } else {
                 Color::Red
             };
 
-            // 5. Layout for Monitor
+            // 3. Layout for Monitor
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
@@ -255,13 +655,12 @@ impl App {
                 .split(inner_area);
 
             // Row 1: Header
-            let side_str = if gross_pnl >= Decimal::ZERO {
-                "LONG (Est.)"
-            } else {
-                "SHORT (Est.)"
-            }; // Упрощение
+            let side_str = match self.side {
+                PositionSide::Long => "LONG",
+                PositionSide::Short => "SHORT",
+            };
             f.render_widget(
-                Paragraph::new(format!("{} Position: {}", side_str, self.symbol))
+                Paragraph::new(format!("{} {} {}", side_str, qty.normalize(), self.symbol))
                     .alignment(Alignment::Center)
                     .style(Style::default().add_modifier(Modifier::BOLD)),
                 chunks[1],
@@ -278,8 +677,9 @@ impl App {
             );
 
             // Row 3: Gross
+            let quote = self.quote_asset();
             f.render_widget(
-                Paragraph::new(format!("Gross PnL: {:.4} USDT", gross_pnl))
+                Paragraph::new(format!("Gross PnL: {:.4} {}", gross_pnl, quote))
                     .alignment(Alignment::Center)
                     .style(Style::default().fg(if gross_pnl >= Decimal::ZERO {
                         Color::Green
@@ -291,14 +691,19 @@ impl App {
 
             // Row 4: Fees
             f.render_widget(
-                Paragraph::new(format!("Est. Fees: -{:.4} USDT (0.1%)", total_fees))
-                    .alignment(Alignment::Center)
-                    .style(Style::default().fg(Color::Yellow)),
+                Paragraph::new(format!(
+                    "Est. Fees: -{:.4} {} ({}%)",
+                    total_fees,
+                    quote,
+                    round_trip_pct.normalize()
+                ))
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::Yellow)),
                 chunks[5],
             );
 
             // Row 5: Net PnL
-            let net_text = format!(" NET PNL: {:.4} USDT ({:.2}%) ", net_pnl, net_pnl_pct);
+            let net_text = format!(" NET PNL: {:.4} {} ({:.2}%) ", net_pnl, quote, net_pnl_pct);
             f.render_widget(
                 Paragraph::new(net_text).alignment(Alignment::Center).style(
                     Style::default()
@@ -319,8 +724,7 @@ impl App {
                 ])
                 .split(inner_area);
 
-            let status_text = format!("WAITING FOR SIGNAL | RSI: {:.1}", self.rsi);
-            let p = Paragraph::new(status_text)
+            let p = Paragraph::new("WAITING FOR SIGNAL")
                 .alignment(Alignment::Center)
                 .style(Style::default().fg(Color::DarkGray));
 
@@ -328,12 +732,196 @@ impl App {
         }
     }
 
+    /// Индикаторы стратегии — какие она сама положила в снапшот
+    fn render_indicators(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(" Indicators ");
+        if self.indicators.is_empty() {
+            f.render_widget(
+                Paragraph::new("n/a")
+                    .alignment(Alignment::Center)
+                    .style(Style::default().fg(Color::DarkGray))
+                    .block(block),
+                area,
+            );
+            return;
+        }
+        let mut items: Vec<ListItem> = Vec::new();
+        // Пока индикаторы не прогреты, их значения ещё не для торговли
+        if let Some((processed, needed)) = self.warmup {
+            items.push(
+                ListItem::new(format!("warming up: {}/{}", processed, needed))
+                    .style(Style::default().fg(Color::Yellow)),
+            );
+        }
+        items.extend(
+            self.indicators
+                .iter()
+                .map(|(name, value)| ListItem::new(format!("{:<10} {}", name, value))),
+        );
+        f.render_widget(List::new(items).block(block), area);
+    }
+
+    fn render_equity_curve(&self, f: &mut Frame, area: Rect) {
+        let points: Vec<(f64, f64)> = self.equity_curve.iter().copied().collect();
+        let current = points.last().map(|(_, pnl)| *pnl).unwrap_or_default();
+        // Выше старта сессии — зелёный, ниже — красный
+        let color = if current >= 0.0 {
+            Color::Green
+        } else {
+            Color::Red
+        };
+
+        let (x_min, x_max) = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (first.0, last.0.max(first.0 + 1.0)),
+            _ => (0.0, 1.0),
+        };
+        // Ноль (старт сессии) всегда в кадре
+        let (mut y_min, mut y_max) = points.iter().fold((0.0f64, 0.0f64), |(lo, hi), (_, pnl)| {
+            (lo.min(*pnl), hi.max(*pnl))
+        });
+        if y_max - y_min < 0.01 {
+            y_min -= 0.01;
+            y_max += 0.01;
+        }
+        let baseline = [(x_min, 0.0), (x_max, 0.0)];
+
+        let datasets = vec![
+            Dataset::default()
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&baseline),
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(color))
+                .data(&points),
+        ];
+        let chart = Chart::new(datasets)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(format!(
+                        " Session PnL: {:.2} {} (e: hide) ",
+                        current,
+                        self.quote_asset()
+                    )),
+            )
+            .x_axis(Axis::default().bounds([x_min, x_max]))
+            .y_axis(
+                Axis::default()
+                    .bounds([y_min, y_max])
+                    .labels(vec![
+                        Span::raw(format!("{:.2}", y_min)),
+                        Span::raw(format!("{:.2}", y_max)),
+                    ])
+                    .style(Style::default().fg(Color::Gray)),
+            );
+        f.render_widget(chart, area);
+    }
+
+    /// Калькулятор what-if поверх монитора: вход на order_size по текущей цене и чистый PnL
+    /// (taker на входе и выходе) при выходе на WHAT_IF_MOVES_PCT. Ордеров не ставит
+    fn render_what_if(&self, f: &mut Frame, area: Rect) {
+        let width = area.width.min(60);
+        let height = area.height.min(WHAT_IF_MOVES_PCT.len() as u16 + 5);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(Span::styled(
+                " WHAT-IF (w: close) ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        f.render_widget(Clear, popup);
+
+        if self.price.is_zero() || self.order_size.is_zero() {
+            f.render_widget(
+                Paragraph::new("No price yet")
+                    .alignment(Alignment::Center)
+                    .block(block),
+                popup,
+            );
+            return;
+        }
+
+        let qty = self.order_size / self.price;
+        let pnl_span = |pnl: Decimal| {
+            let color = if pnl >= Decimal::ZERO {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            Span::styled(format!("{:>+12.4}", pnl), Style::default().fg(color))
+        };
+
+        let mut lines = vec![
+            Line::from(format!(
+                "Entry {} x {} {} ({} {})",
+                self.price,
+                qty.round_dp(6),
+                self.base_asset(),
+                self.order_size,
+                self.quote_asset()
+            )),
+            Line::from(Span::styled(
+                format!(
+                    "{:>7}  {:>14}  {:>12}  {:>12}",
+                    "Move", "Exit price", "Long PnL", "Short PnL"
+                ),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        for (num, scale) in WHAT_IF_MOVES_PCT {
+            let move_pct = Decimal::new(num, scale);
+            let exit_price = self.price * (Decimal::ONE + move_pct / Decimal::ONE_HUNDRED);
+            let net = |side| {
+                self.fees.net_pnl(
+                    side,
+                    self.price,
+                    exit_price,
+                    qty,
+                    Liquidity::Taker,
+                    Liquidity::Taker,
+                )
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!(
+                    "{:>+6}%  {:>14}  ",
+                    move_pct,
+                    exit_price.round_dp(4)
+                )),
+                pnl_span(net(PositionSide::Long)),
+                Span::raw("  "),
+                pnl_span(net(PositionSide::Short)),
+            ]));
+        }
+        f.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+
     fn render_logs(&self, f: &mut Frame, area: Rect) {
         let log_items: Vec<ListItem> = self
             .logs
             .iter()
             .rev()
             .map(|l| {
+                // Пропуски сигналов — приглушённо: их много, но они не ошибки
+                if l.contains("SKIP:") {
+                    let style = Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::DIM);
+                    return ListItem::new(Span::styled(l.clone(), style));
+                }
                 let color = if l.contains("SIGNAL") {
                     Color::Yellow
                 } else if l.contains("Error") {
@@ -349,4 +937,91 @@ impl App {
             List::new(log_items).block(Block::default().borders(Borders::TOP).title(" Logs "));
         f.render_widget(logs_list, area);
     }
+
+    /// Последние закрытые сделки (новые сверху) и итог сессии. ↑/↓ — прокрутка
+    fn render_trades(&self, f: &mut Frame, area: Rect) {
+        let pnl_style = |pnl: Decimal| {
+            Style::default().fg(if pnl >= Decimal::ZERO {
+                Color::Green
+            } else {
+                Color::Red
+            })
+        };
+        // Рамка, заголовок таблицы и строка итога
+        let visible = area.height.saturating_sub(4) as usize;
+        let scroll = self
+            .trade_scroll
+            .min(self.trades.len().saturating_sub(visible));
+        let title = if self.trades.len() > visible {
+            format!(
+                " Trades {}-{} of {} (↑/↓) ",
+                scroll + 1,
+                (scroll + visible).min(self.trades.len()),
+                self.trades.len()
+            )
+        } else {
+            " Trades ".to_string()
+        };
+
+        let mut rows: Vec<Row> = self
+            .trades
+            .iter()
+            .skip(scroll)
+            .take(visible)
+            .map(|trade| {
+                let time = DateTime::from_timestamp_millis(trade.timestamp as i64)
+                    .map(|dt| dt.with_timezone(&Local).format("%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                let side = match trade.side {
+                    PositionSide::Long => "LONG",
+                    PositionSide::Short => "SHORT",
+                };
+                let held = trade
+                    .duration_secs()
+                    .map(|secs| format!("{}m {:02}s", secs / 60, secs % 60))
+                    .unwrap_or_else(|| "n/a".to_string());
+                Row::new(vec![
+                    Cell::from(time),
+                    Cell::from(side),
+                    Cell::from(format!("{:.2}", trade.entry_price)),
+                    Cell::from(format!("{:.2}", trade.exit_price)),
+                    Cell::from(format!("{:+.4}", trade.net_pnl)).style(pnl_style(trade.net_pnl)),
+                    Cell::from(held),
+                ])
+            })
+            .collect();
+        rows.push(
+            Row::new(vec![
+                Cell::from("Session"),
+                Cell::from(format!("{} trades", self.session_trades)),
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(format!("{:+.4}", self.session_trades_pnl))
+                    .style(pnl_style(self.session_trades_pnl)),
+                Cell::from(""),
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+
+        let widths = [
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Min(7),
+        ];
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(vec!["Time", "Side", "Entry", "Exit", "PnL", "Held"])
+                    .style(Style::default().fg(Color::DarkGray)),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(title),
+            );
+        f.render_widget(table, area);
+    }
 }
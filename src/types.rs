@@ -1,7 +1,8 @@
 // src/types.rs
+use crate::core::ledger::TradeRecord;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Side {
@@ -12,7 +13,8 @@ pub enum Side {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ticker {
     pub symbol: String,
-    pub price: Decimal, // Mid-Price
+    // Mid-price (data_stream = bookTicker) или цена последней сделки (trade | both)
+    pub price: Decimal,
     pub bid_price: Decimal,
     pub ask_price: Decimal,
     pub bid_qty: Decimal,
@@ -20,42 +22,359 @@ pub struct Ticker {
     pub timestamp: u64,
 }
 
+// Закрытая историческая свеча (1m kline) для прогрева индикаторов
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Kline {
+    pub open_time: u64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    pub close_time: u64,
+}
+
+// Текущая ставка финансирования перпетуала (/fapi/v1/premiumIndex)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingInfo {
+    pub rate: Decimal,
+    pub next_funding_time: u64,
+}
+
+// Позиция по символу на бирже (/fapi/v2/positionRisk). amount знаковый: > 0 лонг, < 0 шорт
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangePosition {
+    pub amount: Decimal,
+    pub entry_price: Decimal,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Signal {
     Advice(Side, Decimal),
+    // Частичный выход: сторона, цена, доля открытой позиции (0, 1]
+    PartialExit(Side, Decimal, Decimal),
     StateChanged, // <--- НОВОЕ: Сигнал изменения внутреннего состояния
     Hold,
 }
 
+/// Направление позиции
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PositionSide {
+    #[default]
+    Long,
+    Short,
+}
+
+impl PositionSide {
+    /// Позицию открывает ордер этой стороны
+    pub fn from_entry(side: Side) -> Self {
+        match side {
+            Side::Buy => Self::Long,
+            Side::Sell => Self::Short,
+        }
+    }
+
+    pub fn entry_side(self) -> Side {
+        match self {
+            Self::Long => Side::Buy,
+            Self::Short => Side::Sell,
+        }
+    }
+
+    pub fn exit_side(self) -> Side {
+        match self {
+            Self::Long => Side::Sell,
+            Self::Short => Side::Buy,
+        }
+    }
+
+    /// Валовый PnL объёма quantity от entry до exit
+    pub fn pnl(self, entry_price: Decimal, exit_price: Decimal, quantity: Decimal) -> Decimal {
+        match self {
+            Self::Long => (exit_price - entry_price) * quantity,
+            Self::Short => (entry_price - exit_price) * quantity,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub symbol: String,
+    // Старые файлы состояния без side — это лонги
+    #[serde(default)]
+    pub side: PositionSide,
+    // Всегда положительный, направление — в side
     pub quantity: Decimal,
     pub entry_price: Decimal,
     pub unrealized_pnl: Decimal,
-    pub highest_price: Decimal, // Для Trailing Stop
+    // Для Trailing Stop: лучшая цена с момента входа (максимум у лонга, минимум у шорта)
+    pub highest_price: Decimal,
+    // Время открытия (мс, по времени тика) для min_hold_seconds. 0 — неизвестно (старый файл)
+    #[serde(default)]
+    pub opened_at: u64,
+    // Трейлинг взведён (цена дошла до trail_activation_pct). Раз взведённый, не сбрасывается.
+    // В старых файлах состояния поля нет: тогда трейлинг работал с входа
+    #[serde(default = "default_armed")]
+    pub armed: bool,
+    // Сколько уровней take_profit_levels уже сработало
+    #[serde(default)]
+    pub tp_levels_hit: usize,
+    // Сколько ступеней trail_tiers достигнуто. Только растёт: трейлинг не расширяется
+    #[serde(default)]
+    pub trail_tier: usize,
+}
+
+fn default_armed() -> bool {
+    true
 }
 
-#[derive(Debug, Default, Clone)]
+impl Position {
+    /// Переоценка по текущей цене: валовый PnL минус оценка комиссии выхода
+    pub fn mark_to_market(&mut self, price: Decimal, exit_fee: Decimal) {
+        self.unrealized_pnl = self.side.pnl(self.entry_price, price, self.quantity) - exit_fee;
+    }
+}
+
+/// Портфель движка: свободный баланс в квотируемой валюте и открытые позиции по символам.
+/// Целиком пишется в файл состояния
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Inventory {
+    #[serde(default)]
     pub quote_balance: Decimal,
+    #[serde(default)]
     pub positions: HashMap<String, Position>,
 }
 
+impl Inventory {
+    /// None — позиция по символу закрыта
+    pub fn set_position(&mut self, symbol: &str, position: Option<Position>) {
+        match position {
+            Some(pos) => {
+                self.positions.insert(symbol.to_string(), pos);
+            }
+            None => {
+                self.positions.remove(symbol);
+            }
+        }
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<Position> {
+        self.positions.get(symbol).cloned()
+    }
+
+    pub fn open_positions(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Суммарный нотионал открытых позиций по цене входа
+    pub fn exposure(&self) -> Decimal {
+        self.positions
+            .values()
+            .map(|pos| pos.quantity * pos.entry_price)
+            .sum()
+    }
+
+    /// Нереализованный PnL всех позиций (каждая переоценена по своему последнему тику)
+    pub fn unrealized_pnl(&self) -> Decimal {
+        self.positions.values().map(|pos| pos.unrealized_pnl).sum()
+    }
+
+    /// Чистая направленная экспозиция по символам: лонги плюс, шорты минус (нотионал входа)
+    pub fn net_exposure(&self, symbols: &[String]) -> Decimal {
+        self.positions
+            .iter()
+            .filter(|(symbol, _)| symbols.contains(symbol))
+            .map(|(_, pos)| {
+                let notional = pos.quantity * pos.entry_price;
+                match pos.side {
+                    PositionSide::Long => notional,
+                    PositionSide::Short => -notional,
+                }
+            })
+            .sum()
+    }
+
+    /// Новый вход по `symbol` упрётся в лимит (докупка в открытую позицию лимит не меняет)
+    pub fn at_limit(&self, symbol: &str, max_positions: Option<usize>) -> bool {
+        max_positions
+            .is_some_and(|max| !self.positions.contains_key(symbol) && self.open_positions() >= max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    Gtc,
+    Ioc,
+    Fok,
+    // Post-only (Good Till Crossing)
+    Gtx,
+}
+
+impl TimeInForce {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Gtc => "GTC",
+            Self::Ioc => "IOC",
+            Self::Fok => "FOK",
+            Self::Gtx => "GTX",
+        }
+    }
+}
+
+/// Тип ордера. Стоп и тейк-профит — рыночные ордера, которые биржа выставит,
+/// когда цена дойдёт до trigger (против позиции и в сторону прибыли соответственно)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderType {
+    Market,
+    Limit {
+        price: Decimal,
+        time_in_force: TimeInForce,
+    },
+    // Движок пока сам их не выставляет: это основа для стопов и тейков на стороне биржи
+    #[allow(dead_code)]
+    StopMarket {
+        trigger: Decimal,
+    },
+    #[allow(dead_code)]
+    TakeProfitMarket {
+        trigger: Decimal,
+    },
+}
+
+impl OrderType {
+    /// Цена лимитного ордера. None — исполнение по рынку
+    pub fn limit_price(self) -> Option<Decimal> {
+        match self {
+            Self::Limit { price, .. } => Some(price),
+            _ => None,
+        }
+    }
+}
+
+/// Параметры ордера для ExecutionHandler
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: Decimal,
+    pub order_type: OrderType,
+    // Только уменьшает позицию: биржа отклонит ордер, который открыл бы обратную
+    pub reduce_only: bool,
+    // Наш id ордера (newClientOrderId / clOrdId): один на задуманный ордер,
+    // повтор запроса с тем же id биржа не примет как новый ордер
+    pub client_order_id: String,
+}
+
+/// Статус ордера на бирже
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    // Ордер стоит в стакане (GTC/GTX), исполнения ещё нет
+    New,
+    Accepted,
+    PartiallyFilled,
+    Filled,
+    // Снят или истёк без исполнения (IOC/FOK/GTX не прошёл)
+    Canceled,
+    Expired,
+    ExpiredInMatch,
+}
+
+impl OrderStatus {
+    /// None — статус, который бот не умеет обрабатывать (в т.ч. REJECTED)
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "NEW" => Some(Self::New),
+            "ACCEPTED" => Some(Self::Accepted),
+            "PARTIALLY_FILLED" => Some(Self::PartiallyFilled),
+            "FILLED" => Some(Self::Filled),
+            "CANCELED" => Some(Self::Canceled),
+            "EXPIRED" => Some(Self::Expired),
+            "EXPIRED_IN_MATCH" => Some(Self::ExpiredInMatch),
+            _ => None,
+        }
+    }
+
+    /// Ордер стоит в стакане и может исполниться позже
+    pub fn is_resting(self) -> bool {
+        matches!(self, Self::New | Self::Accepted)
+    }
+
+    /// Хотя бы часть объёма исполнена
+    pub fn is_filled(self) -> bool {
+        matches!(self, Self::Filled | Self::PartiallyFilled)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderResponse {
     pub id: String,
+    #[serde(default)]
+    pub client_order_id: String,
     pub symbol: String,
-    pub status: String,
+    pub status: OrderStatus,
+    // Исполненный объём (0 для висящего или истёкшего ордера)
+    pub executed_qty: Decimal,
+    // Средняя цена исполненной части. None — ничего не исполнено или биржа её не вернула
+    #[serde(default)]
+    pub avg_price: Option<Decimal>,
+}
+
+/// Лимитный ордер, который ещё стоит в стакане
+#[derive(Debug, Clone)]
+pub struct PendingOrder {
+    pub id: String,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub placed_at: u64,
+    // Сколько раз вход уже перевыставлялся по таймауту
+    pub reprices: u32,
 }
 
 // --- Новые структуры для TUI ---
 
 #[derive(Debug, Clone, Default)]
 pub struct StrategySnapshot {
-    pub rsi: f64,
-    pub obi: Decimal,
+    // Индикаторы стратегии по имени, уже отформатированные для TUI ("RSI" -> "28.4").
+    // BTreeMap: порядок строк в панели не скачет между снапшотами
+    pub indicators: BTreeMap<String, String>,
+    // Прогрев индикаторов: (принято свечей, нужно). None — прогрев завершён или не нужен
+    pub warmup: Option<(usize, usize)>,
+    // None — позиции нет, остальные поля позиции тогда не заполнены
     pub position_pnl: Option<Decimal>,
+    pub side: PositionSide,
+    pub entry_price: Decimal,
+    pub quantity: Decimal,
+    // Нереализованный PnL позиции за вычетом комиссии выхода (Position.unrealized_pnl)
+    pub unrealized_pnl: Decimal,
+    // PnL сессии: реализованный (за вычетом комиссий) + нереализованный всех позиций инвентаря
+    pub session_pnl: Decimal,
+}
+
+// Состояние риск-контроля движка
+#[derive(Debug, Clone, Default)]
+pub struct RiskSnapshot {
+    pub daily_pnl: Decimal,
+    // None — дневной лимит не задан
+    pub daily_loss_remaining: Option<Decimal>,
+    pub entries_blocked: bool,
+    pub funding_rate: Option<Decimal>,
+    // Отклонение эталонного стейблкоина от 1.0 (None — не отслеживается)
+    pub depeg_deviation: Option<Decimal>,
+    pub depegged: bool,
+    // Аварийный стоп портфеля сработал: просадка от пика сессии на момент снимка
+    pub portfolio_halted: bool,
+    pub drawdown: Decimal,
+    // Входы на ручной паузе ('p')
+    pub paused: bool,
+    // Открытые позиции по всем символам и лимит risk.max_concurrent_positions
+    pub open_positions: usize,
+    pub max_positions: Option<usize>,
+    // Нотионал открытых позиций по цене входа
+    pub exposure: Decimal,
 }
 
 #[derive(Debug, Clone)]
@@ -63,5 +382,115 @@ pub enum UiEvent {
     TickerUpdate(Ticker),
     Signal(Signal),
     Snapshot(StrategySnapshot),
+    Risk(RiskSnapshot),
+    StrategyState { name: String, enabled: bool },
     Log(String),
+    // Состояние WS-потока котировок (из цикла переподключения коннектора)
+    WsConnected,
+    WsDisconnected,
+    WsReconnecting,
+    // Попытки переподключения исчерпаны (max_reconnect_attempts), поток брошен
+    WsFailed,
+    // Движок переключился на другой символ
+    SymbolChanged(String),
+    // Закрытая сделка (та же запись, что уходит в trades.jsonl)
+    TradeClosed(TradeRecord),
+}
+
+// Торговые события движка -> уведомления (broadcast: медленный подписчик не тормозит торговлю)
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    Entry {
+        symbol: String,
+        side: PositionSide,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    // Закрытие (или частичное закрытие); pnl — чистый, за вычетом комиссий.
+    // opened_at/closed_at — мс по времени тиков (opened_at = 0 — неизвестно)
+    Exit {
+        symbol: String,
+        side: PositionSide,
+        entry_price: Decimal,
+        price: Decimal,
+        quantity: Decimal,
+        pnl: Decimal,
+        opened_at: u64,
+        closed_at: u64,
+    },
+    // Kill-switch: входы остановлены (дневной лимит, просадка, депег, маржа, dead man's switch)
+    Halt {
+        reason: String,
+    },
+}
+
+// События аккаунта из user-data stream биржи -> движок
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountEvent {
+    // Позиция по символу на бирже. amount знаковый: > 0 лонг, < 0 шорт, 0 — нет позиции
+    PositionUpdate {
+        symbol: String,
+        amount: Decimal,
+        entry_price: Decimal,
+    },
+    OrderUpdate {
+        symbol: String,
+        order_id: String,
+        client_order_id: String,
+        side: Side,
+        status: OrderStatus,
+        filled_qty: Decimal,
+        avg_price: Decimal,
+    },
+}
+
+// Команды оператора (TUI) -> движок
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineCommand {
+    // id = Strategy::name()
+    EnableStrategy(String),
+    DisableStrategy(String),
+    // Ручное закрытие позиции по рынку (reduce-only), независимо от стратегии
+    Flatten,
+    // Пауза новых входов; выходы (трейлинг, стопы) продолжают работать
+    TogglePause,
+    // Смена торгуемого символа: позиция закрывается, поток и прогрев начинаются заново
+    SwitchSymbol(String),
+    // От коннектора: поток котировок брошен после max_reconnect_attempts. Движок останавливается
+    StreamFailed(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_status_parses_every_known_binance_status() {
+        let cases = [
+            ("NEW", OrderStatus::New),
+            ("ACCEPTED", OrderStatus::Accepted),
+            ("PARTIALLY_FILLED", OrderStatus::PartiallyFilled),
+            ("FILLED", OrderStatus::Filled),
+            ("CANCELED", OrderStatus::Canceled),
+            ("EXPIRED", OrderStatus::Expired),
+            ("EXPIRED_IN_MATCH", OrderStatus::ExpiredInMatch),
+        ];
+        for (raw, status) in cases {
+            assert_eq!(OrderStatus::parse(raw), Some(status), "{}", raw);
+        }
+        // Незнакомый статус не угадываем: place_order вернёт ошибку
+        assert_eq!(OrderStatus::parse("REJECTED"), None);
+        assert_eq!(OrderStatus::parse("filled"), None);
+    }
+
+    #[test]
+    fn order_status_groups_resting_and_filled() {
+        assert!(OrderStatus::New.is_resting());
+        assert!(OrderStatus::Accepted.is_resting());
+        assert!(!OrderStatus::PartiallyFilled.is_resting());
+        assert!(OrderStatus::PartiallyFilled.is_filled());
+        assert!(OrderStatus::Filled.is_filled());
+        assert!(!OrderStatus::Expired.is_filled());
+        assert!(!OrderStatus::Canceled.is_resting());
+    }
 }
@@ -0,0 +1,45 @@
+// src/utils/clock.rs
+use chrono::{DateTime, TimeZone, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Источник "текущего" времени для движка и стратегии. В live это системные часы,
+/// в бэктесте и replay — время последнего тика, поэтому повторный прогон даёт те же решения
+pub trait Clock: Send + Sync {
+    /// Мс с эпохи
+    fn now_ms(&self) -> u64;
+
+    /// Движок сообщает время каждого тика. Системные часы его игнорируют
+    fn observe(&self, _timestamp_ms: u64) {}
+
+    fn now(&self) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(self.now_ms() as i64)
+            .single()
+            .unwrap_or_default()
+    }
+}
+
+/// Системные часы
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now_ms(&self) -> u64 {
+        Utc::now().timestamp_millis().max(0) as u64
+    }
+}
+
+/// Время по тикам: двигается только вперёд, до первого тика — эпоха
+#[derive(Debug, Default)]
+pub struct SimulatedClock {
+    now_ms: AtomicU64,
+}
+
+impl Clock for SimulatedClock {
+    fn now_ms(&self) -> u64 {
+        self.now_ms.load(Ordering::Relaxed)
+    }
+
+    fn observe(&self, timestamp_ms: u64) {
+        self.now_ms.fetch_max(timestamp_ms, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,227 @@
+// src/utils/error.rs
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Result коннекторов и движка. anyhow остаётся только на границе main (и в стратегиях)
+pub type Result<T, E = BotError> = std::result::Result<T, E>;
+
+/// Типизированные ошибки: вызывающий код (kill-switch, реконнект, повторы) решает по варианту,
+/// а не по тексту сообщения
+#[derive(Debug, Error)]
+pub enum BotError {
+    // Сеть: таймаут, обрыв, DNS. Запрос можно повторить
+    #[error("network error: {0}")]
+    Network(String),
+    // Неверные ключи, нет прав или расходятся часы (-1022/-2014/-2015, HTTP 401/403)
+    #[error("authentication failed ({code}): {msg}")]
+    Auth { code: i64, msg: String },
+    // Биржа отклонила ордер; code — код биржи (0, если его нет)
+    #[error("order rejected ({code}): {msg}")]
+    OrderRejected { code: i64, msg: String },
+    // Прочие отказы REST API
+    #[error("exchange API error {code} (HTTP {status}): {msg}")]
+    Api { status: u16, code: i64, msg: String },
+    // Превышен лимит запросов (HTTP 429/418, -1003): нужно подождать
+    #[error("rate limited (HTTP {status}): {msg}")]
+    RateLimited { status: u16, msg: String },
+    // Ответ не того формата, что ожидали
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("config error: {0}")]
+    Config(String),
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("strategy error: {0:#}")]
+    Strategy(anyhow::Error),
+}
+
+/// Известные коды отказа Binance, на которые движок реагирует отдельно
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinanceErrorKind {
+    // -2019 Margin is insufficient
+    InsufficientMargin,
+    // -4164 / -1013 notional меньше минимального
+    MinNotional,
+    // -2022 ReduceOnly Order is rejected: уменьшать уже нечего
+    ReduceOnlyRejected,
+    Other,
+}
+
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    code: i64,
+    msg: String,
+}
+
+impl BotError {
+    /// Ошибка REST API Binance из тела ответа: {"code":-2019,"msg":"Margin is insufficient."}
+    pub fn from_binance_body(status: u16, body: &str) -> Self {
+        let (code, msg) = match serde_json::from_str::<ErrorEnvelope>(body) {
+            Ok(envelope) => (envelope.code, envelope.msg),
+            // code 0 — тело не в формате Binance (прокси, HTML-страница и т.п.)
+            Err(_) => (0, body.trim().chars().take(200).collect()),
+        };
+        match (status, code) {
+            (401 | 403, _) | (_, -1022 | -2014 | -2015) => Self::Auth { code, msg },
+            (418 | 429, _) | (_, -1003) => Self::RateLimited { status, msg },
+            _ => Self::Api { status, code, msg },
+        }
+    }
+
+    /// Отказ API на запросе ордера — это отказ самого ордера. Временный сбой (5xx) остаётся
+    /// как есть: дошёл ли ордер до биржи, неизвестно
+    pub fn into_order_rejection(self) -> Self {
+        if self.is_transient() {
+            return self;
+        }
+        match self {
+            Self::Api { code, msg, .. } => Self::OrderRejected { code, msg },
+            other => other,
+        }
+    }
+
+    pub fn kind(&self) -> BinanceErrorKind {
+        let (code, msg) = match self {
+            Self::OrderRejected { code, msg } | Self::Api { code, msg, .. } => (*code, msg),
+            _ => return BinanceErrorKind::Other,
+        };
+        match code {
+            -2019 => BinanceErrorKind::InsufficientMargin,
+            -4164 => BinanceErrorKind::MinNotional,
+            -1013 if msg.contains("MIN_NOTIONAL") => BinanceErrorKind::MinNotional,
+            -2022 => BinanceErrorKind::ReduceOnlyRejected,
+            _ => BinanceErrorKind::Other,
+        }
+    }
+
+    /// Временный сбой: таймаут, обрыв соединения, 5xx. Такой запрос можно повторить,
+    /// отказ по существу (маржа, min notional, ключи, лимит запросов) — нет
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Network(_) => true,
+            Self::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+}
+
+impl From<reqwest::Error> for BotError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Self::Parse(e.to_string())
+        } else {
+            Self::Network(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for BotError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<rust_decimal::Error> for BotError {
+    fn from(e: rust_decimal::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for BotError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binance_error_body_maps_to_error_class() {
+        let margin =
+            BotError::from_binance_body(400, r#"{"code":-2019,"msg":"Margin is insufficient."}"#);
+        assert!(matches!(
+            margin,
+            BotError::Api { status: 400, code: -2019, ref msg } if msg == "Margin is insufficient."
+        ));
+
+        let auth = BotError::from_binance_body(400, r#"{"code":-2015,"msg":"Invalid API-key"}"#);
+        assert!(matches!(auth, BotError::Auth { code: -2015, .. }));
+        assert!(matches!(
+            BotError::from_binance_body(403, "Forbidden"),
+            BotError::Auth { code: 0, .. }
+        ));
+
+        let limited =
+            BotError::from_binance_body(429, r#"{"code":-1003,"msg":"Too many requests"}"#);
+        assert!(matches!(limited, BotError::RateLimited { status: 429, .. }));
+
+        // Не JSON Binance (страница прокси): код 0, текст тела как сообщение
+        let html = BotError::from_binance_body(502, "  <html>Bad Gateway</html>\n");
+        assert!(matches!(
+            html,
+            BotError::Api { status: 502, code: 0, ref msg } if msg == "<html>Bad Gateway</html>"
+        ));
+    }
+
+    #[test]
+    fn order_rejection_kinds_by_code() {
+        let rejection = |body: &str| BotError::from_binance_body(400, body).into_order_rejection();
+
+        let margin = rejection(r#"{"code":-2019,"msg":"Margin is insufficient."}"#);
+        assert!(matches!(
+            margin,
+            BotError::OrderRejected { code: -2019, .. }
+        ));
+        assert_eq!(margin.kind(), BinanceErrorKind::InsufficientMargin);
+
+        let reduce_only = rejection(r#"{"code":-2022,"msg":"ReduceOnly Order is rejected."}"#);
+        assert_eq!(reduce_only.kind(), BinanceErrorKind::ReduceOnlyRejected);
+
+        let notional =
+            rejection(r#"{"code":-4164,"msg":"Order's notional must be no smaller than 5"}"#);
+        assert_eq!(notional.kind(), BinanceErrorKind::MinNotional);
+        let filter = rejection(r#"{"code":-1013,"msg":"Filter failure: MIN_NOTIONAL"}"#);
+        assert_eq!(filter.kind(), BinanceErrorKind::MinNotional);
+        let price_filter = rejection(r#"{"code":-1013,"msg":"Filter failure: PRICE_FILTER"}"#);
+        assert_eq!(price_filter.kind(), BinanceErrorKind::Other);
+
+        // Сетевые ошибки — не отказ биржи
+        assert_eq!(
+            BotError::Network("timeout".to_string()).kind(),
+            BinanceErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn server_error_stays_transient_after_order_rejection() {
+        // 5xx: дошёл ли ордер, неизвестно — движок должен спросить биржу, а не считать его отклонённым
+        let unknown = BotError::from_binance_body(
+            503,
+            r#"{"code":-1001,"msg":"Internal error; unable to process your request."}"#,
+        )
+        .into_order_rejection();
+        assert!(matches!(unknown, BotError::Api { status: 503, .. }));
+        assert!(unknown.is_transient());
+
+        let rejected = BotError::from_binance_body(400, r#"{"code":-1111,"msg":"Precision"}"#)
+            .into_order_rejection();
+        assert!(matches!(
+            rejected,
+            BotError::OrderRejected { code: -1111, .. }
+        ));
+        assert!(!rejected.is_transient());
+    }
+}
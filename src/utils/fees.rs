@@ -0,0 +1,215 @@
+// src/utils/fees.rs
+use crate::types::PositionSide;
+use crate::utils::math::{pct, BPS_PER_UNIT};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Ставки, полученные с биржи, по символам. Живут между запусками и нужны бэктесту без сети
+pub const FEE_CACHE_FILE: &str = "fee_cache.json";
+
+/// Чья ликвидность: maker — ордер стоял в стакане, taker — забрал из стакана
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+/// Комиссии биржи в bps от нотионала (1 bps = 0.01%).
+/// По умолчанию — базовый уровень Binance USDT-M: 0.02% maker / 0.05% taker.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct FeeModel {
+    pub maker_bps: Decimal,
+    pub taker_bps: Decimal,
+    // Брать реальные ставки аккаунта (VIP-уровень, скидка BNB) с биржи при старте.
+    // Пока не получены — ставки выше
+    pub auto_detect: bool,
+}
+
+impl Default for FeeModel {
+    fn default() -> Self {
+        Self {
+            maker_bps: Decimal::new(2, 0),
+            taker_bps: Decimal::new(5, 0),
+            auto_detect: false,
+        }
+    }
+}
+
+impl FeeModel {
+    /// Ставки из долей, как их отдаёт биржа (0.0002 -> 2 bps)
+    pub fn with_rates(self, maker_rate: Decimal, taker_rate: Decimal) -> Self {
+        Self {
+            maker_bps: maker_rate * BPS_PER_UNIT,
+            taker_bps: taker_rate * BPS_PER_UNIT,
+            ..self
+        }
+    }
+
+    /// Ставка долей (5 bps -> 0.0005)
+    pub fn rate(&self, liquidity: Liquidity) -> Decimal {
+        self.bps(liquidity) / BPS_PER_UNIT
+    }
+
+    fn bps(&self, liquidity: Liquidity) -> Decimal {
+        match liquidity {
+            Liquidity::Maker => self.maker_bps,
+            Liquidity::Taker => self.taker_bps,
+        }
+    }
+
+    /// Комиссия одной сделки (вход или выход) на нотионал
+    pub fn fee(&self, notional: Decimal, liquidity: Liquidity) -> Decimal {
+        pct(notional.abs(), self.bps(liquidity))
+    }
+
+    /// Комиссия круга: вход по entry_notional + выход по exit_notional
+    pub fn round_trip(
+        &self,
+        entry_notional: Decimal,
+        exit_notional: Decimal,
+        entry: Liquidity,
+        exit: Liquidity,
+    ) -> Decimal {
+        self.fee(entry_notional, entry) + self.fee(exit_notional, exit)
+    }
+
+    /// PnL позиции за вычетом комиссий входа и выхода закрытого объёма
+    pub fn net_pnl(
+        &self,
+        side: PositionSide,
+        entry_price: Decimal,
+        exit_price: Decimal,
+        quantity: Decimal,
+        entry: Liquidity,
+        exit: Liquidity,
+    ) -> Decimal {
+        let gross = side.pnl(entry_price, exit_price, quantity);
+        gross - self.round_trip(entry_price * quantity, exit_price * quantity, entry, exit)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFees {
+    maker_bps: Decimal,
+    taker_bps: Decimal,
+    // Когда получены (unix, секунды)
+    fetched_at: i64,
+}
+
+async fn read_fee_cache() -> HashMap<String, CachedFees> {
+    match tokio::fs::read_to_string(FEE_CACHE_FILE).await {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+            warn!("⚠️ {} is unreadable, ignoring: {}", FEE_CACHE_FILE, e);
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+/// Последние полученные с биржи ставки символа поверх `fees`. None — в кэше их нет
+pub async fn load_cached_fees(symbol: &str, fees: FeeModel) -> Option<FeeModel> {
+    read_fee_cache()
+        .await
+        .remove(symbol)
+        .map(|cached| FeeModel {
+            maker_bps: cached.maker_bps,
+            taker_bps: cached.taker_bps,
+            ..fees
+        })
+}
+
+/// Запоминает ставки символа. Ошибка записи не мешает торговле — только лог
+pub async fn save_cached_fees(symbol: &str, fees: &FeeModel) {
+    let mut cache = read_fee_cache().await;
+    cache.insert(
+        symbol.to_string(),
+        CachedFees {
+            maker_bps: fees.maker_bps,
+            taker_bps: fees.taker_bps,
+            fetched_at: Utc::now().timestamp(),
+        },
+    );
+    let result = match serde_json::to_string_pretty(&cache) {
+        Ok(data) => tokio::fs::write(FEE_CACHE_FILE, data)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        warn!("⚠️ Failed to write {}: {}", FEE_CACHE_FILE, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn exchange_rates_convert_to_bps_and_back() {
+        let fees = FeeModel::default().with_rates(dec("0.00018"), dec("0.00045"));
+        assert_eq!(fees.maker_bps, dec("1.8"));
+        assert_eq!(fees.taker_bps, dec("4.5"));
+        assert_eq!(fees.rate(Liquidity::Maker), dec("0.00018"));
+        assert_eq!(fees.rate(Liquidity::Taker), dec("0.00045"));
+    }
+
+    #[test]
+    fn maker_and_taker_fees_use_their_own_rate() {
+        let fees = FeeModel::default();
+        // 1000 USDT: maker 2 bps = 0.2, taker 5 bps = 0.5. Знак нотионала не важен
+        assert_eq!(fees.fee(dec("1000"), Liquidity::Maker), dec("0.2"));
+        assert_eq!(fees.fee(dec("1000"), Liquidity::Taker), dec("0.5"));
+        assert_eq!(fees.fee(dec("-1000"), Liquidity::Taker), dec("0.5"));
+    }
+
+    #[test]
+    fn round_trip_adds_entry_and_exit_fees() {
+        let fees = FeeModel::default();
+        // Вход maker по 1000, выход taker по 1100: 0.2 + 0.55
+        assert_eq!(
+            fees.round_trip(dec("1000"), dec("1100"), Liquidity::Maker, Liquidity::Taker),
+            dec("0.75")
+        );
+        assert_eq!(
+            fees.round_trip(dec("1000"), dec("1000"), Liquidity::Taker, Liquidity::Taker),
+            dec("1.0")
+        );
+    }
+
+    #[test]
+    fn net_pnl_subtracts_round_trip_fees_for_both_sides() {
+        let fees = FeeModel::default();
+        // Лонг 10 @ 100 -> 101: +10 брутто, комиссии taker 0.5 + 0.505
+        assert_eq!(
+            fees.net_pnl(
+                PositionSide::Long,
+                dec("100"),
+                dec("101"),
+                dec("10"),
+                Liquidity::Taker,
+                Liquidity::Taker
+            ),
+            dec("8.995")
+        );
+        // Шорт 10 @ 100 -> 99: +10 брутто, вход maker 0.2, выход taker 0.495
+        assert_eq!(
+            fees.net_pnl(
+                PositionSide::Short,
+                dec("100"),
+                dec("99"),
+                dec("10"),
+                Liquidity::Maker,
+                Liquidity::Taker
+            ),
+            dec("9.305")
+        );
+    }
+}
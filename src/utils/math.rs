@@ -0,0 +1,22 @@
+// src/utils/math.rs
+use rust_decimal::Decimal;
+
+/// Базисных пунктов в единице: 1 bps = 0.01%
+pub const BPS_PER_UNIT: Decimal = Decimal::from_parts(10_000, 0, 0, false, 0);
+
+// Частые доли — константами, а не Decimal::from_str на каждом тике
+pub const ONE_TENTH_PCT: Decimal = Decimal::from_parts(1, 0, 0, false, 3); // 0.001
+pub const ONE_PCT: Decimal = Decimal::from_parts(1, 0, 0, false, 2); // 0.01
+
+/// pct_bps базисных пунктов от base: pct(10000, 5) = 5
+pub fn pct(base: Decimal, pct_bps: Decimal) -> Decimal {
+    base * pct_bps / BPS_PER_UNIT
+}
+
+/// Изменение от `from` до `to` в bps от `from`. None — база нулевая
+pub fn bps_diff(from: Decimal, to: Decimal) -> Option<Decimal> {
+    if from.is_zero() {
+        return None;
+    }
+    Some((to - from) / from * BPS_PER_UNIT)
+}
@@ -1,3 +1,12 @@
 // src/utils/mod.rs
+pub mod clock;
 pub mod error;
+pub mod fees;
+pub mod math;
+pub mod order_id;
 pub mod precision;
+pub mod rate_limit;
+pub mod retry;
+pub mod ring_channel;
+pub mod serde_decimal;
+pub mod trade_volume;
@@ -0,0 +1,17 @@
+// src/utils/order_id.rs
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Порядковый номер ордера в процессе: в одну миллисекунду может уйти несколько ордеров
+static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+/// Новый client order id: "snp" + мс с эпохи + порядковый номер.
+/// Только буквы и цифры, до 32 символов — подходит и Binance, и OKX (clOrdId)
+pub fn new_client_order_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let seq = SEQUENCE.fetch_add(1, Ordering::Relaxed) % 1_000_000;
+    format!("snp{}{:06}", millis, seq)
+}
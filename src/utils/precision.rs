@@ -2,6 +2,15 @@
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal; // Для доступа к методам округления, если понадобятся, но основные есть у Decimal
 
+/// Шаг цены и объёма символа (фильтры PRICE_FILTER / LOT_SIZE биржи)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolPrecision {
+    pub tick_size: Decimal,
+    pub step_size: Decimal,
+    // Минимальный нотионал ордера в котируемом активе (MIN_NOTIONAL / NOTIONAL). 0 — неизвестен
+    pub min_notional: Decimal,
+}
+
 /// Округляет количество ВНИЗ до ближайшего кратного step_size.
 /// Пример: amount=10.999, step=1.0 -> 10.0
 pub fn normalize_quantity(amount: Decimal, step_size: Decimal) -> Decimal {
@@ -21,3 +30,66 @@ pub fn normalize_price(price: Decimal, tick_size: Decimal) -> Decimal {
     // (price / tick_size).round() * tick_size
     (price / tick_size).round() * tick_size
 }
+
+/// Округляет количество ВВЕРХ до кратного step_size.
+/// Пример: amount=10.001, step=1.0 -> 11.0
+pub fn ceil_quantity(amount: Decimal, step_size: Decimal) -> Decimal {
+    if step_size.is_zero() {
+        return amount;
+    }
+    (amount / step_size).ceil() * step_size
+}
+
+/// Минимальный объём (кратный step_size), нотионал которого не меньше min_notional.
+/// None — такой объём дороже max_notional.
+pub fn bump_to_min_notional(
+    price: Decimal,
+    min_notional: Decimal,
+    step_size: Decimal,
+    max_notional: Decimal,
+) -> Option<Decimal> {
+    if price.is_zero() {
+        return None;
+    }
+    let quantity = ceil_quantity(min_notional / price, step_size);
+    if quantity * price > max_notional {
+        return None;
+    }
+    Some(quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn bump_rounds_up_to_the_first_step_above_min_notional() {
+        // 5.5 / 100 = 0.055 ровно на шаге
+        assert_eq!(
+            bump_to_min_notional(dec("100"), dec("5.5"), dec("0.001"), dec("6")),
+            Some(dec("0.055"))
+        );
+        // 5.5 / 30000 = 0.000183.. -> вверх до 0.001 (30 USDT)
+        assert_eq!(
+            bump_to_min_notional(dec("30000"), dec("5.5"), dec("0.001"), dec("50")),
+            Some(dec("0.001"))
+        );
+    }
+
+    #[test]
+    fn bump_is_refused_above_max_notional() {
+        // Минимальный объём стоит 30 USDT, а подниматься разрешено только до 6
+        assert_eq!(
+            bump_to_min_notional(dec("30000"), dec("5.5"), dec("0.001"), dec("6")),
+            None
+        );
+        assert_eq!(
+            bump_to_min_notional(Decimal::ZERO, dec("5.5"), dec("0.001"), dec("6")),
+            None
+        );
+    }
+}
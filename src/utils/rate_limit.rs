@@ -0,0 +1,51 @@
+// src/utils/rate_limit.rs
+use tokio::time::{sleep, Duration, Instant};
+
+/// Token bucket для весов REST-запросов: `capacity` токенов, пополняются равномерно
+/// за `period`. Пустое ведро — запрос ждёт, а не получает 429 от биржи
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / period.as_secs_f64().max(f64::EPSILON),
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Ждёт, пока наберётся `weight` токенов, и списывает их
+    pub async fn acquire(&mut self, weight: u32) {
+        let weight = f64::from(weight).min(self.capacity);
+        loop {
+            self.refill();
+            if self.tokens >= weight {
+                self.tokens -= weight;
+                return;
+            }
+            let wait = (weight - self.tokens) / self.refill_per_sec;
+            sleep(Duration::from_secs_f64(wait)).await;
+        }
+    }
+
+    /// Биржа всё равно ответила 429: ведро пустое, дальше только по мере пополнения
+    pub fn drain(&mut self) {
+        self.refill();
+        self.tokens = 0.0;
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+}
@@ -0,0 +1,127 @@
+// src/utils/retry.rs
+use crate::utils::error::Result;
+use std::future::Future;
+use tokio::time::{sleep, Duration};
+use tracing::warn;
+
+/// Повторы REST-запроса при временных сбоях (см. `BotError::is_transient`).
+/// Отказы по существу (маржа, min notional, ключи) возвращаются сразу
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    // Всего попыток, включая первую. 1 — без повторов
+    pub attempts: u32,
+    // Пауза перед первым повтором, дальше удваивается
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            backoff: Duration::ZERO,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(attempts: u32, backoff: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            backoff,
+        }
+    }
+
+    /// Выполняет `op` до `attempts` раз. `op` собирает запрос заново: подпись и timestamp свежие
+    pub async fn run<T, F, Fut>(&self, what: &str, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if e.is_transient() && attempt < self.attempts => {
+                    let delay = self.backoff * 2u32.saturating_pow(attempt - 1);
+                    warn!(
+                        "🔁 {} failed (attempt {}/{}): {}. Retrying in {:?}",
+                        what, attempt, self.attempts, e, delay
+                    );
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::error::BotError;
+    use std::cell::Cell;
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_until_success() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(1));
+        let calls = Cell::new(0);
+        // Две сетевые ошибки подряд, третья попытка проходит
+        let result = policy
+            .run("place order", || {
+                calls.set(calls.get() + 1);
+                let attempt = calls.get();
+                async move {
+                    if attempt < 3 {
+                        Err(BotError::Network("connection reset".to_string()))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_after_the_last_attempt() {
+        let policy = RetryPolicy::new(2, Duration::ZERO);
+        let calls = Cell::new(0);
+        let result: Result<()> = policy
+            .run("place order", || {
+                calls.set(calls.get() + 1);
+                async {
+                    Err(BotError::Api {
+                        status: 503,
+                        code: 0,
+                        msg: "Service Unavailable".to_string(),
+                    })
+                }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn rejections_are_not_retried() {
+        let policy = RetryPolicy::new(5, Duration::ZERO);
+        let calls = Cell::new(0);
+        let result: Result<()> = policy
+            .run("place order", || {
+                calls.set(calls.get() + 1);
+                async {
+                    Err(BotError::OrderRejected {
+                        code: -2019,
+                        msg: "Margin is insufficient.".to_string(),
+                    })
+                }
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(BotError::OrderRejected { code: -2019, .. })
+        ));
+        assert_eq!(calls.get(), 1);
+    }
+}
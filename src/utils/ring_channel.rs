@@ -0,0 +1,174 @@
+// src/utils/ring_channel.rs
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Ограниченный канал с семантикой "последнее значение важнее":
+/// при переполнении выбрасывается САМЫЙ СТАРЫЙ элемент, а отправитель никогда не ждёт.
+/// Для цен это правильнее, чем mpsc::try_send, который теряет как раз свежий тик.
+///
+/// Компромисс: движок видит самую свежую цену, но пропускает промежуточные тики.
+/// Для стратегии на bookTicker это безопасно (следующий тик несёт полное состояние стакана),
+/// а там, где важен каждый тик (replay), используется `send_wait` с backpressure.
+/// Вытеснения не молчаливые: `send` сообщает о них, и отправитель обязан их учесть.
+pub fn ring_channel<T>(capacity: usize) -> (RingSender<T>, RingReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::with_capacity(capacity.max(1))),
+        capacity: capacity.max(1),
+        notify: Notify::new(),
+        space: Notify::new(),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+    });
+    (
+        RingSender {
+            shared: shared.clone(),
+        },
+        RingReceiver { shared },
+    )
+}
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<T>>,
+    capacity: usize,
+    notify: Notify,
+    // Будит send_wait, когда получатель освободил место
+    space: Notify,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+}
+
+pub struct RingSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> RingSender<T> {
+    /// Кладёт значение, вытесняя самое старое при переполнении.
+    /// Ok(true) — ради этого значения выброшено самое старое, Err(value) — получатель уже закрыт.
+    pub fn send(&self, value: T) -> Result<bool, T> {
+        if !self.shared.receiver_alive.load(Ordering::Acquire) {
+            return Err(value);
+        }
+        let evicted = {
+            let mut queue = self.shared.queue.lock().unwrap();
+            let evicted = queue.len() >= self.shared.capacity;
+            if evicted {
+                queue.pop_front();
+            }
+            queue.push_back(value);
+            evicted
+        };
+        self.shared.notify.notify_one();
+        Ok(evicted)
+    }
+
+    /// Без потерь: ждёт свободного места вместо вытеснения (для replay, где важен каждый тик).
+    /// Err(value) — получатель закрыт.
+    pub async fn send_wait(&self, value: T) -> Result<(), T> {
+        loop {
+            if !self.shared.receiver_alive.load(Ordering::Acquire) {
+                return Err(value);
+            }
+            {
+                let mut queue = self.shared.queue.lock().unwrap();
+                if queue.len() < self.shared.capacity {
+                    queue.push_back(value);
+                    drop(queue);
+                    self.shared.notify.notify_one();
+                    return Ok(());
+                }
+            }
+            self.shared.space.notified().await;
+        }
+    }
+}
+
+impl<T> Clone for RingSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::AcqRel);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for RingSender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Последний отправитель: будим получателя, чтобы он увидел закрытие
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+pub struct RingReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> RingReceiver<T> {
+    /// Следующее значение в порядке поступления. None — все отправители закрыты и очередь пуста
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            let next = self.shared.queue.lock().unwrap().pop_front();
+            if let Some(value) = next {
+                self.shared.space.notify_one();
+                return Some(value);
+            }
+            if self.shared.senders.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+            // notify_one хранит permit, поэтому пробуждение между проверкой и ожиданием не теряется
+            self.shared.notify.notified().await;
+        }
+    }
+}
+
+impl<T> Drop for RingReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::Release);
+        self.shared.space.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn overflow_drops_oldest_and_keeps_latest() {
+        let (tx, mut rx) = ring_channel(2);
+        assert_eq!(tx.send(1), Ok(false));
+        assert_eq!(tx.send(2), Ok(false));
+        // Очередь полна: третье значение вытесняет первое, и отправитель узнаёт об этом
+        assert_eq!(tx.send(3), Ok(true));
+        drop(tx);
+
+        assert_eq!(rx.recv().await, Some(2));
+        assert_eq!(rx.recv().await, Some(3));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn send_fails_after_receiver_is_dropped() {
+        let (tx, rx) = ring_channel(1);
+        drop(rx);
+        assert_eq!(tx.send(7), Err(7));
+        assert_eq!(tx.send_wait(8).await, Err(8));
+    }
+
+    #[tokio::test]
+    async fn send_wait_waits_for_space_instead_of_dropping() {
+        let (tx, mut rx) = ring_channel(1);
+        tx.send_wait(1).await.unwrap();
+        let writer = tokio::spawn(async move {
+            // Канал полон: второе значение ждёт, пока получатель не заберёт первое
+            tx.send_wait(2).await.unwrap();
+        });
+
+        assert_eq!(rx.recv().await, Some(1));
+        assert_eq!(rx.recv().await, Some(2));
+        writer.await.unwrap();
+        assert_eq!(rx.recv().await, None);
+    }
+}
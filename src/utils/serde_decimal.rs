@@ -0,0 +1,156 @@
+// src/utils/serde_decimal.rs
+// `#[serde(with = "crate::utils::serde_decimal")]`: Decimal из строки ("0.01") или из числа (0.01).
+// Binance отдаёт числа то так, то так, в зависимости от эндпоинта и рынка
+use rust_decimal::Decimal;
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use std::fmt;
+use std::str::FromStr;
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer
+        .deserialize_any(DecimalVisitor { optional: false })?
+        .ok_or_else(|| de::Error::invalid_value(Unexpected::Str(""), &"a decimal"))
+}
+
+/// `Option<Decimal>`: null и пустая строка — None. С `#[serde(default)]` — и отсутствующее поле
+pub mod option {
+    use super::DecimalVisitor;
+    use rust_decimal::Decimal;
+    use serde::de::Deserializer;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DecimalVisitor { optional: true })
+    }
+}
+
+struct DecimalVisitor {
+    // Пустое значение (null, "") допустимо и даёт None
+    optional: bool,
+}
+
+impl DecimalVisitor {
+    fn empty<E: de::Error>(&self, unexpected: Unexpected) -> Result<Option<Decimal>, E> {
+        if self.optional {
+            Ok(None)
+        } else {
+            Err(E::invalid_type(unexpected, self))
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for DecimalVisitor {
+    type Value = Option<Decimal>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal as a string or a number")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let v = v.trim();
+        if v.is_empty() {
+            return self.empty(Unexpected::Str(v));
+        }
+        Decimal::from_str(v)
+            .or_else(|_| Decimal::from_scientific(v))
+            .map(Some)
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Some(Decimal::from(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Some(Decimal::from(v)))
+    }
+
+    // Через кратчайшую десятичную запись f64: 0.1 остаётся 0.1, а не 0.1000000000000000055
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        if !v.is_finite() {
+            return Err(E::invalid_value(Unexpected::Float(v), &self));
+        }
+        let text = v.to_string();
+        Decimal::from_str(&text)
+            .or_else(|_| Decimal::from_scientific(&text))
+            .map(Some)
+            .map_err(|_| E::invalid_value(Unexpected::Float(v), &self))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.empty(Unexpected::Unit)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.empty(Unexpected::Option)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Filter {
+        #[serde(with = "super")]
+        value: Decimal,
+        #[serde(with = "super::option", default)]
+        optional: Option<Decimal>,
+    }
+
+    fn parse(json: &str) -> Result<Filter, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn decimal_from_string_or_number() {
+        let cases = [
+            (r#"{"value":"0.01"}"#, Decimal::new(1, 2)),
+            (r#"{"value":0.1}"#, Decimal::new(1, 1)),
+            (r#"{"value":125}"#, Decimal::from(125)),
+            (r#"{"value":-3}"#, Decimal::from(-3)),
+            (r#"{"value":" 64000.50 "}"#, Decimal::new(6400050, 2)),
+            (r#"{"value":"1e-8"}"#, Decimal::new(1, 8)),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(parse(json).unwrap().value, expected, "{}", json);
+        }
+    }
+
+    #[test]
+    fn empty_or_bad_required_decimal_is_an_error() {
+        for json in [
+            r#"{"value":""}"#,
+            r#"{"value":null}"#,
+            r#"{"value":"abc"}"#,
+            r#"{"value":true}"#,
+        ] {
+            assert!(parse(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn optional_decimal_treats_empty_as_none() {
+        let cases = [
+            (r#"{"value":1}"#, None),
+            (r#"{"value":1,"optional":null}"#, None),
+            (r#"{"value":1,"optional":""}"#, None),
+            (r#"{"value":1,"optional":"2.5"}"#, Some(Decimal::new(25, 1))),
+            (r#"{"value":1,"optional":2.5}"#, Some(Decimal::new(25, 1))),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(parse(json).unwrap().optional, expected, "{}", json);
+        }
+        assert!(parse(r#"{"value":1,"optional":"x"}"#).is_err());
+    }
+}
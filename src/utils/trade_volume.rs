@@ -0,0 +1,46 @@
+// src/utils/trade_volume.rs
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Объём сделок по свечам: пишет поток aggTrade, забирает стратегия при закрытии свечи.
+/// Отдельно от канала тиков: тот при отставании выбрасывает старое, а объём терять нельзя
+#[derive(Debug)]
+pub struct TradeVolume {
+    candle_ms: u64,
+    // open_time свечи -> объём
+    buckets: Mutex<BTreeMap<u64, Decimal>>,
+}
+
+impl TradeVolume {
+    pub fn new(candle_ms: u64) -> Self {
+        Self {
+            candle_ms: candle_ms.max(1),
+            buckets: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Сделка попадает в свечу по своему времени, а не по времени получения
+    pub fn add(&self, trade_time_ms: u64, quantity: Decimal) {
+        let open_time = (trade_time_ms / self.candle_ms) * self.candle_ms;
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        *buckets.entry(open_time).or_default() += quantity;
+    }
+
+    /// Объём свечи `open_time`. Эта и более старые корзины выбрасываются
+    pub fn take(&self, open_time: u64) -> Decimal {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let newer = buckets.split_off(&(open_time + 1));
+        let volume = buckets.get(&open_time).copied().unwrap_or_default();
+        *buckets = newer;
+        volume
+    }
+
+    /// Новая подписка (другой символ): накопленное по старому не годится
+    pub fn clear(&self) {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}